@@ -1,7 +1,10 @@
+pub mod animated_transform;
 pub mod color;
 pub mod image;
 pub mod mat4;
 pub mod math;
+pub mod polynomial;
+pub mod quaternion;
 pub mod sampling;
 pub mod vec2;
 pub mod vec3;
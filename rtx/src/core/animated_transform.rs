@@ -0,0 +1,223 @@
+use crate::core::mat4;
+use crate::core::math;
+use crate::core::quaternion;
+use crate::core::vec3;
+use crate::core::vec4;
+
+#[derive(Copy, Clone, Debug)]
+struct TransformKeyframe {
+    time: f32,
+    translation: vec3::Vec3,
+    rotation: quaternion::Quaternion,
+    scale: mat4::Mat4,
+}
+
+impl TransformKeyframe {
+    fn new(time: f32, transform: &mat4::Mat4) -> TransformKeyframe {
+        let translation = vec4::Vec4::to_vec3(&transform[3]);
+
+        // strip the translation and keep the linear part of the transform
+        let mut linear = *transform;
+        linear[3] = vec4::Vec4::new(0.0, 0.0, 0.0, 1.0);
+        for col in 0..3 {
+            linear[col].w = 0.0;
+        }
+
+        // polar decomposition: average the matrix with its inverse transpose until it converges to the rotation
+        let mut rotation = linear;
+        for _ in 0..100 {
+            let inverse_transpose = match rotation.transpose().inverse() {
+                Some(inverse_transpose) => inverse_transpose,
+                None => break,
+            };
+            let next = 0.5 * (rotation + inverse_transpose);
+
+            let mut norm = 0.0f32;
+            for col in 0..3 {
+                let diff = next[col] - rotation[col];
+                norm = f32::max(norm, f32::abs(diff.x) + f32::abs(diff.y) + f32::abs(diff.z));
+            }
+
+            rotation = next;
+            if norm < 0.0001 {
+                break;
+            }
+        }
+
+        let scale = match rotation.inverse() {
+            Some(inverse_rotation) => inverse_rotation * linear,
+            None => linear,
+        };
+
+        return TransformKeyframe {
+            time,
+            translation,
+            rotation: quaternion::Quaternion::from_mat4(&rotation),
+            scale,
+        };
+    }
+
+    fn compose(
+        translation: &vec3::Vec3,
+        rotation: &quaternion::Quaternion,
+        scale: &mat4::Mat4,
+    ) -> mat4::Mat4 {
+        return mat4::Mat4::new().translate(translation) * rotation.to_mat4() * scale;
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AnimatedTransform {
+    keyframes: Vec<TransformKeyframe>,
+}
+
+impl AnimatedTransform {
+    // keyframes must be sorted by time
+    pub fn new(keyframes: &[(f32, mat4::Mat4)]) -> AnimatedTransform {
+        assert!(!keyframes.is_empty());
+        assert!(keyframes.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+
+        return AnimatedTransform {
+            keyframes: keyframes
+                .iter()
+                .map(|(time, transform)| TransformKeyframe::new(*time, transform))
+                .collect(),
+        };
+    }
+
+    pub fn is_animated(&self) -> bool {
+        return self.keyframes.len() > 1;
+    }
+
+    pub fn start_time(&self) -> f32 {
+        return self.keyframes[0].time;
+    }
+
+    pub fn end_time(&self) -> f32 {
+        return self.keyframes[self.keyframes.len() - 1].time;
+    }
+
+    pub fn interpolate(&self, time: f32) -> mat4::Mat4 {
+        let first = &self.keyframes[0];
+        if !self.is_animated() || time <= first.time {
+            return TransformKeyframe::compose(&first.translation, &first.rotation, &first.scale);
+        }
+
+        let last = &self.keyframes[self.keyframes.len() - 1];
+        if time >= last.time {
+            return TransformKeyframe::compose(&last.translation, &last.rotation, &last.scale);
+        }
+
+        let next_idx = self
+            .keyframes
+            .iter()
+            .position(|keyframe| keyframe.time > time)
+            .unwrap();
+        let start = &self.keyframes[next_idx - 1];
+        let end = &self.keyframes[next_idx];
+        let ratio = (time - start.time) / (end.time - start.time);
+
+        let translation = math::lerp(ratio, start.translation, end.translation);
+        let rotation = quaternion::Quaternion::slerp(ratio, &start.rotation, &end.rotation);
+        let scale = math::lerp(ratio, start.scale, end.scale);
+        return TransformKeyframe::compose(&translation, &rotation, &scale);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_interpolate_at_keyframes() {
+        let axis = vec3::Vec3::normalize(&vec3::Vec3::new(1.0, 1.0, 0.0)).unwrap();
+        let start = mat4::Mat4::new()
+            .translate(&vec3::Vec3::new(1.0, -2.0, 3.0))
+            .rotate(math::degree_to_radian(30.0), &axis)
+            .scale(&vec3::Vec3::new(2.0, 1.0, 0.5));
+        let end = mat4::Mat4::new()
+            .translate(&vec3::Vec3::new(-4.0, 0.0, 1.0))
+            .rotate(math::degree_to_radian(120.0), &axis)
+            .scale(&vec3::Vec3::new(1.0, 3.0, 1.0));
+
+        let animated = AnimatedTransform::new(&[(0.0, start), (1.0, end)]);
+        assert!(animated.is_animated());
+        assert!(animated
+            .interpolate(0.0)
+            .equal_epsilon(&start, math::EPSILON_F32_4));
+        assert!(animated
+            .interpolate(1.0)
+            .equal_epsilon(&end, math::EPSILON_F32_4));
+
+        // outside the keyframe range the transform is held
+        assert!(animated
+            .interpolate(-1.0)
+            .equal_epsilon(&start, math::EPSILON_F32_4));
+        assert!(animated
+            .interpolate(2.0)
+            .equal_epsilon(&end, math::EPSILON_F32_4));
+    }
+
+    #[test]
+    fn test_interpolate_between_keyframes() {
+        let axis = vec3::Vec3::new(0.0, 1.0, 0.0);
+        let start = mat4::Mat4::new().translate(&vec3::Vec3::new(0.0, 0.0, 0.0));
+        let end = mat4::Mat4::new()
+            .translate(&vec3::Vec3::new(4.0, 2.0, 0.0))
+            .rotate(math::degree_to_radian(90.0), &axis)
+            .scale(&vec3::Vec3::new(3.0, 3.0, 3.0));
+
+        let animated = AnimatedTransform::new(&[(1.0, start), (3.0, end)]);
+        let expected = mat4::Mat4::new()
+            .translate(&vec3::Vec3::new(2.0, 1.0, 0.0))
+            .rotate(math::degree_to_radian(45.0), &axis)
+            .scale(&vec3::Vec3::new(2.0, 2.0, 2.0));
+        assert!(animated
+            .interpolate(2.0)
+            .equal_epsilon(&expected, math::EPSILON_F32_4));
+    }
+
+    #[test]
+    fn test_interpolate_multiple_keyframes() {
+        let keyframes = [
+            (
+                0.0,
+                mat4::Mat4::new().translate(&vec3::Vec3::new(0.0, 0.0, 0.0)),
+            ),
+            (
+                1.0,
+                mat4::Mat4::new().translate(&vec3::Vec3::new(1.0, 0.0, 0.0)),
+            ),
+            (
+                2.0,
+                mat4::Mat4::new().translate(&vec3::Vec3::new(1.0, 5.0, 0.0)),
+            ),
+        ];
+
+        let animated = AnimatedTransform::new(&keyframes);
+        let expected = mat4::Mat4::new().translate(&vec3::Vec3::new(1.0, 2.5, 0.0));
+        assert!(animated
+            .interpolate(1.5)
+            .equal_epsilon(&expected, math::EPSILON_F32_4));
+        assert!(math::equal_epsilon_f32(
+            animated.start_time(),
+            0.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            animated.end_time(),
+            2.0,
+            math::EPSILON_F32_5
+        ));
+    }
+
+    #[test]
+    fn test_static_transform() {
+        let transform = mat4::Mat4::new().translate(&vec3::Vec3::new(1.0, 2.0, 3.0));
+        let animated = AnimatedTransform::new(&[(0.0, transform)]);
+        assert!(!animated.is_animated());
+        assert!(animated
+            .interpolate(10.0)
+            .equal_epsilon(&transform, math::EPSILON_F32_5));
+    }
+}
@@ -15,9 +15,14 @@ impl Color {
 
     pub fn from_vec3(v: &vec3::Vec3) -> Color {
         let gamma_correct = v.powf(1.0 / 2.2);
-        let red = (math::clamp(gamma_correct.x, 0.0, 1.0) * 255.999) as u8;
-        let blue = (math::clamp(gamma_correct.y, 0.0, 1.0) * 255.999) as u8;
-        let green = (math::clamp(gamma_correct.z, 0.0, 1.0) * 255.999) as u8;
+        return Color::from_encoded_vec3(&gamma_correct);
+    }
+
+    // for values that are already encoded, e.g by a sensor response curve
+    pub fn from_encoded_vec3(v: &vec3::Vec3) -> Color {
+        let red = (math::clamp(v.x, 0.0, 1.0) * 255.999) as u8;
+        let blue = (math::clamp(v.y, 0.0, 1.0) * 255.999) as u8;
+        let green = (math::clamp(v.z, 0.0, 1.0) * 255.999) as u8;
         return Color { red, blue, green };
     }
 }
@@ -43,4 +48,13 @@ mod test {
         assert_eq!(color.blue, 148);
         assert_eq!(color.green, 168);
     }
+
+    #[test]
+    fn test_from_encoded_vec3() {
+        let v = vec3::Vec3::new(2.0, 0.5, -0.4);
+        let color = Color::from_encoded_vec3(&v);
+        assert_eq!(color.red, 255);
+        assert_eq!(color.blue, 127);
+        assert_eq!(color.green, 0);
+    }
 }
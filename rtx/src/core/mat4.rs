@@ -2,6 +2,7 @@ use crate::core::math;
 use crate::core::vec3;
 use crate::core::vec4;
 use auto_ops::{impl_op_ex, impl_op_ex_commutative};
+use std::ops;
 
 #[derive(Copy, Clone, Debug)]
 pub struct Mat4 {
@@ -262,6 +263,20 @@ impl Mat4 {
     }
 }
 
+impl ops::Index<usize> for Mat4 {
+    type Output = vec4::Vec4;
+
+    fn index(&self, idx: usize) -> &Self::Output {
+        return &self.cols[idx];
+    }
+}
+
+impl ops::IndexMut<usize> for Mat4 {
+    fn index_mut(&mut self, idx: usize) -> &mut vec4::Vec4 {
+        return &mut self.cols[idx];
+    }
+}
+
 impl_op_ex!(+ |lhs: &Mat4, rhs: &Mat4| -> Mat4 {
     return Mat4 {
         cols: [
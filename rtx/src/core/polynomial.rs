@@ -0,0 +1,211 @@
+use crate::core::math;
+
+// real roots of polynomials up to degree four, in double precision and sorted in increasing order.
+// The closed forms lose precision when the roots are close together, so the roots of the cubic and
+// the quartic are polished with a few newton iterations on the original polynomial
+const NEWTON_ITERATIONS: usize = 4;
+
+pub fn solve_linear(a: f64, b: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return Vec::new();
+    }
+
+    return vec![-b / a];
+}
+
+// a x^2 + b x + c = 0, without the cancellation of the textbook formula
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return solve_linear(b, c);
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return Vec::new();
+    }
+
+    let q = -0.5 * (b + f64::copysign(discriminant.sqrt(), b));
+    let mut roots = if q == 0.0 {
+        vec![0.0, 0.0]
+    } else {
+        vec![q / a, c / q]
+    };
+    sort_roots(&mut roots);
+    return roots;
+}
+
+// a x^3 + b x^2 + c x + d = 0
+pub fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return solve_quadratic(b, c, d);
+    }
+
+    // depressed cubic t^3 + p t + q = 0 with x = t - b / 3a
+    let b = b / a;
+    let c = c / a;
+    let d = d / a;
+    let shift = -b / 3.0;
+    let p = c - b * b / 3.0;
+    let q = 2.0 * b * b * b / 27.0 - b * c / 3.0 + d;
+
+    let discriminant = 0.25 * q * q + p * p * p / 27.0;
+    let mut roots = if p == 0.0 && q == 0.0 {
+        vec![shift]
+    } else if discriminant > 0.0 {
+        let sqrt_discriminant = discriminant.sqrt();
+        let t = f64::cbrt(-0.5 * q + sqrt_discriminant) + f64::cbrt(-0.5 * q - sqrt_discriminant);
+        vec![t + shift]
+    } else if discriminant == 0.0 {
+        vec![3.0 * q / p + shift, -1.5 * q / p + shift]
+    } else {
+        // three real roots from the trigonometric form
+        let radius = 2.0 * f64::sqrt(-p / 3.0);
+        let cos_angle = math::clamp(1.5 * q / p * f64::sqrt(-3.0 / p), -1.0, 1.0);
+        let angle = f64::acos(cos_angle) / 3.0;
+        let third_turn = 2.0 * std::f64::consts::PI / 3.0;
+        (0..3)
+            .map(|k| radius * f64::cos(angle - third_turn * k as f64) + shift)
+            .collect()
+    };
+
+    let coefficients = [1.0, b, c, d];
+    for root in roots.iter_mut() {
+        *root = polish_root(&coefficients, *root);
+    }
+
+    sort_roots(&mut roots);
+    return roots;
+}
+
+// a x^4 + b x^3 + c x^2 + d x + e = 0 with ferrari's method
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return solve_cubic(b, c, d, e);
+    }
+
+    // depressed quartic y^4 + p y^2 + q y + r = 0 with x = y - b / 4a
+    let b = b / a;
+    let c = c / a;
+    let d = d / a;
+    let e = e / a;
+    let shift = -0.25 * b;
+    let b_sq = b * b;
+    let p = c - 0.375 * b_sq;
+    let q = d - 0.5 * b * c + 0.125 * b_sq * b;
+    let r = e - 0.25 * b * d + b_sq * c / 16.0 - 3.0 * b_sq * b_sq / 256.0;
+
+    let mut depressed_roots = Vec::with_capacity(4);
+    if q.abs() <= 1e-12 * (1.0 + p.abs() + r.abs()) {
+        // biquadratic, a quadratic in y^2
+        for y_sq in solve_quadratic(1.0, p, r) {
+            if y_sq >= 0.0 {
+                let y = y_sq.sqrt();
+                depressed_roots.push(-y);
+                depressed_roots.push(y);
+            }
+        }
+    } else {
+        // (y^2 + p / 2 + m)^2 = (sqrt(2m) y - q / (2 sqrt(2m)))^2 for a root m > 0 of the resolvent
+        let m = solve_cubic(8.0, 8.0 * p, 2.0 * p * p - 8.0 * r, -q * q)
+            .into_iter()
+            .fold(0.0, f64::max);
+        if m <= 0.0 {
+            return Vec::new();
+        }
+
+        let sqrt_2m = f64::sqrt(2.0 * m);
+        let offset = q / (2.0 * sqrt_2m);
+        depressed_roots.extend(solve_quadratic(1.0, -sqrt_2m, 0.5 * p + m + offset));
+        depressed_roots.extend(solve_quadratic(1.0, sqrt_2m, 0.5 * p + m - offset));
+    }
+
+    let coefficients = [1.0, b, c, d, e];
+    let mut roots: Vec<f64> = depressed_roots
+        .into_iter()
+        .map(|y| polish_root(&coefficients, y + shift))
+        .collect();
+    sort_roots(&mut roots);
+    return roots;
+}
+
+// coefficients from the highest degree
+fn polish_root(coefficients: &[f64], root: f64) -> f64 {
+    let mut root = root;
+    for _ in 0..NEWTON_ITERATIONS {
+        let mut value = 0.0;
+        let mut derivative = 0.0;
+        for coefficient in coefficients.iter() {
+            derivative = derivative * root + value;
+            value = value * root + coefficient;
+        }
+
+        if derivative == 0.0 || !value.is_finite() {
+            break;
+        }
+
+        let polished = root - value / derivative;
+        if !polished.is_finite() {
+            break;
+        }
+
+        root = polished;
+    }
+
+    return root;
+}
+
+fn sort_roots(roots: &mut [f64]) {
+    roots.sort_by(|lhs, rhs| lhs.partial_cmp(rhs).unwrap_or(std::cmp::Ordering::Equal));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_roots(roots: &[f64], expected: &[f64]) {
+        assert_eq!(roots.len(), expected.len(), "{:?}", roots);
+        for (root, expected_root) in roots.iter().zip(expected.iter()) {
+            assert!(
+                math::equal_epsilon_f64(*root, *expected_root, 1e-9),
+                "{:?}",
+                roots
+            );
+        }
+    }
+
+    #[test]
+    fn test_solve_quadratic() {
+        assert_roots(&solve_quadratic(1.0, -3.0, 2.0), &[1.0, 2.0]);
+        assert_roots(&solve_quadratic(0.0, 2.0, -1.0), &[0.5]);
+        assert!(solve_quadratic(1.0, 0.0, 1.0).is_empty());
+
+        // roots far apart lose the small one with the textbook formula
+        assert_roots(&solve_quadratic(1.0, -1e8, 1.0), &[1e-8, 1e8]);
+    }
+
+    #[test]
+    fn test_solve_cubic() {
+        // (x - 1)(x - 2)(x + 3)
+        assert_roots(&solve_cubic(1.0, 0.0, -7.0, 6.0), &[-3.0, 1.0, 2.0]);
+        // (x - 2)(x^2 + 1)
+        assert_roots(&solve_cubic(2.0, -4.0, 2.0, -4.0), &[2.0]);
+        assert_roots(&solve_cubic(1.0, -3.0, 3.0, -1.0), &[1.0]);
+    }
+
+    #[test]
+    fn test_solve_quartic() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        assert_roots(
+            &solve_quartic(1.0, -10.0, 35.0, -50.0, 24.0),
+            &[1.0, 2.0, 3.0, 4.0],
+        );
+        // (x^2 + 1)(x - 2)(x + 0.5)
+        assert_roots(&solve_quartic(2.0, -3.0, 0.0, -3.0, -2.0), &[-0.5, 2.0]);
+        // (x^2 - 1)(x^2 - 4)
+        assert_roots(
+            &solve_quartic(1.0, 0.0, -5.0, 0.0, 4.0),
+            &[-2.0, -1.0, 1.0, 2.0],
+        );
+        assert!(solve_quartic(1.0, 0.0, 2.0, 0.0, 1.0).is_empty());
+    }
+}
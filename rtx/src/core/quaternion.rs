@@ -0,0 +1,230 @@
+use crate::core::mat4;
+use crate::core::math;
+use crate::core::vec3;
+use auto_ops::{impl_op_ex, impl_op_ex_commutative};
+
+#[derive(Copy, Clone, Debug)]
+pub struct Quaternion {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Quaternion {
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Quaternion {
+        return Quaternion { x, y, z, w };
+    }
+
+    pub fn identity() -> Quaternion {
+        return Quaternion::new(0.0, 0.0, 0.0, 1.0);
+    }
+
+    pub fn from_axis_angle(angle: f32, axis: &vec3::Vec3) -> Quaternion {
+        let axis = vec3::Vec3::normalize(axis).unwrap();
+        let half_sin = f32::sin(angle * 0.5);
+        return Quaternion::new(
+            axis.x * half_sin,
+            axis.y * half_sin,
+            axis.z * half_sin,
+            f32::cos(angle * 0.5),
+        );
+    }
+
+    // the upper 3x3 of the matrix must be a pure rotation
+    pub fn from_mat4(mat: &mat4::Mat4) -> Quaternion {
+        let m = |row: usize, col: usize| -> f32 {
+            let c = &mat[col];
+            return match row {
+                0 => c.x,
+                1 => c.y,
+                _ => c.z,
+            };
+        };
+
+        let trace = m(0, 0) + m(1, 1) + m(2, 2);
+        if trace > 0.0 {
+            let s = f32::sqrt(trace + 1.0);
+            let w = 0.5 * s;
+            let s = 0.5 / s;
+            return Quaternion::new(
+                (m(2, 1) - m(1, 2)) * s,
+                (m(0, 2) - m(2, 0)) * s,
+                (m(1, 0) - m(0, 1)) * s,
+                w,
+            );
+        }
+
+        let next = [1, 2, 0];
+        let mut i = 0;
+        if m(1, 1) > m(0, 0) {
+            i = 1;
+        }
+        if m(2, 2) > m(i, i) {
+            i = 2;
+        }
+        let j = next[i];
+        let k = next[j];
+
+        let mut s = f32::sqrt(m(i, i) - (m(j, j) + m(k, k)) + 1.0);
+        let mut q = [0.0; 3];
+        q[i] = 0.5 * s;
+        if s != 0.0 {
+            s = 0.5 / s;
+        }
+        q[j] = (m(j, i) + m(i, j)) * s;
+        q[k] = (m(k, i) + m(i, k)) * s;
+        let w = (m(k, j) - m(j, k)) * s;
+        return Quaternion::new(q[0], q[1], q[2], w);
+    }
+
+    pub fn to_mat4(&self) -> mat4::Mat4 {
+        let xx = self.x * self.x;
+        let yy = self.y * self.y;
+        let zz = self.z * self.z;
+        let xy = self.x * self.y;
+        let xz = self.x * self.z;
+        let yz = self.y * self.z;
+        let wx = self.w * self.x;
+        let wy = self.w * self.y;
+        let wz = self.w * self.z;
+
+        return mat4::Mat4::from_scalars(
+            1.0 - 2.0 * (yy + zz),
+            2.0 * (xy + wz),
+            2.0 * (xz - wy),
+            0.0,
+            2.0 * (xy - wz),
+            1.0 - 2.0 * (xx + zz),
+            2.0 * (yz + wx),
+            0.0,
+            2.0 * (xz + wy),
+            2.0 * (yz - wx),
+            1.0 - 2.0 * (xx + yy),
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            1.0,
+        );
+    }
+
+    pub fn dot(lhs: &Quaternion, rhs: &Quaternion) -> f32 {
+        return lhs.x * rhs.x + lhs.y * rhs.y + lhs.z * rhs.z + lhs.w * rhs.w;
+    }
+
+    pub fn length(&self) -> f32 {
+        return f32::sqrt(Quaternion::dot(self, self));
+    }
+
+    pub fn normalize(q: &Quaternion) -> Option<Quaternion> {
+        let length = q.length();
+        if length == 0.0 {
+            return None;
+        }
+
+        return Some(q / length);
+    }
+
+    pub fn slerp(ratio: f32, start: &Quaternion, end: &Quaternion) -> Quaternion {
+        // take the shortest path between the two rotations
+        let mut end = *end;
+        let mut cos_theta = Quaternion::dot(start, &end);
+        if cos_theta < 0.0 {
+            end = -end;
+            cos_theta = -cos_theta;
+        }
+
+        if cos_theta > 0.9995 {
+            return Quaternion::normalize(&((1.0 - ratio) * start + ratio * end)).unwrap();
+        }
+
+        let theta = f32::acos(math::clamp(cos_theta, -1.0, 1.0)) * ratio;
+        let perpendicular = Quaternion::normalize(&(end - start * cos_theta)).unwrap();
+        return start * f32::cos(theta) + perpendicular * f32::sin(theta);
+    }
+
+    pub fn equal_epsilon(&self, rhs: &Quaternion, epsilon: f32) -> bool {
+        return math::equal_epsilon_f32(self.x, rhs.x, epsilon)
+            && math::equal_epsilon_f32(self.y, rhs.y, epsilon)
+            && math::equal_epsilon_f32(self.z, rhs.z, epsilon)
+            && math::equal_epsilon_f32(self.w, rhs.w, epsilon);
+    }
+}
+
+impl_op_ex!(+ |lhs: &Quaternion, rhs: &Quaternion| -> Quaternion {
+    return Quaternion::new(lhs.x + rhs.x, lhs.y + rhs.y, lhs.z + rhs.z, lhs.w + rhs.w);
+});
+
+impl_op_ex!(-|lhs: &Quaternion, rhs: &Quaternion| -> Quaternion {
+    return Quaternion::new(lhs.x - rhs.x, lhs.y - rhs.y, lhs.z - rhs.z, lhs.w - rhs.w);
+});
+
+impl_op_ex_commutative!(*|lhs: &Quaternion, rhs: &f32| -> Quaternion {
+    return Quaternion::new(lhs.x * rhs, lhs.y * rhs, lhs.z * rhs, lhs.w * rhs);
+});
+
+impl_op_ex!(/ |lhs: &Quaternion, rhs: &f32| -> Quaternion {
+    return Quaternion::new(lhs.x / rhs, lhs.y / rhs, lhs.z / rhs, lhs.w / rhs);
+});
+
+impl_op_ex!(-|q: &Quaternion| -> Quaternion {
+    return Quaternion::new(-q.x, -q.y, -q.z, -q.w);
+});
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::f32;
+
+    #[test]
+    fn test_to_mat4_matches_rotate() {
+        let axis = vec3::Vec3::normalize(&vec3::Vec3::new(1.0, 2.0, -0.5)).unwrap();
+        let angle = math::degree_to_radian(73.0);
+        let q = Quaternion::from_axis_angle(angle, &axis);
+        let expected = mat4::Mat4::new().rotate(angle, &axis);
+        assert!(q.to_mat4().equal_epsilon(&expected, math::EPSILON_F32_5));
+    }
+
+    #[test]
+    fn test_from_mat4_round_trip() {
+        let axis = vec3::Vec3::normalize(&vec3::Vec3::new(0.3, -1.0, 0.2)).unwrap();
+        for degree in [10.0, 90.0, 179.0, 250.0].iter() {
+            let rotation = mat4::Mat4::new().rotate(math::degree_to_radian(*degree), &axis);
+            let q = Quaternion::from_mat4(&rotation);
+            assert!(math::equal_epsilon_f32(
+                q.length(),
+                1.0,
+                math::EPSILON_F32_5
+            ));
+            assert!(q.to_mat4().equal_epsilon(&rotation, math::EPSILON_F32_4));
+        }
+    }
+
+    #[test]
+    fn test_slerp() {
+        let axis = vec3::Vec3::new(0.0, 1.0, 0.0);
+        let start = Quaternion::identity();
+        let end = Quaternion::from_axis_angle(f32::consts::PI * 0.5, &axis);
+
+        let halfway = Quaternion::slerp(0.5, &start, &end);
+        let expected = Quaternion::from_axis_angle(f32::consts::PI * 0.25, &axis);
+        assert!(halfway.equal_epsilon(&expected, math::EPSILON_F32_5));
+
+        assert!(Quaternion::slerp(0.0, &start, &end).equal_epsilon(&start, math::EPSILON_F32_5));
+        assert!(Quaternion::slerp(1.0, &start, &end).equal_epsilon(&end, math::EPSILON_F32_5));
+    }
+
+    #[test]
+    fn test_slerp_takes_shortest_path() {
+        let axis = vec3::Vec3::new(0.0, 0.0, 1.0);
+        let start = Quaternion::from_axis_angle(math::degree_to_radian(10.0), &axis);
+        let end = -Quaternion::from_axis_angle(math::degree_to_radian(30.0), &axis);
+
+        let halfway = Quaternion::slerp(0.5, &start, &end);
+        let expected = mat4::Mat4::new().rotate(math::degree_to_radian(20.0), &axis);
+        assert!(halfway
+            .to_mat4()
+            .equal_epsilon(&expected, math::EPSILON_F32_5));
+    }
+}
@@ -71,4 +71,258 @@ pub fn sample_cosine_weighted_unit_hemisphere(sample: &vec2::Vec2) -> vec3::Vec3
 pub fn pdf_cosine_weighted_unit_hemisphere(cos_theta: f32) -> f32 {
     debug_assert!(cos_theta > 0.0);
     return cos_theta / math::PI_F32;
-}
\ No newline at end of file
+}
+
+// low discrepancy sequence from reversing the digits of index in the given base
+pub fn radical_inverse(base: u64, index: u64) -> f32 {
+    let inverse_base = 1.0 / (base as f64);
+    let mut inverse_base_n = 1.0;
+    let mut reversed_digits = 0;
+    let mut remain = index;
+    while remain > 0 {
+        let next = remain / base;
+        let digit = remain - next * base;
+        reversed_digits = reversed_digits * base + digit;
+        inverse_base_n *= inverse_base;
+        remain = next;
+    }
+
+    return f32::min(
+        ((reversed_digits as f64) * inverse_base_n) as f32,
+        1.0 - f32::EPSILON,
+    );
+}
+
+pub struct Distribution1D {
+    func: Vec<f32>,
+    cdf: Vec<f32>,
+    func_integral: f32,
+}
+
+impl Distribution1D {
+    pub fn new(func: &[f32]) -> Distribution1D {
+        assert!(
+            !func.is_empty(),
+            "distribution needs at least one function value"
+        );
+        let n = func.len();
+        let mut func = func.to_vec();
+        let mut cdf = vec![0.0; n + 1];
+        for i in 1..(n + 1) {
+            cdf[i] = cdf[i - 1] + f32::abs(func[i - 1]) / (n as f32);
+        }
+
+        // an all zero function is sampled uniformly
+        let func_integral = cdf[n];
+        for (i, value) in cdf.iter_mut().enumerate() {
+            if func_integral == 0.0 {
+                *value = (i as f32) / (n as f32);
+            } else {
+                *value /= func_integral;
+            }
+        }
+
+        for value in func.iter_mut() {
+            *value = f32::abs(*value);
+        }
+
+        return Distribution1D {
+            func,
+            cdf,
+            func_integral,
+        };
+    }
+
+    pub fn count(&self) -> usize {
+        return self.func.len();
+    }
+
+    pub fn func_integral(&self) -> f32 {
+        return self.func_integral;
+    }
+
+    pub fn sample_continuous(&self, sample: f32) -> (f32, f32, usize) {
+        let offset = self.find_interval(sample);
+        let mut du = sample - self.cdf[offset];
+        let cdf_range = self.cdf[offset + 1] - self.cdf[offset];
+        if cdf_range > 0.0 {
+            du /= cdf_range;
+        }
+
+        let pdf = if self.func_integral > 0.0 {
+            self.func[offset] / self.func_integral
+        } else {
+            1.0
+        };
+
+        let value = ((offset as f32) + du) / (self.count() as f32);
+        return (f32::min(value, 1.0 - f32::EPSILON), pdf, offset);
+    }
+
+    pub fn sample_discrete(&self, sample: f32) -> (usize, f32) {
+        let offset = self.find_interval(sample);
+        return (offset, self.discrete_pdf(offset));
+    }
+
+    pub fn discrete_pdf(&self, index: usize) -> f32 {
+        if self.func_integral == 0.0 {
+            return 1.0 / (self.count() as f32);
+        }
+
+        return self.func[index] / (self.func_integral * (self.count() as f32));
+    }
+
+    fn find_interval(&self, sample: f32) -> usize {
+        // find the last cdf entry that is less than or equal to the sample
+        let mut first = 0;
+        let mut len = self.cdf.len();
+        while len > 0 {
+            let half = len >> 1;
+            let middle = first + half;
+            if self.cdf[middle] <= sample {
+                first = middle + 1;
+                len -= half + 1;
+            } else {
+                len = half;
+            }
+        }
+
+        return math::clamp(first as i64 - 1, 0, self.cdf.len() as i64 - 2) as usize;
+    }
+}
+
+pub struct Distribution2D {
+    conditionals: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    pub fn new(func: &[f32], width: usize, height: usize) -> Distribution2D {
+        debug_assert!(func.len() == width * height);
+        let mut conditionals = Vec::with_capacity(height);
+        for v in 0..height {
+            conditionals.push(Distribution1D::new(&func[v * width..(v + 1) * width]));
+        }
+
+        let marginal_func: Vec<f32> = conditionals
+            .iter()
+            .map(|conditional| conditional.func_integral())
+            .collect();
+        let marginal = Distribution1D::new(&marginal_func);
+
+        return Distribution2D {
+            conditionals,
+            marginal,
+        };
+    }
+
+    pub fn sample_continuous(&self, sample: &vec2::Vec2) -> (vec2::Vec2, f32) {
+        let (v, marginal_pdf, row) = self.marginal.sample_continuous(sample.y);
+        let (u, conditional_pdf, _) = self.conditionals[row].sample_continuous(sample.x);
+        return (vec2::Vec2::new(u, v), marginal_pdf * conditional_pdf);
+    }
+
+    pub fn pdf(&self, point: &vec2::Vec2) -> f32 {
+        let width = self.conditionals[0].count();
+        let height = self.marginal.count();
+        let column = math::clamp((point.x * width as f32) as usize, 0, width - 1);
+        let row = math::clamp((point.y * height as f32) as usize, 0, height - 1);
+        if self.marginal.func_integral() == 0.0 {
+            return 1.0;
+        }
+
+        return self.conditionals[row].func[column] / self.marginal.func_integral();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_radical_inverse() {
+        let expected_base_2 = [0.0, 0.5, 0.25, 0.75, 0.125];
+        for (i, expected) in expected_base_2.iter().enumerate() {
+            assert!(math::equal_epsilon_f32(
+                radical_inverse(2, i as u64),
+                *expected,
+                math::EPSILON_F32_5
+            ));
+        }
+
+        let expected_base_3 = [0.0, 1.0 / 3.0, 2.0 / 3.0, 1.0 / 9.0, 4.0 / 9.0];
+        for (i, expected) in expected_base_3.iter().enumerate() {
+            assert!(math::equal_epsilon_f32(
+                radical_inverse(3, i as u64),
+                *expected,
+                math::EPSILON_F32_5
+            ));
+        }
+    }
+
+    #[test]
+    fn test_distribution_1d_sample_continuous() {
+        let distribution = Distribution1D::new(&[1.0, 3.0]);
+        assert!(math::equal_epsilon_f32(
+            distribution.func_integral(),
+            2.0,
+            math::EPSILON_F32_5
+        ));
+
+        let (value, pdf, offset) = distribution.sample_continuous(0.125);
+        assert_eq!(offset, 0);
+        assert!(math::equal_epsilon_f32(value, 0.25, math::EPSILON_F32_5));
+        assert!(math::equal_epsilon_f32(pdf, 0.5, math::EPSILON_F32_5));
+
+        let (value, pdf, offset) = distribution.sample_continuous(0.625);
+        assert_eq!(offset, 1);
+        assert!(math::equal_epsilon_f32(value, 0.75, math::EPSILON_F32_5));
+        assert!(math::equal_epsilon_f32(pdf, 1.5, math::EPSILON_F32_5));
+    }
+
+    #[test]
+    fn test_distribution_1d_sample_discrete() {
+        let distribution = Distribution1D::new(&[0.0, 1.0, 0.0, 3.0]);
+        let (index, pdf) = distribution.sample_discrete(0.1);
+        assert_eq!(index, 1);
+        assert!(math::equal_epsilon_f32(pdf, 0.25, math::EPSILON_F32_5));
+
+        let (index, pdf) = distribution.sample_discrete(0.9);
+        assert_eq!(index, 3);
+        assert!(math::equal_epsilon_f32(pdf, 0.75, math::EPSILON_F32_5));
+    }
+
+    #[test]
+    fn test_distribution_1d_all_zero() {
+        let distribution = Distribution1D::new(&[0.0, 0.0]);
+        let (value, pdf, offset) = distribution.sample_continuous(0.75);
+        assert_eq!(offset, 1);
+        assert!(math::equal_epsilon_f32(value, 0.75, math::EPSILON_F32_5));
+        assert!(math::equal_epsilon_f32(pdf, 1.0, math::EPSILON_F32_5));
+    }
+
+    #[test]
+    #[should_panic(expected = "distribution needs at least one function value")]
+    fn test_distribution_1d_empty() {
+        Distribution1D::new(&[]);
+    }
+
+    #[test]
+    fn test_distribution_2d_sample_continuous() {
+        // only the bottom right cell has energy
+        let distribution = Distribution2D::new(&[0.0, 0.0, 0.0, 2.0], 2, 2);
+        let (point, pdf) = distribution.sample_continuous(&vec2::Vec2::new(0.5, 0.5));
+        assert!(point.x >= 0.5 && point.y >= 0.5);
+        assert!(math::equal_epsilon_f32(pdf, 4.0, math::EPSILON_F32_5));
+        assert!(math::equal_epsilon_f32(
+            distribution.pdf(&point),
+            4.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            distribution.pdf(&vec2::Vec2::new(0.25, 0.25)),
+            0.0,
+            math::EPSILON_F32_5
+        ));
+    }
+}
@@ -2,6 +2,7 @@ use crate::core::math;
 use crate::core::vec2;
 use auto_ops::{impl_op_ex, impl_op_ex_commutative};
 use std::convert;
+use std::ops;
 
 #[derive(Copy, Clone, Debug)]
 pub struct Vec3 {
@@ -94,6 +95,20 @@ impl Vec3 {
         return -self + 2.0 * Vec3::dot(normal, self) * normal;
     }
 
+    // eta is the ratio of the incident over the transmitted index of refraction, returns none
+    // on total internal reflection
+    pub fn refract(&self, normal: &Vec3, eta: f32) -> Option<Vec3> {
+        let cos_theta_i = Vec3::dot(normal, self);
+        let sin_theta_i_sq = f32::max(0.0, 1.0 - cos_theta_i * cos_theta_i);
+        let sin_theta_t_sq = eta * eta * sin_theta_i_sq;
+        if sin_theta_t_sq >= 1.0 {
+            return None;
+        }
+
+        let cos_theta_t = f32::sqrt(1.0 - sin_theta_t_sq);
+        return Some(eta * -self + (eta * cos_theta_i - cos_theta_t) * normal);
+    }
+
     pub fn coordinate_system(v1: &Vec3, v2: &mut Vec3, v3: &mut Vec3) {
         if f32::abs(v1.x) > f32::abs(v1.y) {
             *v2 = Vec3::normalize(&Vec3::new(v1.z, 0.0, -v1.x)).unwrap(); // cross(y, v1)
@@ -117,6 +132,30 @@ impl convert::From<f32> for Vec3 {
     }
 }
 
+impl ops::Index<usize> for Vec3 {
+    type Output = f32;
+
+    fn index(&self, idx: usize) -> &Self::Output {
+        return match idx {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vec3 index out of range: {}", idx),
+        };
+    }
+}
+
+impl ops::IndexMut<usize> for Vec3 {
+    fn index_mut(&mut self, idx: usize) -> &mut f32 {
+        return match idx {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            _ => panic!("Vec3 index out of range: {}", idx),
+        };
+    }
+}
+
 impl_op_ex!(-|a: &Vec3| -> Vec3 {
     return Vec3 {
         x: -a.x,
@@ -206,6 +245,15 @@ mod test {
         ));
     }
 
+    #[test]
+    fn test_index() {
+        let mut v = Vec3::new(1.0, 2.0, 3.0);
+        v[1] = 5.0;
+        assert!(math::equal_epsilon_f32(v[0], 1.0, math::EPSILON_F32_5));
+        assert!(math::equal_epsilon_f32(v[1], 5.0, math::EPSILON_F32_5));
+        assert!(math::equal_epsilon_f32(v[2], 3.0, math::EPSILON_F32_5));
+    }
+
     #[test]
     fn test_dot() {
         let lhs = Vec3::new(12.0, 34.0, 12.0);
@@ -554,6 +602,27 @@ mod test {
         ));
     }
 
+    #[test]
+    fn test_refract() {
+        let v = Vec3::normalize(&Vec3::new(1.0, 1.0, 0.0)).unwrap();
+        let n = Vec3::new(0.0, 1.0, 0.0);
+        let refract = Vec3::refract(&v, &n, 1.0).unwrap();
+        assert!(Vec3::equal_epsilon(&refract, &-v, math::EPSILON_F32_5));
+
+        // snell's law
+        let refract = Vec3::refract(&v, &n, 1.0 / 1.5).unwrap();
+        let sin_theta_t = f32::sqrt(refract.x * refract.x + refract.z * refract.z);
+        assert!(math::equal_epsilon_f32(
+            1.5 * sin_theta_t,
+            f32::sqrt(0.5),
+            math::EPSILON_F32_5
+        ));
+        assert!(refract.y < 0.0);
+
+        // total internal reflection
+        assert!(Vec3::refract(&v, &n, 1.5).is_none());
+    }
+
     #[test]
     fn test_coordinate_system() {
         let y_axis = Vec3::normalize(&Vec3::new(1.0, 0.0, 1.0)).unwrap();
@@ -1,5 +1,6 @@
 use crate::core::color;
 use crate::core::image;
+use crate::core::vec3;
 use std::fs::File;
 use std::io::BufWriter;
 use std::io::Write;
@@ -17,6 +18,32 @@ pub fn write_to_file(file: &str, image: &image::Image) -> std::io::Result<()> {
 pub fn write_to_writer(
     writer: &mut impl std::io::Write,
     image: &image::Image,
+) -> std::io::Result<()> {
+    return write_colors(writer, image, color::Color::from_vec3);
+}
+
+// writes values that are already encoded without gamma correction
+pub fn write_encoded_to_file(file: &str, image: &image::Image) -> std::io::Result<()> {
+    let mut writer = match File::create(file) {
+        Ok(f) => BufWriter::new(f),
+        Err(e) => panic!("Problem open file {}.\nEncounter error: {:#?}", file, e),
+    };
+
+    write_encoded_to_writer(&mut writer, image)?;
+    writer.flush()
+}
+
+pub fn write_encoded_to_writer(
+    writer: &mut impl std::io::Write,
+    image: &image::Image,
+) -> std::io::Result<()> {
+    return write_colors(writer, image, color::Color::from_encoded_vec3);
+}
+
+fn write_colors(
+    writer: &mut impl std::io::Write,
+    image: &image::Image,
+    to_color: fn(&vec3::Vec3) -> color::Color,
 ) -> std::io::Result<()> {
     writer.write_fmt(format_args!(
         "P3\n{width} {height}\n{max_color}\n",
@@ -27,7 +54,7 @@ pub fn write_to_writer(
 
     for y in 0..image.height() {
         for x in 0..image.width() {
-            let color = color::Color::from_vec3(&image[y][x]);
+            let color = to_color(&image[y][x]);
             writer.write_fmt(format_args!(
                 "{} {} {}\n",
                 color.red, color.blue, color.green
@@ -110,6 +137,20 @@ mod test {
         assert_eq!(lines.next(), None);
     }
 
+    #[test]
+    fn test_write_encoded_to_writer() {
+        let mut str_writer = MockStringWriter::new();
+        let mut image = image::Image::new(1, 1);
+        image[0][0] = vec3::Vec3::new(0.5, 1.0, 0.0);
+        write_encoded_to_writer(&mut str_writer, &image).unwrap();
+
+        // values are written without gamma correction
+        let buffer = &str_writer.buffer;
+        let mut lines = buffer.lines();
+        assert_eq!(lines.nth(3), Some("127 255 0"));
+        assert_eq!(lines.next(), None);
+    }
+
     #[test]
     #[should_panic(expected = "Mock Error Write")]
     fn test_write_to_writer_with_err() {
@@ -0,0 +1,7 @@
+pub mod gltf;
+pub mod heightmap;
+pub mod json;
+pub mod lens_table;
+pub mod mtl;
+pub mod obj;
+pub mod ply;
@@ -0,0 +1,1285 @@
+use crate::core::mat4;
+use crate::core::quaternion;
+use crate::core::vec2;
+use crate::core::vec3;
+use crate::core::vec4;
+use crate::importer::json;
+use crate::scene::camera::{orthographic_camera, perspective_camera};
+use crate::scene::light;
+use crate::scene::light::{area_light, directional_light, point_light, spot_light};
+use crate::scene::material;
+use crate::scene::material::{glass, matte, metal};
+use crate::scene::shape::triangle_mesh;
+use crate::scene::world;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path;
+use std::rc;
+
+const GLB_MAGIC: u32 = 0x4654_6c67;
+const GLB_CHUNK_JSON: u32 = 0x4e4f_534a;
+const GLB_CHUNK_BIN: u32 = 0x004e_4942;
+
+// the metallic-roughness model only has a base color for metals, so they share a generic bright
+// conductor tinted by it
+const METAL_ETA: f32 = 0.2;
+const METAL_K: f32 = 3.9;
+
+// metallic factors are blends between a dielectric and a conductor, the closer one is used
+const METALLIC_THRESHOLD: f32 = 0.5;
+
+const MODE_TRIANGLES: usize = 4;
+const MODE_TRIANGLE_STRIP: usize = 5;
+const MODE_TRIANGLE_FAN: usize = 6;
+
+// each triangle primitive of a mesh instanced by a node becomes one triangle mesh
+pub struct GltfMesh {
+    pub name: String,
+    pub mesh: rc::Rc<triangle_mesh::TriangleMesh>,
+    pub material: rc::Rc<dyn material::Material>,
+    pub emission: Option<vec3::Vec3>,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum GltfProjection {
+    Perspective { yfov: f32 },
+    Orthographic { xmag: f32, ymag: f32 },
+}
+
+// cameras look down their local -z axis with +y up
+#[derive(Clone, Debug)]
+pub struct GltfCamera {
+    pub name: String,
+    pub camera_to_world: mat4::Mat4,
+    pub projection: GltfProjection,
+}
+
+impl GltfCamera {
+    pub fn location(&self) -> vec3::Vec3 {
+        return (self.camera_to_world * vec4::Vec4::new(0.0, 0.0, 0.0, 1.0)).to_vec3();
+    }
+
+    pub fn out_direction(&self) -> vec3::Vec3 {
+        return (self.camera_to_world * vec4::Vec4::new(0.0, 0.0, -1.0, 0.0))
+            .to_vec3()
+            .normalize()
+            .unwrap();
+    }
+
+    pub fn up_direction(&self) -> vec3::Vec3 {
+        return (self.camera_to_world * vec4::Vec4::new(0.0, 1.0, 0.0, 0.0))
+            .to_vec3()
+            .normalize()
+            .unwrap();
+    }
+
+    // the vertical field of view is kept and the aspect ratio of the image decides the horizontal one
+    pub fn create_perspective_camera(
+        &self,
+        image_width: usize,
+        image_height: usize,
+    ) -> Option<perspective_camera::PerspectiveCamera> {
+        let yfov = match self.projection {
+            GltfProjection::Perspective { yfov } => yfov,
+            GltfProjection::Orthographic { .. } => return None,
+        };
+
+        let aspect_ratio = (image_width as f32) / (image_height as f32);
+        let view_angle = 2.0 * f32::atan(f32::tan(yfov / 2.0) * aspect_ratio);
+        return Some(perspective_camera::PerspectiveCamera::new(
+            self.location(),
+            self.out_direction(),
+            self.up_direction(),
+            view_angle,
+            1.0,
+            image_width,
+            image_height,
+        ));
+    }
+
+    // xmag is half of the horizontal extent of the view
+    pub fn create_orthographic_camera(
+        &self,
+        image_width: usize,
+        image_height: usize,
+    ) -> Option<orthographic_camera::OrthographicCamera> {
+        let xmag = match self.projection {
+            GltfProjection::Orthographic { xmag, .. } => xmag,
+            GltfProjection::Perspective { .. } => return None,
+        };
+
+        return Some(orthographic_camera::OrthographicCamera::new(
+            self.location(),
+            self.out_direction(),
+            self.up_direction(),
+            2.0 * f32::abs(xmag),
+            image_width,
+            image_height,
+        ));
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum GltfLightKind {
+    Point,
+    Spot {
+        inner_cone_angle: f32,
+        outer_cone_angle: f32,
+    },
+    Directional,
+}
+
+// punctual lights shine down their local -z axis, color is already scaled by the intensity. Point
+// and spot intensities are in candela and fall off with the inverse square of the distance, the
+// range cuts them off
+#[derive(Clone, Debug)]
+pub struct GltfLight {
+    pub name: String,
+    pub kind: GltfLightKind,
+    pub color: vec3::Vec3,
+    pub range: Option<f32>,
+    pub light_to_world: mat4::Mat4,
+}
+
+impl GltfLight {
+    pub fn create_light(&self) -> Box<dyn light::Light> {
+        let position = (self.light_to_world * vec4::Vec4::new(0.0, 0.0, 0.0, 1.0)).to_vec3();
+        let direction = (self.light_to_world * vec4::Vec4::new(0.0, 0.0, -1.0, 0.0)).to_vec3();
+        let radius = self.range.unwrap_or(f32::INFINITY);
+
+        return match self.kind {
+            GltfLightKind::Point => Box::new(
+                point_light::PointLight::new(position, self.color, radius)
+                    .with_falloff(light::DistanceFalloff::InverseSquare),
+            ),
+            GltfLightKind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            } => Box::new(
+                spot_light::SpotLight::new(
+                    position,
+                    direction,
+                    self.color,
+                    radius,
+                    inner_cone_angle,
+                    outer_cone_angle,
+                )
+                .with_falloff(light::DistanceFalloff::InverseSquare),
+            ),
+            GltfLightKind::Directional => Box::new(directional_light::DirectionalLight::new(
+                direction, self.color,
+            )),
+        };
+    }
+}
+
+pub struct GltfScene {
+    pub meshes: Vec<GltfMesh>,
+    pub cameras: Vec<GltfCamera>,
+    pub lights: Vec<GltfLight>,
+}
+
+impl GltfScene {
+    // meshes with an emissive material are also added as area lights
+    pub fn add_to_world(&self, world: &mut world::World, light_samples: u32) {
+        for gltf_mesh in self.meshes.iter() {
+            world.add_shape(gltf_mesh.mesh.clone(), gltf_mesh.material.clone());
+            if let Some(emission) = gltf_mesh.emission {
+                world.add_light(Box::new(area_light::AreaLight::new(
+                    emission,
+                    Box::new(gltf_mesh.mesh.as_ref().clone()),
+                    light_samples,
+                )));
+            }
+        }
+
+        for gltf_light in self.lights.iter() {
+            world.add_light(gltf_light.create_light());
+        }
+    }
+}
+
+// external buffers are resolved relative to the directory of the gltf file
+pub fn read_from_file(file: &str, scene_to_world: &mat4::Mat4) -> io::Result<GltfScene> {
+    let directory = path::Path::new(file)
+        .parent()
+        .map(|parent| parent.to_path_buf())
+        .unwrap_or_default();
+    let bytes = fs::read(file)?;
+    return read_from_slice(&bytes, scene_to_world, |uri| {
+        let buffer_file = directory.join(uri);
+        return fs::read(&buffer_file)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", buffer_file.display(), e)));
+    });
+}
+
+// bytes are either a json .gltf document or a binary .glb container, load_buffer returns the
+// content of a buffer referenced by an external uri
+pub fn read_from_slice<L>(
+    bytes: &[u8],
+    scene_to_world: &mat4::Mat4,
+    mut load_buffer: L,
+) -> io::Result<GltfScene>
+where
+    L: FnMut(&str) -> io::Result<Vec<u8>>,
+{
+    let (json_bytes, binary_chunk) = if bytes.len() >= 4 && read_u32(bytes, 0) == GLB_MAGIC {
+        read_glb_chunks(bytes)?
+    } else {
+        (bytes, None)
+    };
+
+    let text = std::str::from_utf8(json_bytes).map_err(|e| invalid_data(&e.to_string()))?;
+    let document = json::parse(text).map_err(|e| invalid_data(&e))?;
+
+    let version = document
+        .get("asset")
+        .and_then(|asset| asset.get("version"))
+        .and_then(|version| version.as_str())
+        .ok_or_else(|| invalid_data("asset.version is missing"))?;
+    if !version.starts_with("2.") {
+        return Err(invalid_data(&format!(
+            "version {} is not supported",
+            version
+        )));
+    }
+
+    let mut buffers = Vec::new();
+    for (buffer_idx, buffer) in array(&document, "buffers").iter().enumerate() {
+        let data = match buffer.get("uri").and_then(|uri| uri.as_str()) {
+            Some(uri) if uri.starts_with("data:") => {
+                let encoded = uri
+                    .find(";base64,")
+                    .map(|start| &uri[start + ";base64,".len()..])
+                    .ok_or_else(|| {
+                        invalid_data(&format!("buffer {}: data uri is not base64", buffer_idx))
+                    })?;
+                decode_base64(encoded).ok_or_else(|| {
+                    invalid_data(&format!("buffer {}: invalid base64 data", buffer_idx))
+                })?
+            }
+            Some(uri) => load_buffer(uri)?,
+            None => match (buffer_idx, binary_chunk) {
+                (0, Some(binary_chunk)) => binary_chunk.to_vec(),
+                _ => {
+                    return Err(invalid_data(&format!(
+                        "buffer {} has no uri and no binary chunk",
+                        buffer_idx
+                    )))
+                }
+            },
+        };
+
+        let byte_length = usize_member(buffer, "byteLength").unwrap_or(data.len());
+        if data.len() < byte_length {
+            return Err(invalid_data(&format!(
+                "buffer {} is shorter than its byteLength",
+                buffer_idx
+            )));
+        }
+
+        buffers.push(data);
+    }
+
+    let mut importer = Importer {
+        document: &document,
+        buffers,
+        materials: HashMap::new(),
+        scene: GltfScene {
+            meshes: Vec::new(),
+            cameras: Vec::new(),
+            lights: Vec::new(),
+        },
+    };
+
+    let nodes = array(&document, "nodes");
+    let scenes = array(&document, "scenes");
+    let roots: Vec<usize> = if scenes.is_empty() {
+        // without scenes every node that is not a child of another node is a root
+        let children: Vec<usize> = nodes
+            .iter()
+            .flat_map(|node| indices_member(node, "children"))
+            .collect();
+        (0..nodes.len())
+            .filter(|node_idx| !children.contains(node_idx))
+            .collect()
+    } else {
+        let scene_idx = usize_member(&document, "scene").unwrap_or(0);
+        let scene = scenes
+            .get(scene_idx)
+            .ok_or_else(|| invalid_data(&format!("scene {} does not exist", scene_idx)))?;
+        indices_member(scene, "nodes")
+    };
+
+    let mut visited = vec![false; nodes.len()];
+    for root_idx in roots.into_iter() {
+        importer.read_node(root_idx, scene_to_world, &mut visited)?;
+    }
+
+    return Ok(importer.scene);
+}
+
+struct Importer<'a> {
+    document: &'a json::JsonValue,
+    buffers: Vec<Vec<u8>>,
+    materials: HashMap<usize, (rc::Rc<dyn material::Material>, Option<vec3::Vec3>)>,
+    scene: GltfScene,
+}
+
+impl<'a> Importer<'a> {
+    fn read_node(
+        &mut self,
+        node_idx: usize,
+        parent_to_world: &mat4::Mat4,
+        visited: &mut Vec<bool>,
+    ) -> io::Result<()> {
+        let node = array(self.document, "nodes")
+            .get(node_idx)
+            .ok_or_else(|| invalid_data(&format!("node {} does not exist", node_idx)))?;
+        if visited[node_idx] {
+            return Err(invalid_data(&format!(
+                "node {} appears more than once in the hierarchy",
+                node_idx
+            )));
+        }
+
+        visited[node_idx] = true;
+
+        let node_to_world = parent_to_world * node_transform(node_idx, node)?;
+        let name = string_member(node, "name").unwrap_or_else(|| format!("node {}", node_idx));
+
+        if let Some(mesh_idx) = usize_member(node, "mesh") {
+            self.read_mesh(mesh_idx, &name, &node_to_world)?;
+        }
+
+        if let Some(camera_idx) = usize_member(node, "camera") {
+            let camera = self.read_camera(camera_idx, &name, &node_to_world)?;
+            self.scene.cameras.push(camera);
+        }
+
+        let light_idx = node
+            .get("extensions")
+            .and_then(|extensions| extensions.get("KHR_lights_punctual"))
+            .and_then(|punctual| usize_member(punctual, "light"));
+        if let Some(light_idx) = light_idx {
+            let light = self.read_light(light_idx, &name, &node_to_world)?;
+            self.scene.lights.push(light);
+        }
+
+        for child_idx in indices_member(node, "children").into_iter() {
+            self.read_node(child_idx, &node_to_world, visited)?;
+        }
+
+        return Ok(());
+    }
+
+    fn read_mesh(
+        &mut self,
+        mesh_idx: usize,
+        node_name: &str,
+        mesh_to_world: &mat4::Mat4,
+    ) -> io::Result<()> {
+        let mesh = array(self.document, "meshes")
+            .get(mesh_idx)
+            .ok_or_else(|| invalid_data(&format!("mesh {} does not exist", mesh_idx)))?;
+        let name = string_member(mesh, "name").unwrap_or_else(|| node_name.to_string());
+
+        for (primitive_idx, primitive) in array(mesh, "primitives").iter().enumerate() {
+            let invalid_primitive = |message: &str| {
+                invalid_data(&format!(
+                    "mesh {} primitive {}: {}",
+                    mesh_idx, primitive_idx, message
+                ))
+            };
+
+            let mode = usize_member(primitive, "mode").unwrap_or(MODE_TRIANGLES);
+            if ![MODE_TRIANGLES, MODE_TRIANGLE_STRIP, MODE_TRIANGLE_FAN].contains(&mode) {
+                // points and lines have no surface to render
+                continue;
+            }
+
+            let attributes = primitive
+                .get("attributes")
+                .ok_or_else(|| invalid_primitive("attributes are missing"))?;
+            let position_idx = usize_member(attributes, "POSITION")
+                .ok_or_else(|| invalid_primitive("POSITION is missing"))?;
+            let positions = to_vec3s(&self.read_accessor(position_idx, &[3])?);
+
+            let normals = match usize_member(attributes, "NORMAL") {
+                Some(accessor_idx) => to_vec3s(&self.read_accessor(accessor_idx, &[3])?),
+                None => Vec::new(),
+            };
+            // texture coordinates start at the top left, they are flipped to start at the bottom left
+            let uvs = match usize_member(attributes, "TEXCOORD_0") {
+                Some(accessor_idx) => self
+                    .read_accessor(accessor_idx, &[2])?
+                    .chunks(2)
+                    .map(|uv| vec2::Vec2::new(uv[0] as f32, 1.0 - uv[1] as f32))
+                    .collect(),
+                None => Vec::new(),
+            };
+            let colors = match usize_member(attributes, "COLOR_0") {
+                Some(accessor_idx) => {
+                    let components = self.accessor_components(accessor_idx)?;
+                    self.read_accessor(accessor_idx, &[3, 4])?
+                        .chunks(components)
+                        .map(|rgb| vec3::Vec3::new(rgb[0] as f32, rgb[1] as f32, rgb[2] as f32))
+                        .collect()
+                }
+                None => Vec::new(),
+            };
+
+            let attribute_counts = [
+                ("NORMAL", normals.len()),
+                ("TEXCOORD_0", uvs.len()),
+                ("COLOR_0", colors.len()),
+            ];
+            for (attribute, count) in attribute_counts.iter() {
+                if *count != 0 && *count != positions.len() {
+                    return Err(invalid_primitive(&format!(
+                        "{} does not have one value per vertex",
+                        attribute
+                    )));
+                }
+            }
+
+            let vertex_indices: Vec<usize> = match usize_member(primitive, "indices") {
+                Some(accessor_idx) => self
+                    .read_accessor(accessor_idx, &[1])?
+                    .into_iter()
+                    .map(|index| index as usize)
+                    .collect(),
+                None => (0..positions.len()).collect(),
+            };
+            if let Some(index) = vertex_indices
+                .iter()
+                .find(|index| **index >= positions.len())
+            {
+                return Err(invalid_primitive(&format!(
+                    "vertex index {} is out of range",
+                    index
+                )));
+            }
+
+            let indices = triangulate(mode, &vertex_indices);
+            if indices.is_empty() {
+                continue;
+            }
+
+            let (material, emission) = match usize_member(primitive, "material") {
+                Some(material_idx) => self.read_material(material_idx)?,
+                None => (
+                    rc::Rc::new(matte::Matte::new(vec3::Vec3::from(0.5), 0.0))
+                        as rc::Rc<dyn material::Material>,
+                    None,
+                ),
+            };
+
+            self.scene.meshes.push(GltfMesh {
+                name: name.clone(),
+                mesh: rc::Rc::new(triangle_mesh::TriangleMesh::new_with_colors(
+                    *mesh_to_world,
+                    indices,
+                    positions,
+                    normals,
+                    uvs,
+                    colors,
+                )),
+                material,
+                emission,
+            });
+        }
+
+        return Ok(());
+    }
+
+    fn read_camera(
+        &self,
+        camera_idx: usize,
+        node_name: &str,
+        camera_to_world: &mat4::Mat4,
+    ) -> io::Result<GltfCamera> {
+        let camera = array(self.document, "cameras")
+            .get(camera_idx)
+            .ok_or_else(|| invalid_data(&format!("camera {} does not exist", camera_idx)))?;
+        let invalid_camera =
+            |message: &str| invalid_data(&format!("camera {}: {}", camera_idx, message));
+
+        let projection = match camera.get("type").and_then(|kind| kind.as_str()) {
+            Some("perspective") => {
+                let yfov = camera
+                    .get("perspective")
+                    .and_then(|perspective| f32_member(perspective, "yfov"))
+                    .ok_or_else(|| invalid_camera("perspective.yfov is missing"))?;
+                GltfProjection::Perspective { yfov }
+            }
+            Some("orthographic") => {
+                let orthographic = camera
+                    .get("orthographic")
+                    .ok_or_else(|| invalid_camera("orthographic is missing"))?;
+                GltfProjection::Orthographic {
+                    xmag: f32_member(orthographic, "xmag")
+                        .ok_or_else(|| invalid_camera("orthographic.xmag is missing"))?,
+                    ymag: f32_member(orthographic, "ymag")
+                        .ok_or_else(|| invalid_camera("orthographic.ymag is missing"))?,
+                }
+            }
+            _ => return Err(invalid_camera("unknown camera type")),
+        };
+
+        return Ok(GltfCamera {
+            name: string_member(camera, "name").unwrap_or_else(|| node_name.to_string()),
+            camera_to_world: *camera_to_world,
+            projection,
+        });
+    }
+
+    fn read_light(
+        &self,
+        light_idx: usize,
+        node_name: &str,
+        light_to_world: &mat4::Mat4,
+    ) -> io::Result<GltfLight> {
+        let light = self
+            .document
+            .get("extensions")
+            .and_then(|extensions| extensions.get("KHR_lights_punctual"))
+            .map(|punctual| array(punctual, "lights"))
+            .and_then(|lights| lights.get(light_idx))
+            .ok_or_else(|| invalid_data(&format!("light {} does not exist", light_idx)))?;
+
+        let kind = match light.get("type").and_then(|kind| kind.as_str()) {
+            Some("point") => GltfLightKind::Point,
+            Some("directional") => GltfLightKind::Directional,
+            Some("spot") => {
+                let spot = light.get("spot");
+                GltfLightKind::Spot {
+                    inner_cone_angle: spot
+                        .and_then(|spot| f32_member(spot, "innerConeAngle"))
+                        .unwrap_or(0.0),
+                    outer_cone_angle: spot
+                        .and_then(|spot| f32_member(spot, "outerConeAngle"))
+                        .unwrap_or(std::f32::consts::FRAC_PI_4),
+                }
+            }
+            _ => {
+                return Err(invalid_data(&format!(
+                    "light {}: unknown light type",
+                    light_idx
+                )))
+            }
+        };
+
+        let color = vec3_member(light, "color").unwrap_or_else(|| vec3::Vec3::from(1.0));
+        let intensity = f32_member(light, "intensity").unwrap_or(1.0);
+        return Ok(GltfLight {
+            name: string_member(light, "name").unwrap_or_else(|| node_name.to_string()),
+            kind,
+            color: color * intensity,
+            range: f32_member(light, "range"),
+            light_to_world: *light_to_world,
+        });
+    }
+
+    // only the factors of a material are used, textures are not supported
+    fn read_material(
+        &mut self,
+        material_idx: usize,
+    ) -> io::Result<(rc::Rc<dyn material::Material>, Option<vec3::Vec3>)> {
+        if let Some(created) = self.materials.get(&material_idx) {
+            return Ok(created.clone());
+        }
+
+        let gltf_material = array(self.document, "materials")
+            .get(material_idx)
+            .ok_or_else(|| invalid_data(&format!("material {} does not exist", material_idx)))?;
+
+        let pbr = gltf_material.get("pbrMetallicRoughness");
+        let base_color = pbr
+            .and_then(|pbr| vec3_member(pbr, "baseColorFactor"))
+            .unwrap_or_else(|| vec3::Vec3::from(1.0));
+        let metallic = pbr
+            .and_then(|pbr| f32_member(pbr, "metallicFactor"))
+            .unwrap_or(1.0);
+        let roughness = pbr
+            .and_then(|pbr| f32_member(pbr, "roughnessFactor"))
+            .unwrap_or(1.0);
+
+        let extensions = gltf_material.get("extensions");
+        let extension_factor = |extension: &str, factor: &str| {
+            extensions
+                .and_then(|extensions| extensions.get(extension))
+                .and_then(|extension| f32_member(extension, factor))
+        };
+        let transmission =
+            extension_factor("KHR_materials_transmission", "transmissionFactor").unwrap_or(0.0);
+        let ior = extension_factor("KHR_materials_ior", "ior").unwrap_or(1.5);
+        let emissive_strength =
+            extension_factor("KHR_materials_emissive_strength", "emissiveStrength").unwrap_or(1.0);
+
+        let material: rc::Rc<dyn material::Material> = if transmission > 0.0 {
+            rc::Rc::new(glass::Glass::new(
+                base_color * transmission,
+                vec3::Vec3::from(1.0),
+                1.0,
+                ior,
+            ))
+        } else if metallic >= METALLIC_THRESHOLD {
+            // the roughness factor is perceptual, the microfacet distribution expects its square
+            rc::Rc::new(metal::Metal::new(
+                base_color,
+                vec3::Vec3::from(METAL_ETA),
+                vec3::Vec3::from(METAL_K),
+                roughness * roughness,
+            ))
+        } else {
+            rc::Rc::new(matte::Matte::new(base_color, 0.0))
+        };
+
+        let emission = vec3_member(gltf_material, "emissiveFactor")
+            .map(|emissive| emissive * emissive_strength)
+            .filter(|emission| emission.x > 0.0 || emission.y > 0.0 || emission.z > 0.0);
+
+        self.materials
+            .insert(material_idx, (material.clone(), emission));
+        return Ok((material, emission));
+    }
+
+    fn accessor_components(&self, accessor_idx: usize) -> io::Result<usize> {
+        let accessor = array(self.document, "accessors")
+            .get(accessor_idx)
+            .ok_or_else(|| invalid_data(&format!("accessor {} does not exist", accessor_idx)))?;
+        return match accessor.get("type").and_then(|kind| kind.as_str()) {
+            Some("SCALAR") => Ok(1),
+            Some("VEC2") => Ok(2),
+            Some("VEC3") => Ok(3),
+            Some("VEC4") => Ok(4),
+            _ => Err(invalid_data(&format!(
+                "accessor {}: unsupported type",
+                accessor_idx
+            ))),
+        };
+    }
+
+    // reads every component of an accessor, normalized integers are mapped to [0, 1] or [-1, 1]
+    fn read_accessor(
+        &self,
+        accessor_idx: usize,
+        allowed_components: &[usize],
+    ) -> io::Result<Vec<f64>> {
+        let invalid_accessor =
+            |message: &str| invalid_data(&format!("accessor {}: {}", accessor_idx, message));
+
+        let accessor = &array(self.document, "accessors")[accessor_idx];
+        let components = self.accessor_components(accessor_idx)?;
+        if !allowed_components.contains(&components) {
+            return Err(invalid_accessor(&format!(
+                "{} components are not expected here",
+                components
+            )));
+        }
+
+        if accessor.get("sparse").is_some() {
+            return Err(invalid_accessor("sparse accessors are not supported"));
+        }
+
+        let count =
+            usize_member(accessor, "count").ok_or_else(|| invalid_accessor("count is missing"))?;
+        let component_type = usize_member(accessor, "componentType")
+            .ok_or_else(|| invalid_accessor("componentType is missing"))?;
+        let component_size = match component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            _ => return Err(invalid_accessor("unknown componentType")),
+        };
+        let normalized = accessor
+            .get("normalized")
+            .and_then(|normalized| normalized.as_bool())
+            .unwrap_or(false);
+
+        let buffer_view_idx = match usize_member(accessor, "bufferView") {
+            Some(buffer_view_idx) => buffer_view_idx,
+            // accessors without a buffer view are filled with zeros
+            None => return Ok(vec![0.0; count * components]),
+        };
+        let buffer_view = array(self.document, "bufferViews")
+            .get(buffer_view_idx)
+            .ok_or_else(|| invalid_accessor("bufferView does not exist"))?;
+        let buffer = usize_member(buffer_view, "buffer")
+            .and_then(|buffer_idx| self.buffers.get(buffer_idx))
+            .ok_or_else(|| invalid_accessor("buffer does not exist"))?;
+
+        let element_size = components * component_size;
+        let stride = usize_member(buffer_view, "byteStride").unwrap_or(element_size);
+        let view_offset = usize_member(buffer_view, "byteOffset").unwrap_or(0);
+        let view_length = usize_member(buffer_view, "byteLength")
+            .ok_or_else(|| invalid_accessor("bufferView.byteLength is missing"))?;
+        let accessor_offset = usize_member(accessor, "byteOffset").unwrap_or(0);
+
+        let view_end = view_offset + view_length;
+        let accessor_end = if count == 0 {
+            0
+        } else {
+            accessor_offset + stride * (count - 1) + element_size
+        };
+        if view_end > buffer.len() || accessor_end > view_length {
+            return Err(invalid_accessor("data is out of the bounds of its buffer"));
+        }
+
+        let data = &buffer[view_offset..view_end];
+        let mut values = Vec::with_capacity(count * components);
+        for element_idx in 0..count {
+            for component_idx in 0..components {
+                let offset =
+                    accessor_offset + element_idx * stride + component_idx * component_size;
+                let value = match component_type {
+                    5120 => {
+                        let value = data[offset] as i8 as f64;
+                        if normalized {
+                            f64::max(value / i8::MAX as f64, -1.0)
+                        } else {
+                            value
+                        }
+                    }
+                    5121 => {
+                        let value = data[offset] as f64;
+                        if normalized {
+                            value / u8::MAX as f64
+                        } else {
+                            value
+                        }
+                    }
+                    5122 => {
+                        let value = i16::from_le_bytes([data[offset], data[offset + 1]]) as f64;
+                        if normalized {
+                            f64::max(value / i16::MAX as f64, -1.0)
+                        } else {
+                            value
+                        }
+                    }
+                    5123 => {
+                        let value = u16::from_le_bytes([data[offset], data[offset + 1]]) as f64;
+                        if normalized {
+                            value / u16::MAX as f64
+                        } else {
+                            value
+                        }
+                    }
+                    5125 => read_u32(data, offset) as f64,
+                    _ => f32::from_bits(read_u32(data, offset)) as f64,
+                };
+                values.push(value);
+            }
+        }
+
+        return Ok(values);
+    }
+}
+
+// a glb file is a header followed by a json chunk and an optional binary chunk
+fn read_glb_chunks(bytes: &[u8]) -> io::Result<(&[u8], Option<&[u8]>)> {
+    if bytes.len() < 12 {
+        return Err(invalid_data("glb header is truncated"));
+    }
+
+    let version = read_u32(bytes, 4);
+    if version != 2 {
+        return Err(invalid_data(&format!(
+            "glb version {} is not supported",
+            version
+        )));
+    }
+
+    let length = usize::min(read_u32(bytes, 8) as usize, bytes.len());
+    let mut json_chunk = None;
+    let mut binary_chunk = None;
+    let mut offset = 12;
+    while offset + 8 <= length {
+        let chunk_length = read_u32(bytes, offset) as usize;
+        let chunk_type = read_u32(bytes, offset + 4);
+        let chunk_start = offset + 8;
+        let chunk_end = chunk_start + chunk_length;
+        if chunk_end > length {
+            return Err(invalid_data("glb chunk is truncated"));
+        }
+
+        let chunk = &bytes[chunk_start..chunk_end];
+        match chunk_type {
+            GLB_CHUNK_JSON if json_chunk.is_none() => json_chunk = Some(chunk),
+            GLB_CHUNK_BIN if binary_chunk.is_none() => binary_chunk = Some(chunk),
+            _ => {}
+        }
+
+        offset = chunk_end;
+    }
+
+    let json_chunk = json_chunk.ok_or_else(|| invalid_data("glb has no json chunk"))?;
+    return Ok((json_chunk, binary_chunk));
+}
+
+// a node has either a matrix or a translation, rotation and scale applied in reverse order
+fn node_transform(node_idx: usize, node: &json::JsonValue) -> io::Result<mat4::Mat4> {
+    let invalid_node = |message: &str| invalid_data(&format!("node {}: {}", node_idx, message));
+
+    if let Some(matrix) = node.get("matrix") {
+        let m = matrix
+            .as_f32_array()
+            .filter(|m| m.len() == 16)
+            .ok_or_else(|| invalid_node("matrix needs 16 numbers"))?;
+        return Ok(mat4::Mat4::from_scalars(
+            m[0], m[1], m[2], m[3], m[4], m[5], m[6], m[7], m[8], m[9], m[10], m[11], m[12], m[13],
+            m[14], m[15],
+        ));
+    }
+
+    let translation = vec3_member(node, "translation").unwrap_or_else(|| vec3::Vec3::from(0.0));
+    let scale = vec3_member(node, "scale").unwrap_or_else(|| vec3::Vec3::from(1.0));
+    let rotation = match node.get("rotation") {
+        Some(rotation) => {
+            let q = rotation
+                .as_f32_array()
+                .filter(|q| q.len() == 4)
+                .ok_or_else(|| invalid_node("rotation needs 4 numbers"))?;
+            quaternion::Quaternion::new(q[0], q[1], q[2], q[3])
+        }
+        None => quaternion::Quaternion::identity(),
+    };
+
+    return Ok(mat4::Mat4::new().translate(&translation)
+        * rotation.to_mat4()
+        * mat4::Mat4::new().scale(&scale));
+}
+
+// strips and fans are converted to a list of triangles, strips alternate their winding
+fn triangulate(mode: usize, vertex_indices: &[usize]) -> Vec<usize> {
+    if mode == MODE_TRIANGLES {
+        let triangle_count = vertex_indices.len() / 3;
+        return vertex_indices[..triangle_count * 3].to_vec();
+    }
+
+    let mut indices = Vec::new();
+    for i in 2..vertex_indices.len() {
+        let triangle = if mode == MODE_TRIANGLE_FAN {
+            [vertex_indices[0], vertex_indices[i - 1], vertex_indices[i]]
+        } else if i % 2 == 0 {
+            [
+                vertex_indices[i - 2],
+                vertex_indices[i - 1],
+                vertex_indices[i],
+            ]
+        } else {
+            [
+                vertex_indices[i - 1],
+                vertex_indices[i - 2],
+                vertex_indices[i],
+            ]
+        };
+        indices.extend_from_slice(&triangle);
+    }
+
+    return indices;
+}
+
+fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(encoded.len() * 3 / 4);
+    let mut bits: u32 = 0;
+    let mut bit_count = 0;
+    for c in encoded.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            _ => return None,
+        };
+
+        bits = (bits << 6) | value as u32;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            bytes.push((bits >> bit_count) as u8);
+            bits &= (1 << bit_count) - 1;
+        }
+    }
+
+    return Some(bytes);
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    return u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ]);
+}
+
+fn to_vec3s(values: &[f64]) -> Vec<vec3::Vec3> {
+    return values
+        .chunks(3)
+        .map(|v| vec3::Vec3::new(v[0] as f32, v[1] as f32, v[2] as f32))
+        .collect();
+}
+
+fn array<'a>(value: &'a json::JsonValue, key: &str) -> &'a [json::JsonValue] {
+    return value
+        .get(key)
+        .and_then(|member| member.as_array())
+        .map(|values| &values[..])
+        .unwrap_or(&[]);
+}
+
+fn usize_member(value: &json::JsonValue, key: &str) -> Option<usize> {
+    return value.get(key).and_then(|member| member.as_usize());
+}
+
+fn f32_member(value: &json::JsonValue, key: &str) -> Option<f32> {
+    return value.get(key).and_then(|member| member.as_f32());
+}
+
+fn string_member(value: &json::JsonValue, key: &str) -> Option<String> {
+    return value
+        .get(key)
+        .and_then(|member| member.as_str())
+        .map(|member| member.to_string());
+}
+
+fn indices_member(value: &json::JsonValue, key: &str) -> Vec<usize> {
+    return array(value, key)
+        .iter()
+        .filter_map(|index| index.as_usize())
+        .collect();
+}
+
+// colors with an alpha channel keep their rgb part
+fn vec3_member(value: &json::JsonValue, key: &str) -> Option<vec3::Vec3> {
+    return value
+        .get(key)
+        .and_then(|member| member.as_f32_array())
+        .filter(|v| v.len() >= 3)
+        .map(|v| vec3::Vec3::new(v[0], v[1], v[2]));
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    return io::Error::new(io::ErrorKind::InvalidData, format!("gltf: {}", message));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+    use crate::scene::camera::Camera;
+    use crate::scene::ray;
+    use crate::scene::shape::IntersectableShape;
+
+    fn no_buffer(uri: &str) -> io::Result<Vec<u8>> {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} not found", uri),
+        ));
+    }
+
+    fn encode_base64(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut encoded = String::new();
+        for chunk in bytes.chunks(3) {
+            let b = [
+                chunk[0],
+                *chunk.get(1).unwrap_or(&0),
+                *chunk.get(2).unwrap_or(&0),
+            ];
+            let bits = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    encoded.push(ALPHABET[((bits >> (18 - 6 * i)) & 63) as usize] as char);
+                } else {
+                    encoded.push('=');
+                }
+            }
+        }
+
+        return encoded;
+    }
+
+    // a unit quad in the xy plane, 4 float positions followed by 6 u16 indices
+    fn quad_buffer() -> Vec<u8> {
+        let mut buffer = Vec::new();
+        for position in [
+            [-1.0f32, -1.0, 0.0],
+            [1.0, -1.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [-1.0, 1.0, 0.0],
+        ]
+        .iter()
+        {
+            for value in position.iter() {
+                buffer.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        for index in [0u16, 1, 2, 0, 2, 3].iter() {
+            buffer.extend_from_slice(&index.to_le_bytes());
+        }
+
+        return buffer;
+    }
+
+    fn quad_document(buffer: &str) -> String {
+        return format!(
+            "{{\"asset\": {{\"version\": \"2.0\"}},\n\
+             \"scene\": 0, \"scenes\": [{{\"nodes\": [0, 2, 3]}}],\n\
+             \"nodes\": [\n\
+               {{\"name\": \"parent\", \"translation\": [0, 0, -5], \"children\": [1]}},\n\
+               {{\"mesh\": 0, \"rotation\": [0, 0.7071068, 0, 0.7071068], \"scale\": [2, 2, 2]}},\n\
+               {{\"camera\": 0, \"translation\": [0, 0, 10]}},\n\
+               {{\"extensions\": {{\"KHR_lights_punctual\": {{\"light\": 0}}}}}}\n\
+             ],\n\
+             \"meshes\": [{{\"name\": \"quad\", \"primitives\": [{{\"attributes\": {{\"POSITION\": 0}}, \"indices\": 1, \"material\": 0}}]}}],\n\
+             \"materials\": [{{\"pbrMetallicRoughness\": {{\"baseColorFactor\": [0.8, 0.2, 0.1, 1], \"metallicFactor\": 0}},\n\
+                              \"emissiveFactor\": [1, 1, 1]}}],\n\
+             \"cameras\": [{{\"type\": \"perspective\", \"perspective\": {{\"yfov\": 0.8, \"znear\": 0.1}}}}],\n\
+             \"extensions\": {{\"KHR_lights_punctual\": {{\"lights\": [{{\"type\": \"spot\", \"intensity\": 2, \"spot\": {{\"outerConeAngle\": 0.5}}}}]}}}},\n\
+             \"accessors\": [\n\
+               {{\"bufferView\": 0, \"componentType\": 5126, \"count\": 4, \"type\": \"VEC3\"}},\n\
+               {{\"bufferView\": 1, \"componentType\": 5123, \"count\": 6, \"type\": \"SCALAR\"}}\n\
+             ],\n\
+             \"bufferViews\": [\n\
+               {{\"buffer\": 0, \"byteOffset\": 0, \"byteLength\": 48}},\n\
+               {{\"buffer\": 0, \"byteOffset\": 48, \"byteLength\": 12}}\n\
+             ],\n\
+             \"buffers\": [{{{}\"byteLength\": 60}}]}}",
+            buffer
+        );
+    }
+
+    fn check_quad_scene(scene: &GltfScene) {
+        assert_eq!(scene.meshes.len(), 1);
+        let gltf_mesh = &scene.meshes[0];
+        assert_eq!(gltf_mesh.name, "quad");
+        assert_eq!(gltf_mesh.mesh.triangles().len(), 2);
+        assert!(math::equal_epsilon_f32(
+            gltf_mesh.mesh.area(),
+            16.0,
+            math::EPSILON_F32_4
+        ));
+        assert!(gltf_mesh
+            .emission
+            .unwrap()
+            .equal_epsilon(&vec3::Vec3::from(1.0), math::EPSILON_F32_5));
+
+        // the quad is rotated to face +x and moved to z = -5
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(10.0, 1.0, -4.0),
+            vec3::Vec3::new(-1.0, 0.0, 0.0),
+        );
+        let surface = gltf_mesh.mesh.intersect_ray(&ray).unwrap();
+        assert!(surface
+            .calc_world_position()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 1.0, -4.0), math::EPSILON_F32_4));
+
+        assert_eq!(scene.cameras.len(), 1);
+        let camera = &scene.cameras[0];
+        assert!(camera
+            .location()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 0.0, 10.0), math::EPSILON_F32_5));
+        assert!(camera
+            .out_direction()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 0.0, -1.0), math::EPSILON_F32_5));
+        assert!(camera.create_orthographic_camera(64, 32).is_none());
+        let perspective_camera = camera.create_perspective_camera(64, 32).unwrap();
+        assert!(!perspective_camera.has_channel_rays());
+
+        assert_eq!(scene.lights.len(), 1);
+        let light = &scene.lights[0];
+        assert!(light
+            .color
+            .equal_epsilon(&vec3::Vec3::from(2.0), math::EPSILON_F32_5));
+        match light.kind {
+            GltfLightKind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            } => {
+                assert!(math::equal_epsilon_f32(
+                    inner_cone_angle,
+                    0.0,
+                    math::EPSILON_F32_6
+                ));
+                assert!(math::equal_epsilon_f32(
+                    outer_cone_angle,
+                    0.5,
+                    math::EPSILON_F32_6
+                ));
+            }
+            _ => panic!("expected a spot light"),
+        }
+
+        let mut world = world::World::new();
+        scene.add_to_world(&mut world, 1);
+        assert_eq!(world.lights().len(), 2);
+    }
+
+    #[test]
+    fn test_light_falloff() {
+        let light_at = |kind: GltfLightKind, range: Option<f32>| {
+            GltfLight {
+                name: String::from("light"),
+                kind,
+                color: vec3::Vec3::from(4.0),
+                range,
+                light_to_world: mat4::Mat4::new(),
+            }
+            .create_light()
+        };
+        let spot = GltfLightKind::Spot {
+            inner_cone_angle: 0.5,
+            outer_cone_angle: 1.0,
+        };
+        let world = world::World::new();
+        let normal = vec3::Vec3::new(0.0, 0.0, 1.0);
+        let sample = vec2::Vec2::from(0.0);
+
+        // 4 candela at one and two meters below the light
+        for kind in [GltfLightKind::Point, spot].iter() {
+            let light = light_at(*kind, None);
+            for (distance, li) in [(1.0f32, 4.0), (2.0, 1.0)].iter() {
+                let radiance = light
+                    .sample_li_no_shadow_check(
+                        &sample,
+                        &world,
+                        &vec3::Vec3::new(0.0, 0.0, -distance),
+                        &normal,
+                    )
+                    .unwrap();
+                assert!(math::equal_epsilon_f32(
+                    radiance.li.x,
+                    *li,
+                    math::EPSILON_F32_5
+                ));
+            }
+
+            let light = light_at(*kind, Some(1.5));
+            let radiance = light
+                .sample_li_no_shadow_check(
+                    &sample,
+                    &world,
+                    &vec3::Vec3::new(0.0, 0.0, -2.0),
+                    &normal,
+                )
+                .unwrap();
+            assert_eq!(radiance.li.x, 0.0);
+        }
+    }
+
+    #[test]
+    fn test_read_gltf_with_data_uri() {
+        let uri = format!(
+            "\"uri\": \"data:application/octet-stream;base64,{}\", ",
+            encode_base64(&quad_buffer())
+        );
+        let document = quad_document(&uri);
+        let scene = read_from_slice(document.as_bytes(), &mat4::Mat4::new(), no_buffer).unwrap();
+        check_quad_scene(&scene);
+    }
+
+    #[test]
+    fn test_read_gltf_with_external_buffer() {
+        let document = quad_document("\"uri\": \"quad.bin\", ");
+        let scene = read_from_slice(document.as_bytes(), &mat4::Mat4::new(), |uri| {
+            assert_eq!(uri, "quad.bin");
+            return Ok(quad_buffer());
+        })
+        .unwrap();
+        check_quad_scene(&scene);
+    }
+
+    #[test]
+    fn test_read_glb() {
+        let mut document = quad_document("").into_bytes();
+        while document.len() % 4 != 0 {
+            document.push(b' ');
+        }
+        let buffer = quad_buffer();
+
+        let mut glb = Vec::new();
+        let length = 12 + 8 + document.len() + 8 + buffer.len();
+        glb.extend_from_slice(&GLB_MAGIC.to_le_bytes());
+        glb.extend_from_slice(&2u32.to_le_bytes());
+        glb.extend_from_slice(&(length as u32).to_le_bytes());
+        glb.extend_from_slice(&(document.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&GLB_CHUNK_JSON.to_le_bytes());
+        glb.extend_from_slice(&document);
+        glb.extend_from_slice(&(buffer.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&GLB_CHUNK_BIN.to_le_bytes());
+        glb.extend_from_slice(&buffer);
+
+        let scene = read_from_slice(&glb, &mat4::Mat4::new(), no_buffer).unwrap();
+        check_quad_scene(&scene);
+    }
+
+    #[test]
+    fn test_materials() {
+        let document = format!(
+            "{{\"asset\": {{\"version\": \"2.0\"}},\n\
+             \"nodes\": [{{\"mesh\": 0}}],\n\
+             \"meshes\": [{{\"primitives\": [\n\
+               {{\"attributes\": {{\"POSITION\": 0}}, \"material\": 0}},\n\
+               {{\"attributes\": {{\"POSITION\": 0}}, \"material\": 1}},\n\
+               {{\"attributes\": {{\"POSITION\": 0}}, \"material\": 0, \"mode\": 1}}\n\
+             ]}}],\n\
+             \"materials\": [{{}}, {{\"extensions\": {{\"KHR_materials_transmission\": {{\"transmissionFactor\": 1}}}}}}],\n\
+             \"accessors\": [{{\"bufferView\": 0, \"componentType\": 5126, \"count\": 3, \"type\": \"VEC3\"}}],\n\
+             \"bufferViews\": [{{\"buffer\": 0, \"byteLength\": 36}}],\n\
+             \"buffers\": [{{\"uri\": \"data:application/octet-stream;base64,{}\", \"byteLength\": 60}}]}}",
+            encode_base64(&quad_buffer())
+        );
+        let scene = read_from_slice(document.as_bytes(), &mat4::Mat4::new(), no_buffer).unwrap();
+        // lines are skipped, without scenes the nodes that are not children are the roots
+        assert_eq!(scene.meshes.len(), 2);
+        assert!(scene
+            .meshes
+            .iter()
+            .all(|gltf_mesh| gltf_mesh.emission.is_none()));
+        assert!(scene.cameras.is_empty());
+    }
+
+    #[test]
+    fn test_triangulate() {
+        assert_eq!(triangulate(MODE_TRIANGLES, &[0, 1, 2, 3]), vec![0, 1, 2]);
+        assert_eq!(
+            triangulate(MODE_TRIANGLE_STRIP, &[0, 1, 2, 3]),
+            vec![0, 1, 2, 2, 1, 3]
+        );
+        assert_eq!(
+            triangulate(MODE_TRIANGLE_FAN, &[0, 1, 2, 3]),
+            vec![0, 1, 2, 0, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        assert!(read_from_slice(b"{", &mat4::Mat4::new(), no_buffer).is_err());
+        assert!(read_from_slice(
+            b"{\"asset\": {\"version\": \"1.0\"}}",
+            &mat4::Mat4::new(),
+            no_buffer
+        )
+        .is_err());
+
+        let document = quad_document("\"uri\": \"missing.bin\", ");
+        let error = read_from_slice(document.as_bytes(), &mat4::Mat4::new(), no_buffer)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("missing.bin"));
+
+        let error = read_from_slice(document.as_bytes(), &mat4::Mat4::new(), |_| {
+            return Ok(quad_buffer()[..40].to_vec());
+        })
+        .err()
+        .unwrap();
+        assert!(error.to_string().contains("byteLength"));
+    }
+}
@@ -0,0 +1,329 @@
+use crate::core::mat4;
+use crate::scene::shape::heightfield;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::io::Read;
+
+// reads elevation rasters stored as pgm (P2 or P5, 8 or 16 bits) or pfm (Pf or PF) images. Pgm
+// values are divided by their maximum so the heights lie in [0, 1], pfm values are used as they are
+// and color pfm images take the mean of their channels. The top row of the image is the far end of
+// the unit square at y = 1, the height scale and the extent come from the transform
+pub fn read_from_file(
+    file: &str,
+    object_to_world: &mat4::Mat4,
+) -> io::Result<heightfield::Heightfield> {
+    let mut reader = BufReader::new(File::open(file)?);
+    return read_from_reader(&mut reader, object_to_world);
+}
+
+pub fn read_from_reader(
+    reader: &mut impl io::BufRead,
+    object_to_world: &mat4::Mat4,
+) -> io::Result<heightfield::Heightfield> {
+    let magic = read_header_token(reader)?;
+    let width = parse_header_value::<usize>(&read_header_token(reader)?, "width")?;
+    let height = parse_header_value::<usize>(&read_header_token(reader)?, "height")?;
+    if width < 2 || height < 2 {
+        return Err(invalid_data(
+            "heightmap needs at least two samples along each axis",
+        ));
+    }
+
+    let sample_count = width
+        .checked_mul(height)
+        .ok_or_else(|| invalid_data("heightmap size overflows"))?;
+
+    // rows as they are stored, pgm files store them from the top and pfm files from the bottom
+    let (rows, from_top): (Vec<f32>, bool) = match magic.as_str() {
+        "P2" | "P5" => {
+            let max_value = parse_header_value::<u32>(&read_header_token(reader)?, "max value")?;
+            if max_value == 0 || max_value > u16::MAX as u32 {
+                return Err(invalid_data(&format!("invalid max value {}", max_value)));
+            }
+
+            let values = if magic == "P2" {
+                read_ascii_values(reader, sample_count)?
+            } else {
+                read_binary_values(reader, sample_count, max_value)?
+            };
+            let scale = 1.0 / max_value as f32;
+            (
+                values
+                    .into_iter()
+                    .map(|value| value as f32 * scale)
+                    .collect(),
+                true,
+            )
+        }
+        "Pf" | "PF" => {
+            let channels = if magic == "PF" { 3 } else { 1 };
+            // the sign of the scale gives the byte order, its magnitude is not applied
+            let scale = parse_header_value::<f32>(&read_header_token(reader)?, "scale")?;
+            let value_count = sample_count
+                .checked_mul(channels)
+                .ok_or_else(|| invalid_data("heightmap size overflows"))?;
+            let values = read_float_values(reader, value_count, scale < 0.0)?;
+            let samples = values
+                .chunks(channels)
+                .map(|channels| channels.iter().sum::<f32>() / channels.len() as f32)
+                .collect::<Vec<f32>>();
+            (samples, false)
+        }
+        _ => return Err(invalid_data(&format!("unknown magic number {}", magic))),
+    };
+
+    let heights = if from_top {
+        rows.chunks(width).rev().flatten().cloned().collect()
+    } else {
+        rows
+    };
+    return Ok(heightfield::Heightfield::new(
+        *object_to_world,
+        width,
+        height,
+        heights,
+    ));
+}
+
+// whitespace separated header tokens with # starting a comment. The single whitespace after the
+// token is consumed as well, which is where the samples of a binary image start
+fn read_header_token(reader: &mut impl io::BufRead) -> io::Result<String> {
+    let mut token = String::new();
+    let mut in_comment = false;
+    loop {
+        let mut byte = [0u8; 1];
+        if reader.read(&mut byte)? == 0 {
+            if token.is_empty() {
+                return Err(invalid_data("unexpected end of header"));
+            }
+
+            return Ok(token);
+        }
+
+        let c = byte[0] as char;
+        if in_comment {
+            in_comment = c != '\n';
+        } else if c.is_ascii_whitespace() {
+            if !token.is_empty() {
+                return Ok(token);
+            }
+        } else if c == '#' && token.is_empty() {
+            in_comment = true;
+        } else {
+            token.push(c);
+        }
+    }
+}
+
+fn parse_header_value<T: std::str::FromStr>(token: &str, name: &str) -> io::Result<T> {
+    return token
+        .parse::<T>()
+        .map_err(|_| invalid_data(&format!("invalid {} \"{}\"", name, token)));
+}
+
+fn read_ascii_values(reader: &mut impl io::BufRead, count: usize) -> io::Result<Vec<u32>> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let values = text
+        .split_whitespace()
+        .take(count)
+        .map(|token| parse_header_value::<u32>(token, "sample"))
+        .collect::<io::Result<Vec<u32>>>()?;
+    if values.len() != count {
+        return Err(invalid_data("heightmap has fewer samples than its size"));
+    }
+
+    return Ok(values);
+}
+
+// samples with a max value above 255 take two bytes, most significant first
+fn read_binary_values(
+    reader: &mut impl io::BufRead,
+    count: usize,
+    max_value: u32,
+) -> io::Result<Vec<u32>> {
+    let sample_size = if max_value > u8::MAX as u32 { 2 } else { 1 };
+    let bytes = read_sample_bytes(reader, count, sample_size)?;
+    return Ok(bytes
+        .chunks(sample_size)
+        .map(|sample| {
+            sample
+                .iter()
+                .fold(0, |value, byte| (value << 8) | *byte as u32)
+        })
+        .collect());
+}
+
+fn read_float_values(
+    reader: &mut impl io::BufRead,
+    count: usize,
+    little_endian: bool,
+) -> io::Result<Vec<f32>> {
+    let bytes = read_sample_bytes(reader, count, 4)?;
+    return Ok(bytes
+        .chunks(4)
+        .map(|sample| {
+            let sample = [sample[0], sample[1], sample[2], sample[3]];
+            if little_endian {
+                f32::from_le_bytes(sample)
+            } else {
+                f32::from_be_bytes(sample)
+            }
+        })
+        .collect());
+}
+
+// the size comes from the header, so the buffer only grows with the bytes the reader actually has
+fn read_sample_bytes(
+    reader: &mut impl io::BufRead,
+    count: usize,
+    sample_size: usize,
+) -> io::Result<Vec<u8>> {
+    let byte_count = count
+        .checked_mul(sample_size)
+        .ok_or_else(|| invalid_data("heightmap size overflows"))?;
+    let mut bytes = Vec::new();
+    reader.take(byte_count as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != byte_count {
+        return Err(invalid_data("heightmap has fewer samples than its size"));
+    }
+
+    return Ok(bytes);
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    return io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("heightmap: {}", message),
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+    use crate::core::vec3;
+    use crate::scene::ray;
+    use crate::scene::shape::IntersectableShape;
+
+    // height under a point of the unit square, found with a ray from above
+    fn height_at(heightfield: &heightfield::Heightfield, x: f32, y: f32) -> f32 {
+        let ray = ray::Ray::new(vec3::Vec3::new(x, y, 10.0), vec3::Vec3::new(0.0, 0.0, -1.0));
+        return heightfield
+            .intersect_ray(&ray)
+            .unwrap()
+            .calc_world_position()
+            .z;
+    }
+
+    #[test]
+    fn test_read_pgm() {
+        let ascii = "P2\n# elevation\n2 2\n4\n0 1\n2 4\n";
+        let heightfield = read_from_reader(&mut ascii.as_bytes(), &mat4::Mat4::new()).unwrap();
+        // the first row of the image is at the far end of y
+        assert!(math::equal_epsilon_f32(
+            height_at(&heightfield, 0.0, 1.0),
+            0.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            height_at(&heightfield, 1.0, 0.0),
+            1.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            height_at(&heightfield, 0.0, 0.0),
+            0.5,
+            math::EPSILON_F32_5
+        ));
+
+        let mut binary = b"P5 2 2 65535\n".to_vec();
+        binary.extend_from_slice(&[0, 0, 0x40, 0, 0x80, 0, 0xff, 0xff]);
+        let heightfield = read_from_reader(&mut binary.as_slice(), &mat4::Mat4::new()).unwrap();
+        assert!(math::equal_epsilon_f32(
+            height_at(&heightfield, 0.0, 0.0),
+            0x8000 as f32 / 65535.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            height_at(&heightfield, 1.0, 1.0),
+            0x4000 as f32 / 65535.0,
+            math::EPSILON_F32_5
+        ));
+    }
+
+    #[test]
+    fn test_read_pfm() {
+        let mut grayscale = b"Pf\n2 2\n-1.0\n".to_vec();
+        for value in [1.5f32, 2.0, -0.5, 3.0].iter() {
+            grayscale.extend_from_slice(&value.to_le_bytes());
+        }
+        let heightfield = read_from_reader(&mut grayscale.as_slice(), &mat4::Mat4::new()).unwrap();
+        // the first row of a pfm is at y = 0
+        assert!(math::equal_epsilon_f32(
+            height_at(&heightfield, 1.0, 0.0),
+            2.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            height_at(&heightfield, 0.0, 1.0),
+            -0.5,
+            math::EPSILON_F32_5
+        ));
+
+        let mut color = b"PF\n2 2\n1.0\n".to_vec();
+        for value in [
+            0.0f32, 0.0, 3.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 2.0, 2.0, 2.0,
+        ]
+        .iter()
+        {
+            color.extend_from_slice(&value.to_be_bytes());
+        }
+        let heightfield = read_from_reader(&mut color.as_slice(), &mat4::Mat4::new()).unwrap();
+        assert!(math::equal_epsilon_f32(
+            height_at(&heightfield, 0.0, 0.0),
+            1.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            height_at(&heightfield, 1.0, 1.0),
+            2.0,
+            math::EPSILON_F32_5
+        ));
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        let identity = mat4::Mat4::new();
+        assert!(read_from_reader(&mut "P3\n2 2\n255\n".as_bytes(), &identity).is_err());
+        assert!(read_from_reader(&mut "P2\n1 2\n255\n0 0\n".as_bytes(), &identity).is_err());
+        assert!(read_from_reader(&mut "P2\n2 2\n255\n0 1 2\n".as_bytes(), &identity).is_err());
+        assert!(read_from_reader(&mut "P2\n2 2\n0\n0 0 0 0\n".as_bytes(), &identity).is_err());
+        assert!(read_from_reader(&mut "P5\n2 2\n255\n\x01\x02".as_bytes(), &identity).is_err());
+        assert!(read_from_reader(&mut "Pf\n2 2\n".as_bytes(), &identity).is_err());
+    }
+
+    #[test]
+    fn test_read_oversized_header() {
+        let identity = mat4::Mat4::new();
+        // a huge size without the samples behind it fails without allocating for the size
+        let truncated = "P5\n100000 100000\n65535\n\x01\x02";
+        let error = read_from_reader(&mut truncated.as_bytes(), &identity)
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let overflowing = format!("PF\n{} {}\n-1.0\n", usize::MAX / 2, 2);
+        let error = read_from_reader(&mut overflowing.as_bytes(), &identity)
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let overflowing = format!("P2\n{} {}\n255\n", usize::MAX, 2);
+        let error = read_from_reader(&mut overflowing.as_bytes(), &identity)
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,318 @@
+// a small json reader for the scene formats that are stored as json, objects keep their key order
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        return match self {
+            JsonValue::Object(members) => members
+                .iter()
+                .find(|(member_key, _)| member_key == key)
+                .map(|(_, value)| value),
+            _ => None,
+        };
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        return match self {
+            JsonValue::Number(number) => Some(*number),
+            _ => None,
+        };
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        return self.as_f64().map(|number| number as f32);
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        return match self {
+            JsonValue::Number(number) if *number >= 0.0 && number.fract() == 0.0 => {
+                Some(*number as usize)
+            }
+            _ => None,
+        };
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        return match self {
+            JsonValue::Bool(value) => Some(*value),
+            _ => None,
+        };
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        return match self {
+            JsonValue::String(value) => Some(value),
+            _ => None,
+        };
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JsonValue>> {
+        return match self {
+            JsonValue::Array(values) => Some(values),
+            _ => None,
+        };
+    }
+
+    // numbers of an array, None when the value is not an array of numbers
+    pub fn as_f32_array(&self) -> Option<Vec<f32>> {
+        return self
+            .as_array()?
+            .iter()
+            .map(|value| value.as_f32())
+            .collect();
+    }
+}
+
+pub fn parse(text: &str) -> Result<JsonValue, String> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        position: 0,
+    };
+    parser.skip_whitespace();
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    if parser.position < parser.chars.len() {
+        return Err(parser.error("unexpected trailing characters"));
+    }
+
+    return Ok(value);
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> String {
+        let consumed = &self.chars[..self.position.min(self.chars.len())];
+        let line = consumed.iter().filter(|c| **c == '\n').count() + 1;
+        let column = consumed.iter().rev().take_while(|c| **c != '\n').count() + 1;
+        return format!("json line {} column {}: {}", line, column, message);
+    }
+
+    fn peek(&self) -> Option<char> {
+        return self.chars.get(self.position).copied();
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.position += 1;
+        return c;
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        return match self.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => {
+                self.position -= 1;
+                Err(self.error(&format!("expected '{}'", expected)))
+            }
+        };
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+
+            self.position += 1;
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<JsonValue, String> {
+        return match self.peek() {
+            Some('{') => self.parse_object(),
+            Some('[') => self.parse_array(),
+            Some('"') => Ok(JsonValue::String(self.parse_string()?)),
+            Some('t') => self.parse_literal("true", JsonValue::Bool(true)),
+            Some('f') => self.parse_literal("false", JsonValue::Bool(false)),
+            Some('n') => self.parse_literal("null", JsonValue::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.parse_number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        };
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: JsonValue) -> Result<JsonValue, String> {
+        for expected in literal.chars() {
+            self.expect(expected)?;
+        }
+
+        return Ok(value);
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, String> {
+        let start = self.position;
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_digit() || c == '-' || c == '+' || c == '.' || c == 'e' || c == 'E') {
+                break;
+            }
+
+            self.position += 1;
+        }
+
+        let text: String = self.chars[start..self.position].iter().collect();
+        return text.parse::<f64>().map(JsonValue::Number).map_err(|_| {
+            self.position = start;
+            self.error(&format!("invalid number {}", text))
+        });
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(value),
+                Some('\\') => {
+                    let escaped = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.parse_unicode_escape()?,
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    value.push(escaped);
+                }
+                Some(c) => value.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    // utf-16 surrogate pairs are written as two escapes
+    fn parse_unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.parse_hex4()?;
+        if (0xd800..0xdc00).contains(&high) {
+            self.expect('\\')?;
+            self.expect('u')?;
+            let low = self.parse_hex4()?;
+            let code = 0x10000 + ((high - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+            return std::char::from_u32(code).ok_or_else(|| self.error("invalid surrogate pair"));
+        }
+
+        return std::char::from_u32(high).ok_or_else(|| self.error("invalid unicode escape"));
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .next()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.error("invalid unicode escape"))?;
+            code = code * 16 + digit;
+        }
+
+        return Ok(code);
+    }
+
+    fn parse_array(&mut self) -> Result<JsonValue, String> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(JsonValue::Array(values));
+        }
+
+        loop {
+            self.skip_whitespace();
+            values.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(JsonValue::Array(values)),
+                _ => {
+                    self.position -= 1;
+                    return Err(self.error("expected ',' or ']'"));
+                }
+            }
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<JsonValue, String> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(JsonValue::Object(members));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            self.skip_whitespace();
+            let value = self.parse_value()?;
+            members.push((key, value));
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(JsonValue::Object(members)),
+                _ => {
+                    self.position -= 1;
+                    return Err(self.error("expected ',' or '}'"));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let value = parse(
+            "{\"name\": \"cube \\\"a\\\" \\u00e9\", \"count\": 3, \"scale\": [1.5, -2e1, 0],\n\
+             \"visible\": true, \"parent\": null, \"children\": []}",
+        )
+        .unwrap();
+        assert_eq!(
+            value.get("name").unwrap().as_str(),
+            Some("cube \"a\" \u{e9}")
+        );
+        assert_eq!(value.get("count").unwrap().as_usize(), Some(3));
+        assert_eq!(
+            value.get("scale").unwrap().as_f32_array(),
+            Some(vec![1.5, -20.0, 0.0])
+        );
+        assert_eq!(value.get("visible").unwrap().as_bool(), Some(true));
+        assert_eq!(value.get("parent"), Some(&JsonValue::Null));
+        assert!(value
+            .get("children")
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .is_empty());
+        assert!(value.get("missing").is_none());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let error = parse("{\n  \"a\": [1, 2,, 3]\n}").unwrap_err();
+        assert!(error.contains("line 2"));
+        assert!(parse("{\"a\" 1}").is_err());
+        assert!(parse("\"unterminated").is_err());
+        assert!(parse("[1] 2").is_err());
+    }
+}
@@ -0,0 +1,117 @@
+use crate::scene::camera::realistic_camera;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+
+// lens tables list one interface per line from the front of the lens to the film as
+// "curvature_radius thickness eta aperture_diameter", with # starting a comment. lengths are
+// multiplied by unit_scale, e.g 0.001 for a table in millimeters and a scene in meters
+pub fn read_from_file(
+    file: &str,
+    unit_scale: f32,
+) -> io::Result<Vec<realistic_camera::LensElement>> {
+    let mut reader = BufReader::new(File::open(file)?);
+    return read_from_reader(&mut reader, unit_scale);
+}
+
+pub fn read_from_reader(
+    reader: &mut impl io::BufRead,
+    unit_scale: f32,
+) -> io::Result<Vec<realistic_camera::LensElement>> {
+    let mut elements = Vec::new();
+    for (line_idx, line) in io::BufRead::lines(reader).enumerate() {
+        let line = line?;
+        let content = match line.find('#') {
+            Some(comment_start) => &line[..comment_start],
+            None => &line[..],
+        };
+
+        if content.trim().is_empty() {
+            continue;
+        }
+
+        let values = content
+            .split_whitespace()
+            .map(|value| value.parse::<f32>())
+            .collect::<Result<Vec<f32>, _>>()
+            .map_err(|e| invalid_data(line_idx, &e.to_string()))?;
+        if values.len() != 4 {
+            return Err(invalid_data(line_idx, "expected 4 values"));
+        }
+
+        elements.push(realistic_camera::LensElement::new(
+            values[0] * unit_scale,
+            values[1] * unit_scale,
+            values[2],
+            0.5 * values[3] * unit_scale,
+        ));
+    }
+
+    if elements.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "lens table has no elements",
+        ));
+    }
+
+    return Ok(elements);
+}
+
+fn invalid_data(line_idx: usize, message: &str) -> io::Error {
+    return io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("lens table line {}: {}", line_idx + 1, message),
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+
+    #[test]
+    fn test_read_from_reader() {
+        let table = "# wide-angle (38-degree) lens\n\
+                     # radius thickness eta aperture\n\
+                     35.98738 1.21638 1.54 23.716\n\
+                     \n\
+                     0 2.5 0 17.1 # aperture stop\n\
+                     -22.13898 0 1 23.716\n";
+
+        let elements = read_from_reader(&mut table.as_bytes(), 0.001).unwrap();
+        assert_eq!(elements.len(), 3);
+        assert!(math::equal_epsilon_f32(
+            elements[0].curvature_radius,
+            0.03598738,
+            math::EPSILON_F32_6
+        ));
+        assert!(math::equal_epsilon_f32(
+            elements[0].thickness,
+            0.00121638,
+            math::EPSILON_F32_6
+        ));
+        assert!(math::equal_epsilon_f32(
+            elements[0].eta,
+            1.54,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            elements[0].aperture_radius,
+            0.011858,
+            math::EPSILON_F32_6
+        ));
+        assert!(elements[1].is_aperture_stop());
+        assert!(math::equal_epsilon_f32(
+            elements[1].eta,
+            1.0,
+            math::EPSILON_F32_5
+        ));
+    }
+
+    #[test]
+    fn test_read_from_reader_invalid() {
+        assert!(read_from_reader(&mut "1.0 2.0 1.5\n".as_bytes(), 1.0).is_err());
+        assert!(read_from_reader(&mut "1.0 2.0 abc 4.0\n".as_bytes(), 1.0).is_err());
+        assert!(read_from_reader(&mut "# empty\n".as_bytes(), 1.0).is_err());
+    }
+}
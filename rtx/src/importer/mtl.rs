@@ -0,0 +1,350 @@
+use crate::core::vec3;
+use crate::scene::material;
+use crate::scene::material::{glass, matte, metal};
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::rc;
+
+// phong exponents at or above this value are treated as perfect mirrors
+const MIRROR_SPECULAR_EXPONENT: f32 = 1000.0;
+
+// mtl files only carry a specular color for metals, so they share a generic bright conductor tinted by ks
+const METAL_ETA: f32 = 0.2;
+const METAL_K: f32 = 3.9;
+
+// oren nayar slope deviation in radians, about 20 degrees, of matte materials with the broadest
+// highlight
+const MATTE_MAX_SIGMA: f32 = 0.35;
+
+#[derive(Clone, Debug)]
+pub struct MtlMaterial {
+    pub name: String,
+    pub kd: vec3::Vec3,
+    pub ks: vec3::Vec3,
+    pub ke: vec3::Vec3,
+    pub tf: vec3::Vec3,
+    pub ns: f32,
+    pub ni: f32,
+    pub d: f32,
+    pub illum: u32,
+}
+
+impl MtlMaterial {
+    pub fn new(name: &str) -> MtlMaterial {
+        return MtlMaterial {
+            name: name.to_string(),
+            kd: vec3::Vec3::from(0.8),
+            ks: vec3::Vec3::from(0.0),
+            ke: vec3::Vec3::from(0.0),
+            tf: vec3::Vec3::from(1.0),
+            ns: 0.0,
+            ni: 1.0,
+            d: 1.0,
+            illum: 2,
+        };
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        return self.d < 1.0 || [4, 6, 7, 9].contains(&self.illum);
+    }
+
+    // ray traced reflection models with a specular color, and highlight models whose specular
+    // color is brighter than the diffuse one
+    pub fn is_metal(&self) -> bool {
+        if is_black(&self.ks) {
+            return false;
+        }
+
+        return [3, 5, 8].contains(&self.illum)
+            || (self.illum == 2 && max_component(&self.ks) > max_component(&self.kd));
+    }
+
+    // emitted radiance for materials that should become area lights
+    pub fn emission(&self) -> Option<vec3::Vec3> {
+        if is_black(&self.ke) {
+            return None;
+        }
+
+        return Some(self.ke);
+    }
+
+    // mtl statements map to the materials as
+    //   d < 1, Tr > 0 or illum 4, 6, 7, 9  glass with kr = Ks (white when Ks is black),
+    //                                      kt = Tf * (1 - d) and eta = Ni (1.5 when not above 1)
+    //   illum 3, 5, 8 with Ks              metal tinted by Ks, roughness from Ns
+    //   illum 2 with Ks brighter than Kd   metal tinted by Ks, roughness from Ns
+    //   illum 2 with Ks                    matte Kd, oren nayar sigma from Ns
+    //   anything else                      lambertian matte Kd
+    // there is no layered material, so the highlight of a plastic like illum 2 material only
+    // carries its breadth over to the roughness of the matte surface
+    pub fn create_material(&self) -> rc::Rc<dyn material::Material> {
+        if self.is_transparent() {
+            let kr = if is_black(&self.ks) {
+                vec3::Vec3::from(1.0)
+            } else {
+                self.ks
+            };
+            // dissolve scales the transmission, fully opaque glass illumination models use the filter
+            let transmission = if self.d < 1.0 { 1.0 - self.d } else { 1.0 };
+            let kt = self.tf * transmission;
+            let eta = if self.ni > 1.0 { self.ni } else { 1.5 };
+            return rc::Rc::new(glass::Glass::new(kt, kr, 1.0, eta));
+        }
+
+        if self.is_metal() {
+            return rc::Rc::new(metal::Metal::new(
+                self.ks,
+                vec3::Vec3::from(METAL_ETA),
+                vec3::Vec3::from(METAL_K),
+                self.roughness(),
+            ));
+        }
+
+        return rc::Rc::new(matte::Matte::new(self.kd, self.matte_sigma()));
+    }
+
+    // converts the phong exponent to a microfacet roughness
+    pub fn roughness(&self) -> f32 {
+        if self.ns >= MIRROR_SPECULAR_EXPONENT {
+            return 0.0;
+        }
+
+        return f32::sqrt(2.0 / (f32::max(self.ns, 0.0) + 2.0));
+    }
+
+    // broad highlights roughen the matte surface, sharp ones leave it lambertian
+    pub fn matte_sigma(&self) -> f32 {
+        if self.illum != 2 || is_black(&self.ks) {
+            return 0.0;
+        }
+
+        return MATTE_MAX_SIGMA * self.roughness();
+    }
+}
+
+pub fn read_from_file(file: &str) -> io::Result<Vec<MtlMaterial>> {
+    let mut reader = BufReader::new(File::open(file)?);
+    return read_from_reader(&mut reader);
+}
+
+pub fn read_from_reader(reader: &mut impl io::BufRead) -> io::Result<Vec<MtlMaterial>> {
+    let mut materials: Vec<MtlMaterial> = Vec::new();
+    for (line_idx, line) in io::BufRead::lines(reader).enumerate() {
+        let line = line?;
+        let content = match line.find('#') {
+            Some(comment_start) => &line[..comment_start],
+            None => &line[..],
+        };
+
+        let mut tokens = content.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let arguments: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            if arguments.is_empty() {
+                return Err(invalid_data(line_idx, "newmtl needs a material name"));
+            }
+
+            materials.push(MtlMaterial::new(&arguments.join(" ")));
+            continue;
+        }
+
+        let is_known_keyword =
+            ["Kd", "Ks", "Ke", "Tf", "Ns", "Ni", "d", "Tr", "illum"].contains(&keyword);
+        if !is_known_keyword {
+            // texture maps and other statements are not supported
+            continue;
+        }
+
+        let material = match materials.last_mut() {
+            Some(material) => material,
+            None => {
+                return Err(invalid_data(
+                    line_idx,
+                    &format!("{} appears before newmtl", keyword),
+                ))
+            }
+        };
+
+        match keyword {
+            "Kd" => material.kd = parse_color(line_idx, &arguments)?,
+            "Ks" => material.ks = parse_color(line_idx, &arguments)?,
+            "Ke" => material.ke = parse_color(line_idx, &arguments)?,
+            "Tf" => material.tf = parse_color(line_idx, &arguments)?,
+            "Ns" => material.ns = parse_scalar(line_idx, &arguments)?,
+            "Ni" => material.ni = parse_scalar(line_idx, &arguments)?,
+            "d" => material.d = parse_scalar(line_idx, &arguments)?,
+            "Tr" => material.d = 1.0 - parse_scalar(line_idx, &arguments)?,
+            _ => {
+                material.illum = arguments
+                    .first()
+                    .ok_or_else(|| invalid_data(line_idx, "illum needs a value"))?
+                    .parse::<u32>()
+                    .map_err(|e| invalid_data(line_idx, &e.to_string()))?
+            }
+        }
+    }
+
+    return Ok(materials);
+}
+
+fn is_black(color: &vec3::Vec3) -> bool {
+    return color.x <= 0.0 && color.y <= 0.0 && color.z <= 0.0;
+}
+
+fn max_component(color: &vec3::Vec3) -> f32 {
+    return f32::max(color.x, f32::max(color.y, color.z));
+}
+
+fn parse_scalar(line_idx: usize, arguments: &[&str]) -> io::Result<f32> {
+    return arguments
+        .first()
+        .ok_or_else(|| invalid_data(line_idx, "expected a value"))?
+        .parse::<f32>()
+        .map_err(|e| invalid_data(line_idx, &e.to_string()));
+}
+
+// a single value is used for all three channels
+fn parse_color(line_idx: usize, arguments: &[&str]) -> io::Result<vec3::Vec3> {
+    if arguments.first() == Some(&"spectral") || arguments.first() == Some(&"xyz") {
+        return Err(invalid_data(line_idx, "only rgb colors are supported"));
+    }
+
+    let values = arguments
+        .iter()
+        .map(|value| value.parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|e| invalid_data(line_idx, &e.to_string()))?;
+    return match values.len() {
+        1 => Ok(vec3::Vec3::from(values[0])),
+        3 => Ok(vec3::Vec3::new(values[0], values[1], values[2])),
+        _ => Err(invalid_data(line_idx, "expected 1 or 3 color values")),
+    };
+}
+
+fn invalid_data(line_idx: usize, message: &str) -> io::Error {
+    return io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("mtl line {}: {}", line_idx + 1, message),
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+
+    #[test]
+    fn test_read_from_reader() {
+        let library = "# two materials\n\
+                       newmtl red wall\n\
+                       Kd 0.8 0.1 0.1\n\
+                       map_Kd wall.png\n\
+                       \n\
+                       newmtl lamp\n\
+                       Kd 0.5\n\
+                       Ke 4 4 3\n\
+                       Ns 10\n\
+                       Ni 1.45\n\
+                       Tr 0.25\n\
+                       illum 7\n";
+
+        let materials = read_from_reader(&mut library.as_bytes()).unwrap();
+        assert_eq!(materials.len(), 2);
+        assert_eq!(materials[0].name, "red wall");
+        assert!(materials[0]
+            .kd
+            .equal_epsilon(&vec3::Vec3::new(0.8, 0.1, 0.1), math::EPSILON_F32_5));
+        assert!(materials[0].emission().is_none());
+        assert!(!materials[0].is_transparent());
+
+        assert_eq!(materials[1].name, "lamp");
+        assert!(materials[1]
+            .kd
+            .equal_epsilon(&vec3::Vec3::from(0.5), math::EPSILON_F32_5));
+        assert!(materials[1]
+            .emission()
+            .unwrap()
+            .equal_epsilon(&vec3::Vec3::new(4.0, 4.0, 3.0), math::EPSILON_F32_5));
+        assert!(math::equal_epsilon_f32(
+            materials[1].ns,
+            10.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            materials[1].ni,
+            1.45,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            materials[1].d,
+            0.75,
+            math::EPSILON_F32_5
+        ));
+        assert_eq!(materials[1].illum, 7);
+        assert!(materials[1].is_transparent());
+    }
+
+    #[test]
+    fn test_metal() {
+        let library = "newmtl chrome\nKs 0.9 0.9 0.9\nNs 2000\nillum 3\n\
+                       newmtl brushed\nKs 0.9 0.8 0.6\nNs 30\nillum 3\n";
+        let materials = read_from_reader(&mut library.as_bytes()).unwrap();
+        assert!(materials[0].is_metal());
+        assert!(math::equal_epsilon_f32(
+            materials[0].roughness(),
+            0.0,
+            math::EPSILON_F32_6
+        ));
+        assert!(materials[1].is_metal());
+        assert!(math::equal_epsilon_f32(
+            materials[1].roughness(),
+            0.25,
+            math::EPSILON_F32_5
+        ));
+    }
+
+    #[test]
+    fn test_specular_highlight() {
+        let library = "newmtl plastic\nKd 0.6 0.1 0.1\nKs 0.3 0.3 0.3\nNs 6\nillum 2\n\
+                       newmtl brass\nKd 0.1 0.1 0.05\nKs 0.8 0.6 0.3\nNs 30\nillum 2\n\
+                       newmtl flat\nKd 0.6 0.6 0.6\nKs 0.3 0.3 0.3\nNs 6\nillum 1\n";
+        let materials = read_from_reader(&mut library.as_bytes()).unwrap();
+
+        // a diffuse color brighter than the highlight stays matte, roughened by the broad highlight
+        assert!(!materials[0].is_metal());
+        assert!(math::equal_epsilon_f32(
+            materials[0].matte_sigma(),
+            MATTE_MAX_SIGMA * 0.5,
+            math::EPSILON_F32_5
+        ));
+
+        // a highlight brighter than the diffuse color makes a metal
+        assert!(materials[1].is_metal());
+        assert!(math::equal_epsilon_f32(
+            materials[1].roughness(),
+            0.25,
+            math::EPSILON_F32_5
+        ));
+
+        // illum 1 has no highlight
+        assert!(!materials[2].is_metal());
+        assert_eq!(materials[2].matte_sigma(), 0.0);
+    }
+
+    #[test]
+    fn test_read_from_reader_invalid() {
+        let error = read_from_reader(&mut "Kd 1 1 1\n".as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("line 1"));
+
+        let error = read_from_reader(&mut "newmtl a\n\nKd 1 x 1\n".as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("line 3"));
+
+        assert!(read_from_reader(&mut "newmtl a\nKd 1 1\n".as_bytes()).is_err());
+        assert!(read_from_reader(&mut "newmtl\n".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,626 @@
+use crate::core::mat4;
+use crate::core::vec2;
+use crate::core::vec3;
+use crate::importer::mtl;
+use crate::scene::light::area_light;
+use crate::scene::material;
+use crate::scene::material::matte;
+use crate::scene::shape::triangle_mesh;
+use crate::scene::world;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::path;
+use std::rc;
+
+// faces that share an object name and a material are gathered into one mesh
+pub struct ObjMesh {
+    pub name: String,
+    pub material_name: Option<String>,
+    pub mesh: rc::Rc<triangle_mesh::TriangleMesh>,
+    pub material: rc::Rc<dyn material::Material>,
+    pub emission: Option<vec3::Vec3>,
+}
+
+pub struct ObjScene {
+    pub meshes: Vec<ObjMesh>,
+}
+
+impl ObjScene {
+    // meshes with an emissive material are also added as area lights
+    pub fn add_to_world(&self, world: &mut world::World, light_samples: u32) {
+        for obj_mesh in self.meshes.iter() {
+            world.add_shape(obj_mesh.mesh.clone(), obj_mesh.material.clone());
+            if let Some(emission) = obj_mesh.emission {
+                world.add_light(Box::new(area_light::AreaLight::new(
+                    emission,
+                    Box::new(obj_mesh.mesh.as_ref().clone()),
+                    light_samples,
+                )));
+            }
+        }
+    }
+}
+
+struct FaceGroup {
+    name: String,
+    material_name: Option<String>,
+    indices: Vec<usize>,
+    positions: Vec<vec3::Vec3>,
+    normals: Vec<Option<vec3::Vec3>>,
+    uvs: Vec<Option<vec2::Vec2>>,
+    vertex_lookup: HashMap<(usize, Option<usize>, Option<usize>), usize>,
+}
+
+impl FaceGroup {
+    fn new(name: &str, material_name: &Option<String>) -> FaceGroup {
+        return FaceGroup {
+            name: name.to_string(),
+            material_name: material_name.clone(),
+            indices: Vec::new(),
+            positions: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            vertex_lookup: HashMap::new(),
+        };
+    }
+}
+
+// mtllib statements are resolved relative to the directory of the obj file
+pub fn read_from_file(file: &str, object_to_world: &mat4::Mat4) -> io::Result<ObjScene> {
+    let directory = path::Path::new(file)
+        .parent()
+        .map(|parent| parent.to_path_buf())
+        .unwrap_or_default();
+    let mut reader = BufReader::new(File::open(file)?);
+    return read_from_reader(&mut reader, object_to_world, |library| {
+        let library_file = directory.join(library);
+        return mtl::read_from_file(library_file.to_str().unwrap_or(library))
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", library_file.display(), e)));
+    });
+}
+
+// load_material_library returns the materials of a library named by a mtllib statement
+pub fn read_from_reader<L>(
+    reader: &mut impl io::BufRead,
+    object_to_world: &mat4::Mat4,
+    mut load_material_library: L,
+) -> io::Result<ObjScene>
+where
+    L: FnMut(&str) -> io::Result<Vec<mtl::MtlMaterial>>,
+{
+    let mut positions: Vec<vec3::Vec3> = Vec::new();
+    let mut uvs: Vec<vec2::Vec2> = Vec::new();
+    let mut normals: Vec<vec3::Vec3> = Vec::new();
+    let mut materials: HashMap<String, mtl::MtlMaterial> = HashMap::new();
+
+    let mut object_name = String::from("default");
+    let mut material_name: Option<String> = None;
+    let mut groups: Vec<FaceGroup> = Vec::new();
+    let mut current_group: Option<usize> = None;
+
+    for (line_idx, line) in io::BufRead::lines(reader).enumerate() {
+        let line = line?;
+        let content = match line.find('#') {
+            Some(comment_start) => &line[..comment_start],
+            None => &line[..],
+        };
+
+        let mut tokens = content.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let arguments: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => {
+                let values = parse_floats(line_idx, &arguments, 3)?;
+                positions.push(vec3::Vec3::new(values[0], values[1], values[2]));
+            }
+            "vt" => {
+                let values = parse_floats(line_idx, &arguments, 1)?;
+                uvs.push(vec2::Vec2::new(
+                    values[0],
+                    values.get(1).copied().unwrap_or(0.0),
+                ));
+            }
+            "vn" => {
+                let values = parse_floats(line_idx, &arguments, 3)?;
+                normals.push(vec3::Vec3::new(values[0], values[1], values[2]));
+            }
+            "o" | "g" => {
+                object_name = if arguments.is_empty() {
+                    String::from("default")
+                } else {
+                    arguments.join(" ")
+                };
+                current_group = None;
+            }
+            "usemtl" => {
+                let name = arguments.join(" ");
+                if !materials.contains_key(&name) {
+                    return Err(invalid_data(
+                        line_idx,
+                        &format!("unknown material \"{}\"", name),
+                    ));
+                }
+
+                material_name = Some(name);
+                current_group = None;
+            }
+            "mtllib" => {
+                if arguments.is_empty() {
+                    return Err(invalid_data(line_idx, "mtllib needs a file name"));
+                }
+
+                for library in arguments.iter() {
+                    for material in load_material_library(library)? {
+                        materials.insert(material.name.clone(), material);
+                    }
+                }
+            }
+            "f" => {
+                if arguments.len() < 3 {
+                    return Err(invalid_data(line_idx, "a face needs at least 3 vertices"));
+                }
+
+                let group_idx = match current_group {
+                    Some(group_idx) => group_idx,
+                    None => {
+                        let existing = groups.iter().position(|group| {
+                            group.name == object_name && group.material_name == material_name
+                        });
+                        let group_idx = existing.unwrap_or_else(|| {
+                            groups.push(FaceGroup::new(&object_name, &material_name));
+                            groups.len() - 1
+                        });
+                        current_group = Some(group_idx);
+                        group_idx
+                    }
+                };
+
+                let group = &mut groups[group_idx];
+                let mut face_vertices = Vec::with_capacity(arguments.len());
+                for argument in arguments.iter() {
+                    let key = parse_face_vertex(
+                        line_idx,
+                        argument,
+                        positions.len(),
+                        uvs.len(),
+                        normals.len(),
+                    )?;
+                    let vertex_idx = match group.vertex_lookup.get(&key) {
+                        Some(vertex_idx) => *vertex_idx,
+                        None => {
+                            let (position_idx, uv_idx, normal_idx) = key;
+                            group.positions.push(positions[position_idx]);
+                            group.uvs.push(uv_idx.map(|uv_idx| uvs[uv_idx]));
+                            group
+                                .normals
+                                .push(normal_idx.map(|normal_idx| normals[normal_idx]));
+                            group.vertex_lookup.insert(key, group.positions.len() - 1);
+                            group.positions.len() - 1
+                        }
+                    };
+                    face_vertices.push(vertex_idx);
+                }
+
+                let face_positions: Vec<vec3::Vec3> = face_vertices
+                    .iter()
+                    .map(|vertex_idx| group.positions[*vertex_idx])
+                    .collect();
+                for triangle in triangulate_polygon(&face_positions).iter() {
+                    for corner in triangle.iter() {
+                        group.indices.push(face_vertices[*corner]);
+                    }
+                }
+            }
+            _ => {
+                // smoothing groups, lines, points and free form geometry are not supported
+            }
+        }
+    }
+
+    let default_material: rc::Rc<dyn material::Material> =
+        rc::Rc::new(matte::Matte::new(vec3::Vec3::from(0.5), 0.0));
+    let mut created_materials: HashMap<String, rc::Rc<dyn material::Material>> = HashMap::new();
+    let mut meshes = Vec::with_capacity(groups.len());
+    for group in groups.into_iter() {
+        // attributes are only kept when every vertex of the mesh has them
+        let group_normals = group
+            .normals
+            .iter()
+            .copied()
+            .collect::<Option<Vec<vec3::Vec3>>>()
+            .unwrap_or_default();
+        let group_uvs = group
+            .uvs
+            .iter()
+            .copied()
+            .collect::<Option<Vec<vec2::Vec2>>>()
+            .unwrap_or_default();
+        let mesh = triangle_mesh::TriangleMesh::new(
+            *object_to_world,
+            group.indices,
+            group.positions,
+            group_normals,
+            group_uvs,
+        );
+
+        let (material, emission) = match &group.material_name {
+            Some(name) => {
+                let mtl_material = &materials[name];
+                let material = created_materials
+                    .entry(name.clone())
+                    .or_insert_with(|| mtl_material.create_material())
+                    .clone();
+                (material, mtl_material.emission())
+            }
+            None => (default_material.clone(), None),
+        };
+
+        meshes.push(ObjMesh {
+            name: group.name,
+            material_name: group.material_name,
+            mesh: rc::Rc::new(mesh),
+            material,
+            emission,
+        });
+    }
+
+    return Ok(ObjScene { meshes });
+}
+
+fn parse_floats(line_idx: usize, arguments: &[&str], min_count: usize) -> io::Result<Vec<f32>> {
+    let values = arguments
+        .iter()
+        .map(|value| value.parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|e| invalid_data(line_idx, &e.to_string()))?;
+    if values.len() < min_count {
+        return Err(invalid_data(
+            line_idx,
+            &format!("expected at least {} values", min_count),
+        ));
+    }
+
+    return Ok(values);
+}
+
+// ear clipping in the plane of the polygon, which handles concave polygons. The triangles keep the
+// winding of the polygon, polygons without ears, e.g self intersecting ones, fall back to a fan
+fn triangulate_polygon(positions: &[vec3::Vec3]) -> Vec<[usize; 3]> {
+    let count = positions.len();
+    let fan = |corners: &[usize]| -> Vec<[usize; 3]> {
+        return (1..corners.len() - 1)
+            .map(|i| [corners[0], corners[i], corners[i + 1]])
+            .collect();
+    };
+    let mut remaining: Vec<usize> = (0..count).collect();
+    if count == 3 {
+        return fan(&remaining);
+    }
+
+    // newell normal, the polygon is projected along its largest axis
+    let mut normal = vec3::Vec3::from(0.0);
+    for i in 0..count {
+        normal += positions[i].cross(&positions[(i + 1) % count]);
+    }
+    let normal_abs = normal.abs();
+    let axis = if normal_abs.x > normal_abs.y && normal_abs.x > normal_abs.z {
+        0
+    } else if normal_abs.y > normal_abs.z {
+        1
+    } else {
+        2
+    };
+    if normal[axis] == 0.0 {
+        return fan(&remaining);
+    }
+
+    // the cyclic order of the other axes keeps the polygon counterclockwise for a positive normal
+    let points: Vec<vec2::Vec2> = positions
+        .iter()
+        .map(|position| {
+            vec2::Vec2::new(
+                position[(axis + 1) % 3],
+                position[(axis + 2) % 3] * f32::signum(normal[axis]),
+            )
+        })
+        .collect();
+    let cross = |a: usize, b: usize, c: usize| -> f32 {
+        let ab = points[b] - points[a];
+        let ac = points[c] - points[a];
+        return ab.x * ac.y - ab.y * ac.x;
+    };
+
+    let mut triangles = Vec::with_capacity(count - 2);
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|i| {
+            let (a, b, c) = (
+                remaining[(i + n - 1) % n],
+                remaining[*i],
+                remaining[(i + 1) % n],
+            );
+            if cross(a, b, c) <= 0.0 {
+                return false;
+            }
+
+            // no other corner may lie in the ear, corners on its edges included
+            return remaining.iter().all(|p| {
+                [a, b, c].contains(p)
+                    || cross(a, b, *p) < 0.0
+                    || cross(b, c, *p) < 0.0
+                    || cross(c, a, *p) < 0.0
+            });
+        });
+        let i = match ear {
+            Some(i) => i,
+            None => {
+                triangles.extend(fan(&remaining));
+                return triangles;
+            }
+        };
+
+        triangles.push([
+            remaining[(i + n - 1) % n],
+            remaining[i],
+            remaining[(i + 1) % n],
+        ]);
+        remaining.remove(i);
+    }
+
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+    return triangles;
+}
+
+// face vertices are written as v, v/vt, v//vn or v/vt/vn
+fn parse_face_vertex(
+    line_idx: usize,
+    argument: &str,
+    num_positions: usize,
+    num_uvs: usize,
+    num_normals: usize,
+) -> io::Result<(usize, Option<usize>, Option<usize>)> {
+    let mut parts = argument.split('/');
+    let position_idx = match parts.next() {
+        Some(part) if !part.is_empty() => resolve_index(line_idx, part, num_positions, "vertex")?,
+        _ => return Err(invalid_data(line_idx, "face vertex needs a position index")),
+    };
+    let uv_idx = match parts.next() {
+        Some(part) if !part.is_empty() => Some(resolve_index(line_idx, part, num_uvs, "texture")?),
+        _ => None,
+    };
+    let normal_idx = match parts.next() {
+        Some(part) if !part.is_empty() => {
+            Some(resolve_index(line_idx, part, num_normals, "normal")?)
+        }
+        _ => None,
+    };
+
+    if parts.next().is_some() {
+        return Err(invalid_data(
+            line_idx,
+            &format!("invalid face vertex \"{}\"", argument),
+        ));
+    }
+
+    return Ok((position_idx, uv_idx, normal_idx));
+}
+
+// indices start at 1, negative indices count back from the latest element
+fn resolve_index(line_idx: usize, part: &str, count: usize, kind: &str) -> io::Result<usize> {
+    let index = part
+        .parse::<i64>()
+        .map_err(|e| invalid_data(line_idx, &format!("{} index \"{}\": {}", kind, part, e)))?;
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        (count as i64) + index
+    };
+
+    if index == 0 || resolved < 0 || resolved >= (count as i64) {
+        return Err(invalid_data(
+            line_idx,
+            &format!("{} index {} is out of range", kind, index),
+        ));
+    }
+
+    return Ok(resolved as usize);
+}
+
+fn invalid_data(line_idx: usize, message: &str) -> io::Error {
+    return io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("obj line {}: {}", line_idx + 1, message),
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+    use crate::scene::ray;
+    use crate::scene::shape::IntersectableShape;
+
+    fn no_library(library: &str) -> io::Result<Vec<mtl::MtlMaterial>> {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} not found", library),
+        ));
+    }
+
+    #[test]
+    fn test_read_from_reader() {
+        let obj = "# quad written as a single polygon\n\
+                   v -1 -1 0\n\
+                   v 1 -1 0\n\
+                   v 1 1 0\n\
+                   v -1 1 0\n\
+                   vt 0 0\n\
+                   vt 1 0\n\
+                   vt 1 1\n\
+                   vt 0 1\n\
+                   vn 0 0 1\n\
+                   o quad\n\
+                   f 1/1/1 2/2/1 3/3/1 4/4/1\n";
+
+        let scene = read_from_reader(&mut obj.as_bytes(), &mat4::Mat4::new(), no_library).unwrap();
+        assert_eq!(scene.meshes.len(), 1);
+        let obj_mesh = &scene.meshes[0];
+        assert_eq!(obj_mesh.name, "quad");
+        assert!(obj_mesh.material_name.is_none());
+        assert!(obj_mesh.emission.is_none());
+        assert_eq!(obj_mesh.mesh.triangles().len(), 2);
+        assert_eq!(obj_mesh.mesh.buffers().positions().len(), 4);
+        assert_eq!(obj_mesh.mesh.buffers().normals().len(), 4);
+        assert_eq!(obj_mesh.mesh.buffers().uvs().len(), 4);
+        assert!(math::equal_epsilon_f32(
+            obj_mesh.mesh.area(),
+            4.0,
+            math::EPSILON_F32_5
+        ));
+
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.5, 0.5, 2.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        assert!(obj_mesh.mesh.intersect_ray(&ray).is_some());
+    }
+
+    #[test]
+    fn test_triangulate_concave_polygon() {
+        // an l shaped polygon, a fan around its first vertex would cover the notch
+        let obj = "v 0 0 0\n\
+                   v 2 0 0\n\
+                   v 2 1 0\n\
+                   v 1 1 0\n\
+                   v 1 2 0\n\
+                   v 0 2 0\n\
+                   f 4 5 6 1 2 3\n";
+
+        let scene = read_from_reader(&mut obj.as_bytes(), &mat4::Mat4::new(), no_library).unwrap();
+        let mesh = &scene.meshes[0].mesh;
+        assert_eq!(mesh.triangles().len(), 4);
+        assert!(math::equal_epsilon_f32(
+            mesh.area(),
+            3.0,
+            math::EPSILON_F32_5
+        ));
+
+        let notch = ray::Ray::new(
+            vec3::Vec3::new(1.5, 1.5, 1.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        assert!(mesh.intersect_ray(&notch).is_none());
+        let inside = ray::Ray::new(
+            vec3::Vec3::new(0.5, 1.5, 1.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        assert!(mesh.intersect_ray(&inside).is_some());
+
+        // the triangles keep the winding of the polygon, also when it faces down
+        let positions = [
+            vec3::Vec3::new(0.0, 0.0, 0.0),
+            vec3::Vec3::new(0.0, 2.0, 0.0),
+            vec3::Vec3::new(1.0, 2.0, 0.0),
+            vec3::Vec3::new(1.0, 1.0, 0.0),
+            vec3::Vec3::new(2.0, 1.0, 0.0),
+            vec3::Vec3::new(2.0, 0.0, 0.0),
+        ];
+        for triangle in triangulate_polygon(&positions).iter() {
+            let [a, b, c] = *triangle;
+            let normal = (positions[b] - positions[a]).cross(&(positions[c] - positions[a]));
+            assert!(normal.z < 0.0);
+        }
+    }
+
+    #[test]
+    fn test_negative_indices_and_materials() {
+        let obj = "mtllib scene.mtl\n\
+                   v 0 0 0\n\
+                   v 1 0 0\n\
+                   v 0 1 0\n\
+                   usemtl light\n\
+                   f -3 -2 -1\n\
+                   v 0 0 1\n\
+                   v 1 0 1\n\
+                   v 0 1 1\n\
+                   usemtl floor\n\
+                   f -3//1 -2 -1\n\
+                   vn 0 0 1\n";
+
+        let mut loaded_libraries = Vec::new();
+        let scene = read_from_reader(&mut obj.as_bytes(), &mat4::Mat4::new(), |library| {
+            loaded_libraries.push(library.to_string());
+            return mtl::read_from_reader(
+                &mut "newmtl light\nKe 5 5 5\nnewmtl floor\nKd 0.2 0.3 0.4\n".as_bytes(),
+            );
+        });
+
+        // the normal is referenced before it is declared
+        assert!(scene.is_err());
+        assert!(scene.err().unwrap().to_string().contains("obj line 11"));
+
+        let obj = obj.replace("f -3//1 -2 -1\n", "f -3 -2 -1\n");
+        let scene = read_from_reader(&mut obj.as_bytes(), &mat4::Mat4::new(), |library| {
+            loaded_libraries.push(library.to_string());
+            return mtl::read_from_reader(
+                &mut "newmtl light\nKe 5 5 5\nnewmtl floor\nKd 0.2 0.3 0.4\n".as_bytes(),
+            );
+        })
+        .unwrap();
+        assert_eq!(loaded_libraries, vec!["scene.mtl", "scene.mtl"]);
+        assert_eq!(scene.meshes.len(), 2);
+        assert_eq!(scene.meshes[0].material_name.as_deref(), Some("light"));
+        assert!(scene.meshes[0]
+            .emission
+            .unwrap()
+            .equal_epsilon(&vec3::Vec3::from(5.0), math::EPSILON_F32_5));
+        assert!(scene.meshes[0].mesh.buffers().positions()[0]
+            .equal_epsilon(&vec3::Vec3::new(0.0, 0.0, 0.0), math::EPSILON_F32_5));
+        assert!(scene.meshes[1].emission.is_none());
+        assert!(scene.meshes[1].mesh.buffers().positions()[0]
+            .equal_epsilon(&vec3::Vec3::new(0.0, 0.0, 1.0), math::EPSILON_F32_5));
+
+        let mut world = world::World::new();
+        scene.add_to_world(&mut world, 4);
+        assert_eq!(world.lights().len(), 1);
+    }
+
+    #[test]
+    fn test_read_from_reader_invalid() {
+        let error = read_from_reader(
+            &mut "v 0 0 0\nv 1 0 0\n\nf 1 2 3\n".as_bytes(),
+            &mat4::Mat4::new(),
+            no_library,
+        )
+        .err()
+        .unwrap();
+        assert!(error.to_string().contains("obj line 4"));
+
+        let error = read_from_reader(&mut "v 0 0\n".as_bytes(), &mat4::Mat4::new(), no_library)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("obj line 1"));
+
+        let error = read_from_reader(
+            &mut "usemtl missing\n".as_bytes(),
+            &mat4::Mat4::new(),
+            no_library,
+        )
+        .err()
+        .unwrap();
+        assert!(error.to_string().contains("unknown material"));
+
+        assert!(read_from_reader(
+            &mut "mtllib missing.mtl\n".as_bytes(),
+            &mat4::Mat4::new(),
+            no_library
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,704 @@
+use crate::core::mat4;
+use crate::core::vec2;
+use crate::core::vec3;
+use crate::scene::shape::triangle_mesh;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum ScalarType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Option<ScalarType> {
+        return match name {
+            "char" | "int8" => Some(ScalarType::Int8),
+            "uchar" | "uint8" => Some(ScalarType::UInt8),
+            "short" | "int16" => Some(ScalarType::Int16),
+            "ushort" | "uint16" => Some(ScalarType::UInt16),
+            "int" | "int32" => Some(ScalarType::Int32),
+            "uint" | "uint32" => Some(ScalarType::UInt32),
+            "float" | "float32" => Some(ScalarType::Float32),
+            "double" | "float64" => Some(ScalarType::Float64),
+            _ => None,
+        };
+    }
+
+    // integer colors are normalized by the largest value of their type
+    fn color_scale(&self) -> f64 {
+        return match self {
+            ScalarType::Int8 => i8::MAX as f64,
+            ScalarType::UInt8 => u8::MAX as f64,
+            ScalarType::Int16 => i16::MAX as f64,
+            ScalarType::UInt16 => u16::MAX as f64,
+            ScalarType::Int32 => i32::MAX as f64,
+            ScalarType::UInt32 => u32::MAX as f64,
+            ScalarType::Float32 | ScalarType::Float64 => 1.0,
+        };
+    }
+
+    fn size(&self) -> usize {
+        return match self {
+            ScalarType::Int8 | ScalarType::UInt8 => 1,
+            ScalarType::Int16 | ScalarType::UInt16 => 2,
+            ScalarType::Int32 | ScalarType::UInt32 | ScalarType::Float32 => 4,
+            ScalarType::Float64 => 8,
+        };
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum PropertyType {
+    Scalar(ScalarType),
+    List(ScalarType, ScalarType),
+}
+
+#[derive(Debug)]
+struct Property {
+    name: String,
+    property_type: PropertyType,
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    fn property_index(&self, names: &[&str]) -> Option<usize> {
+        return self
+            .properties
+            .iter()
+            .position(|property| names.contains(&property.name.as_str()));
+    }
+}
+
+// values of one element row, list properties keep all of their items
+enum PropertyValue {
+    Scalar(f64),
+    List(Vec<f64>),
+}
+
+impl PropertyValue {
+    fn scalar(&self) -> Option<f64> {
+        return match self {
+            PropertyValue::Scalar(value) => Some(*value),
+            PropertyValue::List(_) => None,
+        };
+    }
+}
+
+// body values are either whitespace separated text, with the file line of each token, or packed
+// binary scalars, with the file offset of the next byte
+enum BodyReader<'a, R: io::BufRead> {
+    Ascii(
+        std::iter::Peekable<std::vec::IntoIter<(usize, String)>>,
+        usize,
+    ),
+    Binary(&'a mut R, Format, usize),
+}
+
+impl<'a, R: io::BufRead> BodyReader<'a, R> {
+    // where the next value starts, a line for text and a byte offset for binary files
+    fn location(&mut self) -> String {
+        return match self {
+            BodyReader::Ascii(tokens, end_line_idx) => {
+                let line_idx = tokens
+                    .peek()
+                    .map_or(*end_line_idx, |(line_idx, _)| *line_idx);
+                format!("line {}", line_idx + 1)
+            }
+            BodyReader::Binary(_, _, offset) => format!("byte {}", offset),
+        };
+    }
+
+    fn read_scalar(&mut self, scalar_type: ScalarType) -> Result<f64, String> {
+        return match self {
+            BodyReader::Ascii(tokens, _) => {
+                let (_, token) = tokens
+                    .next()
+                    .ok_or_else(|| String::from("unexpected end of file"))?;
+                token
+                    .parse::<f64>()
+                    .map_err(|e| format!("value \"{}\": {}", token, e))
+            }
+            BodyReader::Binary(reader, format, offset) => {
+                let value =
+                    read_binary_scalar(*reader, *format, scalar_type).map_err(|e| e.to_string())?;
+                *offset += scalar_type.size();
+                Ok(value)
+            }
+        };
+    }
+
+    fn read_property(&mut self, property_type: PropertyType) -> Result<PropertyValue, String> {
+        return match property_type {
+            PropertyType::Scalar(scalar_type) => {
+                Ok(PropertyValue::Scalar(self.read_scalar(scalar_type)?))
+            }
+            PropertyType::List(count_type, item_type) => {
+                let count = self.read_scalar(count_type)?;
+                if count < 0.0 || count.fract() != 0.0 {
+                    return Err(format!("invalid list length {}", count));
+                }
+
+                // the count comes from the file, so the items grow as they are read instead of
+                // reserving for it up front
+                let mut items = Vec::new();
+                for _ in 0..(count as usize) {
+                    items.push(self.read_scalar(item_type)?);
+                }
+
+                Ok(PropertyValue::List(items))
+            }
+        };
+    }
+}
+
+fn read_binary_scalar(
+    reader: &mut impl io::Read,
+    format: Format,
+    scalar_type: ScalarType,
+) -> io::Result<f64> {
+    macro_rules! read_as {
+        ($type:ty, $size:expr) => {{
+            let mut bytes = [0u8; $size];
+            reader.read_exact(&mut bytes)?;
+            if format == Format::BinaryBigEndian {
+                <$type>::from_be_bytes(bytes) as f64
+            } else {
+                <$type>::from_le_bytes(bytes) as f64
+            }
+        }};
+    }
+
+    let value = match scalar_type {
+        ScalarType::Int8 => read_as!(i8, 1),
+        ScalarType::UInt8 => read_as!(u8, 1),
+        ScalarType::Int16 => read_as!(i16, 2),
+        ScalarType::UInt16 => read_as!(u16, 2),
+        ScalarType::Int32 => read_as!(i32, 4),
+        ScalarType::UInt32 => read_as!(u32, 4),
+        ScalarType::Float32 => read_as!(f32, 4),
+        ScalarType::Float64 => read_as!(f64, 8),
+    };
+    return Ok(value);
+}
+
+pub fn read_from_file(
+    file: &str,
+    object_to_world: &mat4::Mat4,
+) -> io::Result<triangle_mesh::TriangleMesh> {
+    let mut reader = BufReader::new(File::open(file)?);
+    return read_from_reader(&mut reader, object_to_world);
+}
+
+// reads the vertex and face elements, other elements are skipped. Vertex colors are stored on the mesh
+// so they can be looked up by a vertex color texture
+pub fn read_from_reader<R: io::BufRead>(
+    reader: &mut R,
+    object_to_world: &mat4::Mat4,
+) -> io::Result<triangle_mesh::TriangleMesh> {
+    let (format, elements, header_lines, header_bytes) = read_header(reader)?;
+    let mut body = match format {
+        Format::Ascii => {
+            let mut text = String::new();
+            reader.read_to_string(&mut text)?;
+            let mut end_line_idx = header_lines;
+            let mut tokens = Vec::new();
+            for (line_idx, line) in text.lines().enumerate() {
+                end_line_idx = header_lines + line_idx;
+                tokens.extend(
+                    line.split_whitespace()
+                        .map(|token| (end_line_idx, token.to_string())),
+                );
+            }
+            BodyReader::Ascii(tokens.into_iter().peekable(), end_line_idx)
+        }
+        _ => BodyReader::Binary(reader, format, header_bytes),
+    };
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut colors = Vec::new();
+    let mut indices = Vec::new();
+    for element in elements.iter() {
+        for _ in 0..element.count {
+            let location = body.location();
+            let row = element
+                .properties
+                .iter()
+                .map(|property| body.read_property(property.property_type))
+                .collect::<Result<Vec<PropertyValue>, String>>()
+                .map_err(|message| invalid_body(element, &location, &message))?;
+
+            match element.name.as_str() {
+                "vertex" => read_vertex(
+                    element,
+                    &location,
+                    &row,
+                    &mut positions,
+                    &mut normals,
+                    &mut uvs,
+                    &mut colors,
+                )?,
+                "face" => read_face(element, &location, &row, &mut indices)?,
+                _ => {}
+            }
+        }
+    }
+
+    // faces may come before the vertices, so their indices are checked once everything is read
+    if let Some(index) = indices.iter().find(|index| **index >= positions.len()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("ply face: vertex index {} is out of range", index),
+        ));
+    }
+
+    return Ok(triangle_mesh::TriangleMesh::new_with_colors(
+        *object_to_world,
+        indices,
+        positions,
+        normals,
+        uvs,
+        colors,
+    ));
+}
+
+// returns the number of header lines and bytes as well, so body errors can point into the file
+fn read_header(reader: &mut impl io::BufRead) -> io::Result<(Format, Vec<Element>, usize, usize)> {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut line_idx = 0;
+    let mut byte_count = 0;
+    loop {
+        let mut line = String::new();
+        let line_size = reader.read_line(&mut line)?;
+        if line_size == 0 {
+            return Err(invalid_header(line_idx, "missing end_header"));
+        }
+
+        byte_count += line_size;
+
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if line_idx == 0 {
+            if tokens != ["ply"] {
+                return Err(invalid_header(line_idx, "file does not start with ply"));
+            }
+
+            line_idx += 1;
+            continue;
+        }
+
+        match tokens.first() {
+            None | Some(&"comment") | Some(&"obj_info") => {}
+            Some(&"format") => {
+                format = match tokens.get(1) {
+                    Some(&"ascii") => Some(Format::Ascii),
+                    Some(&"binary_little_endian") => Some(Format::BinaryLittleEndian),
+                    Some(&"binary_big_endian") => Some(Format::BinaryBigEndian),
+                    _ => return Err(invalid_header(line_idx, "unknown format")),
+                };
+            }
+            Some(&"element") => {
+                if tokens.len() != 3 {
+                    return Err(invalid_header(line_idx, "expected element name and count"));
+                }
+
+                let count = tokens[2]
+                    .parse::<usize>()
+                    .map_err(|e| invalid_header(line_idx, &e.to_string()))?;
+                elements.push(Element {
+                    name: tokens[1].to_string(),
+                    count,
+                    properties: Vec::new(),
+                });
+            }
+            Some(&"property") => {
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| invalid_header(line_idx, "property appears before element"))?;
+                let parse_type = |name: &str| {
+                    ScalarType::parse(name).ok_or_else(|| {
+                        invalid_header(line_idx, &format!("unknown property type {}", name))
+                    })
+                };
+                let (property_type, name) = match tokens.as_slice() {
+                    ["property", "list", count_type, item_type, name] => (
+                        PropertyType::List(parse_type(count_type)?, parse_type(item_type)?),
+                        name,
+                    ),
+                    ["property", scalar_type, name] => {
+                        (PropertyType::Scalar(parse_type(scalar_type)?), name)
+                    }
+                    _ => return Err(invalid_header(line_idx, "invalid property")),
+                };
+                element.properties.push(Property {
+                    name: name.to_string(),
+                    property_type,
+                });
+            }
+            Some(&"end_header") => break,
+            Some(keyword) => {
+                return Err(invalid_header(
+                    line_idx,
+                    &format!("unknown keyword {}", keyword),
+                ))
+            }
+        }
+
+        line_idx += 1;
+    }
+
+    let format = format.ok_or_else(|| invalid_header(line_idx, "missing format"))?;
+    return Ok((format, elements, line_idx + 1, byte_count));
+}
+
+fn read_vertex(
+    element: &Element,
+    location: &str,
+    row: &[PropertyValue],
+    positions: &mut Vec<vec3::Vec3>,
+    normals: &mut Vec<vec3::Vec3>,
+    uvs: &mut Vec<vec2::Vec2>,
+    colors: &mut Vec<vec3::Vec3>,
+) -> io::Result<()> {
+    // each attribute component is looked up by any of its common property names
+    let read_attribute = |names: &[&[&str]], is_color: bool| -> io::Result<Option<Vec<f32>>> {
+        let mut values = Vec::with_capacity(names.len());
+        for component_names in names.iter() {
+            let property_idx = match element.property_index(component_names) {
+                Some(property_idx) => property_idx,
+                None => return Ok(None),
+            };
+            let value = row[property_idx].scalar().ok_or_else(|| {
+                invalid_body(element, location, "vertex attributes must be scalars")
+            })?;
+            values.push(match element.properties[property_idx].property_type {
+                PropertyType::Scalar(scalar_type) if is_color => value / scalar_type.color_scale(),
+                _ => value,
+            } as f32);
+        }
+
+        return Ok(Some(values));
+    };
+
+    let position = read_attribute(&[&["x"], &["y"], &["z"]], false)?
+        .ok_or_else(|| invalid_body(element, location, "vertex needs x, y and z"))?;
+    positions.push(vec3::Vec3::new(position[0], position[1], position[2]));
+
+    if let Some(normal) = read_attribute(&[&["nx"], &["ny"], &["nz"]], false)? {
+        normals.push(vec3::Vec3::new(normal[0], normal[1], normal[2]));
+    }
+
+    if let Some(uv) = read_attribute(
+        &[
+            &["u", "s", "texture_u", "texture_s"],
+            &["v", "t", "texture_v", "texture_t"],
+        ],
+        false,
+    )? {
+        uvs.push(vec2::Vec2::new(uv[0], uv[1]));
+    }
+
+    if let Some(color) = read_attribute(
+        &[
+            &["red", "diffuse_red", "r"],
+            &["green", "diffuse_green", "g"],
+            &["blue", "diffuse_blue", "b"],
+        ],
+        true,
+    )? {
+        colors.push(vec3::Vec3::new(color[0], color[1], color[2]));
+    }
+
+    return Ok(());
+}
+
+// polygons are triangulated as a fan around their first vertex
+fn read_face(
+    element: &Element,
+    location: &str,
+    row: &[PropertyValue],
+    indices: &mut Vec<usize>,
+) -> io::Result<()> {
+    let property_idx = element
+        .property_index(&["vertex_indices", "vertex_index"])
+        .ok_or_else(|| invalid_body(element, location, "face needs vertex_indices"))?;
+    let face_indices = match &row[property_idx] {
+        PropertyValue::List(face_indices) => face_indices,
+        PropertyValue::Scalar(_) => {
+            return Err(invalid_body(
+                element,
+                location,
+                "vertex_indices must be a list",
+            ))
+        }
+    };
+
+    if face_indices.len() < 3 {
+        return Err(invalid_body(
+            element,
+            location,
+            "a face needs at least 3 vertices",
+        ));
+    }
+
+    if face_indices
+        .iter()
+        .any(|index| *index < 0.0 || index.fract() != 0.0)
+    {
+        return Err(invalid_body(element, location, "invalid vertex index"));
+    }
+
+    for i in 1..(face_indices.len() - 1) {
+        indices.push(face_indices[0] as usize);
+        indices.push(face_indices[i] as usize);
+        indices.push(face_indices[i + 1] as usize);
+    }
+
+    return Ok(());
+}
+
+fn invalid_header(line_idx: usize, message: &str) -> io::Error {
+    return io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("ply header line {}: {}", line_idx + 1, message),
+    );
+}
+
+fn invalid_body(element: &Element, location: &str, message: &str) -> io::Error {
+    return io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("ply {} {}: {}", element.name, location, message),
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+    use crate::scene::material::textured_matte;
+    use crate::scene::ray;
+    use crate::scene::shape;
+    use crate::scene::shape::IntersectableShape;
+    use crate::scene::texture::vertex_color_texture;
+    use crate::scene::texture::Texture;
+    use std::rc;
+
+    const HEADER: &str = "element vertex 4\n\
+                          property uchar red\n\
+                          property float z\n\
+                          property float x\n\
+                          property float y\n\
+                          property uchar green\n\
+                          property uchar blue\n\
+                          element edge 1\n\
+                          property int vertex1\n\
+                          property int vertex2\n\
+                          element face 1\n\
+                          property uchar intensity\n\
+                          property list uchar int vertex_indices\n\
+                          end_header\n";
+
+    // unit quad on the xy plane with a red and a blue edge
+    const VERTICES: [(u8, f32, f32, f32, u8, u8); 4] = [
+        (255, 0.0, 0.0, 0.0, 0, 0),
+        (0, 0.0, 1.0, 0.0, 0, 255),
+        (0, 0.0, 1.0, 1.0, 0, 255),
+        (255, 0.0, 0.0, 1.0, 0, 0),
+    ];
+
+    fn create_binary(big_endian: bool) -> Vec<u8> {
+        let format = if big_endian {
+            "binary_big_endian"
+        } else {
+            "binary_little_endian"
+        };
+        let mut bytes = format!("ply\nformat {} 1.0\n{}", format, HEADER).into_bytes();
+        let f32_bytes = |value: f32| {
+            if big_endian {
+                value.to_be_bytes()
+            } else {
+                value.to_le_bytes()
+            }
+        };
+        let i32_bytes = |value: i32| {
+            if big_endian {
+                value.to_be_bytes()
+            } else {
+                value.to_le_bytes()
+            }
+        };
+
+        for (red, z, x, y, green, blue) in VERTICES.iter() {
+            bytes.push(*red);
+            bytes.extend_from_slice(&f32_bytes(*z));
+            bytes.extend_from_slice(&f32_bytes(*x));
+            bytes.extend_from_slice(&f32_bytes(*y));
+            bytes.push(*green);
+            bytes.push(*blue);
+        }
+
+        bytes.extend_from_slice(&i32_bytes(0));
+        bytes.extend_from_slice(&i32_bytes(1));
+
+        bytes.push(7);
+        bytes.push(4);
+        for index in 0..4 {
+            bytes.extend_from_slice(&i32_bytes(index));
+        }
+
+        return bytes;
+    }
+
+    fn check_quad(mesh: &triangle_mesh::TriangleMesh) {
+        assert_eq!(mesh.triangles().len(), 2);
+        assert_eq!(mesh.buffers().colors().len(), 4);
+        assert!(mesh.buffers().normals().is_empty());
+        assert!(mesh.buffers().positions()[2]
+            .equal_epsilon(&vec3::Vec3::new(1.0, 1.0, 0.0), math::EPSILON_F32_6));
+
+        // the color is interpolated halfway between the red and the blue edge
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.5, 0.25, 1.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        let surface = mesh.intersect_ray(&ray).unwrap();
+        assert!(surface
+            .vertex_color()
+            .unwrap()
+            .equal_epsilon(&vec3::Vec3::new(0.5, 0.0, 0.5), math::EPSILON_F32_5));
+    }
+
+    #[test]
+    fn test_read_ascii() {
+        let mut text = format!("ply\nformat ascii 1.0\ncomment exported quad\n{}", HEADER);
+        for (red, z, x, y, green, blue) in VERTICES.iter() {
+            text += &format!("{} {} {} {} {} {}\n", red, z, x, y, green, blue);
+        }
+        text += "0 1\n7 4 0 1 2 3\n";
+
+        let mesh = read_from_reader(&mut text.as_bytes(), &mat4::Mat4::new()).unwrap();
+        check_quad(&mesh);
+    }
+
+    #[test]
+    fn test_read_binary() {
+        let bytes = create_binary(false);
+        let mesh = read_from_reader(&mut bytes.as_slice(), &mat4::Mat4::new()).unwrap();
+        check_quad(&mesh);
+
+        let bytes = create_binary(true);
+        let mesh = read_from_reader(&mut bytes.as_slice(), &mat4::Mat4::new()).unwrap();
+        check_quad(&mesh);
+    }
+
+    #[test]
+    fn test_vertex_color_texture() {
+        let bytes = create_binary(false);
+        let mesh = read_from_reader(&mut bytes.as_slice(), &mat4::Mat4::new()).unwrap();
+        let texture = vertex_color_texture::VertexColorTexture::new(vec3::Vec3::from(0.5));
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.0, 0.5, 1.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        let surface = mesh.intersect_ray(&ray).unwrap();
+        assert!(texture
+            .evaluate(&surface)
+            .equal_epsilon(&vec3::Vec3::new(1.0, 0.0, 0.0), math::EPSILON_F32_5));
+
+        let material = rc::Rc::new(textured_matte::TexturedMatte::new(
+            rc::Rc::new(texture),
+            0.0,
+        ));
+        let renderable = shape::RenderableShape::new(rc::Rc::new(mesh), material);
+        let renderable_surface = renderable.intersect_ray(&ray).unwrap();
+        let normal = renderable_surface.shape_surface().calc_world_normal();
+        let dpdu = renderable_surface.shape_surface().calc_world_dpdu();
+        let bxdf = renderable_surface
+            .material()
+            .bxdf(&normal, &dpdu, &normal, &normal);
+        assert!(bxdf.equal_epsilon(
+            &vec3::Vec3::new(1.0 / math::PI_F32, 0.0, 0.0),
+            math::EPSILON_F32_5
+        ));
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        let error = read_from_reader(
+            &mut "ply\nformat ascii 1.0\nelement vertex\n".as_bytes(),
+            &mat4::Mat4::new(),
+        )
+        .err()
+        .unwrap();
+        assert!(error.to_string().contains("header line 3"));
+
+        let error = read_from_reader(
+            &mut "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nend_header\n1\n"
+                .as_bytes(),
+            &mat4::Mat4::new(),
+        )
+        .err()
+        .unwrap();
+        assert!(error.to_string().contains("vertex line 6"));
+
+        // the second face has too few vertices
+        let error = read_from_reader(
+            &mut "ply\nformat ascii 1.0\nelement vertex 3\n\
+                  property float x\nproperty float y\nproperty float z\n\
+                  element face 2\nproperty list uchar int vertex_indices\nend_header\n\
+                  0 0 0\n1 0 0\n0 1 0\n3 0 1 2\n\n2 0 1\n"
+                .as_bytes(),
+            &mat4::Mat4::new(),
+        )
+        .err()
+        .unwrap();
+        assert!(error.to_string().contains("face line 15"));
+
+        // the face starts after the header, 4 vertices of 15 bytes and the edge of 8 bytes
+        let truncated = create_binary(false);
+        let header_size = truncated.len() - 4 * 15 - 8 - 2 - 4 * 4;
+        let error = read_from_reader(&mut &truncated[..truncated.len() - 2], &mat4::Mat4::new())
+            .err()
+            .unwrap();
+        assert!(error
+            .to_string()
+            .contains(&format!("face byte {}", header_size + 4 * 15 + 8)));
+
+        // a list claiming u32::MAX items fails at the end of the file
+        let mut huge_list = "ply\nformat binary_little_endian 1.0\nelement face 1\n\
+                             property list uint int vertex_indices\nend_header\n"
+            .as_bytes()
+            .to_vec();
+        huge_list.extend_from_slice(&u32::MAX.to_le_bytes());
+        huge_list.extend_from_slice(&0i32.to_le_bytes());
+        let error = read_from_reader(&mut huge_list.as_slice(), &mat4::Mat4::new())
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("face byte"));
+
+        assert!(read_from_reader(&mut "obj\n".as_bytes(), &mat4::Mat4::new()).is_err());
+    }
+}
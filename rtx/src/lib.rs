@@ -1,4 +1,5 @@
 pub mod core;
 pub mod exporter;
+pub mod importer;
 pub mod scene;
 pub mod tracer;
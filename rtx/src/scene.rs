@@ -1,4 +1,6 @@
 pub mod aabb;
+pub mod accelerator;
+pub mod animation;
 pub mod camera;
 pub mod fresnel;
 pub mod light;
@@ -8,4 +10,5 @@ pub mod ray;
 pub mod reflectance;
 pub mod sampler;
 pub mod shape;
+pub mod texture;
 pub mod world;
@@ -1,5 +1,12 @@
+use crate::core::mat4;
 use crate::core::vec3;
+use crate::core::vec4;
+use crate::scene::ray;
 
+// widens the far slab distance to cover the rounding error of the slab test
+const SLAB_ERROR_SCALE: f32 = 1.0 + 2.0 * 3.0 * f32::EPSILON;
+
+#[derive(Copy, Clone, Debug)]
 pub struct AABB {
     min: vec3::Vec3,
     max: vec3::Vec3,
@@ -10,6 +17,22 @@ impl AABB {
         return AABB { min, max };
     }
 
+    // an inverted box that any merged point or box replaces
+    pub fn empty() -> AABB {
+        return AABB {
+            min: vec3::Vec3::from(f32::MAX),
+            max: vec3::Vec3::from(f32::MIN),
+        };
+    }
+
+    // bounds of shapes that extend forever, like planes
+    pub fn infinite() -> AABB {
+        return AABB {
+            min: vec3::Vec3::from(f32::NEG_INFINITY),
+            max: vec3::Vec3::from(f32::INFINITY),
+        };
+    }
+
     pub fn min(&self) -> &vec3::Vec3 {
         return &self.min;
     }
@@ -31,6 +54,118 @@ impl AABB {
         self.max.y = f32::max(self.max.y, v.y);
         self.max.z = f32::max(self.max.z, v.z);
     }
+
+    pub fn merge_aabb(&mut self, aabb: &AABB) {
+        self.merge(&aabb.min);
+        self.merge(&aabb.max);
+    }
+
+    // the box both boxes overlap in, which is empty when they are disjoint
+    pub fn intersect_aabb(&self, aabb: &AABB) -> AABB {
+        return AABB::new(
+            vec3::Vec3::new(
+                f32::max(self.min.x, aabb.min.x),
+                f32::max(self.min.y, aabb.min.y),
+                f32::max(self.min.z, aabb.min.z),
+            ),
+            vec3::Vec3::new(
+                f32::min(self.max.x, aabb.max.x),
+                f32::min(self.max.y, aabb.max.y),
+                f32::min(self.max.z, aabb.max.z),
+            ),
+        );
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z;
+    }
+
+    pub fn is_finite(&self) -> bool {
+        return [self.min, self.max]
+            .iter()
+            .all(|v| v.x.is_finite() && v.y.is_finite() && v.z.is_finite());
+    }
+
+    pub fn diagonal(&self) -> vec3::Vec3 {
+        return self.max - self.min;
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+
+        let d = self.diagonal();
+        return 2.0 * (d.x * d.y + d.x * d.z + d.y * d.z);
+    }
+
+    // index of the longest axis, 0 for x, 1 for y and 2 for z
+    pub fn max_extent(&self) -> usize {
+        let d = self.diagonal();
+        if d.x > d.y && d.x > d.z {
+            return 0;
+        }
+
+        if d.y > d.z {
+            return 1;
+        }
+
+        return 2;
+    }
+
+    // bounds of the eight transformed corners
+    pub fn transform(&self, mat: &mat4::Mat4) -> AABB {
+        if !self.is_finite() {
+            return *self;
+        }
+
+        let mut aabb = AABB::empty();
+        for i in 0..8 {
+            let corner = vec3::Vec3::new(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            );
+            aabb.merge(&(mat * vec4::Vec4::from_vec3(&corner, 1.0)).to_vec3());
+        }
+
+        return aabb;
+    }
+
+    // returns the distances along the ray where it enters and leaves the box
+    pub fn intersect_ray(&self, ray: &ray::Ray, max_distance: f32) -> Option<(f32, f32)> {
+        let direction = ray.direction();
+        let inverse_direction =
+            vec3::Vec3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z);
+        return self.intersect_inverse_ray(ray.origin(), &inverse_direction, max_distance);
+    }
+
+    // slab test with the reciprocal of the ray direction, so traversals can compute it once per ray
+    pub fn intersect_inverse_ray(
+        &self,
+        origin: &vec3::Vec3,
+        inverse_direction: &vec3::Vec3,
+        max_distance: f32,
+    ) -> Option<(f32, f32)> {
+        let mut t_near = 0.0;
+        let mut t_far = max_distance;
+        for axis in 0..3 {
+            let mut t0 = (self.min[axis] - origin[axis]) * inverse_direction[axis];
+            let mut t1 = (self.max[axis] - origin[axis]) * inverse_direction[axis];
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            // nan from a ray lying on a slab plane is ignored by min and max
+            t_near = f32::max(t0, t_near);
+            t_far = f32::min(t1 * SLAB_ERROR_SCALE, t_far);
+            if t_near > t_far {
+                return None;
+            }
+        }
+
+        return Some((t_near, t_far));
+    }
 }
 
 #[cfg(test)]
@@ -86,4 +221,70 @@ mod test {
         assert!(math::equal_epsilon_f32(center.y, 3.0, math::EPSILON_F32_5));
         assert!(math::equal_epsilon_f32(center.z, 4.0, math::EPSILON_F32_5));
     }
+
+    #[test]
+    fn test_surface_area_and_transform() {
+        let aabb = AABB::new(
+            vec3::Vec3::new(-1.0, -1.0, -1.0),
+            vec3::Vec3::new(1.0, 2.0, 1.0),
+        );
+        assert!(math::equal_epsilon_f32(
+            aabb.surface_area(),
+            32.0,
+            math::EPSILON_F32_5
+        ));
+        assert_eq!(aabb.max_extent(), 1);
+        assert!(math::equal_epsilon_f32(
+            AABB::empty().surface_area(),
+            0.0,
+            math::EPSILON_F32_5
+        ));
+
+        let rotated = aabb.transform(
+            &mat4::Mat4::new()
+                .translate(&vec3::Vec3::new(5.0, 0.0, 0.0))
+                .rotate(math::PI_F32 / 2.0, &vec3::Vec3::new(0.0, 0.0, 1.0)),
+        );
+        assert!(rotated
+            .min()
+            .equal_epsilon(&vec3::Vec3::new(3.0, -1.0, -1.0), math::EPSILON_F32_5));
+        assert!(rotated
+            .max()
+            .equal_epsilon(&vec3::Vec3::new(6.0, 1.0, 1.0), math::EPSILON_F32_5));
+        assert!(!AABB::infinite().is_finite());
+    }
+
+    #[test]
+    fn test_intersect_ray() {
+        let aabb = AABB::new(
+            vec3::Vec3::new(-1.0, -1.0, -1.0),
+            vec3::Vec3::new(1.0, 1.0, 1.0),
+        );
+
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.5, 0.0, 5.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        let (t_near, t_far) = aabb.intersect_ray(&ray, f32::MAX).unwrap();
+        assert!(math::equal_epsilon_f32(t_near, 4.0, math::EPSILON_F32_5));
+        assert!(math::equal_epsilon_f32(t_far, 6.0, math::EPSILON_F32_5));
+        assert!(aabb.intersect_ray(&ray, 3.0).is_none());
+
+        // rays starting inside enter at zero
+        let ray = ray::Ray::new(vec3::Vec3::from(0.0), vec3::Vec3::new(1.0, 1.0, 0.0));
+        let (t_near, _) = aabb.intersect_ray(&ray, f32::MAX).unwrap();
+        assert!(math::equal_epsilon_f32(t_near, 0.0, math::EPSILON_F32_5));
+
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(2.0, 0.0, 5.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        assert!(aabb.intersect_ray(&ray, f32::MAX).is_none());
+
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.0, 0.0, 5.0),
+            vec3::Vec3::new(0.0, 0.0, 1.0),
+        );
+        assert!(aabb.intersect_ray(&ray, f32::MAX).is_none());
+    }
 }
@@ -0,0 +1,251 @@
+pub mod bvh;
+pub mod kd_tree;
+pub mod lbvh;
+pub mod linear;
+
+use crate::scene::aabb;
+use crate::scene::ray;
+
+// spatial structures over primitives that are given by their bounds and referred to by their index
+pub trait Accelerator {
+    fn aabb(&self) -> aabb::AABB;
+
+    // intersect receives a primitive and the distance of the closest hit so far, it returns the
+    // distance of a closer hit. Returns the distance of the closest hit
+    fn closest_hit(
+        &self,
+        ray: &ray::Ray,
+        intersect: &mut dyn FnMut(usize, f32) -> Option<f32>,
+    ) -> Option<f32>;
+
+    // stops at the first primitive that is_intersect reports as hit
+    fn any_hit(
+        &self,
+        ray: &ray::Ray,
+        max_distance: f32,
+        is_intersect: &mut dyn FnMut(usize) -> bool,
+    ) -> bool;
+
+    // updates the structure for primitives that moved, primitive_bounds holds the new bounds of the
+    // same primitives in the same order. Cheaper than a rebuild for the kinds that can keep their
+    // tree, which gets worse the further the primitives move from where they were when it was built
+    fn refit(&mut self, primitive_bounds: &[aabb::AABB]);
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AcceleratorKind {
+    Linear,
+    Bvh,
+    Lbvh,
+    KdTree,
+}
+
+pub fn create_accelerator(
+    kind: AcceleratorKind,
+    primitive_bounds: &[aabb::AABB],
+) -> Box<dyn Accelerator> {
+    return match kind {
+        AcceleratorKind::Linear => Box::new(linear::Linear::new(primitive_bounds)),
+        AcceleratorKind::Bvh => Box::new(bvh::Bvh::new(primitive_bounds)),
+        AcceleratorKind::Lbvh => Box::new(lbvh::LbvhBuilder::new().build(primitive_bounds)),
+        AcceleratorKind::KdTree => Box::new(kd_tree::KdTree::new(primitive_bounds)),
+    };
+}
+
+// intersect returns the hit of a primitive and its distance along the ray, hits that are not closer
+// than the closest one so far are ignored
+pub fn find_closest_hit<T, F>(
+    accelerator: &dyn Accelerator,
+    ray: &ray::Ray,
+    mut intersect: F,
+) -> Option<T>
+where
+    F: FnMut(usize, f32) -> Option<(T, f32)>,
+{
+    let mut closest: Option<T> = None;
+    accelerator.closest_hit(ray, &mut |primitive_idx, max_distance| {
+        let (hit, distance) = intersect(primitive_idx, max_distance)?;
+        if distance >= max_distance {
+            return None;
+        }
+
+        closest = Some(hit);
+        return Some(distance);
+    });
+
+    return closest;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::vec3;
+    use rand::Rng;
+    use rand::SeedableRng;
+
+    const KINDS: [AcceleratorKind; 4] = [
+        AcceleratorKind::Linear,
+        AcceleratorKind::Bvh,
+        AcceleratorKind::Lbvh,
+        AcceleratorKind::KdTree,
+    ];
+
+    fn cube_bounds(center: &vec3::Vec3, half_size: f32) -> aabb::AABB {
+        let half_extent = vec3::Vec3::from(half_size);
+        return aabb::AABB::new(center - half_extent, center + half_extent);
+    }
+
+    // cubes hit by the ray with the distance where the ray enters them
+    fn brute_force_hits(bounds: &[aabb::AABB], ray: &ray::Ray) -> Vec<(usize, f32)> {
+        return bounds
+            .iter()
+            .enumerate()
+            .filter_map(|(i, aabb)| {
+                aabb.intersect_ray(ray, f32::INFINITY)
+                    .map(|(t_near, _)| (i, t_near))
+            })
+            .collect();
+    }
+
+    #[test]
+    fn test_closest_hit_matches_brute_force() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let bounds: Vec<aabb::AABB> = (0..500)
+            .map(|_| {
+                let center = vec3::Vec3::new(
+                    rng.gen_range(-20.0, 20.0),
+                    rng.gen_range(-20.0, 20.0),
+                    rng.gen_range(-20.0, 20.0),
+                );
+                cube_bounds(&center, rng.gen_range(0.1, 1.0))
+            })
+            .collect();
+        let accelerators: Vec<Box<dyn Accelerator>> = KINDS
+            .iter()
+            .map(|kind| create_accelerator(*kind, &bounds))
+            .collect();
+
+        for _ in 0..200 {
+            let origin = vec3::Vec3::new(
+                rng.gen_range(-30.0, 30.0),
+                rng.gen_range(-30.0, 30.0),
+                -40.0,
+            );
+            let target = vec3::Vec3::new(
+                rng.gen_range(-20.0, 20.0),
+                rng.gen_range(-20.0, 20.0),
+                rng.gen_range(-20.0, 20.0),
+            );
+            let ray = ray::Ray::new(origin, target - origin);
+
+            let hits = brute_force_hits(&bounds, &ray);
+            let expected =
+                hits.iter()
+                    .fold(None, |closest: Option<(usize, f32)>, hit| match closest {
+                        Some(closest) if closest.1 <= hit.1 => Some(closest),
+                        _ => Some(*hit),
+                    });
+            let max_distance = 30.0;
+            let expected_any = hits.iter().any(|(_, t_near)| *t_near < max_distance);
+
+            for accelerator in accelerators.iter() {
+                let closest = find_closest_hit(accelerator.as_ref(), &ray, |i, _| {
+                    bounds[i]
+                        .intersect_ray(&ray, f32::INFINITY)
+                        .map(|(t_near, _)| (i, t_near))
+                });
+                assert_eq!(closest, expected.map(|(i, _)| i));
+
+                let any = accelerator.any_hit(&ray, max_distance, &mut |i| {
+                    bounds[i].intersect_ray(&ray, max_distance).is_some()
+                });
+                assert_eq!(any, expected_any);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unbounded_primitives() {
+        let bounds = vec![
+            cube_bounds(&vec3::Vec3::new(0.0, 0.0, 5.0), 1.0),
+            aabb::AABB::infinite(),
+        ];
+        let ray = ray::Ray::new(vec3::Vec3::from(0.0), vec3::Vec3::new(0.0, 0.0, 1.0));
+
+        for kind in KINDS.iter() {
+            let accelerator = create_accelerator(*kind, &bounds);
+            assert!(!accelerator.aabb().is_finite());
+
+            // the unbounded primitive is a plane at z = 2
+            let closest = find_closest_hit(accelerator.as_ref(), &ray, |i, _| match i {
+                0 => Some((0, 4.0)),
+                _ => Some((1, 2.0)),
+            });
+            assert_eq!(closest, Some(1));
+
+            let mut visited = Vec::new();
+            assert!(!accelerator.any_hit(&ray, 10.0, &mut |i| {
+                visited.push(i);
+                false
+            }));
+            visited.sort();
+            assert_eq!(visited, vec![0, 1]);
+        }
+    }
+
+    #[test]
+    fn test_refit() {
+        let centers: Vec<vec3::Vec3> = (0..50)
+            .map(|i| vec3::Vec3::new(3.0 * i as f32, 0.0, 0.0))
+            .collect();
+        let bounds: Vec<aabb::AABB> = centers
+            .iter()
+            .map(|center| cube_bounds(center, 0.5))
+            .collect();
+
+        // the cubes are mirrored, so the primitive in front of the ray is the other way round
+        let moved_bounds: Vec<aabb::AABB> = centers
+            .iter()
+            .map(|center| cube_bounds(&vec3::Vec3::new(-center.x, 5.0, 0.0), 0.5))
+            .collect();
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(10.0, 5.0, 0.0),
+            vec3::Vec3::new(-1.0, 0.0, 0.0),
+        );
+        let old_ray = ray::Ray::new(
+            vec3::Vec3::new(-10.0, 0.0, 0.0),
+            vec3::Vec3::new(1.0, 0.0, 0.0),
+        );
+
+        for kind in KINDS.iter() {
+            let mut accelerator = create_accelerator(*kind, &bounds);
+            accelerator.refit(&moved_bounds);
+            assert!(accelerator
+                .aabb()
+                .min()
+                .equal_epsilon(&vec3::Vec3::new(-147.5, 4.5, -0.5), 1e-6));
+
+            let closest = find_closest_hit(accelerator.as_ref(), &ray, |i, _| {
+                moved_bounds[i]
+                    .intersect_ray(&ray, f32::INFINITY)
+                    .map(|(t_near, _)| (i, t_near))
+            });
+            assert_eq!(closest, Some(0));
+            assert!(!accelerator.any_hit(&old_ray, f32::INFINITY, &mut |i| {
+                moved_bounds[i]
+                    .intersect_ray(&old_ray, f32::INFINITY)
+                    .is_some()
+            }));
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let ray = ray::Ray::new(vec3::Vec3::from(0.0), vec3::Vec3::new(0.0, 0.0, 1.0));
+        for kind in KINDS.iter() {
+            let accelerator = create_accelerator(*kind, &[]);
+            assert!(find_closest_hit(accelerator.as_ref(), &ray, |i, _| Some((i, 1.0))).is_none());
+            assert!(!accelerator.any_hit(&ray, 10.0, &mut |_| true));
+        }
+    }
+}
@@ -0,0 +1,410 @@
+use crate::core::vec3;
+use crate::scene::aabb;
+use crate::scene::accelerator;
+use crate::scene::ray;
+
+const MAX_PRIMITIVES_IN_NODE: usize = 4;
+const NUM_BUCKETS: usize = 12;
+
+// cost of visiting an interior node relative to intersecting one primitive
+const TRAVERSAL_COST: f32 = 0.125;
+
+// a leaf refers to count primitives starting at offset, an interior node has its first child right
+// after it and its second child at offset
+#[derive(Copy, Clone, Debug)]
+pub(super) struct BvhNode {
+    pub(super) aabb: aabb::AABB,
+    pub(super) offset: usize,
+    pub(super) count: usize,
+    pub(super) axis: usize,
+}
+
+// bounding volume hierarchy over primitives given by their bounds, split with the surface area
+// heuristic. Primitives without finite bounds are kept aside and tested by every ray
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    primitive_indices: Vec<usize>,
+    unbounded_primitives: Vec<usize>,
+}
+
+impl Bvh {
+    pub fn new(primitive_bounds: &[aabb::AABB]) -> Bvh {
+        let (mut primitive_indices, unbounded_primitives): (Vec<usize>, Vec<usize>) =
+            (0..primitive_bounds.len()).partition(|i| primitive_bounds[*i].is_finite());
+        let centroids: Vec<vec3::Vec3> = primitive_bounds
+            .iter()
+            .map(|bounds| bounds.center())
+            .collect();
+
+        let mut nodes = Vec::with_capacity(2 * primitive_indices.len());
+        if !primitive_indices.is_empty() {
+            build_node(
+                &mut nodes,
+                primitive_bounds,
+                &centroids,
+                &mut primitive_indices,
+                0,
+            );
+        }
+
+        return Bvh {
+            nodes,
+            primitive_indices,
+            unbounded_primitives,
+        };
+    }
+
+    // nodes in the layout above with primitive_indices ordered by the leaves, for other builders
+    pub(super) fn from_nodes(
+        nodes: Vec<BvhNode>,
+        primitive_indices: Vec<usize>,
+        unbounded_primitives: Vec<usize>,
+    ) -> Bvh {
+        return Bvh {
+            nodes,
+            primitive_indices,
+            unbounded_primitives,
+        };
+    }
+
+    // visits the primitives of the leaves the ray passes through front to back, until visit returns
+    // true. visit can shorten max_distance to skip the nodes behind a hit
+    fn traverse<F>(&self, ray: &ray::Ray, max_distance: &mut f32, mut visit: F) -> bool
+    where
+        F: FnMut(usize, &mut f32) -> bool,
+    {
+        if self.nodes.is_empty() {
+            return false;
+        }
+
+        let origin = ray.origin();
+        let direction = ray.direction();
+        let inverse_direction =
+            vec3::Vec3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z);
+        let direction_is_negative = [
+            inverse_direction.x < 0.0,
+            inverse_direction.y < 0.0,
+            inverse_direction.z < 0.0,
+        ];
+
+        let mut nodes_to_visit: Vec<usize> = Vec::with_capacity(64);
+        let mut current_node_idx = 0;
+        loop {
+            let node = &self.nodes[current_node_idx];
+            let is_node_hit = node
+                .aabb
+                .intersect_inverse_ray(origin, &inverse_direction, *max_distance)
+                .is_some();
+            if is_node_hit {
+                if node.count == 0 {
+                    // the child on the side the ray comes from is visited first
+                    if direction_is_negative[node.axis] {
+                        nodes_to_visit.push(current_node_idx + 1);
+                        current_node_idx = node.offset;
+                    } else {
+                        nodes_to_visit.push(node.offset);
+                        current_node_idx += 1;
+                    }
+
+                    continue;
+                }
+
+                for primitive_idx in
+                    self.primitive_indices[node.offset..node.offset + node.count].iter()
+                {
+                    if visit(*primitive_idx, max_distance) {
+                        return true;
+                    }
+                }
+            }
+
+            match nodes_to_visit.pop() {
+                Some(node_idx) => current_node_idx = node_idx,
+                None => return false,
+            }
+        }
+    }
+}
+
+impl accelerator::Accelerator for Bvh {
+    fn aabb(&self) -> aabb::AABB {
+        if !self.unbounded_primitives.is_empty() {
+            return aabb::AABB::infinite();
+        }
+
+        return match self.nodes.first() {
+            Some(root) => root.aabb,
+            None => aabb::AABB::empty(),
+        };
+    }
+
+    fn closest_hit(
+        &self,
+        ray: &ray::Ray,
+        intersect: &mut dyn FnMut(usize, f32) -> Option<f32>,
+    ) -> Option<f32> {
+        let mut closest: Option<f32> = None;
+        let mut max_distance = f32::INFINITY;
+        let mut visit = |primitive_idx: usize, max_distance: &mut f32| {
+            if let Some(distance) = intersect(primitive_idx, *max_distance) {
+                *max_distance = distance;
+                closest = Some(distance);
+            }
+
+            return false;
+        };
+
+        for primitive_idx in self.unbounded_primitives.iter() {
+            visit(*primitive_idx, &mut max_distance);
+        }
+
+        self.traverse(ray, &mut max_distance, visit);
+        return closest;
+    }
+
+    fn any_hit(
+        &self,
+        ray: &ray::Ray,
+        max_distance: f32,
+        is_intersect: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        if self
+            .unbounded_primitives
+            .iter()
+            .any(|primitive_idx| is_intersect(*primitive_idx))
+        {
+            return true;
+        }
+
+        let mut max_distance = max_distance;
+        return self.traverse(ray, &mut max_distance, |primitive_idx, _| {
+            is_intersect(primitive_idx)
+        });
+    }
+
+    // recomputes the bounds of the nodes bottom up, keeping the tree. Primitives that were unbounded
+    // at build time stay aside
+    fn refit(&mut self, primitive_bounds: &[aabb::AABB]) {
+        // children are stored after their parent
+        for node_idx in (0..self.nodes.len()).rev() {
+            let node = self.nodes[node_idx];
+            let mut node_aabb = aabb::AABB::empty();
+            if node.count == 0 {
+                node_aabb.merge_aabb(&self.nodes[node_idx + 1].aabb);
+                node_aabb.merge_aabb(&self.nodes[node.offset].aabb);
+            } else {
+                for primitive_idx in
+                    self.primitive_indices[node.offset..node.offset + node.count].iter()
+                {
+                    node_aabb.merge_aabb(&primitive_bounds[*primitive_idx]);
+                }
+            }
+
+            self.nodes[node_idx].aabb = node_aabb;
+        }
+    }
+}
+
+fn build_node(
+    nodes: &mut Vec<BvhNode>,
+    primitive_bounds: &[aabb::AABB],
+    centroids: &[vec3::Vec3],
+    primitive_indices: &mut [usize],
+    offset: usize,
+) -> usize {
+    let node_idx = nodes.len();
+    let mut node_aabb = aabb::AABB::empty();
+    let mut centroid_aabb = aabb::AABB::empty();
+    for primitive_idx in primitive_indices.iter() {
+        node_aabb.merge_aabb(&primitive_bounds[*primitive_idx]);
+        centroid_aabb.merge(&centroids[*primitive_idx]);
+    }
+
+    let count = primitive_indices.len();
+    let axis = centroid_aabb.max_extent();
+    nodes.push(BvhNode {
+        aabb: node_aabb,
+        offset,
+        count,
+        axis,
+    });
+
+    // primitives with the same centroid cannot be told apart by a split
+    if count == 1 || centroid_aabb.min()[axis] == centroid_aabb.max()[axis] {
+        return node_idx;
+    }
+
+    let mid = match find_split(
+        primitive_bounds,
+        centroids,
+        primitive_indices,
+        &node_aabb,
+        &centroid_aabb,
+        axis,
+    ) {
+        Some(mid) => mid,
+        None => return node_idx,
+    };
+
+    build_node(
+        nodes,
+        primitive_bounds,
+        centroids,
+        &mut primitive_indices[..mid],
+        offset,
+    );
+    let second_child_idx = build_node(
+        nodes,
+        primitive_bounds,
+        centroids,
+        &mut primitive_indices[mid..],
+        offset + mid,
+    );
+
+    nodes[node_idx].offset = second_child_idx;
+    nodes[node_idx].count = 0;
+    return node_idx;
+}
+
+// partitions the primitives along the axis and returns how many are on the first side, or None when
+// a leaf is cheaper than any split
+fn find_split(
+    primitive_bounds: &[aabb::AABB],
+    centroids: &[vec3::Vec3],
+    primitive_indices: &mut [usize],
+    node_aabb: &aabb::AABB,
+    centroid_aabb: &aabb::AABB,
+    axis: usize,
+) -> Option<usize> {
+    let count = primitive_indices.len();
+    let node_area = node_aabb.surface_area();
+    if count <= 2 || node_area <= 0.0 {
+        return Some(split_equal_counts(centroids, primitive_indices, axis));
+    }
+
+    let centroid_min = centroid_aabb.min()[axis];
+    let centroid_extent = centroid_aabb.max()[axis] - centroid_min;
+    let bucket_of = |primitive_idx: usize| {
+        let ratio = (centroids[primitive_idx][axis] - centroid_min) / centroid_extent;
+        return usize::min((ratio * NUM_BUCKETS as f32) as usize, NUM_BUCKETS - 1);
+    };
+
+    let mut bucket_counts = [0usize; NUM_BUCKETS];
+    let mut bucket_bounds = [aabb::AABB::empty(); NUM_BUCKETS];
+    for primitive_idx in primitive_indices.iter() {
+        let bucket = bucket_of(*primitive_idx);
+        bucket_counts[bucket] += 1;
+        bucket_bounds[bucket].merge_aabb(&primitive_bounds[*primitive_idx]);
+    }
+
+    // the cost of splitting after each bucket, from sweeps in both directions
+    let mut costs = [0.0f32; NUM_BUCKETS - 1];
+    let mut below_count = 0;
+    let mut below_bounds = aabb::AABB::empty();
+    for split in 0..(NUM_BUCKETS - 1) {
+        below_count += bucket_counts[split];
+        below_bounds.merge_aabb(&bucket_bounds[split]);
+        costs[split] = below_count as f32 * below_bounds.surface_area();
+    }
+
+    let mut above_count = 0;
+    let mut above_bounds = aabb::AABB::empty();
+    for split in (0..(NUM_BUCKETS - 1)).rev() {
+        above_count += bucket_counts[split + 1];
+        above_bounds.merge_aabb(&bucket_bounds[split + 1]);
+        costs[split] += above_count as f32 * above_bounds.surface_area();
+    }
+
+    let (best_split, best_cost) = costs
+        .iter()
+        .enumerate()
+        .map(|(split, cost)| (split, TRAVERSAL_COST + cost / node_area))
+        .fold((0, f32::INFINITY), |best, candidate| {
+            if candidate.1 < best.1 {
+                candidate
+            } else {
+                best
+            }
+        });
+
+    let leaf_cost = count as f32;
+    if count <= MAX_PRIMITIVES_IN_NODE && best_cost >= leaf_cost {
+        return None;
+    }
+
+    let mut mid = 0;
+    for i in 0..count {
+        if bucket_of(primitive_indices[i]) <= best_split {
+            primitive_indices.swap(i, mid);
+            mid += 1;
+        }
+    }
+
+    if mid == 0 || mid == count {
+        return Some(split_equal_counts(centroids, primitive_indices, axis));
+    }
+
+    return Some(mid);
+}
+
+fn split_equal_counts(
+    centroids: &[vec3::Vec3],
+    primitive_indices: &mut [usize],
+    axis: usize,
+) -> usize {
+    primitive_indices.sort_by(|lhs, rhs| {
+        centroids[*lhs][axis]
+            .partial_cmp(&centroids[*rhs][axis])
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    return primitive_indices.len() / 2;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scene::accelerator::Accelerator;
+
+    fn cube_bounds(center: &vec3::Vec3) -> aabb::AABB {
+        let half_extent = vec3::Vec3::from(0.5);
+        return aabb::AABB::new(center - half_extent, center + half_extent);
+    }
+
+    #[test]
+    fn test_refit() {
+        let centers: Vec<vec3::Vec3> = (0..20)
+            .map(|i| vec3::Vec3::new(2.0 * i as f32, 0.0, 0.0))
+            .collect();
+        let bounds: Vec<aabb::AABB> = centers.iter().map(cube_bounds).collect();
+        let mut bvh = Bvh::new(&bounds);
+
+        // every cube moves up, except the last one that moves to the origin in front of the others
+        let mut moved_bounds: Vec<aabb::AABB> = centers
+            .iter()
+            .map(|center| cube_bounds(&(center + vec3::Vec3::new(0.0, 10.0, 0.0))))
+            .collect();
+        moved_bounds[19] = cube_bounds(&vec3::Vec3::new(-2.0, 10.0, 0.0));
+        bvh.refit(&moved_bounds);
+
+        assert!(bvh
+            .aabb()
+            .min()
+            .equal_epsilon(&vec3::Vec3::new(-2.5, 9.5, -0.5), 1e-6));
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(-10.0, 10.0, 0.0),
+            vec3::Vec3::new(1.0, 0.0, 0.0),
+        );
+        let closest = accelerator::find_closest_hit(&bvh, &ray, |i, _| {
+            moved_bounds[i]
+                .intersect_ray(&ray, f32::INFINITY)
+                .map(|(t_near, _)| (i, t_near))
+        });
+        assert_eq!(closest, Some(19));
+
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(-10.0, 0.0, 0.0),
+            vec3::Vec3::new(1.0, 0.0, 0.0),
+        );
+        assert!(!bvh.any_hit(&ray, f32::INFINITY, &mut |_| true));
+    }
+}
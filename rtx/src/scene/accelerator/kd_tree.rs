@@ -0,0 +1,406 @@
+use crate::core::vec3;
+use crate::scene::aabb;
+use crate::scene::accelerator;
+use crate::scene::ray;
+
+// costs of intersecting a primitive and of visiting an interior node
+const INTERSECTION_COST: f32 = 80.0;
+const TRAVERSAL_COST: f32 = 1.0;
+
+// splits that cut off empty space are favored by reducing their cost by this ratio
+const EMPTY_BONUS: f32 = 0.5;
+
+const MAX_PRIMITIVES_IN_NODE: usize = 1;
+
+// splits that are more expensive than a leaf are still tried this many times along a path, since
+// the splits below them may pay off
+const MAX_BAD_REFINES: usize = 3;
+
+#[derive(Copy, Clone, Debug)]
+enum KdNode {
+    Leaf {
+        offset: usize,
+        count: usize,
+    },
+    // the child below the split follows its parent
+    Interior {
+        axis: usize,
+        split: f32,
+        above_child: usize,
+    },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+enum EdgeType {
+    Start,
+    End,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct BoundEdge {
+    position: f32,
+    primitive_idx: usize,
+    edge_type: EdgeType,
+}
+
+// kd-tree over primitives given by their bounds, split with the surface area heuristic. A primitive
+// is referenced by every leaf it overlaps. Primitives without finite bounds are kept aside and tested
+// by every ray
+pub struct KdTree {
+    nodes: Vec<KdNode>,
+    primitive_indices: Vec<usize>,
+    unbounded_primitives: Vec<usize>,
+    aabb: aabb::AABB,
+}
+
+impl KdTree {
+    pub fn new(primitive_bounds: &[aabb::AABB]) -> KdTree {
+        let (bounded_primitives, unbounded_primitives): (Vec<usize>, Vec<usize>) =
+            (0..primitive_bounds.len()).partition(|i| primitive_bounds[*i].is_finite());
+
+        let mut aabb = aabb::AABB::empty();
+        for primitive_idx in bounded_primitives.iter() {
+            aabb.merge_aabb(&primitive_bounds[*primitive_idx]);
+        }
+
+        let mut kd_tree = KdTree {
+            nodes: Vec::new(),
+            primitive_indices: Vec::new(),
+            unbounded_primitives,
+            aabb,
+        };
+
+        if !bounded_primitives.is_empty() {
+            let num_primitives = bounded_primitives.len() as f32;
+            let max_depth = f32::round(8.0 + 1.3 * f32::log2(num_primitives)) as usize;
+            kd_tree.build_node(primitive_bounds, &aabb, bounded_primitives, max_depth, 0);
+        }
+
+        return kd_tree;
+    }
+
+    fn build_node(
+        &mut self,
+        primitive_bounds: &[aabb::AABB],
+        node_aabb: &aabb::AABB,
+        primitives: Vec<usize>,
+        depth: usize,
+        bad_refines: usize,
+    ) {
+        let node_idx = self.nodes.len();
+        let count = primitives.len();
+        if count <= MAX_PRIMITIVES_IN_NODE || depth == 0 {
+            self.push_leaf(primitives);
+            return;
+        }
+
+        let total_area = node_aabb.surface_area();
+        if total_area <= 0.0 {
+            self.push_leaf(primitives);
+            return;
+        }
+
+        let leaf_cost = INTERSECTION_COST * count as f32;
+        let (axis, edges, split_edge_idx, split_cost) =
+            match find_split(primitive_bounds, node_aabb, &primitives) {
+                Some(split) => split,
+                None => {
+                    self.push_leaf(primitives);
+                    return;
+                }
+            };
+
+        let bad_refines = if split_cost > leaf_cost {
+            bad_refines + 1
+        } else {
+            bad_refines
+        };
+        if (split_cost > 4.0 * leaf_cost && count < 16) || bad_refines == MAX_BAD_REFINES {
+            self.push_leaf(primitives);
+            return;
+        }
+
+        // primitives that start before the split overlap the space below it, the ones that end after
+        // it overlap the space above it
+        let below_primitives: Vec<usize> = edges[..split_edge_idx]
+            .iter()
+            .filter(|edge| edge.edge_type == EdgeType::Start)
+            .map(|edge| edge.primitive_idx)
+            .collect();
+        let above_primitives: Vec<usize> = edges[split_edge_idx + 1..]
+            .iter()
+            .filter(|edge| edge.edge_type == EdgeType::End)
+            .map(|edge| edge.primitive_idx)
+            .collect();
+
+        let split = edges[split_edge_idx].position;
+        let mut below_max = *node_aabb.max();
+        below_max[axis] = split;
+        let mut above_min = *node_aabb.min();
+        above_min[axis] = split;
+
+        self.nodes.push(KdNode::Interior {
+            axis,
+            split,
+            above_child: 0,
+        });
+        self.build_node(
+            primitive_bounds,
+            &aabb::AABB::new(*node_aabb.min(), below_max),
+            below_primitives,
+            depth - 1,
+            bad_refines,
+        );
+
+        let above_child_idx = self.nodes.len();
+        self.nodes[node_idx] = KdNode::Interior {
+            axis,
+            split,
+            above_child: above_child_idx,
+        };
+        self.build_node(
+            primitive_bounds,
+            &aabb::AABB::new(above_min, *node_aabb.max()),
+            above_primitives,
+            depth - 1,
+            bad_refines,
+        );
+    }
+
+    fn push_leaf(&mut self, primitives: Vec<usize>) {
+        self.nodes.push(KdNode::Leaf {
+            offset: self.primitive_indices.len(),
+            count: primitives.len(),
+        });
+        self.primitive_indices.extend(primitives);
+    }
+
+    // visits the primitives of the leaves the ray passes through front to back, until visit returns
+    // true. visit can shorten max_distance to skip the leaves behind a hit
+    fn traverse<F>(&self, ray: &ray::Ray, max_distance: &mut f32, mut visit: F) -> bool
+    where
+        F: FnMut(usize, &mut f32) -> bool,
+    {
+        if self.nodes.is_empty() {
+            return false;
+        }
+
+        let origin = ray.origin();
+        let direction = ray.direction();
+        let inverse_direction =
+            vec3::Vec3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z);
+        let (mut t_min, mut t_max) =
+            match self
+                .aabb
+                .intersect_inverse_ray(origin, &inverse_direction, *max_distance)
+            {
+                Some(range) => range,
+                None => return false,
+            };
+
+        let mut nodes_to_visit: Vec<(usize, f32, f32)> = Vec::with_capacity(64);
+        let mut current_node_idx = 0;
+        loop {
+            // hits found so far are closer than anything left in this node
+            if *max_distance < t_min {
+                return false;
+            }
+
+            match self.nodes[current_node_idx] {
+                KdNode::Interior {
+                    axis,
+                    split,
+                    above_child,
+                } => {
+                    let t_plane = (split - origin[axis]) * inverse_direction[axis];
+                    let below_first =
+                        origin[axis] < split || (origin[axis] == split && direction[axis] <= 0.0);
+                    let (first_child, second_child) = if below_first {
+                        (current_node_idx + 1, above_child)
+                    } else {
+                        (above_child, current_node_idx + 1)
+                    };
+
+                    if t_plane > t_max || t_plane <= 0.0 {
+                        current_node_idx = first_child;
+                    } else if t_plane < t_min {
+                        current_node_idx = second_child;
+                    } else {
+                        nodes_to_visit.push((second_child, t_plane, t_max));
+                        current_node_idx = first_child;
+                        t_max = t_plane;
+                    }
+
+                    continue;
+                }
+                KdNode::Leaf { offset, count } => {
+                    for primitive_idx in self.primitive_indices[offset..offset + count].iter() {
+                        if visit(*primitive_idx, max_distance) {
+                            return true;
+                        }
+                    }
+                }
+            }
+
+            match nodes_to_visit.pop() {
+                Some((node_idx, node_t_min, node_t_max)) => {
+                    current_node_idx = node_idx;
+                    t_min = node_t_min;
+                    t_max = node_t_max;
+                }
+                None => return false,
+            }
+        }
+    }
+}
+
+impl accelerator::Accelerator for KdTree {
+    fn aabb(&self) -> aabb::AABB {
+        if !self.unbounded_primitives.is_empty() {
+            return aabb::AABB::infinite();
+        }
+
+        return self.aabb;
+    }
+
+    fn closest_hit(
+        &self,
+        ray: &ray::Ray,
+        intersect: &mut dyn FnMut(usize, f32) -> Option<f32>,
+    ) -> Option<f32> {
+        let mut closest: Option<f32> = None;
+        let mut max_distance = f32::INFINITY;
+        let mut visit = |primitive_idx: usize, max_distance: &mut f32| {
+            if let Some(distance) = intersect(primitive_idx, *max_distance) {
+                *max_distance = distance;
+                closest = Some(distance);
+            }
+
+            return false;
+        };
+
+        for primitive_idx in self.unbounded_primitives.iter() {
+            visit(*primitive_idx, &mut max_distance);
+        }
+
+        self.traverse(ray, &mut max_distance, visit);
+        return closest;
+    }
+
+    fn any_hit(
+        &self,
+        ray: &ray::Ray,
+        max_distance: f32,
+        is_intersect: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        if self
+            .unbounded_primitives
+            .iter()
+            .any(|primitive_idx| is_intersect(*primitive_idx))
+        {
+            return true;
+        }
+
+        let mut max_distance = max_distance;
+        return self.traverse(ray, &mut max_distance, |primitive_idx, _| {
+            is_intersect(primitive_idx)
+        });
+    }
+
+    // the splits are planes through the old bounds, so the tree is rebuilt
+    fn refit(&mut self, primitive_bounds: &[aabb::AABB]) {
+        *self = KdTree::new(primitive_bounds);
+    }
+}
+
+// sweeps the sorted primitive edges along the longest axis of the node, then along the other axes
+// when no edge lies inside the node. Returns the axis, its sorted edges, the index of the splitting
+// edge and the cost of the split
+fn find_split(
+    primitive_bounds: &[aabb::AABB],
+    node_aabb: &aabb::AABB,
+    primitives: &[usize],
+) -> Option<(usize, Vec<BoundEdge>, usize, f32)> {
+    let count = primitives.len();
+    let diagonal = node_aabb.diagonal();
+    let inverse_total_area = 1.0 / node_aabb.surface_area();
+
+    let mut axis = node_aabb.max_extent();
+    for _ in 0..3 {
+        let mut edges: Vec<BoundEdge> = Vec::with_capacity(2 * count);
+        for primitive_idx in primitives.iter() {
+            let bounds = &primitive_bounds[*primitive_idx];
+            edges.push(BoundEdge {
+                position: bounds.min()[axis],
+                primitive_idx: *primitive_idx,
+                edge_type: EdgeType::Start,
+            });
+            edges.push(BoundEdge {
+                position: bounds.max()[axis],
+                primitive_idx: *primitive_idx,
+                edge_type: EdgeType::End,
+            });
+        }
+
+        edges.sort_by(|lhs, rhs| {
+            lhs.position
+                .partial_cmp(&rhs.position)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(
+                    lhs.edge_type
+                        .partial_cmp(&rhs.edge_type)
+                        .unwrap_or(std::cmp::Ordering::Equal),
+                )
+        });
+
+        let other_axis_0 = (axis + 1) % 3;
+        let other_axis_1 = (axis + 2) % 3;
+        let cross_area = diagonal[other_axis_0] * diagonal[other_axis_1];
+        let cross_perimeter = diagonal[other_axis_0] + diagonal[other_axis_1];
+
+        let mut best_edge_idx: Option<usize> = None;
+        let mut best_cost = f32::INFINITY;
+        let mut below_count = 0;
+        let mut above_count = count;
+        for (edge_idx, edge) in edges.iter().enumerate() {
+            if edge.edge_type == EdgeType::End {
+                above_count -= 1;
+            }
+
+            let position = edge.position;
+            if position > node_aabb.min()[axis] && position < node_aabb.max()[axis] {
+                let below_area =
+                    2.0 * (cross_area + (position - node_aabb.min()[axis]) * cross_perimeter);
+                let above_area =
+                    2.0 * (cross_area + (node_aabb.max()[axis] - position) * cross_perimeter);
+                let bonus = if below_count == 0 || above_count == 0 {
+                    EMPTY_BONUS
+                } else {
+                    0.0
+                };
+                let cost = TRAVERSAL_COST
+                    + INTERSECTION_COST
+                        * (1.0 - bonus)
+                        * (below_area * inverse_total_area * below_count as f32
+                            + above_area * inverse_total_area * above_count as f32);
+
+                if cost < best_cost {
+                    best_edge_idx = Some(edge_idx);
+                    best_cost = cost;
+                }
+            }
+
+            if edge.edge_type == EdgeType::Start {
+                below_count += 1;
+            }
+        }
+
+        if let Some(edge_idx) = best_edge_idx {
+            return Some((axis, edges, edge_idx, best_cost));
+        }
+
+        axis = (axis + 1) % 3;
+    }
+
+    return None;
+}
@@ -0,0 +1,341 @@
+use crate::core::math;
+use crate::core::vec3;
+use crate::scene::aabb;
+use crate::scene::accelerator::bvh;
+use std::sync;
+use std::thread;
+
+// bits of each axis in a morton code
+const MORTON_BITS: u32 = 10;
+const MORTON_SCALE: f32 = (1 << MORTON_BITS) as f32;
+
+// the codes are bucketed by their highest bits before the buckets are sorted on their own threads
+const BUCKET_BITS: u32 = 6;
+const RADIX_BITS: u32 = 8;
+
+const MAX_PRIMITIVES_IN_NODE: usize = 4;
+
+// smaller ranges are not worth a thread
+const MIN_PRIMITIVES_PER_THREAD: usize = 1024;
+
+// linear bounding volume hierarchy builder. Primitives are sorted along a morton curve through their
+// centroids and the tree is split where the codes differ in their highest bit, which is much faster
+// than the surface area heuristic but gives a slower tree. Used for scenes that are rebuilt often,
+// together with Accelerator::refit when only the transforms change
+pub struct LbvhBuilder {
+    num_threads: usize,
+}
+
+impl LbvhBuilder {
+    pub fn new() -> LbvhBuilder {
+        let num_threads = thread::available_parallelism()
+            .map(|num_threads| num_threads.get())
+            .unwrap_or(1);
+        return LbvhBuilder { num_threads };
+    }
+
+    pub fn with_num_threads(mut self, num_threads: usize) -> LbvhBuilder {
+        self.num_threads = usize::max(num_threads, 1);
+        return self;
+    }
+
+    pub fn build(&self, primitive_bounds: &[aabb::AABB]) -> bvh::Bvh {
+        let (bounded_primitives, unbounded_primitives): (Vec<usize>, Vec<usize>) =
+            (0..primitive_bounds.len()).partition(|i| primitive_bounds[*i].is_finite());
+        if bounded_primitives.is_empty() {
+            return bvh::Bvh::from_nodes(Vec::new(), Vec::new(), unbounded_primitives);
+        }
+
+        let mut centroid_aabb = aabb::AABB::empty();
+        let centroids: Vec<(usize, vec3::Vec3)> = bounded_primitives
+            .iter()
+            .map(|primitive_idx| {
+                let centroid = primitive_bounds[*primitive_idx].center();
+                centroid_aabb.merge(&centroid);
+                (*primitive_idx, centroid)
+            })
+            .collect();
+
+        let codes = map_chunks(centroids, self.num_threads, move |chunk| {
+            return chunk
+                .into_iter()
+                .map(|(primitive_idx, centroid)| {
+                    (morton_code(&centroid, &centroid_aabb), primitive_idx)
+                })
+                .collect();
+        });
+        let codes = sync::Arc::new(sort_codes(codes, self.num_threads));
+        let bounds = sync::Arc::new(primitive_bounds.to_vec());
+
+        let thread_depth = (usize::BITS - (self.num_threads - 1).leading_zeros()) as usize;
+        let nodes = build_node(&codes, &bounds, 0, codes.len(), thread_depth);
+        let primitive_indices = codes
+            .iter()
+            .map(|(_, primitive_idx)| *primitive_idx)
+            .collect();
+        return bvh::Bvh::from_nodes(nodes, primitive_indices, unbounded_primitives);
+    }
+}
+
+impl Default for LbvhBuilder {
+    fn default() -> LbvhBuilder {
+        return LbvhBuilder::new();
+    }
+}
+
+// 30 bit morton code of a point in the bounds, interleaved as xyz from the highest bit
+fn morton_code(point: &vec3::Vec3, bounds: &aabb::AABB) -> u32 {
+    let extent = bounds.diagonal();
+    let quantize = |axis: usize| {
+        if extent[axis] <= 0.0 {
+            return 0;
+        }
+
+        let ratio = (point[axis] - bounds.min()[axis]) / extent[axis];
+        return math::clamp(ratio * MORTON_SCALE, 0.0, MORTON_SCALE - 1.0) as u32;
+    };
+
+    return (spread_bits(quantize(0)) << 2)
+        | (spread_bits(quantize(1)) << 1)
+        | spread_bits(quantize(2));
+}
+
+// inserts two zero bits after each of the 10 lowest bits
+fn spread_bits(mut value: u32) -> u32 {
+    value = (value | (value << 16)) & 0x030000ff;
+    value = (value | (value << 8)) & 0x0300f00f;
+    value = (value | (value << 4)) & 0x030c30c3;
+    value = (value | (value << 2)) & 0x09249249;
+    return value;
+}
+
+// the axis a bit of a morton code belongs to
+fn bit_axis(bit: u32) -> usize {
+    return 2 - (bit % 3) as usize;
+}
+
+// splits items into one chunk per thread and concatenates the results in order
+fn map_chunks<T, R, F>(items: Vec<T>, num_threads: usize, f: F) -> Vec<R>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+    F: Fn(Vec<T>) -> Vec<R> + Send + Sync + 'static,
+{
+    if num_threads <= 1 || items.len() < 2 * MIN_PRIMITIVES_PER_THREAD {
+        return f(items);
+    }
+
+    let chunk_size = usize::max(items.len().div_ceil(num_threads), MIN_PRIMITIVES_PER_THREAD);
+    let f = sync::Arc::new(f);
+    let mut items = items;
+    let mut handles = Vec::new();
+    while !items.is_empty() {
+        let rest = items.split_off(usize::min(chunk_size, items.len()));
+        let chunk = std::mem::replace(&mut items, rest);
+        let f = f.clone();
+        handles.push(thread::spawn(move || f(chunk)));
+    }
+
+    return handles
+        .into_iter()
+        .flat_map(|handle| handle.join().unwrap())
+        .collect();
+}
+
+// buckets the codes by their highest bits, then radix sorts the buckets in parallel. Codes that are
+// equal keep their order
+fn sort_codes(codes: Vec<(u32, usize)>, num_threads: usize) -> Vec<(u32, usize)> {
+    let bucket_shift = 3 * MORTON_BITS - BUCKET_BITS;
+    let mut buckets: Vec<Vec<(u32, usize)>> = vec![Vec::new(); 1 << BUCKET_BITS];
+    for code in codes.into_iter() {
+        buckets[(code.0 >> bucket_shift) as usize].push(code);
+    }
+
+    let buckets = map_chunks(buckets, num_threads, move |chunk| {
+        return chunk
+            .into_iter()
+            .map(|bucket| radix_sort(bucket, bucket_shift))
+            .collect();
+    });
+    return buckets.into_iter().flatten().collect();
+}
+
+// least significant digit radix sort of the lowest bits of the codes
+fn radix_sort(mut codes: Vec<(u32, usize)>, num_bits: u32) -> Vec<(u32, usize)> {
+    let num_digits = 1 << RADIX_BITS;
+    let mask = num_digits as u32 - 1;
+    let mut sorted = vec![(0, 0); codes.len()];
+    let mut shift = 0;
+    while shift < num_bits {
+        let mut offsets = vec![0usize; num_digits + 1];
+        for (code, _) in codes.iter() {
+            offsets[((code >> shift) & mask) as usize + 1] += 1;
+        }
+
+        for digit in 0..num_digits {
+            offsets[digit + 1] += offsets[digit];
+        }
+
+        for code in codes.iter() {
+            let digit = ((code.0 >> shift) & mask) as usize;
+            sorted[offsets[digit]] = *code;
+            offsets[digit] += 1;
+        }
+
+        std::mem::swap(&mut codes, &mut sorted);
+        shift += RADIX_BITS;
+    }
+
+    return codes;
+}
+
+// builds the subtree over the sorted codes in start..end with nodes indexed from 0, the two halves
+// are built on their own threads while thread_depth lasts
+fn build_node(
+    codes: &sync::Arc<Vec<(u32, usize)>>,
+    bounds: &sync::Arc<Vec<aabb::AABB>>,
+    start: usize,
+    end: usize,
+    thread_depth: usize,
+) -> Vec<bvh::BvhNode> {
+    let count = end - start;
+    let first_code = codes[start].0;
+    let last_code = codes[end - 1].0;
+    if count <= MAX_PRIMITIVES_IN_NODE {
+        let mut node_aabb = aabb::AABB::empty();
+        for (_, primitive_idx) in codes[start..end].iter() {
+            node_aabb.merge_aabb(&bounds[*primitive_idx]);
+        }
+
+        return vec![bvh::BvhNode {
+            aabb: node_aabb,
+            offset: start,
+            count,
+            axis: 0,
+        }];
+    }
+
+    // split where the highest differing bit changes, primitives with the same code are halved
+    let (mid, axis) = if first_code == last_code {
+        (start + count / 2, 0)
+    } else {
+        let bit = 31 - (first_code ^ last_code).leading_zeros();
+        let mid = start + codes[start..end].partition_point(|(code, _)| (code >> bit) & 1 == 0);
+        (mid, bit_axis(bit))
+    };
+
+    let (first_nodes, second_nodes) = if thread_depth > 0 && count >= 2 * MIN_PRIMITIVES_PER_THREAD
+    {
+        let thread_codes = codes.clone();
+        let thread_bounds = bounds.clone();
+        let handle = thread::spawn(move || {
+            build_node(&thread_codes, &thread_bounds, start, mid, thread_depth - 1)
+        });
+        let second_nodes = build_node(codes, bounds, mid, end, thread_depth - 1);
+        (handle.join().unwrap(), second_nodes)
+    } else {
+        (
+            build_node(codes, bounds, start, mid, thread_depth),
+            build_node(codes, bounds, mid, end, thread_depth),
+        )
+    };
+
+    let mut node_aabb = first_nodes[0].aabb;
+    node_aabb.merge_aabb(&second_nodes[0].aabb);
+    let second_child_idx = 1 + first_nodes.len();
+
+    let mut nodes = Vec::with_capacity(second_child_idx + second_nodes.len());
+    nodes.push(bvh::BvhNode {
+        aabb: node_aabb,
+        offset: second_child_idx,
+        count: 0,
+        axis,
+    });
+    append_nodes(&mut nodes, first_nodes);
+    append_nodes(&mut nodes, second_nodes);
+    return nodes;
+}
+
+// interior nodes refer to their second child by index, which moves with the subtree
+fn append_nodes(nodes: &mut Vec<bvh::BvhNode>, subtree: Vec<bvh::BvhNode>) {
+    let shift = nodes.len();
+    nodes.extend(subtree.into_iter().map(|mut node| {
+        if node.count == 0 {
+            node.offset += shift;
+        }
+
+        node
+    }));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scene::accelerator::Accelerator;
+    use crate::scene::ray;
+    use rand::Rng;
+    use rand::SeedableRng;
+
+    fn random_bounds(num_primitives: usize) -> Vec<aabb::AABB> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        return (0..num_primitives)
+            .map(|_| {
+                let center = vec3::Vec3::new(
+                    rng.gen_range(-50.0, 50.0),
+                    rng.gen_range(-50.0, 50.0),
+                    rng.gen_range(-50.0, 50.0),
+                );
+                let half_extent = vec3::Vec3::from(rng.gen_range(0.1, 1.0));
+                aabb::AABB::new(center - half_extent, center + half_extent)
+            })
+            .collect();
+    }
+
+    #[test]
+    fn test_morton_code() {
+        let bounds = aabb::AABB::new(vec3::Vec3::from(0.0), vec3::Vec3::from(1.0));
+        assert_eq!(morton_code(&vec3::Vec3::from(0.0), &bounds), 0);
+        assert_eq!(morton_code(&vec3::Vec3::from(1.0), &bounds), (1 << 30) - 1);
+        assert_eq!(
+            morton_code(&vec3::Vec3::new(1.0, 0.0, 0.0), &bounds),
+            0x24924924
+        );
+        assert_eq!(bit_axis(29), 0);
+        assert_eq!(bit_axis(28), 1);
+        assert_eq!(bit_axis(27), 2);
+    }
+
+    #[test]
+    fn test_sort_codes() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+        let codes: Vec<(u32, usize)> = (0..5000).map(|i| (rng.gen_range(0, 1 << 30), i)).collect();
+        let mut expected = codes.clone();
+        expected.sort();
+        assert_eq!(sort_codes(codes, 4), expected);
+    }
+
+    #[test]
+    fn test_parallel_build_matches_serial_build() {
+        let bounds = random_bounds(10000);
+        let serial = LbvhBuilder::new().with_num_threads(1).build(&bounds);
+        let parallel = LbvhBuilder::new().with_num_threads(4).build(&bounds);
+
+        let origin = vec3::Vec3::new(0.0, 0.0, -80.0);
+        for i in 0..100 {
+            let target = bounds[i * 97].center();
+            let ray = ray::Ray::new(origin, target - origin);
+            let mut serial_visited = Vec::new();
+            serial.any_hit(&ray, f32::INFINITY, &mut |primitive_idx| {
+                serial_visited.push(primitive_idx);
+                false
+            });
+            let mut parallel_visited = Vec::new();
+            parallel.any_hit(&ray, f32::INFINITY, &mut |primitive_idx| {
+                parallel_visited.push(primitive_idx);
+                false
+            });
+            assert!(serial_visited.contains(&(i * 97)));
+            assert_eq!(serial_visited, parallel_visited);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::scene::aabb;
+use crate::scene::accelerator;
+use crate::scene::ray;
+
+// tests every primitive, as a reference to compare the other accelerators against
+pub struct Linear {
+    num_primitives: usize,
+    aabb: aabb::AABB,
+}
+
+impl Linear {
+    pub fn new(primitive_bounds: &[aabb::AABB]) -> Linear {
+        let mut aabb = aabb::AABB::empty();
+        for bounds in primitive_bounds.iter() {
+            aabb.merge_aabb(bounds);
+        }
+
+        return Linear {
+            num_primitives: primitive_bounds.len(),
+            aabb,
+        };
+    }
+}
+
+impl accelerator::Accelerator for Linear {
+    fn aabb(&self) -> aabb::AABB {
+        return self.aabb;
+    }
+
+    fn closest_hit(
+        &self,
+        _ray: &ray::Ray,
+        intersect: &mut dyn FnMut(usize, f32) -> Option<f32>,
+    ) -> Option<f32> {
+        let mut closest: Option<f32> = None;
+        for primitive_idx in 0..self.num_primitives {
+            if let Some(distance) = intersect(primitive_idx, closest.unwrap_or(f32::INFINITY)) {
+                closest = Some(distance);
+            }
+        }
+
+        return closest;
+    }
+
+    fn any_hit(
+        &self,
+        _ray: &ray::Ray,
+        _max_distance: f32,
+        is_intersect: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        return (0..self.num_primitives).any(is_intersect);
+    }
+
+    fn refit(&mut self, primitive_bounds: &[aabb::AABB]) {
+        *self = Linear::new(primitive_bounds);
+    }
+}
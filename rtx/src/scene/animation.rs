@@ -0,0 +1,180 @@
+pub mod camera_animation;
+pub mod transform_animation;
+
+use std::ops;
+
+// interpolation of the segment that starts at a keyframe
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Interpolation {
+    Linear,
+    Bezier,
+}
+
+// bezier segments use the out handle of their first keyframe and the in handle of their last
+// keyframe as the inner control points, evenly spaced in time
+#[derive(Copy, Clone, Debug)]
+pub struct Keyframe<T> {
+    pub time: f32,
+    pub value: T,
+    pub interpolation: Interpolation,
+    pub in_handle: T,
+    pub out_handle: T,
+}
+
+impl<T: Copy> Keyframe<T> {
+    pub fn linear(time: f32, value: T) -> Keyframe<T> {
+        return Keyframe {
+            time,
+            value,
+            interpolation: Interpolation::Linear,
+            in_handle: value,
+            out_handle: value,
+        };
+    }
+
+    pub fn bezier(time: f32, value: T, in_handle: T, out_handle: T) -> Keyframe<T> {
+        return Keyframe {
+            time,
+            value,
+            interpolation: Interpolation::Bezier,
+            in_handle,
+            out_handle,
+        };
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AnimationCurve<T> {
+    keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T> AnimationCurve<T>
+where
+    T: ops::Add<T, Output = T> + ops::Mul<f32, Output = T> + Copy,
+{
+    // keyframes must be sorted by time
+    pub fn new(keyframes: Vec<Keyframe<T>>) -> AnimationCurve<T> {
+        assert!(!keyframes.is_empty());
+        assert!(keyframes
+            .windows(2)
+            .all(|pair| pair[0].time <= pair[1].time));
+        return AnimationCurve { keyframes };
+    }
+
+    pub fn constant(value: T) -> AnimationCurve<T> {
+        return AnimationCurve::new(vec![Keyframe::linear(0.0, value)]);
+    }
+
+    // the curve holds its first and last value outside of the keyframes
+    pub fn evaluate(&self, time: f32) -> T {
+        let first = &self.keyframes[0];
+        if time <= first.time {
+            return first.value;
+        }
+
+        let last = &self.keyframes[self.keyframes.len() - 1];
+        if time >= last.time {
+            return last.value;
+        }
+
+        let next_idx = self
+            .keyframes
+            .iter()
+            .position(|keyframe| keyframe.time > time)
+            .unwrap();
+        let start = &self.keyframes[next_idx - 1];
+        let end = &self.keyframes[next_idx];
+        let u = (time - start.time) / (end.time - start.time);
+
+        return match start.interpolation {
+            Interpolation::Linear => start.value * (1.0 - u) + end.value * u,
+            Interpolation::Bezier => {
+                let one_minus_u = 1.0 - u;
+                start.value * (one_minus_u * one_minus_u * one_minus_u)
+                    + start.out_handle * (3.0 * one_minus_u * one_minus_u * u)
+                    + end.in_handle * (3.0 * one_minus_u * u * u)
+                    + end.value * (u * u * u)
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+    use crate::core::vec3;
+
+    #[test]
+    fn test_evaluate_linear() {
+        let curve = AnimationCurve::new(vec![
+            Keyframe::linear(1.0, 2.0),
+            Keyframe::linear(3.0, 6.0),
+            Keyframe::linear(4.0, 0.0),
+        ]);
+        assert!(math::equal_epsilon_f32(
+            curve.evaluate(2.0),
+            4.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            curve.evaluate(3.5),
+            3.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            curve.evaluate(0.0),
+            2.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            curve.evaluate(10.0),
+            0.0,
+            math::EPSILON_F32_5
+        ));
+    }
+
+    #[test]
+    fn test_evaluate_bezier() {
+        // ease in and out between 0 and 1
+        let curve = AnimationCurve::new(vec![
+            Keyframe::bezier(0.0, 0.0, 0.0, 0.0),
+            Keyframe::bezier(1.0, 1.0, 1.0, 1.0),
+        ]);
+        assert!(math::equal_epsilon_f32(
+            curve.evaluate(0.5),
+            0.5,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            curve.evaluate(0.25),
+            0.15625,
+            math::EPSILON_F32_5
+        ));
+
+        let curve = AnimationCurve::new(vec![
+            Keyframe::bezier(
+                0.0,
+                vec3::Vec3::from(0.0),
+                vec3::Vec3::from(0.0),
+                vec3::Vec3::new(1.0, 0.0, 0.0),
+            ),
+            Keyframe::linear(2.0, vec3::Vec3::new(2.0, 2.0, 0.0)),
+        ]);
+        let expected = vec3::Vec3::new(1.375, 1.0, 0.0);
+        assert!(curve
+            .evaluate(1.0)
+            .equal_epsilon(&expected, math::EPSILON_F32_5));
+    }
+
+    #[test]
+    fn test_constant() {
+        let curve = AnimationCurve::constant(vec3::Vec3::new(1.0, 2.0, 3.0));
+        assert!(curve
+            .evaluate(-5.0)
+            .equal_epsilon(&vec3::Vec3::new(1.0, 2.0, 3.0), math::EPSILON_F32_6));
+        assert!(curve
+            .evaluate(5.0)
+            .equal_epsilon(&vec3::Vec3::new(1.0, 2.0, 3.0), math::EPSILON_F32_6));
+    }
+}
@@ -0,0 +1,89 @@
+use crate::core::vec3;
+use crate::scene::animation;
+use crate::scene::camera::perspective_camera;
+
+// perspective camera aimed at an animated look at point, view angle in radians
+#[derive(Clone, Debug)]
+pub struct CameraAnimation {
+    location: animation::AnimationCurve<vec3::Vec3>,
+    look_at: animation::AnimationCurve<vec3::Vec3>,
+    up_direction: animation::AnimationCurve<vec3::Vec3>,
+    view_angle: animation::AnimationCurve<f32>,
+}
+
+impl CameraAnimation {
+    pub fn new(
+        location: animation::AnimationCurve<vec3::Vec3>,
+        look_at: animation::AnimationCurve<vec3::Vec3>,
+        up_direction: animation::AnimationCurve<vec3::Vec3>,
+        view_angle: animation::AnimationCurve<f32>,
+    ) -> CameraAnimation {
+        return CameraAnimation {
+            location,
+            look_at,
+            up_direction,
+            view_angle,
+        };
+    }
+
+    pub fn evaluate(
+        &self,
+        time: f32,
+        image_width: usize,
+        image_height: usize,
+    ) -> perspective_camera::PerspectiveCamera {
+        let location = self.location.evaluate(time);
+        let out_direction =
+            vec3::Vec3::normalize(&(self.look_at.evaluate(time) - location)).unwrap();
+        return perspective_camera::PerspectiveCamera::new(
+            location,
+            out_direction,
+            self.up_direction.evaluate(time),
+            self.view_angle.evaluate(time),
+            1.0,
+            image_width,
+            image_height,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+    use crate::core::vec2;
+    use crate::scene::camera;
+    use crate::scene::camera::Camera;
+
+    #[test]
+    fn test_evaluate_turntable() {
+        // a quarter turn around the origin
+        let radius = 4.0;
+        let animation = CameraAnimation::new(
+            animation::AnimationCurve::new(vec![
+                animation::Keyframe::linear(0.0, vec3::Vec3::new(0.0, 0.0, radius)),
+                animation::Keyframe::linear(1.0, vec3::Vec3::new(radius, 0.0, 0.0)),
+            ]),
+            animation::AnimationCurve::constant(vec3::Vec3::from(0.0)),
+            animation::AnimationCurve::constant(vec3::Vec3::new(0.0, 1.0, 0.0)),
+            animation::AnimationCurve::constant(math::degree_to_radian(60.0)),
+        );
+
+        let camera_sample =
+            camera::CameraSample::new(vec2::Vec2::new(50.0, 50.0), vec2::Vec2::new(0.5, 0.5), 0.0);
+        let start = animation.evaluate(0.0, 101, 101);
+        let ray = start.create_ray(&camera_sample).unwrap();
+        assert!(ray
+            .origin()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 0.0, radius), math::EPSILON_F32_5));
+        assert!(ray
+            .direction()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 0.0, -1.0), math::EPSILON_F32_5));
+
+        let end = animation.evaluate(1.0, 101, 101);
+        let ray = end.create_ray(&camera_sample).unwrap();
+        assert!(ray
+            .direction()
+            .equal_epsilon(&vec3::Vec3::new(-1.0, 0.0, 0.0), math::EPSILON_F32_5));
+    }
+}
@@ -0,0 +1,101 @@
+use crate::core::animated_transform;
+use crate::core::mat4;
+use crate::core::vec3;
+use crate::scene::animation;
+
+// rotation is given as euler angles in radians, applied around x, then y, then z
+#[derive(Clone, Debug)]
+pub struct TransformAnimation {
+    translation: animation::AnimationCurve<vec3::Vec3>,
+    rotation: animation::AnimationCurve<vec3::Vec3>,
+    scale: animation::AnimationCurve<vec3::Vec3>,
+}
+
+impl TransformAnimation {
+    pub fn new(
+        translation: animation::AnimationCurve<vec3::Vec3>,
+        rotation: animation::AnimationCurve<vec3::Vec3>,
+        scale: animation::AnimationCurve<vec3::Vec3>,
+    ) -> TransformAnimation {
+        return TransformAnimation {
+            translation,
+            rotation,
+            scale,
+        };
+    }
+
+    pub fn evaluate(&self, time: f32) -> mat4::Mat4 {
+        let rotation = self.rotation.evaluate(time);
+        return mat4::Mat4::new()
+            .translate(&self.translation.evaluate(time))
+            .rotate(rotation.z, &vec3::Vec3::new(0.0, 0.0, 1.0))
+            .rotate(rotation.y, &vec3::Vec3::new(0.0, 1.0, 0.0))
+            .rotate(rotation.x, &vec3::Vec3::new(1.0, 0.0, 0.0))
+            .scale(&self.scale.evaluate(time));
+    }
+
+    // samples the animation over a shutter interval for motion blurred shapes
+    pub fn to_animated_transform(
+        &self,
+        start_time: f32,
+        end_time: f32,
+        num_keyframes: usize,
+    ) -> animated_transform::AnimatedTransform {
+        if num_keyframes < 2 || start_time == end_time {
+            return animated_transform::AnimatedTransform::new(&[(
+                start_time,
+                self.evaluate(start_time),
+            )]);
+        }
+
+        let keyframes: Vec<(f32, mat4::Mat4)> = (0..num_keyframes)
+            .map(|i| {
+                let ratio = (i as f32) / ((num_keyframes - 1) as f32);
+                let time = start_time + ratio * (end_time - start_time);
+                return (time, self.evaluate(time));
+            })
+            .collect();
+        return animated_transform::AnimatedTransform::new(&keyframes);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+
+    #[test]
+    fn test_evaluate() {
+        let animation = TransformAnimation::new(
+            animation::AnimationCurve::new(vec![
+                animation::Keyframe::linear(0.0, vec3::Vec3::new(0.0, 0.0, 0.0)),
+                animation::Keyframe::linear(2.0, vec3::Vec3::new(4.0, 0.0, 0.0)),
+            ]),
+            animation::AnimationCurve::new(vec![
+                animation::Keyframe::linear(0.0, vec3::Vec3::from(0.0)),
+                animation::Keyframe::linear(
+                    2.0,
+                    vec3::Vec3::new(0.0, math::degree_to_radian(180.0), 0.0),
+                ),
+            ]),
+            animation::AnimationCurve::constant(vec3::Vec3::from(2.0)),
+        );
+
+        let expected = mat4::Mat4::new()
+            .translate(&vec3::Vec3::new(2.0, 0.0, 0.0))
+            .rotate(
+                math::degree_to_radian(90.0),
+                &vec3::Vec3::new(0.0, 1.0, 0.0),
+            )
+            .scale(&vec3::Vec3::from(2.0));
+        assert!(animation
+            .evaluate(1.0)
+            .equal_epsilon(&expected, math::EPSILON_F32_5));
+
+        let animated = animation.to_animated_transform(1.0, 1.5, 3);
+        assert!(animated.is_animated());
+        assert!(animated
+            .interpolate(1.25)
+            .equal_epsilon(&animation.evaluate(1.25), math::EPSILON_F32_4));
+    }
+}
@@ -1,7 +1,161 @@
+pub mod aperture;
+pub mod cubemap_camera;
+pub mod equirectangular_camera;
+pub mod exposure;
+pub mod fisheye_camera;
+pub mod lens_distortion;
+pub mod omni_stereo_camera;
+pub mod orthographic_camera;
 pub mod perspective_camera;
+pub mod realistic_camera;
+pub mod sensor;
+pub mod stereo_camera;
 
+use crate::core::math;
+use crate::core::vec2;
 use crate::scene::ray;
 
+#[derive(Copy, Clone, Debug)]
+pub struct CameraSample {
+    pub image_point: vec2::Vec2,
+    pub lens_point: vec2::Vec2,
+    pub time: f32,
+}
+
+impl CameraSample {
+    pub fn new(image_point: vec2::Vec2, lens_point: vec2::Vec2, time: f32) -> CameraSample {
+        return CameraSample {
+            image_point,
+            lens_point,
+            time,
+        };
+    }
+}
+
+// position of an image point on the film as fractions of the image size from its top left corner.
+// Pixels cover the film, pixel x spans [x - 0.5, x + 0.5] of the image point and its center is at
+// (x + 0.5) / width, which every camera uses so they all sample the same film positions
+pub fn calc_film_ratio(
+    image_point: &vec2::Vec2,
+    image_width: usize,
+    image_height: usize,
+) -> vec2::Vec2 {
+    return vec2::Vec2::new(
+        (image_point.x + 0.5) / (image_width as f32),
+        (image_point.y + 0.5) / (image_height as f32),
+    );
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Shutter {
+    open: f32,
+    close: f32,
+    readout_duration: f32,
+}
+
+impl Shutter {
+    pub fn new(open: f32, close: f32) -> Shutter {
+        assert!(open <= close, "shutter must open before it closes");
+        return Shutter {
+            open,
+            close,
+            readout_duration: 0.0,
+        };
+    }
+
+    // rows are exposed one after another from the top of the image, the last row starting
+    // readout_duration after the first
+    pub fn with_rolling_shutter(mut self, readout_duration: f32) -> Shutter {
+        assert!(
+            readout_duration >= 0.0,
+            "readout duration must not be negative"
+        );
+        self.readout_duration = readout_duration;
+        return self;
+    }
+
+    pub fn open(&self) -> f32 {
+        return self.open;
+    }
+
+    pub fn close(&self) -> f32 {
+        return self.close;
+    }
+
+    pub fn readout_duration(&self) -> f32 {
+        return self.readout_duration;
+    }
+
+    // row_ratio is 0 at the top row of the image and 1 at the bottom row
+    pub fn sample_time(&self, sample: f32, row_ratio: f32) -> f32 {
+        return math::lerp(sample, self.open, self.close) + row_ratio * self.readout_duration;
+    }
+}
+
 pub trait Camera {
-    fn create_ray(&self, x: f32, y: f32) -> ray::Ray;
+    fn create_ray(&self, camera_sample: &CameraSample) -> Option<ray::Ray>;
+
+    // true when the red, green and blue channels see the scene through different rays, e.g with
+    // lateral chromatic aberration
+    fn has_channel_rays(&self) -> bool {
+        return false;
+    }
+
+    fn create_channel_ray(
+        &self,
+        camera_sample: &CameraSample,
+        _channel: usize,
+    ) -> Option<ray::Ray> {
+        return self.create_ray(camera_sample);
+    }
+
+    // scale of the radiance written to the film, e.g the exposure of a physical camera
+    fn film_scale(&self) -> f32 {
+        return 1.0;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_global_shutter_time() {
+        let shutter = Shutter::new(1.0, 3.0);
+        assert!(math::equal_epsilon_f32(
+            shutter.sample_time(0.0, 0.0),
+            1.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            shutter.sample_time(0.5, 1.0),
+            2.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            shutter.sample_time(1.0, 0.3),
+            3.0,
+            math::EPSILON_F32_5
+        ));
+    }
+
+    #[test]
+    fn test_rolling_shutter_time() {
+        let shutter = Shutter::new(0.0, 0.5).with_rolling_shutter(2.0);
+        assert!(math::equal_epsilon_f32(
+            shutter.sample_time(0.0, 0.0),
+            0.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            shutter.sample_time(0.0, 0.5),
+            1.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            shutter.sample_time(1.0, 1.0),
+            2.5,
+            math::EPSILON_F32_5
+        ));
+    }
 }
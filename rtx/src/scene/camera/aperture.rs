@@ -0,0 +1,10 @@
+pub mod circular_aperture;
+pub mod mask_aperture;
+pub mod polygonal_aperture;
+
+use crate::core::vec2;
+
+pub trait Aperture {
+    // map a uniform sample to a point on the aperture inside the [-1, 1] square
+    fn sample(&self, sample: &vec2::Vec2) -> vec2::Vec2;
+}
@@ -0,0 +1,23 @@
+use crate::core::sampling;
+use crate::core::vec2;
+use crate::scene::camera::aperture;
+
+pub struct CircularAperture {}
+
+impl CircularAperture {
+    pub fn new() -> CircularAperture {
+        return CircularAperture {};
+    }
+}
+
+impl Default for CircularAperture {
+    fn default() -> CircularAperture {
+        return CircularAperture::new();
+    }
+}
+
+impl aperture::Aperture for CircularAperture {
+    fn sample(&self, sample: &vec2::Vec2) -> vec2::Vec2 {
+        return sampling::sample_concentric_unit_disk(sample).to_vec2();
+    }
+}
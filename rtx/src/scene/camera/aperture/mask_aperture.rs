@@ -0,0 +1,63 @@
+use crate::core::image;
+use crate::core::sampling;
+use crate::core::vec2;
+use crate::scene::camera::aperture;
+
+pub struct MaskAperture {
+    distribution: sampling::Distribution2D,
+}
+
+impl MaskAperture {
+    pub fn new(mask: &image::Image) -> MaskAperture {
+        let width = mask.width();
+        let height = mask.height();
+        let mut func = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let color = mask[y][x];
+                func.push(color.x * 0.2989 + color.y * 0.5870 + color.z * 0.1140);
+            }
+        }
+
+        return MaskAperture {
+            distribution: sampling::Distribution2D::new(&func, width, height),
+        };
+    }
+}
+
+impl aperture::Aperture for MaskAperture {
+    fn sample(&self, sample: &vec2::Vec2) -> vec2::Vec2 {
+        // the first image row is at the top of the aperture
+        let (point, _) = self.distribution.sample_continuous(sample);
+        return vec2::Vec2::new(2.0 * point.x - 1.0, 1.0 - 2.0 * point.y);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+    use crate::core::vec3;
+    use crate::scene::camera::aperture::Aperture;
+
+    #[test]
+    fn test_sample_only_open_pixels() {
+        // only the top left quarter of the mask lets light through
+        let mut mask = image::Image::new(4, 4);
+        for y in 0..2 {
+            for x in 0..2 {
+                mask[y][x] = vec3::Vec3::from(1.0);
+            }
+        }
+
+        let aperture = MaskAperture::new(&mask);
+        for i in 0..8 {
+            for j in 0..8 {
+                let sample = vec2::Vec2::new((i as f32 + 0.5) / 8.0, (j as f32 + 0.5) / 8.0);
+                let point = aperture.sample(&sample);
+                assert!(point.x >= -1.0 && point.x <= math::EPSILON_F32_5);
+                assert!(point.y >= -math::EPSILON_F32_5 && point.y <= 1.0);
+            }
+        }
+    }
+}
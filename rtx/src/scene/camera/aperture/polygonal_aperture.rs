@@ -0,0 +1,71 @@
+use crate::core::math;
+use crate::core::vec2;
+use crate::scene::camera::aperture;
+
+pub struct PolygonalAperture {
+    vertices: Vec<vec2::Vec2>,
+}
+
+impl PolygonalAperture {
+    pub fn new(num_blades: u32, rotation: f32) -> PolygonalAperture {
+        assert!(num_blades >= 3, "aperture needs at least three blades");
+
+        let mut vertices = Vec::with_capacity(num_blades as usize);
+        for i in 0..num_blades {
+            let phi = rotation + 2.0 * math::PI_F32 * (i as f32) / (num_blades as f32);
+            vertices.push(vec2::Vec2::new(f32::cos(phi), f32::sin(phi)));
+        }
+
+        return PolygonalAperture { vertices };
+    }
+
+    pub fn num_blades(&self) -> usize {
+        return self.vertices.len();
+    }
+}
+
+impl aperture::Aperture for PolygonalAperture {
+    fn sample(&self, sample: &vec2::Vec2) -> vec2::Vec2 {
+        // every blade spans a triangle of the same area with the aperture center,
+        // so pick one uniformly and reuse the rest of the sample inside it
+        let num_blades = self.num_blades();
+        let scaled_sample_x = sample.x * (num_blades as f32);
+        let blade = usize::min(scaled_sample_x as usize, num_blades - 1);
+        let u = f32::min(scaled_sample_x - (blade as f32), 1.0);
+
+        let sqrt_u = f32::sqrt(u);
+        let b0 = 1.0 - sqrt_u;
+        let b1 = sample.y * sqrt_u;
+
+        let v0 = self.vertices[blade];
+        let v1 = self.vertices[(blade + 1) % num_blades];
+        return b1 * v0 + (1.0 - b0 - b1) * v1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scene::camera::aperture::Aperture;
+
+    #[test]
+    fn test_sample_inside_polygon() {
+        let aperture = PolygonalAperture::new(6, math::degree_to_radian(15.0));
+        let apothem = f32::cos(math::PI_F32 / 6.0);
+        for i in 0..16 {
+            for j in 0..16 {
+                let sample = vec2::Vec2::new((i as f32 + 0.5) / 16.0, (j as f32 + 0.5) / 16.0);
+                let point = aperture.sample(&sample);
+                assert!(point.length() <= 1.0 + math::EPSILON_F32_5);
+
+                // every point lies inside all the half planes of the blades
+                for blade in 0..aperture.num_blades() {
+                    let phi = math::degree_to_radian(15.0)
+                        + math::PI_F32 * (2.0 * blade as f32 + 1.0) / 6.0;
+                    let edge_normal = vec2::Vec2::new(f32::cos(phi), f32::sin(phi));
+                    assert!(point.dot(&edge_normal) <= apothem + math::EPSILON_F32_5);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use crate::core::vec3;
+use crate::scene::camera;
+use crate::scene::ray;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CubemapFace {
+    Right,
+    Left,
+    Up,
+    Down,
+    Front,
+    Back,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CubemapLayout {
+    // six faces side by side: right, left, up, down, front, back
+    Strip,
+    // two rows of three faces: right, left, up on top and down, front, back below
+    Grid,
+    // four by three cross with the front face at the center, the corner cells are empty
+    HorizontalCross,
+}
+
+impl CubemapLayout {
+    pub fn num_columns(&self) -> usize {
+        return match self {
+            CubemapLayout::Strip => 6,
+            CubemapLayout::Grid => 3,
+            CubemapLayout::HorizontalCross => 4,
+        };
+    }
+
+    pub fn num_rows(&self) -> usize {
+        return match self {
+            CubemapLayout::Strip => 1,
+            CubemapLayout::Grid => 2,
+            CubemapLayout::HorizontalCross => 3,
+        };
+    }
+
+    pub fn face(&self, column: usize, row: usize) -> Option<CubemapFace> {
+        let faces = [
+            CubemapFace::Right,
+            CubemapFace::Left,
+            CubemapFace::Up,
+            CubemapFace::Down,
+            CubemapFace::Front,
+            CubemapFace::Back,
+        ];
+
+        if column >= self.num_columns() || row >= self.num_rows() {
+            return None;
+        }
+
+        return match self {
+            CubemapLayout::Strip | CubemapLayout::Grid => {
+                Some(faces[row * self.num_columns() + column])
+            }
+            CubemapLayout::HorizontalCross => match (column, row) {
+                (1, 0) => Some(CubemapFace::Up),
+                (0, 1) => Some(CubemapFace::Left),
+                (1, 1) => Some(CubemapFace::Front),
+                (2, 1) => Some(CubemapFace::Right),
+                (3, 1) => Some(CubemapFace::Back),
+                (1, 2) => Some(CubemapFace::Down),
+                _ => None,
+            },
+        };
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct CubemapCamera {
+    location: vec3::Vec3,
+    view_x_axis: vec3::Vec3,
+    view_y_axis: vec3::Vec3,
+    view_z_axis: vec3::Vec3,
+    layout: CubemapLayout,
+    face_size: usize,
+    shutter: camera::Shutter,
+}
+
+impl CubemapCamera {
+    pub fn new(
+        location: vec3::Vec3,
+        out_direction: vec3::Vec3,
+        up_direction: vec3::Vec3,
+        layout: CubemapLayout,
+        image_width: usize,
+        image_height: usize,
+    ) -> CubemapCamera {
+        let face_size = image_width / layout.num_columns();
+        assert!(
+            face_size > 0
+                && face_size * layout.num_columns() == image_width
+                && face_size * layout.num_rows() == image_height,
+            "image size does not match the cubemap layout"
+        );
+
+        let mut view_x_axis = vec3::Vec3::cross(&out_direction, &up_direction);
+        view_x_axis = vec3::Vec3::normalize(&view_x_axis).unwrap();
+
+        let mut view_y_axis = vec3::Vec3::cross(&view_x_axis, &out_direction);
+        view_y_axis = vec3::Vec3::normalize(&view_y_axis).unwrap();
+
+        let view_z_axis = vec3::Vec3::normalize(&out_direction).unwrap();
+
+        return CubemapCamera {
+            location,
+            view_x_axis,
+            view_y_axis,
+            view_z_axis,
+            layout,
+            face_size,
+            shutter: camera::Shutter::new(0.0, 0.0),
+        };
+    }
+
+    pub fn with_shutter(mut self, shutter: camera::Shutter) -> CubemapCamera {
+        self.shutter = shutter;
+        return self;
+    }
+
+    pub fn face_size(&self) -> usize {
+        return self.face_size;
+    }
+
+    // forward, right and up axes of the image of a face
+    fn face_axes(&self, face: CubemapFace) -> (vec3::Vec3, vec3::Vec3, vec3::Vec3) {
+        let x = self.view_x_axis;
+        let y = self.view_y_axis;
+        let z = self.view_z_axis;
+        return match face {
+            CubemapFace::Front => (z, x, y),
+            CubemapFace::Back => (-z, -x, y),
+            CubemapFace::Right => (x, -z, y),
+            CubemapFace::Left => (-x, z, y),
+            CubemapFace::Up => (y, x, -z),
+            CubemapFace::Down => (-y, x, z),
+        };
+    }
+}
+
+impl camera::Camera for CubemapCamera {
+    fn create_ray(&self, camera_sample: &camera::CameraSample) -> Option<ray::Ray> {
+        let image_x = camera_sample.image_point.x + 0.5;
+        let image_y = camera_sample.image_point.y + 0.5;
+        if image_x < 0.0 || image_y < 0.0 {
+            return None;
+        }
+
+        let face_size = self.face_size as f32;
+        let column = (image_x / face_size) as usize;
+        let row = (image_y / face_size) as usize;
+        let face = self.layout.face(column, row)?;
+
+        let row_ratio = image_y / (face_size * (self.layout.num_rows() as f32));
+        let time = self.shutter.sample_time(camera_sample.time, row_ratio);
+
+        let face_x = 2.0 * (image_x - (column as f32) * face_size) / face_size - 1.0;
+        let face_y = 1.0 - 2.0 * (image_y - (row as f32) * face_size) / face_size;
+        let (forward, right, up) = self.face_axes(face);
+        let direction = forward + face_x * right + face_y * up;
+
+        return Some(ray::Ray::new_with_time(self.location, direction, time));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+    use crate::core::vec2;
+    use crate::scene::camera::Camera;
+
+    fn create_ray(camera: &CubemapCamera, x: f32, y: f32) -> Option<ray::Ray> {
+        return camera.create_ray(&camera::CameraSample::new(
+            vec2::Vec2::new(x - 0.5, y - 0.5),
+            vec2::Vec2::from(0.5),
+            0.0,
+        ));
+    }
+
+    #[test]
+    fn test_create_ray_face_centers() {
+        let camera = CubemapCamera::new(
+            vec3::Vec3::from(0.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+            CubemapLayout::Grid,
+            96,
+            64,
+        );
+        assert_eq!(camera.face_size(), 32);
+
+        let expected_directions = [
+            camera.view_x_axis,
+            -camera.view_x_axis,
+            camera.view_y_axis,
+            -camera.view_y_axis,
+            camera.view_z_axis,
+            -camera.view_z_axis,
+        ];
+        for (i, expected_direction) in expected_directions.iter().enumerate() {
+            let x = ((i % 3) as f32 + 0.5) * 32.0;
+            let y = ((i / 3) as f32 + 0.5) * 32.0;
+            let ray = create_ray(&camera, x, y).unwrap();
+            assert!(ray
+                .direction()
+                .equal_epsilon(expected_direction, math::EPSILON_F32_5));
+        }
+    }
+
+    #[test]
+    fn test_create_ray_seams() {
+        let camera = CubemapCamera::new(
+            vec3::Vec3::from(0.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+            CubemapLayout::HorizontalCross,
+            128,
+            96,
+        );
+
+        // the right edge of the front face and the left edge of the right face meet
+        let front_edge = create_ray(&camera, 64.0 - 0.001, 48.0).unwrap();
+        let right_edge = create_ray(&camera, 64.0 + 0.001, 48.0).unwrap();
+        assert!(front_edge
+            .direction()
+            .equal_epsilon(right_edge.direction(), math::EPSILON_F32_3));
+
+        // the top edge of the front face meets the bottom edge of the up face
+        let front_edge = create_ray(&camera, 48.0, 32.0 + 0.001).unwrap();
+        let up_edge = create_ray(&camera, 48.0, 32.0 - 0.001).unwrap();
+        assert!(front_edge
+            .direction()
+            .equal_epsilon(up_edge.direction(), math::EPSILON_F32_3));
+
+        // the empty corners of the cross have no rays
+        assert!(create_ray(&camera, 16.0, 16.0).is_none());
+        assert!(create_ray(&camera, 112.0, 80.0).is_none());
+        assert!(create_ray(&camera, 129.0, 48.0).is_none());
+    }
+}
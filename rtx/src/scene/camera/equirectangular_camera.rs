@@ -0,0 +1,124 @@
+use crate::core::math;
+use crate::core::vec3;
+use crate::scene::camera;
+use crate::scene::ray;
+
+#[derive(Copy, Clone, Debug)]
+pub struct EquirectangularCamera {
+    location: vec3::Vec3,
+    view_x_axis: vec3::Vec3,
+    view_y_axis: vec3::Vec3,
+    view_z_axis: vec3::Vec3,
+    image_width: usize,
+    image_height: usize,
+    shutter: camera::Shutter,
+}
+
+impl EquirectangularCamera {
+    pub fn new(
+        location: vec3::Vec3,
+        out_direction: vec3::Vec3,
+        up_direction: vec3::Vec3,
+        image_width: usize,
+        image_height: usize,
+    ) -> EquirectangularCamera {
+        let mut view_x_axis = vec3::Vec3::cross(&out_direction, &up_direction);
+        view_x_axis = vec3::Vec3::normalize(&view_x_axis).unwrap();
+
+        let mut view_y_axis = vec3::Vec3::cross(&view_x_axis, &out_direction);
+        view_y_axis = vec3::Vec3::normalize(&view_y_axis).unwrap();
+
+        let view_z_axis = vec3::Vec3::normalize(&out_direction).unwrap();
+
+        return EquirectangularCamera {
+            location,
+            view_x_axis,
+            view_y_axis,
+            view_z_axis,
+            image_width,
+            image_height,
+            shutter: camera::Shutter::new(0.0, 0.0),
+        };
+    }
+
+    pub fn with_shutter(mut self, shutter: camera::Shutter) -> EquirectangularCamera {
+        self.shutter = shutter;
+        return self;
+    }
+}
+
+impl camera::Camera for EquirectangularCamera {
+    fn create_ray(&self, camera_sample: &camera::CameraSample) -> Option<ray::Ray> {
+        // the image spans 360 degrees of longitude and 180 degrees of latitude with the view
+        // direction at its center
+        let film_ratio = camera::calc_film_ratio(
+            &camera_sample.image_point,
+            self.image_width,
+            self.image_height,
+        );
+        let (u, v) = (film_ratio.x, film_ratio.y);
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return None;
+        }
+
+        let time = self.shutter.sample_time(camera_sample.time, v);
+
+        let longitude = (u - 0.5) * 2.0 * math::PI_F32;
+        let latitude = (0.5 - v) * math::PI_F32;
+        let direction = f32::cos(latitude) * f32::sin(longitude) * self.view_x_axis
+            + f32::sin(latitude) * self.view_y_axis
+            + f32::cos(latitude) * f32::cos(longitude) * self.view_z_axis;
+
+        return Some(ray::Ray::new_with_time(self.location, direction, time));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::vec2;
+    use crate::scene::camera::Camera;
+
+    #[test]
+    fn test_create_ray() {
+        let camera = EquirectangularCamera::new(
+            vec3::Vec3::from(0.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+            400,
+            200,
+        );
+
+        let create_ray = |x: f32, y: f32| {
+            return camera
+                .create_ray(&camera::CameraSample::new(
+                    vec2::Vec2::new(x - 0.5, y - 0.5),
+                    vec2::Vec2::from(0.5),
+                    0.0,
+                ))
+                .unwrap();
+        };
+
+        // image center looks forward, the quarter marks look sideways and the edges look back
+        assert!(create_ray(200.0, 100.0)
+            .direction()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 0.0, -1.0), math::EPSILON_F32_5));
+        assert!(create_ray(300.0, 100.0)
+            .direction()
+            .equal_epsilon(&camera.view_x_axis, math::EPSILON_F32_5));
+        assert!(create_ray(100.0, 100.0)
+            .direction()
+            .equal_epsilon(&-camera.view_x_axis, math::EPSILON_F32_5));
+        assert!(create_ray(0.0, 100.0)
+            .direction()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 0.0, 1.0), math::EPSILON_F32_5));
+
+        // top and bottom rows look up and down
+        assert!(create_ray(200.0, 0.0)
+            .direction()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 1.0, 0.0), math::EPSILON_F32_5));
+        assert!(create_ray(200.0, 200.0)
+            .direction()
+            .equal_epsilon(&vec3::Vec3::new(0.0, -1.0, 0.0), math::EPSILON_F32_5));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::core::image;
+use crate::scene::camera;
+
+// photographic exposure, shutter_speed in seconds
+#[derive(Copy, Clone, Debug)]
+pub struct Exposure {
+    iso: f32,
+    shutter_speed: f32,
+    f_stop: f32,
+}
+
+impl Exposure {
+    pub fn new(iso: f32, shutter_speed: f32, f_stop: f32) -> Exposure {
+        assert!(iso > 0.0 && shutter_speed > 0.0 && f_stop > 0.0);
+        return Exposure {
+            iso,
+            shutter_speed,
+            f_stop,
+        };
+    }
+
+    pub fn iso(&self) -> f32 {
+        return self.iso;
+    }
+
+    pub fn shutter_speed(&self) -> f32 {
+        return self.shutter_speed;
+    }
+
+    pub fn f_stop(&self) -> f32 {
+        return self.f_stop;
+    }
+
+    // exposure value at iso 100
+    pub fn ev100(&self) -> f32 {
+        return f32::log2(self.f_stop * self.f_stop / self.shutter_speed * 100.0 / self.iso);
+    }
+
+    // scale from scene luminance to sensor response, saturation based sensitivity puts the
+    // luminance that saturates the sensor at 1.2 * 2^ev100
+    pub fn scale(&self) -> f32 {
+        return 1.0 / (1.2 * f32::powf(2.0, self.ev100()));
+    }
+
+    // shutter interval matching the exposure time, so motion blur and exposure agree
+    pub fn shutter(&self) -> camera::Shutter {
+        return camera::Shutter::new(0.0, self.shutter_speed);
+    }
+
+    pub fn apply(&self, image: &mut image::Image) {
+        let scale = self.scale();
+        for y in 0..image.height() {
+            for x in 0..image.width() {
+                image[y][x] *= scale;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+    use crate::core::vec3;
+
+    #[test]
+    fn test_ev100() {
+        let exposure = Exposure::new(100.0, 1.0, 1.0);
+        assert!(math::equal_epsilon_f32(
+            exposure.ev100(),
+            0.0,
+            math::EPSILON_F32_5
+        ));
+
+        // sunny 16: f/16 at a shutter speed of 1/iso
+        let exposure = Exposure::new(100.0, 0.01, 16.0);
+        assert!(math::equal_epsilon_f32(
+            exposure.ev100(),
+            f32::log2(25600.0),
+            math::EPSILON_F32_5
+        ));
+
+        // doubling the iso and halving the shutter speed keeps the exposure
+        let exposure = Exposure::new(200.0, 0.005, 16.0);
+        assert!(math::equal_epsilon_f32(
+            exposure.ev100(),
+            f32::log2(25600.0),
+            math::EPSILON_F32_4
+        ));
+    }
+
+    #[test]
+    fn test_apply() {
+        let exposure = Exposure::new(100.0, 1.0, 1.0);
+        assert!(math::equal_epsilon_f32(
+            exposure.scale(),
+            1.0 / 1.2,
+            math::EPSILON_F32_5
+        ));
+
+        let mut image = image::Image::new(2, 2);
+        image[1][0] = vec3::Vec3::new(1.2, 0.6, 0.0);
+        exposure.apply(&mut image);
+        assert!(image[1][0].equal_epsilon(&vec3::Vec3::new(1.0, 0.5, 0.0), math::EPSILON_F32_5));
+
+        let shutter = Exposure::new(100.0, 0.02, 2.8).shutter();
+        assert!(math::equal_epsilon_f32(
+            shutter.close(),
+            0.02,
+            math::EPSILON_F32_6
+        ));
+    }
+}
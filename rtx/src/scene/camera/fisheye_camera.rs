@@ -0,0 +1,181 @@
+use crate::core::math;
+use crate::core::vec3;
+use crate::scene::camera;
+use crate::scene::ray;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FisheyeProjection {
+    Equidistant,
+    Equisolid,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct FisheyeCamera {
+    location: vec3::Vec3,
+    view_x_axis: vec3::Vec3,
+    view_y_axis: vec3::Vec3,
+    view_z_axis: vec3::Vec3,
+    projection: FisheyeProjection,
+    field_of_view: f32,
+    image_width: usize,
+    image_height: usize,
+    shutter: camera::Shutter,
+}
+
+impl FisheyeCamera {
+    pub fn new(
+        location: vec3::Vec3,
+        out_direction: vec3::Vec3,
+        up_direction: vec3::Vec3,
+        projection: FisheyeProjection,
+        field_of_view: f32,
+        image_width: usize,
+        image_height: usize,
+    ) -> FisheyeCamera {
+        assert!(
+            field_of_view > 0.0 && field_of_view <= 2.0 * math::PI_F32,
+            "fisheye field of view must be in (0, 2 * PI]"
+        );
+
+        let mut view_x_axis = vec3::Vec3::cross(&out_direction, &up_direction);
+        view_x_axis = vec3::Vec3::normalize(&view_x_axis).unwrap();
+
+        let mut view_y_axis = vec3::Vec3::cross(&view_x_axis, &out_direction);
+        view_y_axis = vec3::Vec3::normalize(&view_y_axis).unwrap();
+
+        let view_z_axis = vec3::Vec3::normalize(&out_direction).unwrap();
+
+        return FisheyeCamera {
+            location,
+            view_x_axis,
+            view_y_axis,
+            view_z_axis,
+            projection,
+            field_of_view,
+            image_width,
+            image_height,
+            shutter: camera::Shutter::new(0.0, 0.0),
+        };
+    }
+
+    pub fn with_shutter(mut self, shutter: camera::Shutter) -> FisheyeCamera {
+        self.shutter = shutter;
+        return self;
+    }
+
+    fn image_radius_to_theta(&self, radius: f32) -> f32 {
+        let half_field_of_view = self.field_of_view * 0.5;
+        return match self.projection {
+            FisheyeProjection::Equidistant => radius * half_field_of_view,
+            FisheyeProjection::Equisolid => {
+                let sin_half_theta = radius * f32::sin(half_field_of_view * 0.5);
+                2.0 * f32::asin(math::clamp(sin_half_theta, -1.0, 1.0))
+            }
+        };
+    }
+}
+
+impl camera::Camera for FisheyeCamera {
+    fn create_ray(&self, camera_sample: &camera::CameraSample) -> Option<ray::Ray> {
+        let film_ratio = camera::calc_film_ratio(
+            &camera_sample.image_point,
+            self.image_width,
+            self.image_height,
+        );
+        let time = self.shutter.sample_time(camera_sample.time, film_ratio.y);
+        // the image circle is inscribed in the shorter image side, pixels outside of it are
+        // outside of the projection
+        let image_radius = 0.5 * (usize::min(self.image_width, self.image_height) as f32);
+        let image_x = (film_ratio.x - 0.5) * (self.image_width as f32) / image_radius;
+        let image_y = (0.5 - film_ratio.y) * (self.image_height as f32) / image_radius;
+        let radius = f32::sqrt(image_x * image_x + image_y * image_y);
+        if radius > 1.0 {
+            return None;
+        }
+
+        let theta = self.image_radius_to_theta(radius);
+        let phi = f32::atan2(image_y, image_x);
+        let sin_theta = f32::sin(theta);
+        let direction = sin_theta * f32::cos(phi) * self.view_x_axis
+            + sin_theta * f32::sin(phi) * self.view_y_axis
+            + f32::cos(theta) * self.view_z_axis;
+
+        return Some(ray::Ray::new_with_time(self.location, direction, time));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::vec2;
+    use crate::scene::camera::Camera;
+
+    fn create_ray(camera: &FisheyeCamera, x: f32, y: f32) -> Option<ray::Ray> {
+        return camera.create_ray(&camera::CameraSample::new(
+            vec2::Vec2::new(x - 0.5, y - 0.5),
+            vec2::Vec2::from(0.5),
+            0.0,
+        ));
+    }
+
+    #[test]
+    fn test_create_ray_equidistant() {
+        let camera = FisheyeCamera::new(
+            vec3::Vec3::from(0.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+            FisheyeProjection::Equidistant,
+            math::degree_to_radian(180.0),
+            300,
+            200,
+        );
+
+        assert!(create_ray(&camera, 150.0, 100.0)
+            .unwrap()
+            .direction()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 0.0, -1.0), math::EPSILON_F32_5));
+
+        // the rim of the image circle is 90 degrees off axis, halfway is 45 degrees
+        assert!(create_ray(&camera, 150.0, 0.0)
+            .unwrap()
+            .direction()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 1.0, 0.0), math::EPSILON_F32_5));
+        let ray = create_ray(&camera, 200.0, 100.0).unwrap();
+        assert!(math::equal_epsilon_f32(
+            ray.direction().dot(&vec3::Vec3::new(0.0, 0.0, -1.0)),
+            f32::cos(math::degree_to_radian(45.0)),
+            math::EPSILON_F32_5
+        ));
+
+        // corners are outside of the image circle
+        assert!(create_ray(&camera, 0.0, 0.0).is_none());
+        assert!(create_ray(&camera, 260.0, 100.0).is_none());
+    }
+
+    #[test]
+    fn test_create_ray_equisolid() {
+        let camera = FisheyeCamera::new(
+            vec3::Vec3::from(0.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+            FisheyeProjection::Equisolid,
+            math::degree_to_radian(180.0),
+            200,
+            200,
+        );
+
+        assert!(create_ray(&camera, 200.0, 100.0)
+            .unwrap()
+            .direction()
+            .equal_epsilon(&camera.view_x_axis, math::EPSILON_F32_5));
+
+        // halfway to the rim is r = 2 sin(theta / 2) / (2 sin(45 / 2))
+        let ray = create_ray(&camera, 150.0, 100.0).unwrap();
+        let theta = 2.0 * f32::asin(0.5 * f32::sin(math::degree_to_radian(45.0)));
+        assert!(math::equal_epsilon_f32(
+            ray.direction().dot(&camera.view_z_axis),
+            f32::cos(theta),
+            math::EPSILON_F32_5
+        ));
+    }
+}
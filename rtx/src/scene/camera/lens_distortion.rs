@@ -0,0 +1,126 @@
+use crate::core::vec2;
+
+const NUM_UNDISTORT_ITERATIONS: usize = 20;
+
+// brown-conrady model on normalized image coordinates, i.e points on the image plane at unit
+// distance from the camera, with k the radial and p the tangential coefficients
+#[derive(Copy, Clone, Debug)]
+pub struct LensDistortion {
+    k1: f32,
+    k2: f32,
+    k3: f32,
+    p1: f32,
+    p2: f32,
+}
+
+impl LensDistortion {
+    pub fn new(k1: f32, k2: f32, k3: f32, p1: f32, p2: f32) -> LensDistortion {
+        return LensDistortion { k1, k2, k3, p1, p2 };
+    }
+
+    pub fn scale(&self, scale: f32) -> LensDistortion {
+        return LensDistortion::new(
+            self.k1 * scale,
+            self.k2 * scale,
+            self.k3 * scale,
+            self.p1 * scale,
+            self.p2 * scale,
+        );
+    }
+
+    fn radial_factor(&self, point: &vec2::Vec2) -> f32 {
+        let r_sq = point.length_sq();
+        return 1.0 + r_sq * (self.k1 + r_sq * (self.k2 + r_sq * self.k3));
+    }
+
+    fn tangential_offset(&self, point: &vec2::Vec2) -> vec2::Vec2 {
+        let r_sq = point.length_sq();
+        let xy = point.x * point.y;
+        return vec2::Vec2::new(
+            2.0 * self.p1 * xy + self.p2 * (r_sq + 2.0 * point.x * point.x),
+            self.p1 * (r_sq + 2.0 * point.y * point.y) + 2.0 * self.p2 * xy,
+        );
+    }
+
+    // maps a point of the ideal pinhole image to where the lens images it
+    pub fn distort(&self, point: &vec2::Vec2) -> vec2::Vec2 {
+        return point * self.radial_factor(point) + self.tangential_offset(point);
+    }
+
+    // the model has no closed form inverse, refine the undistorted point by fixed point iteration
+    pub fn undistort(&self, point: &vec2::Vec2) -> vec2::Vec2 {
+        let mut undistorted = *point;
+        for _ in 0..NUM_UNDISTORT_ITERATIONS {
+            let radial_factor = self.radial_factor(&undistorted);
+            if radial_factor <= 0.0 {
+                break;
+            }
+
+            undistorted = (point - self.tangential_offset(&undistorted)) / radial_factor;
+        }
+
+        return undistorted;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+
+    #[test]
+    fn test_distort() {
+        let point = vec2::Vec2::new(0.3, -0.2);
+        let identity = LensDistortion::new(0.0, 0.0, 0.0, 0.0, 0.0);
+        assert!(identity
+            .distort(&point)
+            .equal_epsilon(&point, math::EPSILON_F32_6));
+
+        // barrel distortion pulls points toward the center
+        let barrel = LensDistortion::new(-0.2, 0.0, 0.0, 0.0, 0.0);
+        let distorted = barrel.distort(&point);
+        assert!(distorted.length() < point.length());
+        assert!(math::equal_epsilon_f32(
+            distorted.x,
+            0.3 * (1.0 - 0.2 * 0.13),
+            math::EPSILON_F32_6
+        ));
+
+        let tangential = LensDistortion::new(0.0, 0.0, 0.0, 0.01, 0.02);
+        let distorted = tangential.distort(&point);
+        assert!(math::equal_epsilon_f32(
+            distorted.x,
+            0.3 + 2.0 * 0.01 * -0.06 + 0.02 * (0.13 + 0.18),
+            math::EPSILON_F32_6
+        ));
+        assert!(math::equal_epsilon_f32(
+            distorted.y,
+            -0.2 + 0.01 * (0.13 + 0.08) + 2.0 * 0.02 * -0.06,
+            math::EPSILON_F32_6
+        ));
+    }
+
+    #[test]
+    fn test_undistort() {
+        let distortion = LensDistortion::new(-0.12, 0.03, -0.004, 0.001, -0.002);
+        for point in [
+            vec2::Vec2::new(0.0, 0.0),
+            vec2::Vec2::new(0.3, -0.2),
+            vec2::Vec2::new(-0.5, 0.4),
+        ]
+        .iter()
+        {
+            let undistorted = distortion.undistort(&distortion.distort(point));
+            assert!(undistorted.equal_epsilon(point, math::EPSILON_F32_5));
+        }
+    }
+
+    #[test]
+    fn test_scale() {
+        let point = vec2::Vec2::new(0.3, -0.2);
+        let distortion = LensDistortion::new(-0.1, 0.02, 0.0, 0.001, 0.002);
+        let offset = distortion.distort(&point) - point;
+        let half_offset = distortion.scale(0.5).distort(&point) - point;
+        assert!((0.5 * offset).equal_epsilon(&half_offset, math::EPSILON_F32_6));
+    }
+}
@@ -0,0 +1,174 @@
+use crate::core::math;
+use crate::core::vec3;
+use crate::scene::camera;
+use crate::scene::camera::stereo_camera;
+use crate::scene::ray;
+
+#[derive(Copy, Clone, Debug)]
+pub struct OmniStereoCamera {
+    location: vec3::Vec3,
+    view_x_axis: vec3::Vec3,
+    view_y_axis: vec3::Vec3,
+    view_z_axis: vec3::Vec3,
+    interpupillary_distance: f32,
+    layout: stereo_camera::StereoLayout,
+    eye_image_width: usize,
+    eye_image_height: usize,
+    shutter: camera::Shutter,
+}
+
+impl OmniStereoCamera {
+    pub fn new(
+        location: vec3::Vec3,
+        out_direction: vec3::Vec3,
+        up_direction: vec3::Vec3,
+        eye_image_width: usize,
+        eye_image_height: usize,
+        interpupillary_distance: f32,
+        layout: stereo_camera::StereoLayout,
+    ) -> OmniStereoCamera {
+        let mut view_x_axis = vec3::Vec3::cross(&out_direction, &up_direction);
+        view_x_axis = vec3::Vec3::normalize(&view_x_axis).unwrap();
+
+        let mut view_y_axis = vec3::Vec3::cross(&view_x_axis, &out_direction);
+        view_y_axis = vec3::Vec3::normalize(&view_y_axis).unwrap();
+
+        let view_z_axis = vec3::Vec3::normalize(&out_direction).unwrap();
+
+        return OmniStereoCamera {
+            location,
+            view_x_axis,
+            view_y_axis,
+            view_z_axis,
+            interpupillary_distance,
+            layout,
+            eye_image_width,
+            eye_image_height,
+            shutter: camera::Shutter::new(0.0, 0.0),
+        };
+    }
+
+    pub fn with_shutter(mut self, shutter: camera::Shutter) -> OmniStereoCamera {
+        self.shutter = shutter;
+        return self;
+    }
+
+    pub fn image_width(&self) -> usize {
+        return self.layout.image_width(self.eye_image_width);
+    }
+
+    pub fn image_height(&self) -> usize {
+        return self.layout.image_height(self.eye_image_height);
+    }
+}
+
+impl camera::Camera for OmniStereoCamera {
+    fn create_ray(&self, camera_sample: &camera::CameraSample) -> Option<ray::Ray> {
+        let (is_left_eye, eye_image_point) = self.layout.split_image_point(
+            &camera_sample.image_point,
+            self.eye_image_width,
+            self.eye_image_height,
+        );
+
+        // every eye is an equirectangular image with the view direction at its center
+        let film_ratio = camera::calc_film_ratio(
+            &eye_image_point,
+            self.eye_image_width,
+            self.eye_image_height,
+        );
+        let (u, v) = (film_ratio.x, film_ratio.y);
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return None;
+        }
+
+        let time = self.shutter.sample_time(camera_sample.time, v);
+
+        let longitude = (u - 0.5) * 2.0 * math::PI_F32;
+        let latitude = (0.5 - v) * math::PI_F32;
+        let horizontal_direction =
+            f32::sin(longitude) * self.view_x_axis + f32::cos(longitude) * self.view_z_axis;
+        let direction =
+            f32::cos(latitude) * horizontal_direction + f32::sin(latitude) * self.view_y_axis;
+
+        // the ray of every column starts on the interpupillary circle, tangent to it
+        let mut eye_offset = 0.5 * self.interpupillary_distance;
+        if is_left_eye {
+            eye_offset = -eye_offset;
+        }
+
+        let tangent_offset =
+            f32::cos(longitude) * self.view_x_axis - f32::sin(longitude) * self.view_z_axis;
+        let origin = self.location + eye_offset * tangent_offset;
+
+        return Some(ray::Ray::new_with_time(origin, direction, time));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::vec2;
+    use crate::scene::camera::Camera;
+
+    #[test]
+    fn test_create_ray() {
+        let camera = OmniStereoCamera::new(
+            vec3::Vec3::from(0.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+            400,
+            200,
+            0.064,
+            stereo_camera::StereoLayout::OverUnder,
+        );
+        assert_eq!(camera.image_width(), 400);
+        assert_eq!(camera.image_height(), 400);
+
+        for x in [0.0, 57.0, 100.0, 213.0, 399.0].iter() {
+            let left_ray = camera
+                .create_ray(&camera::CameraSample::new(
+                    vec2::Vec2::new(*x, 99.5),
+                    vec2::Vec2::from(0.5),
+                    0.0,
+                ))
+                .unwrap();
+            let right_ray = camera
+                .create_ray(&camera::CameraSample::new(
+                    vec2::Vec2::new(*x, 299.5),
+                    vec2::Vec2::from(0.5),
+                    0.0,
+                ))
+                .unwrap();
+
+            // both eyes look the same way from opposite sides of the interpupillary circle
+            assert!(left_ray
+                .direction()
+                .equal_epsilon(right_ray.direction(), math::EPSILON_F32_5));
+            assert!(math::equal_epsilon_f32(
+                left_ray.origin().length(),
+                0.032,
+                math::EPSILON_F32_5
+            ));
+            assert!(left_ray
+                .origin()
+                .equal_epsilon(&-right_ray.origin(), math::EPSILON_F32_5));
+            assert!(math::equal_epsilon_f32(
+                left_ray.origin().dot(left_ray.direction()),
+                0.0,
+                math::EPSILON_F32_5
+            ));
+        }
+
+        // looking forward the left eye sits on the left
+        let left_ray = camera
+            .create_ray(&camera::CameraSample::new(
+                vec2::Vec2::new(199.5, 99.5),
+                vec2::Vec2::from(0.5),
+                0.0,
+            ))
+            .unwrap();
+        assert!(left_ray
+            .origin()
+            .equal_epsilon(&vec3::Vec3::new(-0.032, 0.0, 0.0), math::EPSILON_F32_5));
+    }
+}
@@ -0,0 +1,149 @@
+use crate::core::vec3;
+use crate::scene::camera;
+use crate::scene::ray;
+
+#[derive(Copy, Clone, Debug)]
+pub struct OrthographicCamera {
+    view_x_axis: vec3::Vec3,
+    view_y_axis: vec3::Vec3,
+    view_z_axis: vec3::Vec3,
+    image_width: usize,
+    image_height: usize,
+    screen_window_horizontal_length: f32,
+    screen_window_vertical_length: f32,
+    top_left_position: vec3::Vec3,
+    shutter: camera::Shutter,
+}
+
+impl OrthographicCamera {
+    pub fn new(
+        location: vec3::Vec3,
+        out_direction: vec3::Vec3,
+        up_direction: vec3::Vec3,
+        screen_window_horizontal_length: f32,
+        image_width: usize,
+        image_height: usize,
+    ) -> OrthographicCamera {
+        let mut view_x_axis = vec3::Vec3::cross(&out_direction, &up_direction);
+        view_x_axis = vec3::Vec3::normalize(&view_x_axis).unwrap();
+
+        let mut view_y_axis = vec3::Vec3::cross(&view_x_axis, &out_direction);
+        view_y_axis = vec3::Vec3::normalize(&view_y_axis).unwrap();
+
+        let view_z_axis = vec3::Vec3::normalize(&out_direction).unwrap();
+
+        let screen_window_vertical_length =
+            screen_window_horizontal_length * (image_height as f32) / (image_width as f32);
+        let top_left_position = location - screen_window_horizontal_length / 2.0 * view_x_axis
+            + screen_window_vertical_length / 2.0 * view_y_axis;
+
+        return OrthographicCamera {
+            view_x_axis,
+            view_y_axis,
+            view_z_axis,
+            image_width,
+            image_height,
+            screen_window_horizontal_length,
+            screen_window_vertical_length,
+            top_left_position,
+            shutter: camera::Shutter::new(0.0, 0.0),
+        };
+    }
+
+    pub fn with_shutter(mut self, shutter: camera::Shutter) -> OrthographicCamera {
+        self.shutter = shutter;
+        return self;
+    }
+}
+
+impl camera::Camera for OrthographicCamera {
+    fn create_ray(&self, camera_sample: &camera::CameraSample) -> Option<ray::Ray> {
+        let film_ratio = camera::calc_film_ratio(
+            &camera_sample.image_point,
+            self.image_width,
+            self.image_height,
+        );
+        let time = self.shutter.sample_time(camera_sample.time, film_ratio.y);
+        let origin = self.top_left_position
+            + self.screen_window_horizontal_length * film_ratio.x * self.view_x_axis
+            - self.screen_window_vertical_length * film_ratio.y * self.view_y_axis;
+
+        return Some(ray::Ray::new_with_time(origin, self.view_z_axis, time));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+    use crate::core::vec2;
+    use crate::scene::camera::Camera;
+
+    #[test]
+    fn test_create() {
+        let camera = OrthographicCamera::new(
+            vec3::Vec3::new(0.0, 0.0, 10.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+            8.0,
+            200,
+            100,
+        );
+
+        assert!(math::equal_epsilon_f32(
+            camera.screen_window_horizontal_length,
+            8.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            camera.screen_window_vertical_length,
+            4.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(camera
+            .top_left_position
+            .equal_epsilon(&vec3::Vec3::new(-4.0, 2.0, 10.0), math::EPSILON_F32_5));
+    }
+
+    #[test]
+    fn test_create_ray() {
+        let camera = OrthographicCamera::new(
+            vec3::Vec3::new(0.0, 0.0, 10.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+            8.0,
+            200,
+            100,
+        );
+
+        // rays at the center and the corner of the image are parallel
+        let center_ray = camera
+            .create_ray(&camera::CameraSample::new(
+                vec2::Vec2::new(99.5, 49.5),
+                vec2::Vec2::from(0.5),
+                0.0,
+            ))
+            .unwrap();
+        assert!(center_ray
+            .origin()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 0.0, 10.0), math::EPSILON_F32_5));
+        assert!(center_ray
+            .direction()
+            .equal_epsilon(&camera.view_z_axis, math::EPSILON_F32_5));
+
+        // the outer edge of the last pixel is the corner of the screen window
+        let corner_ray = camera
+            .create_ray(&camera::CameraSample::new(
+                vec2::Vec2::new(199.5, 99.5),
+                vec2::Vec2::from(0.5),
+                0.0,
+            ))
+            .unwrap();
+        assert!(corner_ray
+            .origin()
+            .equal_epsilon(&vec3::Vec3::new(4.0, -2.0, 10.0), math::EPSILON_F32_5));
+        assert!(corner_ray
+            .direction()
+            .equal_epsilon(&camera.view_z_axis, math::EPSILON_F32_5));
+    }
+}
@@ -1,8 +1,52 @@
+use crate::core::image;
+use crate::core::vec2;
 use crate::core::vec3;
 use crate::scene::camera;
+use crate::scene::camera::aperture;
+use crate::scene::camera::aperture::circular_aperture;
+use crate::scene::camera::exposure;
+use crate::scene::camera::lens_distortion;
 use crate::scene::ray;
+use std::rc;
 
+// physical camera lengths are in millimeters and the scene is in meters
+const MILLIMETER: f32 = 0.001;
+
+// lens and sensor of a physical camera, the lengths are in millimeters. The f-stop of the exposure
+// opens the lens, which is focused at the hyperfocal distance unless a focus distance in meters is
+// given
 #[derive(Copy, Clone, Debug)]
+pub struct PhysicalSettings {
+    pub focal_length: f32,
+    pub sensor_width: f32,
+    pub sensor_height: f32,
+    pub exposure: exposure::Exposure,
+    pub focus_distance: Option<f32>,
+}
+
+impl PhysicalSettings {
+    pub fn new(
+        focal_length: f32,
+        sensor_width: f32,
+        sensor_height: f32,
+        exposure: exposure::Exposure,
+    ) -> PhysicalSettings {
+        return PhysicalSettings {
+            focal_length,
+            sensor_width,
+            sensor_height,
+            exposure,
+            focus_distance: None,
+        };
+    }
+
+    pub fn with_focus_distance(mut self, focus_distance: f32) -> PhysicalSettings {
+        self.focus_distance = Some(focus_distance);
+        return self;
+    }
+}
+
+#[derive(Clone)]
 pub struct PerspectiveCamera {
     location: vec3::Vec3,
     view_x_axis: vec3::Vec3,
@@ -15,6 +59,14 @@ pub struct PerspectiveCamera {
     image_horizontal_length: f32,
     image_vertical_length: f32,
     top_left_position: vec3::Vec3,
+    lens_radius: f32,
+    focal_distance: f32,
+    aperture: rc::Rc<dyn aperture::Aperture>,
+    cat_eye_strength: f32,
+    shutter: camera::Shutter,
+    lens_distortion: Option<lens_distortion::LensDistortion>,
+    channel_distortion_scales: Option<vec3::Vec3>,
+    exposure: Option<exposure::Exposure>,
 }
 
 impl PerspectiveCamera {
@@ -54,23 +106,249 @@ impl PerspectiveCamera {
             image_horizontal_length,
             image_vertical_length,
             top_left_position,
+            lens_radius: 0.0,
+            focal_distance: 1.0,
+            aperture: rc::Rc::new(circular_aperture::CircularAperture::new()),
+            cat_eye_strength: 0.0,
+            shutter: camera::Shutter::new(0.0, 0.0),
+            lens_distortion: None,
+            channel_distortion_scales: None,
+            exposure: None,
         };
     }
-}
 
-impl camera::Camera for PerspectiveCamera {
-    fn create_ray(&self, x: f32, y: f32) -> ray::Ray {
-        let origin = self.location;
-        let width_ratio = x / ((self.image_width - 1) as f32);
-        let height_ratio = y / ((self.image_height - 1) as f32);
-        let mut direction = self.top_left_position
+    // the sensor is cropped to the aspect ratio of the image, keeping its width for wider images
+    // and its height for taller ones. The thin lens is focal_length / f_stop wide, the shutter
+    // stays open for the exposure time and the exposure scales the film
+    pub fn from_physical(
+        location: vec3::Vec3,
+        out_direction: vec3::Vec3,
+        up_direction: vec3::Vec3,
+        settings: &PhysicalSettings,
+        image_width: usize,
+        image_height: usize,
+    ) -> PerspectiveCamera {
+        let focal_length = settings.focal_length;
+        let sensor_width = settings.sensor_width;
+        let sensor_height = settings.sensor_height;
+        let exposure = settings.exposure;
+        let image_aspect = (image_width as f32) / (image_height as f32);
+        let mut film_width = sensor_width;
+        if image_aspect < sensor_width / sensor_height {
+            film_width = sensor_height * image_aspect;
+        }
+
+        let view_angle = 2.0 * f32::atan(0.5 * film_width / focal_length);
+        let lens_radius = focal_length / (2.0 * exposure.f_stop());
+        // everything from half of the hyperfocal distance to infinity blurs less than the circle
+        // of confusion, which is the common sensor diagonal / 1500
+        let circle_of_confusion = f32::hypot(sensor_width, sensor_height) / 1500.0;
+        let hyperfocal_distance =
+            focal_length * focal_length / (exposure.f_stop() * circle_of_confusion) + focal_length;
+        let focal_distance = settings
+            .focus_distance
+            .unwrap_or(hyperfocal_distance * MILLIMETER);
+
+        let mut camera = PerspectiveCamera::new(
+            location,
+            out_direction,
+            up_direction,
+            view_angle,
+            focal_length,
+            image_width,
+            image_height,
+        )
+        .with_thin_lens(lens_radius * MILLIMETER, focal_distance)
+        .with_shutter(exposure.shutter());
+        camera.exposure = Some(exposure);
+        return camera;
+    }
+
+    pub fn with_shutter(mut self, shutter: camera::Shutter) -> PerspectiveCamera {
+        self.shutter = shutter;
+        return self;
+    }
+
+    pub fn with_thin_lens(mut self, lens_radius: f32, focal_distance: f32) -> PerspectiveCamera {
+        self.lens_radius = lens_radius;
+        self.focal_distance = focal_distance;
+        return self;
+    }
+
+    // distance of the plane of focus, keeping the lens radius
+    pub fn with_focal_distance(mut self, focal_distance: f32) -> PerspectiveCamera {
+        self.focal_distance = focal_distance;
+        return self;
+    }
+
+    pub fn with_aperture(mut self, aperture: rc::Rc<dyn aperture::Aperture>) -> PerspectiveCamera {
+        self.aperture = aperture;
+        return self;
+    }
+
+    pub fn with_cat_eye_vignetting(mut self, strength: f32) -> PerspectiveCamera {
+        self.cat_eye_strength = strength;
+        return self;
+    }
+
+    // shift the image window parallel to the image plane, in fractions of the image size
+    pub fn with_lens_shift(mut self, shift_x: f32, shift_y: f32) -> PerspectiveCamera {
+        self.top_left_position += shift_x * self.image_horizontal_length * self.view_x_axis
+            + shift_y * self.image_vertical_length * self.view_y_axis;
+        return self;
+    }
+
+    pub fn with_lens_distortion(
+        mut self,
+        lens_distortion: lens_distortion::LensDistortion,
+    ) -> PerspectiveCamera {
+        self.lens_distortion = Some(lens_distortion);
+        return self;
+    }
+
+    // lateral chromatic aberration: the lens distortion of the red, green and blue channels is
+    // scaled by the matching component
+    pub fn with_chromatic_aberration(mut self, channel_scales: vec3::Vec3) -> PerspectiveCamera {
+        self.channel_distortion_scales = Some(channel_scales);
+        return self;
+    }
+
+    // st map of the undistort mapping: every pixel of the undistorted image stores in red and
+    // green where to read the distorted image, with s going right and t going up
+    pub fn create_undistort_st_map(&self) -> image::Image {
+        let mut st_map = image::Image::new(self.image_width, self.image_height);
+        for y in 0..self.image_height {
+            for x in 0..self.image_width {
+                let film_ratio = camera::calc_film_ratio(
+                    &vec2::Vec2::new(x as f32, y as f32),
+                    self.image_width,
+                    self.image_height,
+                );
+                let direction = self.calc_image_plane_direction(film_ratio.x, film_ratio.y);
+                let mut point = self.calc_normalized_image_point(&direction);
+                if let Some(lens_distortion) = self.lens_distortion {
+                    point = lens_distortion.distort(&point);
+                }
+
+                let image_offset = self.location
+                    + self.distance_to_image
+                        * (point.x * self.view_x_axis
+                            + point.y * self.view_y_axis
+                            + self.view_z_axis)
+                    - self.top_left_position;
+                let distorted_width_ratio = vec3::Vec3::dot(&image_offset, &self.view_x_axis)
+                    / self.image_horizontal_length;
+                let distorted_height_ratio =
+                    -vec3::Vec3::dot(&image_offset, &self.view_y_axis) / self.image_vertical_length;
+
+                st_map[y][x] =
+                    vec3::Vec3::new(distorted_width_ratio, 1.0 - distorted_height_ratio, 0.0);
+            }
+        }
+
+        return st_map;
+    }
+
+    fn calc_image_plane_direction(&self, width_ratio: f32, height_ratio: f32) -> vec3::Vec3 {
+        return self.top_left_position
             + self.image_horizontal_length * width_ratio * self.view_x_axis
             - self.image_vertical_length * height_ratio * self.view_y_axis
-            - origin;
+            - self.location;
+    }
+
+    // image plane point at unit distance along the optical axis
+    fn calc_normalized_image_point(&self, direction: &vec3::Vec3) -> vec2::Vec2 {
+        let z = vec3::Vec3::dot(direction, &self.view_z_axis);
+        return vec2::Vec2::new(
+            vec3::Vec3::dot(direction, &self.view_x_axis) / z,
+            vec3::Vec3::dot(direction, &self.view_y_axis) / z,
+        );
+    }
+
+    fn create_distorted_ray(
+        &self,
+        camera_sample: &camera::CameraSample,
+        lens_distortion: Option<&lens_distortion::LensDistortion>,
+    ) -> Option<ray::Ray> {
+        let origin = self.location;
+        let film_ratio = camera::calc_film_ratio(
+            &camera_sample.image_point,
+            self.image_width,
+            self.image_height,
+        );
+        let (width_ratio, height_ratio) = (film_ratio.x, film_ratio.y);
+        let time = self.shutter.sample_time(camera_sample.time, height_ratio);
+        let mut direction = self.calc_image_plane_direction(width_ratio, height_ratio);
+
+        // the image is what the lens sees, so trace the undistorted direction of each image point
+        if let Some(lens_distortion) = lens_distortion {
+            let point = lens_distortion.undistort(&self.calc_normalized_image_point(&direction));
+            direction = point.x * self.view_x_axis + point.y * self.view_y_axis + self.view_z_axis;
+        }
 
         direction = vec3::Vec3::normalize(&direction).unwrap();
+        if self.lens_radius == 0.0 {
+            return Some(ray::Ray::new_with_time(origin, direction, time));
+        }
+
+        // thin lens: every ray through the lens converges on the plane of focus
+        let lens_point = self.aperture.sample(&camera_sample.lens_point);
+        if self.is_vignetted(&lens_point, width_ratio, height_ratio) {
+            return None;
+        }
+
+        let focus_time = self.focal_distance / vec3::Vec3::dot(&direction, &self.view_z_axis);
+        let focus_point = origin + focus_time * direction;
+        let lens_origin = origin
+            + self.lens_radius * lens_point.x * self.view_x_axis
+            + self.lens_radius * lens_point.y * self.view_y_axis;
+
+        return Some(ray::Ray::new_with_time(
+            lens_origin,
+            focus_point - lens_origin,
+            time,
+        ));
+    }
+
+    fn is_vignetted(&self, lens_point: &vec2::Vec2, width_ratio: f32, height_ratio: f32) -> bool {
+        if self.cat_eye_strength == 0.0 {
+            return false;
+        }
 
-        return ray::Ray::new(origin, direction);
+        // off axis the aperture is clipped by the lens barrel, which is modelled as a second
+        // aperture sized disk shifted toward the image corner
+        let image_offset =
+            vec2::Vec2::new(2.0 * width_ratio - 1.0, 1.0 - 2.0 * height_ratio) / f32::sqrt(2.0);
+        let barrel_center = self.cat_eye_strength * image_offset;
+        return lens_point.distance(&barrel_center) > 1.0;
+    }
+}
+
+impl camera::Camera for PerspectiveCamera {
+    fn create_ray(&self, camera_sample: &camera::CameraSample) -> Option<ray::Ray> {
+        return self.create_distorted_ray(camera_sample, self.lens_distortion.as_ref());
+    }
+
+    fn has_channel_rays(&self) -> bool {
+        return self.lens_distortion.is_some() && self.channel_distortion_scales.is_some();
+    }
+
+    fn create_channel_ray(
+        &self,
+        camera_sample: &camera::CameraSample,
+        channel: usize,
+    ) -> Option<ray::Ray> {
+        return match (self.lens_distortion, self.channel_distortion_scales) {
+            (Some(lens_distortion), Some(channel_scales)) => {
+                let scale = [channel_scales.x, channel_scales.y, channel_scales.z][channel];
+                self.create_distorted_ray(camera_sample, Some(&lens_distortion.scale(scale)))
+            }
+            _ => self.create_ray(camera_sample),
+        };
+    }
+
+    fn film_scale(&self) -> f32 {
+        return self.exposure.map_or(1.0, |exposure| exposure.scale());
     }
 }
 
@@ -190,7 +468,13 @@ mod test {
             200,
         );
 
-        let ray = camera.create_ray(199.0 / 2.0, 199.0 / 2.0);
+        let ray = camera
+            .create_ray(&camera::CameraSample::new(
+                vec2::Vec2::new(199.0 / 2.0, 199.0 / 2.0),
+                vec2::Vec2::from(0.5),
+                0.0,
+            ))
+            .unwrap();
 
         // check origin
         assert!(math::equal_epsilon_f32(
@@ -226,4 +510,360 @@ mod test {
             math::EPSILON_F32_5
         ));
     }
+
+    #[test]
+    fn test_create_ray_thin_lens_focus() {
+        let location = vec3::Vec3::new(0.0, 0.0, 5.0);
+        let out_direction = vec3::Vec3::new(0.0, 0.0, -1.0);
+        let up_direction = vec3::Vec3::new(0.0, 1.0, 0.0);
+        let camera = PerspectiveCamera::new(
+            location,
+            out_direction,
+            up_direction,
+            math::degree_to_radian(60.0),
+            1.0,
+            101,
+            101,
+        )
+        .with_thin_lens(0.5, 4.0)
+        .with_aperture(rc::Rc::new(
+            aperture::polygonal_aperture::PolygonalAperture::new(5, 0.0),
+        ));
+
+        // rays through different parts of the lens meet again on the plane of focus
+        let focus_point = vec3::Vec3::new(0.0, 0.0, 1.0);
+        for lens_sample in [
+            vec2::Vec2::new(0.1, 0.2),
+            vec2::Vec2::new(0.5, 0.9),
+            vec2::Vec2::new(0.7, 0.4),
+        ]
+        .iter()
+        {
+            let ray = camera
+                .create_ray(&camera::CameraSample::new(
+                    vec2::Vec2::new(50.0, 50.0),
+                    *lens_sample,
+                    0.0,
+                ))
+                .unwrap();
+            assert!(!math::equal_epsilon_f32(
+                ray.origin().distance(&location),
+                0.0,
+                math::EPSILON_F32_5
+            ));
+
+            let focus_time = (focus_point.z - ray.origin().z) / ray.direction().z;
+            let position = ray.calc_position(focus_time);
+            assert!(position.equal_epsilon(&focus_point, math::EPSILON_F32_5));
+        }
+    }
+
+    #[test]
+    fn test_create_ray_cat_eye_vignetting() {
+        let camera = PerspectiveCamera::new(
+            vec3::Vec3::new(0.0, 0.0, 5.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+            math::degree_to_radian(60.0),
+            1.0,
+            101,
+            101,
+        )
+        .with_thin_lens(0.5, 4.0)
+        .with_cat_eye_vignetting(1.0);
+
+        // the center of the image sees the whole aperture
+        let lens_sample = vec2::Vec2::new(0.01, 0.01);
+        assert!(camera
+            .create_ray(&camera::CameraSample::new(
+                vec2::Vec2::new(50.0, 50.0),
+                lens_sample,
+                0.0
+            ))
+            .is_some());
+
+        // the bottom left part of the aperture is clipped in the top right corner
+        assert!(camera
+            .create_ray(&camera::CameraSample::new(
+                vec2::Vec2::new(100.0, 0.0),
+                lens_sample,
+                0.0
+            ))
+            .is_none());
+        assert!(camera
+            .create_ray(&camera::CameraSample::new(
+                vec2::Vec2::new(100.0, 0.0),
+                vec2::Vec2::new(0.99, 0.99),
+                0.0
+            ))
+            .is_some());
+    }
+
+    #[test]
+    fn test_create_ray_rolling_shutter() {
+        let camera = PerspectiveCamera::new(
+            vec3::Vec3::new(0.0, 0.0, 5.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+            math::degree_to_radian(60.0),
+            1.0,
+            101,
+            101,
+        )
+        .with_shutter(camera::Shutter::new(0.0, 0.25).with_rolling_shutter(1.0));
+
+        // the exposure of every row starts later than the one above it, from the top edge of the
+        // image to its bottom edge
+        for (row, expected_time) in [(-0.5, 0.125), (50.0, 0.625), (100.5, 1.125)].iter() {
+            let ray = camera
+                .create_ray(&camera::CameraSample::new(
+                    vec2::Vec2::new(30.0, *row),
+                    vec2::Vec2::new(0.5, 0.5),
+                    0.5,
+                ))
+                .unwrap();
+            assert!(math::equal_epsilon_f32(
+                ray.time(),
+                *expected_time,
+                math::EPSILON_F32_5
+            ));
+        }
+    }
+
+    fn create_distortion_test_camera() -> PerspectiveCamera {
+        return PerspectiveCamera::new(
+            vec3::Vec3::new(0.0, 0.0, 0.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+            math::degree_to_radian(70.0),
+            1.0,
+            101,
+            81,
+        );
+    }
+
+    #[test]
+    fn test_create_ray_lens_distortion() {
+        let distortion = lens_distortion::LensDistortion::new(-0.15, 0.02, 0.0, 0.001, -0.002);
+        let pinhole = create_distortion_test_camera();
+        let camera = create_distortion_test_camera().with_lens_distortion(distortion);
+        let lens_sample = vec2::Vec2::new(0.5, 0.5);
+
+        // the optical center is not distorted
+        let center = camera::CameraSample::new(vec2::Vec2::new(50.0, 40.0), lens_sample, 0.0);
+        assert!(camera
+            .create_ray(&center)
+            .unwrap()
+            .direction()
+            .equal_epsilon(
+                pinhole.create_ray(&center).unwrap().direction(),
+                math::EPSILON_F32_5
+            ));
+
+        // barrel distortion squeezes a wider view into the image
+        let corner = camera::CameraSample::new(vec2::Vec2::new(0.0, 0.0), lens_sample, 0.0);
+        let distorted = camera.create_ray(&corner).unwrap();
+        let undistorted = pinhole.create_ray(&corner).unwrap();
+        assert!(distorted.direction().z > undistorted.direction().z);
+        assert!(!camera.has_channel_rays());
+    }
+
+    #[test]
+    fn test_create_channel_ray_chromatic_aberration() {
+        let distortion = lens_distortion::LensDistortion::new(-0.15, 0.0, 0.0, 0.0, 0.0);
+        let camera = create_distortion_test_camera()
+            .with_lens_distortion(distortion)
+            .with_chromatic_aberration(vec3::Vec3::new(1.1, 1.0, 0.9));
+        assert!(camera.has_channel_rays());
+
+        let lens_sample = vec2::Vec2::new(0.5, 0.5);
+        let corner = camera::CameraSample::new(vec2::Vec2::new(0.0, 0.0), lens_sample, 0.0);
+        let red = camera.create_channel_ray(&corner, 0).unwrap();
+        let green = camera.create_channel_ray(&corner, 1).unwrap();
+        let blue = camera.create_channel_ray(&corner, 2).unwrap();
+        assert!(green.direction().equal_epsilon(
+            camera.create_ray(&corner).unwrap().direction(),
+            math::EPSILON_F32_5
+        ));
+
+        // the more distorted red channel sees further out than blue
+        assert!(red.direction().z > green.direction().z);
+        assert!(green.direction().z > blue.direction().z);
+    }
+
+    #[test]
+    fn test_create_undistort_st_map() {
+        let pinhole = create_distortion_test_camera();
+        let st_map = pinhole.create_undistort_st_map();
+        assert_eq!(st_map.width(), 101);
+        assert_eq!(st_map.height(), 81);
+        assert!(st_map[0][0].equal_epsilon(
+            &vec3::Vec3::new(0.5 / 101.0, 1.0 - 0.5 / 81.0, 0.0),
+            math::EPSILON_F32_5
+        ));
+        assert!(st_map[80][100].equal_epsilon(
+            &vec3::Vec3::new(100.5 / 101.0, 0.5 / 81.0, 0.0),
+            math::EPSILON_F32_5
+        ));
+
+        // reading the distorted image at the st position gives the pixel of the pinhole image
+        let distortion = lens_distortion::LensDistortion::new(-0.15, 0.02, 0.0, 0.001, -0.002);
+        let camera = create_distortion_test_camera().with_lens_distortion(distortion);
+        let st_map = camera.create_undistort_st_map();
+        let lens_sample = vec2::Vec2::new(0.5, 0.5);
+        for (x, y) in [(0, 0), (20, 70), (100, 10)].iter() {
+            let st = st_map[*y][*x];
+            let distorted_point = vec2::Vec2::new(st.x * 101.0 - 0.5, (1.0 - st.y) * 81.0 - 0.5);
+            let distorted = camera
+                .create_ray(&camera::CameraSample::new(
+                    distorted_point,
+                    lens_sample,
+                    0.0,
+                ))
+                .unwrap();
+            let undistorted = pinhole
+                .create_ray(&camera::CameraSample::new(
+                    vec2::Vec2::new(*x as f32, *y as f32),
+                    lens_sample,
+                    0.0,
+                ))
+                .unwrap();
+            assert!(distorted
+                .direction()
+                .equal_epsilon(undistorted.direction(), math::EPSILON_F32_4));
+        }
+    }
+
+    #[test]
+    fn test_from_physical() {
+        let location = vec3::Vec3::new(0.0, 0.0, 0.0);
+        let out_direction = vec3::Vec3::new(0.0, 0.0, -1.0);
+        let up_direction = vec3::Vec3::new(0.0, 1.0, 0.0);
+        let lens_sample = vec2::Vec2::new(0.5, 0.5);
+
+        // a full frame sensor behind a 50mm lens
+        let camera = PerspectiveCamera::from_physical(
+            location,
+            out_direction,
+            up_direction,
+            &PhysicalSettings::new(50.0, 36.0, 24.0, exposure::Exposure::new(100.0, 0.01, 2.0)),
+            300,
+            200,
+        );
+        // the outer corner of the first pixel is the corner of the sensor
+        let ray = camera
+            .create_ray(&camera::CameraSample::new(
+                vec2::Vec2::new(-0.5, -0.5),
+                lens_sample,
+                0.0,
+            ))
+            .unwrap();
+        let expected = vec3::Vec3::normalize(&vec3::Vec3::new(-18.0, 12.0, -50.0)).unwrap();
+        assert!(ray
+            .direction()
+            .equal_epsilon(&expected, math::EPSILON_F32_5));
+
+        // a square image crops the sides of the sensor
+        let camera = PerspectiveCamera::from_physical(
+            location,
+            out_direction,
+            up_direction,
+            &PhysicalSettings::new(50.0, 36.0, 24.0, exposure::Exposure::new(100.0, 0.01, 2.0)),
+            200,
+            200,
+        );
+        let ray = camera
+            .create_ray(&camera::CameraSample::new(
+                vec2::Vec2::new(199.5, 199.5),
+                lens_sample,
+                0.0,
+            ))
+            .unwrap();
+        let expected = vec3::Vec3::normalize(&vec3::Vec3::new(12.0, -12.0, -50.0)).unwrap();
+        assert!(ray
+            .direction()
+            .equal_epsilon(&expected, math::EPSILON_F32_5));
+    }
+
+    #[test]
+    fn test_from_physical_exposure() {
+        let exposure = exposure::Exposure::new(400.0, 0.02, 2.8);
+        let camera = PerspectiveCamera::from_physical(
+            vec3::Vec3::new(0.0, 0.0, 0.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+            &PhysicalSettings::new(50.0, 36.0, 24.0, exposure),
+            300,
+            200,
+        );
+
+        // a 50mm lens at f/2.8 is 17.9mm wide, with the scene in meters
+        assert!(math::equal_epsilon_f32(
+            camera.lens_radius,
+            0.05 / (2.0 * 2.8),
+            math::EPSILON_F32_6
+        ));
+        let circle_of_confusion = f32::hypot(36.0, 24.0) / 1500.0;
+        let hyperfocal_distance = 50.0 * 50.0 / (2.8 * circle_of_confusion) + 50.0;
+        assert!(math::equal_epsilon_f32(
+            camera.focal_distance,
+            hyperfocal_distance * 0.001,
+            math::EPSILON_F32_4
+        ));
+        assert!(math::equal_epsilon_f32(
+            camera.shutter.close(),
+            0.02,
+            math::EPSILON_F32_6
+        ));
+        assert!(math::equal_epsilon_f32(
+            camera.film_scale(),
+            exposure.scale(),
+            math::EPSILON_F32_6
+        ));
+
+        // rays through the edge of the lens converge on the plane of focus
+        let camera = PerspectiveCamera::from_physical(
+            vec3::Vec3::new(0.0, 0.0, 0.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+            &PhysicalSettings::new(50.0, 36.0, 24.0, exposure).with_focus_distance(3.0),
+            300,
+            200,
+        );
+        let image_point = vec2::Vec2::new(100.0, 60.0);
+        let center_ray = camera
+            .create_ray(&camera::CameraSample::new(
+                image_point,
+                vec2::Vec2::new(0.5, 0.5),
+                0.0,
+            ))
+            .unwrap();
+        let edge_ray = camera
+            .create_ray(&camera::CameraSample::new(
+                image_point,
+                vec2::Vec2::new(1.0, 0.75),
+                0.0,
+            ))
+            .unwrap();
+        assert!(!edge_ray
+            .origin()
+            .equal_epsilon(center_ray.origin(), math::EPSILON_F32_4));
+        let focus_time = 3.0 / -center_ray.direction().z;
+        let edge_time = (3.0 + edge_ray.origin().z) / -edge_ray.direction().z;
+        assert!(center_ray
+            .calc_position(focus_time)
+            .equal_epsilon(&edge_ray.calc_position(edge_time), math::EPSILON_F32_4));
+
+        // without an exposure the film is not scaled
+        let camera = PerspectiveCamera::new(
+            vec3::Vec3::new(0.0, 0.0, 0.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+            math::degree_to_radian(60.0),
+            1.0,
+            300,
+            200,
+        );
+        assert_eq!(camera.film_scale(), 1.0);
+    }
 }
@@ -0,0 +1,652 @@
+use crate::core::math;
+use crate::core::sampling;
+use crate::core::vec2;
+use crate::core::vec3;
+use crate::scene::camera;
+use crate::scene::ray;
+
+const NUM_EXIT_PUPIL_BOUNDS: usize = 32;
+const NUM_EXIT_PUPIL_SAMPLES: usize = 64 * 64;
+
+#[derive(Copy, Clone, Debug)]
+pub struct LensElement {
+    // a curvature radius of zero marks the planar aperture stop
+    pub curvature_radius: f32,
+    // distance along the optical axis to the next element toward the film
+    pub thickness: f32,
+    // index of refraction of the medium between this element and the next one
+    pub eta: f32,
+    pub aperture_radius: f32,
+}
+
+impl LensElement {
+    pub fn new(
+        curvature_radius: f32,
+        thickness: f32,
+        eta: f32,
+        aperture_radius: f32,
+    ) -> LensElement {
+        // lens tables use an index of zero for air
+        let eta = if eta == 0.0 { 1.0 } else { eta };
+        return LensElement {
+            curvature_radius,
+            thickness,
+            eta,
+            aperture_radius,
+        };
+    }
+
+    pub fn is_aperture_stop(&self) -> bool {
+        return self.curvature_radius == 0.0;
+    }
+}
+
+// elements are ordered from the front of the lens to the film, the aperture stop is stopped down to
+// aperture_diameter and the film of film_diagonal is moved until focus_distance is in focus
+#[derive(Clone, Debug)]
+pub struct LensSystem {
+    pub elements: Vec<LensElement>,
+    pub aperture_diameter: f32,
+    pub focus_distance: f32,
+    pub film_diagonal: f32,
+}
+
+impl LensSystem {
+    pub fn new(
+        elements: Vec<LensElement>,
+        aperture_diameter: f32,
+        focus_distance: f32,
+        film_diagonal: f32,
+    ) -> LensSystem {
+        return LensSystem {
+            elements,
+            aperture_diameter,
+            focus_distance,
+            film_diagonal,
+        };
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct PupilBounds {
+    min: vec2::Vec2,
+    max: vec2::Vec2,
+}
+
+impl PupilBounds {
+    fn inside(&self, p: &vec2::Vec2) -> bool {
+        return p.x >= self.min.x && p.x <= self.max.x && p.y >= self.min.y && p.y <= self.max.y;
+    }
+}
+
+// the lens system is described in camera space, with the film centered at the origin and the
+// elements stacked along +z toward the scene
+#[derive(Clone, Debug)]
+pub struct RealisticCamera {
+    location: vec3::Vec3,
+    view_x_axis: vec3::Vec3,
+    view_y_axis: vec3::Vec3,
+    view_z_axis: vec3::Vec3,
+    elements: Vec<LensElement>,
+    film_width: f32,
+    film_height: f32,
+    film_diagonal: f32,
+    image_width: usize,
+    image_height: usize,
+    exit_pupil_bounds: Vec<PupilBounds>,
+    shutter: camera::Shutter,
+}
+
+impl RealisticCamera {
+    // the thickness of the last element is replaced by the film distance that brings the focus
+    // distance of the lens system into focus
+    pub fn new(
+        location: vec3::Vec3,
+        out_direction: vec3::Vec3,
+        up_direction: vec3::Vec3,
+        lens_system: LensSystem,
+        image_width: usize,
+        image_height: usize,
+    ) -> RealisticCamera {
+        let LensSystem {
+            elements,
+            aperture_diameter,
+            focus_distance,
+            film_diagonal,
+        } = lens_system;
+        assert!(!elements.is_empty(), "lens system must have an element");
+
+        let mut view_x_axis = vec3::Vec3::cross(&out_direction, &up_direction);
+        view_x_axis = vec3::Vec3::normalize(&view_x_axis).unwrap();
+
+        let mut view_y_axis = vec3::Vec3::cross(&view_x_axis, &out_direction);
+        view_y_axis = vec3::Vec3::normalize(&view_y_axis).unwrap();
+
+        let view_z_axis = vec3::Vec3::normalize(&out_direction).unwrap();
+
+        let mut elements = elements;
+        for element in elements.iter_mut() {
+            if element.is_aperture_stop() {
+                element.aperture_radius =
+                    f32::min(element.aperture_radius, 0.5 * aperture_diameter);
+            }
+        }
+
+        let aspect = (image_width as f32) / (image_height as f32);
+        let film_height = film_diagonal / f32::sqrt(aspect * aspect + 1.0);
+        let film_width = aspect * film_height;
+
+        let mut camera = RealisticCamera {
+            location,
+            view_x_axis,
+            view_y_axis,
+            view_z_axis,
+            elements,
+            film_width,
+            film_height,
+            film_diagonal,
+            image_width,
+            image_height,
+            exit_pupil_bounds: Vec::new(),
+            shutter: camera::Shutter::new(0.0, 0.0),
+        };
+
+        let film_distance = camera.focus_thick_lens(focus_distance);
+        camera.elements.last_mut().unwrap().thickness = film_distance;
+
+        let half_diagonal = 0.5 * film_diagonal;
+        camera.exit_pupil_bounds = (0..NUM_EXIT_PUPIL_BOUNDS)
+            .map(|i| {
+                let r0 = (i as f32) / (NUM_EXIT_PUPIL_BOUNDS as f32) * half_diagonal;
+                let r1 = ((i + 1) as f32) / (NUM_EXIT_PUPIL_BOUNDS as f32) * half_diagonal;
+                return camera.bound_exit_pupil(r0, r1);
+            })
+            .collect();
+
+        return camera;
+    }
+
+    pub fn with_shutter(mut self, shutter: camera::Shutter) -> RealisticCamera {
+        self.shutter = shutter;
+        return self;
+    }
+
+    pub fn film_distance(&self) -> f32 {
+        return self.lens_rear_z();
+    }
+
+    fn lens_rear_z(&self) -> f32 {
+        return self.elements.last().unwrap().thickness;
+    }
+
+    fn lens_front_z(&self) -> f32 {
+        return self.elements.iter().map(|element| element.thickness).sum();
+    }
+
+    fn rear_element_radius(&self) -> f32 {
+        return self.elements.last().unwrap().aperture_radius;
+    }
+
+    // returns the distance along the ray and the normal facing the incoming ray
+    fn intersect_spherical_element(
+        radius: f32,
+        z_center: f32,
+        origin: &vec3::Vec3,
+        direction: &vec3::Vec3,
+    ) -> Option<(f32, vec3::Vec3)> {
+        let o = origin - vec3::Vec3::new(0.0, 0.0, z_center);
+        let a = vec3::Vec3::dot(direction, direction);
+        let b = 2.0 * vec3::Vec3::dot(direction, &o);
+        let c = vec3::Vec3::dot(&o, &o) - radius * radius;
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let sqrt_discriminant = f32::sqrt(discriminant);
+        let t0 = (-b - sqrt_discriminant) / (2.0 * a);
+        let t1 = (-b + sqrt_discriminant) / (2.0 * a);
+
+        // the element is the half of the sphere facing the ray
+        let use_closer_t = (direction.z > 0.0) ^ (radius < 0.0);
+        let t = if use_closer_t { t0 } else { t1 };
+        if t < 0.0 {
+            return None;
+        }
+
+        let mut normal = vec3::Vec3::normalize(&(o + t * direction)).unwrap();
+        if vec3::Vec3::dot(&normal, direction) > 0.0 {
+            normal = -normal;
+        }
+
+        return Some((t, normal));
+    }
+
+    // trace through a single interface in lens space, where the film is at z = 0 and the scene
+    // toward -z, refracting from eta_i into eta_t
+    fn trace_element(
+        element: &LensElement,
+        element_z: f32,
+        eta_i: f32,
+        eta_t: f32,
+        origin: &mut vec3::Vec3,
+        direction: &mut vec3::Vec3,
+    ) -> bool {
+        let t;
+        let mut normal = vec3::Vec3::from(0.0);
+        if element.is_aperture_stop() {
+            if direction.z == 0.0 {
+                return false;
+            }
+
+            t = (element_z - origin.z) / direction.z;
+            if t < 0.0 {
+                return false;
+            }
+        } else {
+            let z_center = element_z + element.curvature_radius;
+            match RealisticCamera::intersect_spherical_element(
+                element.curvature_radius,
+                z_center,
+                origin,
+                direction,
+            ) {
+                Some((hit_t, hit_normal)) => {
+                    t = hit_t;
+                    normal = hit_normal;
+                }
+                None => return false,
+            }
+        }
+
+        let hit = *origin + t * *direction;
+        if hit.x * hit.x + hit.y * hit.y > element.aperture_radius * element.aperture_radius {
+            return false;
+        }
+
+        *origin = hit;
+        if !element.is_aperture_stop() {
+            let wi = vec3::Vec3::normalize(&-*direction).unwrap();
+            match vec3::Vec3::refract(&wi, &normal, eta_i / eta_t) {
+                Some(refracted) => *direction = refracted,
+                None => return false,
+            }
+        }
+
+        return true;
+    }
+
+    fn trace_lenses_from_film(
+        &self,
+        origin: &vec3::Vec3,
+        direction: &vec3::Vec3,
+    ) -> Option<ray::Ray> {
+        let mut lens_origin = vec3::Vec3::new(origin.x, origin.y, -origin.z);
+        let mut lens_direction = vec3::Vec3::new(direction.x, direction.y, -direction.z);
+        let mut element_z = 0.0;
+        for i in (0..self.elements.len()).rev() {
+            let element = &self.elements[i];
+            element_z -= element.thickness;
+            let eta_t = if i > 0 { self.elements[i - 1].eta } else { 1.0 };
+            if !RealisticCamera::trace_element(
+                element,
+                element_z,
+                element.eta,
+                eta_t,
+                &mut lens_origin,
+                &mut lens_direction,
+            ) {
+                return None;
+            }
+        }
+
+        return Some(ray::Ray::new(
+            vec3::Vec3::new(lens_origin.x, lens_origin.y, -lens_origin.z),
+            vec3::Vec3::new(lens_direction.x, lens_direction.y, -lens_direction.z),
+        ));
+    }
+
+    fn trace_lenses_from_scene(
+        &self,
+        origin: &vec3::Vec3,
+        direction: &vec3::Vec3,
+    ) -> Option<ray::Ray> {
+        let mut lens_origin = vec3::Vec3::new(origin.x, origin.y, -origin.z);
+        let mut lens_direction = vec3::Vec3::new(direction.x, direction.y, -direction.z);
+        let mut element_z = -self.lens_front_z();
+        for i in 0..self.elements.len() {
+            let element = &self.elements[i];
+            let eta_i = if i > 0 { self.elements[i - 1].eta } else { 1.0 };
+            if !RealisticCamera::trace_element(
+                element,
+                element_z,
+                eta_i,
+                element.eta,
+                &mut lens_origin,
+                &mut lens_direction,
+            ) {
+                return None;
+            }
+
+            element_z += element.thickness;
+        }
+
+        return Some(ray::Ray::new(
+            vec3::Vec3::new(lens_origin.x, lens_origin.y, -lens_origin.z),
+            vec3::Vec3::new(lens_direction.x, lens_direction.y, -lens_direction.z),
+        ));
+    }
+
+    // principal plane and focal point along the optical axis for a ray entering parallel to it
+    fn compute_cardinal_points(ray_in: &ray::Ray, ray_out: &ray::Ray) -> (f32, f32) {
+        let tf = -ray_out.origin().x / ray_out.direction().x;
+        let focal_z = -ray_out.calc_position(tf).z;
+        let tp = (ray_in.origin().x - ray_out.origin().x) / ray_out.direction().x;
+        let principal_z = -ray_out.calc_position(tp).z;
+        return (principal_z, focal_z);
+    }
+
+    fn compute_thick_lens_approximation(&self) -> Option<([f32; 2], [f32; 2])> {
+        // trace rays parallel to the optical axis, close enough to it to be paraxial
+        let x = 0.001 * self.film_diagonal;
+        let offset = self.lens_front_z();
+
+        let scene_origin = vec3::Vec3::new(x, 0.0, self.lens_front_z() + offset);
+        let scene_direction = vec3::Vec3::new(0.0, 0.0, -1.0);
+        let film_ray = self.trace_lenses_from_scene(&scene_origin, &scene_direction)?;
+        let (principal_z0, focal_z0) = RealisticCamera::compute_cardinal_points(
+            &ray::Ray::new(scene_origin, scene_direction),
+            &film_ray,
+        );
+
+        let film_origin = vec3::Vec3::new(x, 0.0, self.lens_rear_z() - offset);
+        let film_direction = vec3::Vec3::new(0.0, 0.0, 1.0);
+        let scene_ray = self.trace_lenses_from_film(&film_origin, &film_direction)?;
+        let (principal_z1, focal_z1) = RealisticCamera::compute_cardinal_points(
+            &ray::Ray::new(film_origin, film_direction),
+            &scene_ray,
+        );
+
+        return Some(([principal_z0, principal_z1], [focal_z0, focal_z1]));
+    }
+
+    fn focus_thick_lens(&self, focus_distance: f32) -> f32 {
+        let (principal_z, focal_z) = self
+            .compute_thick_lens_approximation()
+            .expect("lens system does not let paraxial rays through");
+
+        let focal_length = focal_z[0] - principal_z[0];
+        let z = -focus_distance;
+        let c = (principal_z[1] - z - principal_z[0])
+            * (principal_z[1] - z - 4.0 * focal_length - principal_z[0]);
+        assert!(c > 0.0, "lens system can not focus at {}", focus_distance);
+
+        let delta = 0.5 * (principal_z[1] - z + principal_z[0] - f32::sqrt(c));
+        return self.lens_rear_z() + delta;
+    }
+
+    // conservative bounds on the rear element of the directions that make it through the lens
+    // from film points at distances between film_x0 and film_x1 from the center
+    fn bound_exit_pupil(&self, film_x0: f32, film_x1: f32) -> PupilBounds {
+        let rear_radius = 1.5 * self.rear_element_radius();
+        let rear_bounds = PupilBounds {
+            min: vec2::Vec2::new(-rear_radius, -rear_radius),
+            max: vec2::Vec2::new(rear_radius, rear_radius),
+        };
+
+        let mut pupil_bounds = PupilBounds {
+            min: vec2::Vec2::new(f32::INFINITY, f32::INFINITY),
+            max: vec2::Vec2::new(-f32::INFINITY, -f32::INFINITY),
+        };
+        for i in 0..NUM_EXIT_PUPIL_SAMPLES {
+            let film_point = vec3::Vec3::new(
+                math::lerp(
+                    ((i as f32) + 0.5) / (NUM_EXIT_PUPIL_SAMPLES as f32),
+                    film_x0,
+                    film_x1,
+                ),
+                0.0,
+                0.0,
+            );
+            let rear_point = vec2::Vec2::new(
+                math::lerp(
+                    sampling::radical_inverse(2, i as u64),
+                    rear_bounds.min.x,
+                    rear_bounds.max.x,
+                ),
+                math::lerp(
+                    sampling::radical_inverse(3, i as u64),
+                    rear_bounds.min.y,
+                    rear_bounds.max.y,
+                ),
+            );
+
+            if pupil_bounds.inside(&rear_point) {
+                continue;
+            }
+
+            let direction =
+                vec3::Vec3::new(rear_point.x, rear_point.y, self.lens_rear_z()) - film_point;
+            if self
+                .trace_lenses_from_film(&film_point, &direction)
+                .is_some()
+            {
+                pupil_bounds.min.x = f32::min(pupil_bounds.min.x, rear_point.x);
+                pupil_bounds.min.y = f32::min(pupil_bounds.min.y, rear_point.y);
+                pupil_bounds.max.x = f32::max(pupil_bounds.max.x, rear_point.x);
+                pupil_bounds.max.y = f32::max(pupil_bounds.max.y, rear_point.y);
+            }
+        }
+
+        if pupil_bounds.min.x > pupil_bounds.max.x || pupil_bounds.min.y > pupil_bounds.max.y {
+            return rear_bounds;
+        }
+
+        // grow by the sample spacing to account for the missed edges
+        let expand = 2.0 * rear_bounds.min.distance(&rear_bounds.max)
+            / f32::sqrt(NUM_EXIT_PUPIL_SAMPLES as f32);
+        pupil_bounds.min -= vec2::Vec2::new(expand, expand);
+        pupil_bounds.max += vec2::Vec2::new(expand, expand);
+        return pupil_bounds;
+    }
+
+    fn sample_exit_pupil(&self, film_point: &vec2::Vec2, lens_sample: &vec2::Vec2) -> vec3::Vec3 {
+        // the bounds are computed along +x, rotate them to the film point
+        let film_radius = film_point.length();
+        let bounds_idx =
+            (film_radius / (0.5 * self.film_diagonal) * (NUM_EXIT_PUPIL_BOUNDS as f32)) as usize;
+        let bounds = &self.exit_pupil_bounds[usize::min(bounds_idx, NUM_EXIT_PUPIL_BOUNDS - 1)];
+        let lens_x = math::lerp(lens_sample.x, bounds.min.x, bounds.max.x);
+        let lens_y = math::lerp(lens_sample.y, bounds.min.y, bounds.max.y);
+
+        let mut sin_theta = 0.0;
+        let mut cos_theta = 1.0;
+        if film_radius != 0.0 {
+            sin_theta = film_point.y / film_radius;
+            cos_theta = film_point.x / film_radius;
+        }
+
+        return vec3::Vec3::new(
+            cos_theta * lens_x - sin_theta * lens_y,
+            sin_theta * lens_x + cos_theta * lens_y,
+            self.lens_rear_z(),
+        );
+    }
+}
+
+impl camera::Camera for RealisticCamera {
+    fn create_ray(&self, camera_sample: &camera::CameraSample) -> Option<ray::Ray> {
+        let film_ratio = camera::calc_film_ratio(
+            &camera_sample.image_point,
+            self.image_width,
+            self.image_height,
+        );
+        let (u, v) = (film_ratio.x, film_ratio.y);
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return None;
+        }
+
+        // the lens flips the image, so the top left of the image is at the bottom right of the film
+        let film_point = vec2::Vec2::new((0.5 - u) * self.film_width, (v - 0.5) * self.film_height);
+        let rear_point = self.sample_exit_pupil(&film_point, &camera_sample.lens_point);
+        let film_origin = vec3::Vec3::new(film_point.x, film_point.y, 0.0);
+        let camera_ray = self.trace_lenses_from_film(&film_origin, &(rear_point - film_origin))?;
+
+        let time = self.shutter.sample_time(camera_sample.time, v);
+        let origin = self.location
+            + camera_ray.origin().x * self.view_x_axis
+            + camera_ray.origin().y * self.view_y_axis
+            + camera_ray.origin().z * self.view_z_axis;
+        let direction = camera_ray.direction().x * self.view_x_axis
+            + camera_ray.direction().y * self.view_y_axis
+            + camera_ray.direction().z * self.view_z_axis;
+        return Some(ray::Ray::new_with_time(origin, direction, time));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scene::camera::Camera;
+
+    // double gauss 50mm f/2 converted to meters
+    fn create_double_gauss_elements() -> Vec<LensElement> {
+        let table = [
+            (29.475, 3.76, 1.67, 25.2),
+            (84.83, 0.12, 1.0, 25.2),
+            (19.275, 4.025, 1.67, 23.0),
+            (40.77, 3.275, 1.699, 23.0),
+            (12.75, 5.705, 1.0, 18.0),
+            (0.0, 4.5, 0.0, 17.1),
+            (-14.495, 1.18, 1.603, 17.0),
+            (40.77, 6.065, 1.658, 20.0),
+            (-20.385, 0.19, 1.0, 20.0),
+            (437.065, 2.22, 1.717, 20.0),
+            (-39.73, 0.0, 1.0, 20.0),
+        ];
+        return table
+            .iter()
+            .map(|(radius, thickness, eta, aperture)| {
+                LensElement::new(
+                    0.001 * radius,
+                    0.001 * thickness,
+                    *eta,
+                    0.001 * 0.5 * aperture,
+                )
+            })
+            .collect();
+    }
+
+    fn create_camera(focus_distance: f32) -> RealisticCamera {
+        return RealisticCamera::new(
+            vec3::Vec3::new(0.0, 0.0, 0.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+            LensSystem::new(
+                create_double_gauss_elements(),
+                0.0171,
+                focus_distance,
+                0.035,
+            ),
+            101,
+            101,
+        );
+    }
+
+    fn calc_axis_crossing(ray: &ray::Ray) -> vec3::Vec3 {
+        let o = ray.origin();
+        let d = ray.direction();
+        let t = -(o.x * d.x + o.y * d.y) / (d.x * d.x + d.y * d.y);
+        return ray.calc_position(t);
+    }
+
+    #[test]
+    fn test_lens_element_air() {
+        let element = LensElement::new(0.0, 4.5, 0.0, 8.55);
+        assert!(element.is_aperture_stop());
+        assert!(math::equal_epsilon_f32(
+            element.eta,
+            1.0,
+            math::EPSILON_F32_5
+        ));
+    }
+
+    #[test]
+    fn test_create_ray_focus() {
+        for focus_distance in [1.0, 3.0].iter() {
+            let camera = create_camera(*focus_distance);
+
+            // paraxial rays from the film center meet on the optical axis at the focus distance
+            for lens_sample in [vec2::Vec2::new(0.5, 0.52), vec2::Vec2::new(0.53, 0.5)].iter() {
+                let ray = camera
+                    .create_ray(&camera::CameraSample::new(
+                        vec2::Vec2::new(50.0, 50.0),
+                        *lens_sample,
+                        0.0,
+                    ))
+                    .unwrap();
+                let crossing = calc_axis_crossing(&ray);
+                assert!(f32::abs(-crossing.z - focus_distance) < 0.01 * focus_distance);
+            }
+        }
+
+        // the film moves away from the lens to focus closer
+        assert!(create_camera(1.0).film_distance() > create_camera(10.0).film_distance());
+    }
+
+    #[test]
+    fn test_create_ray_image_orientation() {
+        let camera = create_camera(3.0);
+        let lens_sample = vec2::Vec2::new(0.5, 0.5);
+
+        let top_left = camera
+            .create_ray(&camera::CameraSample::new(
+                vec2::Vec2::new(0.0, 0.0),
+                lens_sample,
+                0.0,
+            ))
+            .unwrap();
+        assert!(top_left.direction().x < 0.0);
+        assert!(top_left.direction().y > 0.0);
+        assert!(top_left.direction().z < 0.0);
+
+        let bottom_right = camera
+            .create_ray(&camera::CameraSample::new(
+                vec2::Vec2::new(100.0, 100.0),
+                lens_sample,
+                0.0,
+            ))
+            .unwrap();
+        assert!(bottom_right.direction().x > 0.0);
+        assert!(bottom_right.direction().y < 0.0);
+    }
+
+    #[test]
+    fn test_create_ray_vignetting() {
+        let camera = create_camera(3.0);
+
+        // fewer rays make it through the lens toward the corner of the image
+        let mut num_center_rays = 0;
+        let mut num_corner_rays = 0;
+        for i in 0..16 {
+            for j in 0..16 {
+                let lens_sample =
+                    vec2::Vec2::new(((i as f32) + 0.5) / 16.0, ((j as f32) + 0.5) / 16.0);
+                let center_sample =
+                    camera::CameraSample::new(vec2::Vec2::new(50.0, 50.0), lens_sample, 0.0);
+                if camera.create_ray(&center_sample).is_some() {
+                    num_center_rays += 1;
+                }
+
+                let corner_sample =
+                    camera::CameraSample::new(vec2::Vec2::new(0.0, 0.0), lens_sample, 0.0);
+                if camera.create_ray(&corner_sample).is_some() {
+                    num_corner_rays += 1;
+                }
+            }
+        }
+
+        assert!(num_center_rays > 0);
+        assert!(num_corner_rays < num_center_rays);
+    }
+}
@@ -0,0 +1,195 @@
+use crate::core::image;
+use crate::core::math;
+use crate::core::vec3;
+use rand::prelude::*;
+use rand::rngs;
+
+// maps the linear sensor signal in [0, 1] to the encoded output value
+#[derive(Clone, Debug)]
+pub enum ResponseCurve {
+    Linear,
+    Gamma(f32),
+    Srgb,
+    // measured response sampled at evenly spaced signal values from 0 to 1
+    Tabulated(Vec<f32>),
+}
+
+impl ResponseCurve {
+    pub fn evaluate(&self, signal: f32) -> f32 {
+        let signal = math::clamp(signal, 0.0, 1.0);
+        return match self {
+            ResponseCurve::Linear => signal,
+            ResponseCurve::Gamma(gamma) => f32::powf(signal, 1.0 / gamma),
+            ResponseCurve::Srgb => {
+                if signal <= 0.0031308 {
+                    12.92 * signal
+                } else {
+                    1.055 * f32::powf(signal, 1.0 / 2.4) - 0.055
+                }
+            }
+            ResponseCurve::Tabulated(table) => {
+                if table.len() == 1 {
+                    return table[0];
+                }
+
+                let position = signal * ((table.len() - 1) as f32);
+                let idx = usize::min(position as usize, table.len() - 2);
+                math::lerp(position - (idx as f32), table[idx], table[idx + 1])
+            }
+        };
+    }
+}
+
+// simulates the capture of an exposed image by a digital sensor, where a pixel value of 1
+// fills the full well capacity, both the capacity and the read noise are in electrons
+#[derive(Clone, Debug)]
+pub struct Sensor {
+    full_well_capacity: f32,
+    read_noise: f32,
+    response_curve: ResponseCurve,
+}
+
+impl Sensor {
+    pub fn new(full_well_capacity: f32, read_noise: f32, response_curve: ResponseCurve) -> Sensor {
+        assert!(full_well_capacity > 0.0);
+        assert!(read_noise >= 0.0);
+        return Sensor {
+            full_well_capacity,
+            read_noise,
+            response_curve,
+        };
+    }
+
+    // the output is encoded by the response curve, the seed makes captures reproducible
+    pub fn capture(&self, image: &image::Image, seed: u64) -> image::Image {
+        let mut rng = rngs::StdRng::seed_from_u64(seed);
+        let mut captured = image::Image::new(image.width(), image.height());
+        for y in 0..image.height() {
+            for x in 0..image.width() {
+                let color = image[y][x];
+                captured[y][x] = vec3::Vec3::new(
+                    self.capture_channel(color.x, &mut rng),
+                    self.capture_channel(color.y, &mut rng),
+                    self.capture_channel(color.z, &mut rng),
+                );
+            }
+        }
+
+        return captured;
+    }
+
+    fn capture_channel(&self, value: f32, rng: &mut rngs::StdRng) -> f32 {
+        let expected_electrons = math::clamp(value, 0.0, 1.0) * self.full_well_capacity;
+        let electrons = Sensor::sample_poisson(expected_electrons, rng)
+            + self.read_noise * Sensor::sample_standard_normal(rng);
+        let signal = math::clamp(electrons, 0.0, self.full_well_capacity) / self.full_well_capacity;
+        return self.response_curve.evaluate(signal);
+    }
+
+    fn sample_standard_normal(rng: &mut rngs::StdRng) -> f32 {
+        // box-muller transform
+        let u1: f32 = rng.gen_range(f32::MIN_POSITIVE, 1.0);
+        let u2: f32 = rng.gen_range(0.0, 1.0);
+        return f32::sqrt(-2.0 * f32::ln(u1)) * f32::cos(2.0 * math::PI_F32 * u2);
+    }
+
+    // photon shot noise, large means are well approximated by a normal distribution
+    fn sample_poisson(mean: f32, rng: &mut rngs::StdRng) -> f32 {
+        if mean > 30.0 {
+            return mean + f32::sqrt(mean) * Sensor::sample_standard_normal(rng);
+        }
+
+        let limit = f32::exp(-mean);
+        let mut count = 0.0;
+        let mut product: f32 = rng.gen_range(0.0, 1.0);
+        while product > limit {
+            count += 1.0;
+            product *= rng.gen_range(0.0, 1.0);
+        }
+
+        return count;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_response_curve() {
+        assert!(math::equal_epsilon_f32(
+            ResponseCurve::Linear.evaluate(0.3),
+            0.3,
+            math::EPSILON_F32_6
+        ));
+        assert!(math::equal_epsilon_f32(
+            ResponseCurve::Gamma(2.0).evaluate(0.25),
+            0.5,
+            math::EPSILON_F32_6
+        ));
+        assert!(math::equal_epsilon_f32(
+            ResponseCurve::Srgb.evaluate(0.5),
+            0.735357,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            ResponseCurve::Srgb.evaluate(2.0),
+            1.0,
+            math::EPSILON_F32_5
+        ));
+
+        let tabulated = ResponseCurve::Tabulated(vec![0.0, 0.6, 0.8, 1.0]);
+        assert!(math::equal_epsilon_f32(
+            tabulated.evaluate(0.5),
+            0.7,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            tabulated.evaluate(1.0),
+            1.0,
+            math::EPSILON_F32_5
+        ));
+    }
+
+    #[test]
+    fn test_capture_noise() {
+        let mut image = image::Image::new(64, 64);
+        for y in 0..64 {
+            for x in 0..64 {
+                image[y][x] = vec3::Vec3::new(0.25, 0.01, 1.0);
+            }
+        }
+
+        let sensor = Sensor::new(1000.0, 5.0, ResponseCurve::Linear);
+        let captured = sensor.capture(&image, 7);
+
+        let num_pixels = (64 * 64) as f32;
+        let mut mean = vec3::Vec3::from(0.0);
+        for y in 0..64 {
+            for x in 0..64 {
+                mean += captured[y][x];
+            }
+        }
+        mean /= num_pixels;
+
+        let mut variance = 0.0;
+        for y in 0..64 {
+            for x in 0..64 {
+                let diff = captured[y][x].x - mean.x;
+                variance += diff * diff;
+            }
+        }
+        variance /= num_pixels;
+
+        // shot noise of 250 electrons and read noise add up in variance
+        let expected_deviation = f32::sqrt(250.0 + 25.0) / 1000.0;
+        assert!(f32::abs(mean.x - 0.25) < 0.002);
+        assert!(f32::abs(f32::sqrt(variance) - expected_deviation) < 0.1 * expected_deviation);
+        assert!(f32::abs(mean.y - 0.01) < 0.002);
+        assert!(mean.z > 0.98 && mean.z <= 1.0);
+
+        // the same seed captures the same image
+        let recaptured = sensor.capture(&image, 7);
+        assert!(captured[10][20].equal_epsilon(&recaptured[10][20], math::EPSILON_F32_6));
+    }
+}
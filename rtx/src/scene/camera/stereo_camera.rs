@@ -0,0 +1,282 @@
+use crate::core::vec2;
+use crate::core::vec3;
+use crate::scene::camera;
+use crate::scene::camera::perspective_camera;
+use crate::scene::ray;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StereoLayout {
+    // left eye on the left half of the image, right eye on the right half
+    SideBySide,
+    // left eye on the top half of the image, right eye on the bottom half
+    OverUnder,
+}
+
+impl StereoLayout {
+    pub fn image_width(&self, eye_image_width: usize) -> usize {
+        return match self {
+            StereoLayout::SideBySide => 2 * eye_image_width,
+            StereoLayout::OverUnder => eye_image_width,
+        };
+    }
+
+    pub fn image_height(&self, eye_image_height: usize) -> usize {
+        return match self {
+            StereoLayout::SideBySide => eye_image_height,
+            StereoLayout::OverUnder => 2 * eye_image_height,
+        };
+    }
+
+    // split an image point of the stereo pair into the eye it belongs to and the image point
+    // inside of that eye image
+    pub fn split_image_point(
+        &self,
+        image_point: &vec2::Vec2,
+        eye_image_width: usize,
+        eye_image_height: usize,
+    ) -> (bool, vec2::Vec2) {
+        return match self {
+            StereoLayout::SideBySide => {
+                let is_left_eye = image_point.x < (eye_image_width as f32) - 0.5;
+                if is_left_eye {
+                    (true, *image_point)
+                } else {
+                    (
+                        false,
+                        vec2::Vec2::new(image_point.x - eye_image_width as f32, image_point.y),
+                    )
+                }
+            }
+            StereoLayout::OverUnder => {
+                let is_left_eye = image_point.y < (eye_image_height as f32) - 0.5;
+                if is_left_eye {
+                    (true, *image_point)
+                } else {
+                    (
+                        false,
+                        vec2::Vec2::new(image_point.x, image_point.y - eye_image_height as f32),
+                    )
+                }
+            }
+        };
+    }
+}
+
+// average distance between the eyes of an adult in meters
+const DEFAULT_INTEROCULAR_DISTANCE: f32 = 0.064;
+
+pub struct StereoCamera {
+    location: vec3::Vec3,
+    out_direction: vec3::Vec3,
+    up_direction: vec3::Vec3,
+    view_angle: f32,
+    distance_to_image: f32,
+    interocular_distance: f32,
+    convergence_distance: f32,
+    shutter: camera::Shutter,
+    left_eye: perspective_camera::PerspectiveCamera,
+    right_eye: perspective_camera::PerspectiveCamera,
+    layout: StereoLayout,
+    eye_image_width: usize,
+    eye_image_height: usize,
+}
+
+impl StereoCamera {
+    // eyes at the average interocular distance looking parallel, side by side in the image
+    pub fn new(
+        location: vec3::Vec3,
+        out_direction: vec3::Vec3,
+        up_direction: vec3::Vec3,
+        view_angle: f32,
+        distance_to_image: f32,
+        eye_image_width: usize,
+        eye_image_height: usize,
+    ) -> StereoCamera {
+        let eye = perspective_camera::PerspectiveCamera::new(
+            location,
+            out_direction,
+            up_direction,
+            view_angle,
+            distance_to_image,
+            eye_image_width,
+            eye_image_height,
+        );
+        let mut camera = StereoCamera {
+            location,
+            out_direction,
+            up_direction,
+            view_angle,
+            distance_to_image,
+            interocular_distance: DEFAULT_INTEROCULAR_DISTANCE,
+            convergence_distance: f32::INFINITY,
+            shutter: camera::Shutter::new(0.0, 0.0),
+            left_eye: eye.clone(),
+            right_eye: eye,
+            layout: StereoLayout::SideBySide,
+            eye_image_width,
+            eye_image_height,
+        };
+        camera.update_eyes();
+        return camera;
+    }
+
+    pub fn with_interocular_distance(mut self, interocular_distance: f32) -> StereoCamera {
+        self.interocular_distance = interocular_distance;
+        self.update_eyes();
+        return self;
+    }
+
+    // objects at the convergence distance have zero parallax, an infinite distance keeps the eyes
+    // parallel
+    pub fn with_convergence_distance(mut self, convergence_distance: f32) -> StereoCamera {
+        self.convergence_distance = convergence_distance;
+        self.update_eyes();
+        return self;
+    }
+
+    pub fn with_layout(mut self, layout: StereoLayout) -> StereoCamera {
+        self.layout = layout;
+        return self;
+    }
+
+    pub fn with_shutter(mut self, shutter: camera::Shutter) -> StereoCamera {
+        self.shutter = shutter;
+        self.update_eyes();
+        return self;
+    }
+
+    pub fn image_width(&self) -> usize {
+        return self.layout.image_width(self.eye_image_width);
+    }
+
+    pub fn image_height(&self) -> usize {
+        return self.layout.image_height(self.eye_image_height);
+    }
+
+    fn update_eyes(&mut self) {
+        let mut view_x_axis = vec3::Vec3::cross(&self.out_direction, &self.up_direction);
+        view_x_axis = vec3::Vec3::normalize(&view_x_axis).unwrap();
+        let eye_offset = 0.5 * self.interocular_distance;
+
+        // both eyes look parallel to each other, the image windows are shifted toward each
+        // other so that objects at the convergence distance have zero parallax
+        let mut lens_shift = 0.0;
+        if self.convergence_distance.is_finite() {
+            lens_shift =
+                eye_offset / (self.convergence_distance * 2.0 * f32::tan(self.view_angle / 2.0));
+        }
+
+        let create_eye = |side: f32| {
+            return perspective_camera::PerspectiveCamera::new(
+                self.location + side * eye_offset * view_x_axis,
+                self.out_direction,
+                self.up_direction,
+                self.view_angle,
+                self.distance_to_image,
+                self.eye_image_width,
+                self.eye_image_height,
+            )
+            .with_lens_shift(-side * lens_shift, 0.0)
+            .with_shutter(self.shutter);
+        };
+        let left_eye = create_eye(-1.0);
+        let right_eye = create_eye(1.0);
+        self.left_eye = left_eye;
+        self.right_eye = right_eye;
+    }
+}
+
+impl camera::Camera for StereoCamera {
+    fn create_ray(&self, camera_sample: &camera::CameraSample) -> Option<ray::Ray> {
+        let (is_left_eye, eye_image_point) = self.layout.split_image_point(
+            &camera_sample.image_point,
+            self.eye_image_width,
+            self.eye_image_height,
+        );
+        let eye_camera_sample = camera::CameraSample::new(
+            eye_image_point,
+            camera_sample.lens_point,
+            camera_sample.time,
+        );
+        if is_left_eye {
+            return self.left_eye.create_ray(&eye_camera_sample);
+        }
+
+        return self.right_eye.create_ray(&eye_camera_sample);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+    use crate::scene::camera::Camera;
+
+    fn create_ray(camera: &StereoCamera, x: f32, y: f32) -> ray::Ray {
+        return camera
+            .create_ray(&camera::CameraSample::new(
+                vec2::Vec2::new(x, y),
+                vec2::Vec2::from(0.5),
+                0.0,
+            ))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_create_ray_side_by_side() {
+        let camera = StereoCamera::new(
+            vec3::Vec3::new(0.0, 0.0, 5.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+            math::degree_to_radian(60.0),
+            1.0,
+            101,
+            51,
+        )
+        .with_convergence_distance(2.0);
+        assert_eq!(camera.image_width(), 202);
+        assert_eq!(camera.image_height(), 51);
+
+        // the center rays of both eyes start at the eyes and meet at the convergence distance
+        let left_ray = create_ray(&camera, 50.0, 25.0);
+        let right_ray = create_ray(&camera, 151.0, 25.0);
+        assert!(left_ray
+            .origin()
+            .equal_epsilon(&vec3::Vec3::new(-0.032, 0.0, 5.0), math::EPSILON_F32_5));
+        assert!(right_ray
+            .origin()
+            .equal_epsilon(&vec3::Vec3::new(0.032, 0.0, 5.0), math::EPSILON_F32_5));
+
+        let convergence_point = vec3::Vec3::new(0.0, 0.0, 3.0);
+        let left_position = left_ray.calc_position(left_ray.origin().distance(&convergence_point));
+        let right_position =
+            right_ray.calc_position(right_ray.origin().distance(&convergence_point));
+        assert!(left_position.equal_epsilon(&convergence_point, math::EPSILON_F32_5));
+        assert!(right_position.equal_epsilon(&convergence_point, math::EPSILON_F32_5));
+    }
+
+    #[test]
+    fn test_create_ray_over_under_parallel() {
+        let camera = StereoCamera::new(
+            vec3::Vec3::new(0.0, 0.0, 5.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+            math::degree_to_radian(60.0),
+            1.0,
+            101,
+            51,
+        )
+        .with_interocular_distance(0.064)
+        .with_layout(StereoLayout::OverUnder);
+        assert_eq!(camera.image_width(), 101);
+        assert_eq!(camera.image_height(), 102);
+
+        let left_ray = create_ray(&camera, 50.0, 25.0);
+        let right_ray = create_ray(&camera, 50.0, 76.0);
+        assert!(left_ray.origin().x < 0.0);
+        assert!(right_ray.origin().x > 0.0);
+        assert!(left_ray
+            .direction()
+            .equal_epsilon(right_ray.direction(), math::EPSILON_F32_5));
+    }
+}
@@ -1,10 +1,36 @@
 pub mod area_light;
+pub mod directional_light;
 pub mod point_light;
+pub mod spot_light;
 
 use crate::core::vec2;
 use crate::core::vec3;
 use crate::scene::world;
 
+// how point and spot lights fade with the distance to the lit point
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DistanceFalloff {
+    // fades smoothly to zero at the radius of the light
+    Smooth,
+    // physical falloff of a light whose color is an intensity, the radius only cuts it off
+    InverseSquare,
+}
+
+impl DistanceFalloff {
+    pub fn attenuation(&self, distance_sq: f32, radius: f32) -> f32 {
+        return match self {
+            DistanceFalloff::Smooth => f32::max(1.0 - distance_sq / (radius * radius), 0.0),
+            DistanceFalloff::InverseSquare => {
+                if distance_sq > radius * radius {
+                    0.0
+                } else {
+                    1.0 / distance_sq
+                }
+            }
+        };
+    }
+}
+
 pub struct SampleLightRadiance {
     pub wi: vec3::Vec3,
     pub li: vec3::Vec3,
@@ -25,6 +51,7 @@ pub trait Light {
         world: &world::World,
         surface_point: &vec3::Vec3,
         surface_normal: &vec3::Vec3,
+        time: f32,
     ) -> Option<SampleLightRadiance>;
 
     fn sample_li_no_shadow_check(
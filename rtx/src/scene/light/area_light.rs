@@ -36,6 +36,7 @@ impl light::Light for AreaLight {
         world: &world::World,
         surface_point: &vec3::Vec3,
         surface_normal: &vec3::Vec3,
+        time: f32,
     ) -> Option<light::SampleLightRadiance> {
         let maybe_sample_shape_surface =
             self.shape
@@ -53,7 +54,7 @@ impl light::Light for AreaLight {
         }
 
         let normalize_direction = maybe_normalize_direction.unwrap();
-        let ray = ray::Ray::new(*surface_point, normalize_direction);
+        let ray = ray::Ray::new_with_time(*surface_point, normalize_direction, time);
         let max_distance = direction.length();
         if world.is_intersect(&ray, max_distance) {
             return None;
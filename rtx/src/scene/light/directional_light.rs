@@ -0,0 +1,53 @@
+use crate::core::vec2;
+use crate::core::vec3;
+use crate::scene::light;
+use crate::scene::ray;
+use crate::scene::world;
+
+pub struct DirectionalLight {
+    direction: vec3::Vec3,
+    color: vec3::Vec3,
+}
+
+impl DirectionalLight {
+    // direction is the direction the light travels along
+    pub fn new(direction: vec3::Vec3, color: vec3::Vec3) -> DirectionalLight {
+        return DirectionalLight {
+            direction: direction.normalize().unwrap(),
+            color,
+        };
+    }
+}
+
+impl light::Light for DirectionalLight {
+    fn num_samples(&self) -> u32 {
+        return 1;
+    }
+
+    fn sample_li(
+        &self,
+        _sample: &vec2::Vec2,
+        world: &world::World,
+        surface_point: &vec3::Vec3,
+        _surface_normal: &vec3::Vec3,
+        time: f32,
+    ) -> Option<light::SampleLightRadiance> {
+        let wi = -self.direction;
+        let ray = ray::Ray::new_with_time(*surface_point, wi, time);
+        if world.is_intersect(&ray, f32::INFINITY) {
+            return None;
+        }
+
+        return Some(light::SampleLightRadiance::new(wi, self.color));
+    }
+
+    fn sample_li_no_shadow_check(
+        &self,
+        _sample: &vec2::Vec2,
+        _world: &world::World,
+        _surface_point: &vec3::Vec3,
+        _surface_normal: &vec3::Vec3,
+    ) -> Option<light::SampleLightRadiance> {
+        return Some(light::SampleLightRadiance::new(-self.direction, self.color));
+    }
+}
@@ -8,6 +8,7 @@ pub struct PointLight {
     position: vec3::Vec3,
     radius: f32,
     color: vec3::Vec3,
+    falloff: light::DistanceFalloff,
 }
 
 impl PointLight {
@@ -16,8 +17,14 @@ impl PointLight {
             position,
             radius,
             color,
+            falloff: light::DistanceFalloff::Smooth,
         };
     }
+
+    pub fn with_falloff(mut self, falloff: light::DistanceFalloff) -> PointLight {
+        self.falloff = falloff;
+        return self;
+    }
 }
 
 impl light::Light for PointLight {
@@ -31,18 +38,19 @@ impl light::Light for PointLight {
         world: &world::World,
         surface_point: &vec3::Vec3,
         _surface_normal: &vec3::Vec3,
+        time: f32,
     ) -> Option<light::SampleLightRadiance> {
         let direction = self.position - surface_point;
         let normalize_direction = direction.normalize().unwrap();
 
         let max_distance = vec3::Vec3::length(&direction);
-        let ray = ray::Ray::new(*surface_point, normalize_direction);
+        let ray = ray::Ray::new_with_time(*surface_point, normalize_direction, time);
         if world.is_intersect(&ray, max_distance) {
             return None;
         }
 
         let distance_sq = vec3::Vec3::length_sq(&direction);
-        let attenuation = f32::max(1.0 - distance_sq / (self.radius * self.radius), 0.0);
+        let attenuation = self.falloff.attenuation(distance_sq, self.radius);
 
         return Some(light::SampleLightRadiance::new(
             normalize_direction,
@@ -60,7 +68,7 @@ impl light::Light for PointLight {
         let direction = self.position - surface_point;
         let normalize_direction = direction.normalize().unwrap();
         let distance_sq = vec3::Vec3::length_sq(&direction);
-        let attenuation = f32::max(1.0 - distance_sq / (self.radius * self.radius), 0.0);
+        let attenuation = self.falloff.attenuation(distance_sq, self.radius);
 
         return Some(light::SampleLightRadiance::new(
             normalize_direction,
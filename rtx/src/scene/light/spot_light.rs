@@ -0,0 +1,152 @@
+use crate::core::vec2;
+use crate::core::vec3;
+use crate::scene::light;
+use crate::scene::ray;
+use crate::scene::world;
+
+pub struct SpotLight {
+    position: vec3::Vec3,
+    direction: vec3::Vec3,
+    radius: f32,
+    color: vec3::Vec3,
+    cos_inner_cone_angle: f32,
+    cos_outer_cone_angle: f32,
+    falloff: light::DistanceFalloff,
+}
+
+impl SpotLight {
+    // direction is where the spot points to, the light fades out between the inner and the outer cone angles
+    pub fn new(
+        position: vec3::Vec3,
+        direction: vec3::Vec3,
+        color: vec3::Vec3,
+        radius: f32,
+        inner_cone_angle: f32,
+        outer_cone_angle: f32,
+    ) -> SpotLight {
+        return SpotLight {
+            position,
+            direction: direction.normalize().unwrap(),
+            radius,
+            color,
+            cos_inner_cone_angle: f32::cos(f32::min(inner_cone_angle, outer_cone_angle)),
+            cos_outer_cone_angle: f32::cos(outer_cone_angle),
+            falloff: light::DistanceFalloff::Smooth,
+        };
+    }
+
+    pub fn with_falloff(mut self, falloff: light::DistanceFalloff) -> SpotLight {
+        self.falloff = falloff;
+        return self;
+    }
+
+    fn falloff(&self, light_to_point: &vec3::Vec3) -> f32 {
+        let cos_theta = vec3::Vec3::dot(&self.direction, light_to_point);
+        if cos_theta < self.cos_outer_cone_angle {
+            return 0.0;
+        }
+
+        if cos_theta >= self.cos_inner_cone_angle {
+            return 1.0;
+        }
+
+        let delta = (cos_theta - self.cos_outer_cone_angle)
+            / (self.cos_inner_cone_angle - self.cos_outer_cone_angle);
+        return delta * delta * (3.0 - 2.0 * delta);
+    }
+
+    fn radiance(&self, surface_point: &vec3::Vec3) -> Option<light::SampleLightRadiance> {
+        let direction = self.position - surface_point;
+        let normalize_direction = direction.normalize()?;
+        let falloff = self.falloff(&-normalize_direction);
+        if falloff == 0.0 {
+            return None;
+        }
+
+        let distance_sq = vec3::Vec3::length_sq(&direction);
+        let attenuation = self.falloff.attenuation(distance_sq, self.radius);
+
+        return Some(light::SampleLightRadiance::new(
+            normalize_direction,
+            falloff * attenuation * self.color,
+        ));
+    }
+}
+
+impl light::Light for SpotLight {
+    fn num_samples(&self) -> u32 {
+        return 1;
+    }
+
+    fn sample_li(
+        &self,
+        _sample: &vec2::Vec2,
+        world: &world::World,
+        surface_point: &vec3::Vec3,
+        _surface_normal: &vec3::Vec3,
+        time: f32,
+    ) -> Option<light::SampleLightRadiance> {
+        let sample_light_radiance = self.radiance(surface_point)?;
+
+        let max_distance = vec3::Vec3::distance(&self.position, surface_point);
+        let ray = ray::Ray::new_with_time(*surface_point, sample_light_radiance.wi, time);
+        if world.is_intersect(&ray, max_distance) {
+            return None;
+        }
+
+        return Some(sample_light_radiance);
+    }
+
+    fn sample_li_no_shadow_check(
+        &self,
+        _sample: &vec2::Vec2,
+        _world: &world::World,
+        surface_point: &vec3::Vec3,
+        _surface_normal: &vec3::Vec3,
+    ) -> Option<light::SampleLightRadiance> {
+        return self.radiance(surface_point);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+    use crate::scene::light::Light;
+
+    #[test]
+    fn test_falloff() {
+        let spot_light = SpotLight::new(
+            vec3::Vec3::new(0.0, 0.0, 10.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            vec3::Vec3::from(1.0),
+            100.0,
+            0.0,
+            std::f32::consts::FRAC_PI_4,
+        );
+        let world = world::World::new();
+        let normal = vec3::Vec3::new(0.0, 0.0, 1.0);
+        let sample = vec2::Vec2::from(0.0);
+
+        let center = spot_light
+            .sample_li_no_shadow_check(&sample, &world, &vec3::Vec3::from(0.0), &normal)
+            .unwrap();
+        assert!(center
+            .wi
+            .equal_epsilon(&vec3::Vec3::new(0.0, 0.0, 1.0), math::EPSILON_F32_5));
+        assert!(math::equal_epsilon_f32(
+            center.li.x,
+            0.99,
+            math::EPSILON_F32_5
+        ));
+
+        let edge = spot_light
+            .sample_li_no_shadow_check(&sample, &world, &vec3::Vec3::new(5.0, 0.0, 0.0), &normal)
+            .unwrap();
+        assert!(edge.li.x > 0.0 && edge.li.x < center.li.x);
+
+        assert!(spot_light
+            .sample_li_no_shadow_check(&sample, &world, &vec3::Vec3::new(20.0, 0.0, 0.0), &normal)
+            .is_none());
+    }
+}
@@ -1,9 +1,12 @@
 pub mod glass;
 pub mod matte;
+pub mod metal;
+pub mod textured_matte;
 
 use crate::core::vec2;
 use crate::core::vec3;
 use crate::scene::reflectance;
+use crate::scene::shape;
 
 pub trait Material {
     fn bxdf(
@@ -22,4 +25,12 @@ pub trait Material {
         wo: &vec3::Vec3,
         flags: u32,
     ) -> Option<reflectance::ReflectanceRadiance>;
+
+    // materials that vary over a surface return the material to shade the intersected surface with
+    fn surface_material(
+        &self,
+        _surface: &shape::IntersectableShapeSurface,
+    ) -> Option<Box<dyn Material>> {
+        return None;
+    }
 }
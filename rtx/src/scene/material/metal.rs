@@ -0,0 +1,56 @@
+use crate::core::math;
+use crate::core::vec2;
+use crate::core::vec3;
+use crate::scene::fresnel::conductor;
+use crate::scene::material;
+use crate::scene::microfacet_distribution::trowbridge_reitz;
+use crate::scene::reflectance;
+use crate::scene::reflectance::{microfacet_reflection, reflection};
+use std::rc;
+
+pub struct Metal {
+    reflectances: reflectance::ReflectanceCollection,
+}
+
+impl Metal {
+    // eta and k are the conductor's index of refraction and absorption, a zero roughness is a mirror
+    pub fn new(ks: vec3::Vec3, eta: vec3::Vec3, k: vec3::Vec3, roughness: f32) -> Metal {
+        let mut reflectances = reflectance::ReflectanceCollection::new();
+        let fresnel = rc::Rc::new(conductor::Conductor::new(vec3::Vec3::from(1.0), eta, k));
+
+        if math::equal_epsilon_f32(roughness, 0.0, math::EPSILON_F32_6) {
+            reflectances.add(Box::new(reflection::Reflection::new(ks, fresnel)));
+        } else {
+            reflectances.add(Box::new(microfacet_reflection::MicrofacetReflection::new(
+                ks,
+                rc::Rc::new(trowbridge_reitz::TrowbridgeReitz::new(roughness, roughness)),
+                fresnel,
+            )));
+        }
+
+        return Metal { reflectances };
+    }
+}
+
+impl material::Material for Metal {
+    fn bxdf(
+        &self,
+        normal: &vec3::Vec3,
+        dpdu: &vec3::Vec3,
+        wo: &vec3::Vec3,
+        wi: &vec3::Vec3,
+    ) -> vec3::Vec3 {
+        return self.reflectances.bxdf(normal, dpdu, wo, wi);
+    }
+
+    fn sample_bxdf(
+        &self,
+        sample: &vec2::Vec2,
+        normal: &vec3::Vec3,
+        dpdu: &vec3::Vec3,
+        wo: &vec3::Vec3,
+        flags: u32,
+    ) -> Option<reflectance::ReflectanceRadiance> {
+        return self.reflectances.sample_bxdf(sample, normal, dpdu, wo, flags);
+    }
+}
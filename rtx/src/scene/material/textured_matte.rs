@@ -0,0 +1,54 @@
+use crate::core::vec2;
+use crate::core::vec3;
+use crate::scene::material;
+use crate::scene::material::matte;
+use crate::scene::reflectance;
+use crate::scene::shape;
+use crate::scene::texture;
+use std::rc;
+
+// matte material whose diffuse color is looked up from a texture at every intersected surface
+pub struct TexturedMatte {
+    kd: rc::Rc<dyn texture::Texture>,
+    sigma: f32,
+}
+
+impl TexturedMatte {
+    pub fn new(kd: rc::Rc<dyn texture::Texture>, sigma: f32) -> TexturedMatte {
+        return TexturedMatte { kd, sigma };
+    }
+}
+
+impl material::Material for TexturedMatte {
+    // the material is only shaded through the matte created for each surface
+    fn bxdf(
+        &self,
+        _normal: &vec3::Vec3,
+        _dpdu: &vec3::Vec3,
+        _wo: &vec3::Vec3,
+        _wi: &vec3::Vec3,
+    ) -> vec3::Vec3 {
+        return vec3::Vec3::from(0.0);
+    }
+
+    fn sample_bxdf(
+        &self,
+        _sample: &vec2::Vec2,
+        _normal: &vec3::Vec3,
+        _dpdu: &vec3::Vec3,
+        _wo: &vec3::Vec3,
+        _flags: u32,
+    ) -> Option<reflectance::ReflectanceRadiance> {
+        return None;
+    }
+
+    fn surface_material(
+        &self,
+        surface: &shape::IntersectableShapeSurface,
+    ) -> Option<Box<dyn material::Material>> {
+        return Some(Box::new(matte::Matte::new(
+            self.kd.evaluate(surface),
+            self.sigma,
+        )));
+    }
+}
@@ -6,14 +6,20 @@ use crate::core::vec4;
 pub struct Ray {
     origin: vec3::Vec3,
     direction: vec3::Vec3,
+    time: f32,
 }
 
 impl Ray {
     pub fn new(origin: vec3::Vec3, direction: vec3::Vec3) -> Ray {
+        return Ray::new_with_time(origin, direction, 0.0);
+    }
+
+    pub fn new_with_time(origin: vec3::Vec3, direction: vec3::Vec3, time: f32) -> Ray {
         let norm_direction = vec3::Vec3::normalize(&direction).unwrap();
         return Ray {
             origin,
             direction: norm_direction,
+            time,
         };
     }
 
@@ -25,14 +31,19 @@ impl Ray {
         return &self.direction;
     }
 
+    pub fn time(&self) -> f32 {
+        return self.time;
+    }
+
     pub fn calc_position(&self, t: f32) -> vec3::Vec3 {
         return self.origin + t * self.direction;
     }
 
     pub fn transform(ray: &Ray, mat: &mat4::Mat4) -> Ray {
-        return Ray::new(
+        return Ray::new_with_time(
             vec4::Vec4::to_vec3(&(mat * vec4::Vec4::from_vec3(&ray.origin, 1.0))),
             vec4::Vec4::to_vec3(&(mat * vec4::Vec4::from_vec3(&ray.direction, 0.0))),
+            ray.time,
         );
     }
 }
@@ -102,4 +113,33 @@ mod test {
             math::EPSILON_F32_5
         ));
     }
+
+    #[test]
+    fn test_transform_keeps_time() {
+        let ray = Ray::new_with_time(
+            vec3::Vec3::new(1.0, 3.2, 1.2),
+            vec3::Vec3::new(2.0, 2.0, 1.0),
+            0.75,
+        );
+        assert!(math::equal_epsilon_f32(
+            Ray::new(*ray.origin(), *ray.direction()).time(),
+            0.0,
+            math::EPSILON_F32_5
+        ));
+
+        let transformed_ray = Ray::transform(
+            &ray,
+            &mat4::Mat4::new().translate(&vec3::Vec3::new(1.0, 0.0, 0.0)),
+        );
+        assert!(math::equal_epsilon_f32(
+            transformed_ray.time(),
+            0.75,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            transformed_ray.origin().x,
+            2.0,
+            math::EPSILON_F32_5
+        ));
+    }
 }
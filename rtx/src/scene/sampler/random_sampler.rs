@@ -4,13 +4,20 @@ use rand::prelude::*;
 use rand::rngs;
 
 pub struct RandomSampler {
-    rng: rngs::ThreadRng,
+    rng: rngs::StdRng,
 }
 
 impl RandomSampler {
     pub fn new() -> RandomSampler {
         return RandomSampler {
-            rng: rand::thread_rng(),
+            rng: rngs::StdRng::from_entropy(),
+        };
+    }
+
+    // a seeded sampler produces the same sample sequence on every run
+    pub fn from_seed(seed: u64) -> RandomSampler {
+        return RandomSampler {
+            rng: rngs::StdRng::seed_from_u64(seed),
         };
     }
 }
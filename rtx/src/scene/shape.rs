@@ -1,39 +1,61 @@
+pub mod animated_shape;
 pub mod cone;
+pub mod csg;
 pub mod cylinder;
 pub mod disk;
+pub mod heightfield;
+pub mod hyperboloid;
+pub mod instance;
+pub mod nurbs_surface;
+pub mod paraboloid;
 pub mod plane;
 pub mod rectangle;
+pub mod sdf;
+pub mod shape_group;
 pub mod sphere;
+pub mod subdivision_surface;
+pub mod torus;
+pub mod triangle_mesh;
 
 use crate::core::mat4;
+use crate::core::math;
 use crate::core::vec2;
 use crate::core::vec3;
 use crate::core::vec4;
+use crate::scene::aabb;
 use crate::scene::material;
 use crate::scene::ray;
 use std::rc;
 
-#[derive(Debug)]
-pub struct IntersectableShapeSurface<'a> {
+// distance the ray is moved past a surface before looking for the next one along it
+const SURFACE_OFFSET: f32 = 0.0001;
+
+// upper bound of the surfaces collected along a ray, which stops marching through degenerate shapes
+const MAX_RAY_SURFACES: usize = 64;
+
+pub struct IntersectableShapeSurface {
     ray_time: f32,
     position: vec3::Vec3,
     normal: vec3::Vec3,
     dpdu: vec3::Vec3,
     dpdv: vec3::Vec3,
-    object_to_world: &'a mat4::Mat4,
-    normal_transform: &'a mat4::Mat4,
+    object_to_world: mat4::Mat4,
+    normal_transform: mat4::Mat4,
+    vertex_color: Option<vec3::Vec3>,
+    uv: Option<vec2::Vec2>,
+    material: Option<rc::Rc<dyn material::Material>>,
 }
 
-impl<'a> IntersectableShapeSurface<'a> {
+impl IntersectableShapeSurface {
     pub fn new(
         ray_time: f32,
         position: vec3::Vec3,
         normal: vec3::Vec3,
         dpdu: vec3::Vec3,
         dpdv: vec3::Vec3,
-        object_to_world: &'a mat4::Mat4,
-        normal_transform: &'a mat4::Mat4,
-    ) -> IntersectableShapeSurface<'a> {
+        object_to_world: mat4::Mat4,
+        normal_transform: mat4::Mat4,
+    ) -> IntersectableShapeSurface {
         return IntersectableShapeSurface {
             ray_time,
             position,
@@ -42,6 +64,75 @@ impl<'a> IntersectableShapeSurface<'a> {
             dpdv,
             object_to_world,
             normal_transform,
+            vertex_color: None,
+            uv: None,
+            material: None,
+        };
+    }
+
+    // color interpolated from the vertices of a mesh, for textures that read it
+    pub fn with_vertex_color(mut self, vertex_color: vec3::Vec3) -> IntersectableShapeSurface {
+        self.vertex_color = Some(vertex_color);
+        return self;
+    }
+
+    // texture coordinates of shapes that have a parameterization over the unit square
+    pub fn with_uv(mut self, uv: vec2::Vec2) -> IntersectableShapeSurface {
+        self.uv = Some(uv);
+        return self;
+    }
+
+    // ray time measured in the space of a shape that contains the intersected one
+    pub fn with_ray_time(mut self, ray_time: f32) -> IntersectableShapeSurface {
+        self.ray_time = ray_time;
+        return self;
+    }
+
+    // material chosen by a composite shape, which replaces the material of the shape it is rendered with
+    pub fn with_material(
+        mut self,
+        material: rc::Rc<dyn material::Material>,
+    ) -> IntersectableShapeSurface {
+        self.material = Some(material);
+        return self;
+    }
+
+    // turns the normal to the other side, for surfaces that bound a solid from the outside
+    pub fn with_flipped_normal(mut self) -> IntersectableShapeSurface {
+        self.normal = -self.normal;
+        return self;
+    }
+
+    pub fn vertex_color(&self) -> Option<vec3::Vec3> {
+        return self.vertex_color;
+    }
+
+    pub fn uv(&self) -> Option<vec2::Vec2> {
+        return self.uv;
+    }
+
+    pub fn material(&self) -> Option<&rc::Rc<dyn material::Material>> {
+        return self.material.as_ref();
+    }
+
+    // place the surface of a shape that was intersected in the space of another transform
+    pub fn transform(
+        &self,
+        ray_time: f32,
+        object_to_world: &mat4::Mat4,
+        normal_transform: &mat4::Mat4,
+    ) -> IntersectableShapeSurface {
+        return IntersectableShapeSurface {
+            ray_time,
+            position: self.position,
+            normal: self.normal,
+            dpdu: self.dpdu,
+            dpdv: self.dpdv,
+            object_to_world: object_to_world * self.object_to_world,
+            normal_transform: normal_transform * self.normal_transform,
+            vertex_color: self.vertex_color,
+            uv: self.uv,
+            material: self.material.clone(),
         };
     }
 
@@ -75,10 +166,44 @@ impl<'a> IntersectableShapeSurface<'a> {
     }
 }
 
+// angle of a local point around the z axis in [0, 2 pi), which partial quadrics are swept along
+pub fn calc_phi(local_position: &vec3::Vec3) -> f32 {
+    let phi = f32::atan2(local_position.y, local_position.x);
+    if phi < 0.0 {
+        return phi + 2.0 * math::PI_F32;
+    }
+
+    return phi;
+}
+
 pub trait IntersectableShape {
     fn is_intersect(&self, ray: &ray::Ray, max_distance: f32) -> bool;
 
     fn intersect_ray(&self, ray: &ray::Ray) -> Option<IntersectableShapeSurface>;
+
+    // every surface along the ray ordered by distance, with the ray time set to the distance from the
+    // ray origin. Marches from hit to hit unless a shape knows all of its hits at once
+    fn intersect_ray_all(&self, ray: &ray::Ray) -> Vec<IntersectableShapeSurface> {
+        let mut surfaces = Vec::new();
+        let mut offset = 0.0;
+        while surfaces.len() < MAX_RAY_SURFACES {
+            let offset_ray =
+                ray::Ray::new_with_time(ray.calc_position(offset), *ray.direction(), ray.time());
+            let surface = match self.intersect_ray(&offset_ray) {
+                Some(surface) => surface,
+                None => break,
+            };
+
+            let distance = (surface.calc_world_position() - *ray.origin()).dot(ray.direction());
+            offset = f32::max(distance, offset) + SURFACE_OFFSET;
+            surfaces.push(surface.with_ray_time(distance));
+        }
+
+        return surfaces;
+    }
+
+    // bounds in the space the shape is intersected in, which is world space for shapes added to the world
+    fn aabb(&self) -> aabb::AABB;
 }
 
 #[derive(Debug)]
@@ -103,18 +228,28 @@ pub trait SamplableShape {
 }
 
 pub struct RenderableShapeSurface<'a> {
-    shape_surface: IntersectableShapeSurface<'a>,
+    shape_surface: IntersectableShapeSurface,
     material: &'a dyn material::Material,
+    operand_material: Option<rc::Rc<dyn material::Material>>,
+    surface_material: Option<Box<dyn material::Material>>,
 }
 
 impl<'a> RenderableShapeSurface<'a> {
     pub fn new(
-        shape_surface: IntersectableShapeSurface<'a>,
+        mut shape_surface: IntersectableShapeSurface,
         material: &'a dyn material::Material,
     ) -> RenderableShapeSurface<'a> {
+        // a material picked by a composite shape overrides the one of the renderable shape
+        let operand_material = shape_surface.material.take();
+        let surface_material = match &operand_material {
+            Some(operand_material) => operand_material.surface_material(&shape_surface),
+            None => material.surface_material(&shape_surface),
+        };
         return RenderableShapeSurface {
             shape_surface,
             material,
+            operand_material,
+            surface_material,
         };
     }
 
@@ -122,8 +257,16 @@ impl<'a> RenderableShapeSurface<'a> {
         return &self.shape_surface;
     }
 
+    // textured materials are resolved at the intersected surface
     pub fn material(&self) -> &dyn material::Material {
-        return self.material;
+        if let Some(surface_material) = &self.surface_material {
+            return surface_material.as_ref();
+        }
+
+        return match &self.operand_material {
+            Some(operand_material) => operand_material.as_ref(),
+            None => self.material,
+        };
     }
 }
 
@@ -140,10 +283,18 @@ impl RenderableShape {
         return RenderableShape { shape, material };
     }
 
+    pub fn set_shape(&mut self, shape: rc::Rc<dyn IntersectableShape>) {
+        self.shape = shape;
+    }
+
     pub fn is_intersect(&self, ray: &ray::Ray, max_distance: f32) -> bool {
         return self.shape.is_intersect(ray, max_distance);
     }
 
+    pub fn aabb(&self) -> aabb::AABB {
+        return self.shape.aabb();
+    }
+
     pub fn intersect_ray(&self, ray: &ray::Ray) -> Option<RenderableShapeSurface> {
         return match self.shape.intersect_ray(ray) {
             Some(hit_record) => Some(RenderableShapeSurface::new(
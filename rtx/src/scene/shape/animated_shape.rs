@@ -0,0 +1,192 @@
+use crate::core::animated_transform;
+use crate::core::mat4;
+use crate::core::vec3;
+use crate::core::vec4;
+use crate::scene::aabb;
+use crate::scene::ray;
+use crate::scene::shape;
+use std::rc;
+
+const NUM_BOUND_SAMPLES: usize = 128;
+const BOUND_PADDING: f32 = 0.001;
+
+pub struct AnimatedShape {
+    shape: rc::Rc<dyn shape::IntersectableShape>,
+    transform: animated_transform::AnimatedTransform,
+}
+
+impl AnimatedShape {
+    pub fn new(
+        shape: rc::Rc<dyn shape::IntersectableShape>,
+        transform: animated_transform::AnimatedTransform,
+    ) -> AnimatedShape {
+        return AnimatedShape { shape, transform };
+    }
+
+    // returns the transform at the ray time and the ray in the shape's space
+    fn local_ray(&self, ray: &ray::Ray) -> Option<(mat4::Mat4, ray::Ray)> {
+        let object_to_world = self.transform.interpolate(ray.time());
+        let world_to_object = object_to_world.inverse()?;
+        let local_direction =
+            vec4::Vec4::to_vec3(&(world_to_object * vec4::Vec4::from_vec3(ray.direction(), 0.0)));
+        if vec3::Vec3::length(&local_direction) == 0.0 {
+            return None;
+        }
+
+        let local_ray = ray::Ray::transform(ray, &world_to_object);
+        return Some((object_to_world, local_ray));
+    }
+}
+
+impl shape::IntersectableShape for AnimatedShape {
+    // the closest hit is compared, since max_distance can not be mapped into the shape's space
+    fn is_intersect(&self, ray: &ray::Ray, max_distance: f32) -> bool {
+        return match self.intersect_ray(ray) {
+            Some(surface) => surface.ray_time() < max_distance,
+            None => false,
+        };
+    }
+
+    // the shape reports ray times in its own space, which can be scaled again by its own transforms,
+    // so the ray time is measured from the hit in world space
+    fn intersect_ray(&self, ray: &ray::Ray) -> Option<shape::IntersectableShapeSurface> {
+        let (object_to_world, local_ray) = self.local_ray(ray)?;
+        let surface = self.shape.intersect_ray(&local_ray)?;
+        let normal_transform = object_to_world.transpose().inverse()?;
+        let world_surface = surface.transform(0.0, &object_to_world, &normal_transform);
+        let distance = world_surface.calc_world_position().distance(ray.origin());
+        return Some(world_surface.with_ray_time(distance));
+    }
+
+    // the bounds of the shape are swept along the motion at sampled times, then padded a little for
+    // the arcs of rotations between the samples
+    fn aabb(&self) -> aabb::AABB {
+        let local_aabb = self.shape.aabb();
+        if !self.transform.is_animated() {
+            return local_aabb.transform(&self.transform.interpolate(self.transform.start_time()));
+        }
+
+        let start_time = self.transform.start_time();
+        let duration = self.transform.end_time() - start_time;
+        let mut aabb = aabb::AABB::empty();
+        for i in 0..=NUM_BOUND_SAMPLES {
+            let time = start_time + duration * (i as f32) / (NUM_BOUND_SAMPLES as f32);
+            aabb.merge_aabb(&local_aabb.transform(&self.transform.interpolate(time)));
+        }
+
+        let padding = vec3::Vec3::from(BOUND_PADDING * aabb.diagonal().length());
+        return aabb::AABB::new(aabb.min() - padding, aabb.max() + padding);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+    use crate::scene::shape::sphere;
+    use crate::scene::shape::IntersectableShape;
+
+    fn create_moving_sphere() -> AnimatedShape {
+        let sphere = rc::Rc::new(sphere::Sphere::new(mat4::Mat4::new(), 1.0));
+        let transform = animated_transform::AnimatedTransform::new(&[
+            (0.0, mat4::Mat4::new()),
+            (
+                1.0,
+                mat4::Mat4::new()
+                    .translate(&vec3::Vec3::new(4.0, 0.0, 0.0))
+                    .scale(&vec3::Vec3::new(2.0, 2.0, 2.0)),
+            ),
+        ]);
+        return AnimatedShape::new(sphere, transform);
+    }
+
+    #[test]
+    fn test_intersect_ray_at_time() {
+        let shape = create_moving_sphere();
+
+        let ray = ray::Ray::new_with_time(
+            vec3::Vec3::new(0.0, 0.0, 10.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            0.0,
+        );
+        let surface = shape.intersect_ray(&ray).unwrap();
+        assert!(math::equal_epsilon_f32(
+            surface.ray_time(),
+            9.0,
+            math::EPSILON_F32_4
+        ));
+
+        // halfway through the sphere has moved by 2 and has a radius of 1.5
+        let ray = ray::Ray::new_with_time(
+            vec3::Vec3::new(2.0, 0.0, 10.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            0.5,
+        );
+        let surface = shape.intersect_ray(&ray).unwrap();
+        assert!(math::equal_epsilon_f32(
+            surface.ray_time(),
+            8.5,
+            math::EPSILON_F32_4
+        ));
+        assert!(surface
+            .calc_world_position()
+            .equal_epsilon(&vec3::Vec3::new(2.0, 0.0, 1.5), math::EPSILON_F32_4));
+        assert!(surface
+            .calc_world_normal()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 0.0, 1.0), math::EPSILON_F32_4));
+
+        let ray = ray::Ray::new_with_time(
+            vec3::Vec3::new(0.0, 0.0, 10.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            1.0,
+        );
+        assert!(shape.intersect_ray(&ray).is_none());
+    }
+
+    #[test]
+    fn test_is_intersect_at_time() {
+        let shape = create_moving_sphere();
+        let ray = ray::Ray::new_with_time(
+            vec3::Vec3::new(4.0, 0.0, 10.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            1.0,
+        );
+        assert!(shape.is_intersect(&ray, 8.5));
+        assert!(!shape.is_intersect(&ray, 7.5));
+
+        let ray = ray::Ray::new_with_time(
+            vec3::Vec3::new(4.0, 0.0, 10.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            0.0,
+        );
+        assert!(!shape.is_intersect(&ray, 100.0));
+    }
+
+    #[test]
+    fn test_scaled_shape() {
+        // a sphere of radius 2 from its own transform, halved by the motion at time 1
+        let sphere = rc::Rc::new(sphere::Sphere::new(
+            mat4::Mat4::new().scale(&vec3::Vec3::from(2.0)),
+            1.0,
+        ));
+        let transform = animated_transform::AnimatedTransform::new(&[
+            (0.0, mat4::Mat4::new()),
+            (1.0, mat4::Mat4::new().scale(&vec3::Vec3::from(0.5))),
+        ]);
+        let shape = AnimatedShape::new(sphere, transform);
+        let ray = ray::Ray::new_with_time(
+            vec3::Vec3::new(0.0, 0.0, 10.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+            1.0,
+        );
+
+        let surface = shape.intersect_ray(&ray).unwrap();
+        assert!(math::equal_epsilon_f32(
+            surface.ray_time(),
+            9.0,
+            math::EPSILON_F32_4
+        ));
+        assert!(shape.is_intersect(&ray, 9.5));
+        assert!(!shape.is_intersect(&ray, 8.5));
+    }
+}
@@ -3,6 +3,7 @@ use crate::core::math;
 use crate::core::vec2;
 use crate::core::vec3;
 use crate::core::vec4;
+use crate::scene::aabb;
 use crate::scene::ray;
 use crate::scene::shape;
 use std::mem;
@@ -13,6 +14,7 @@ pub struct Cone {
     normal_transform: mat4::Mat4,
     radius: f32,
     height: f32,
+    phi_max: f32,
 }
 
 impl Cone {
@@ -27,8 +29,30 @@ impl Cone {
             normal_transform,
             radius,
             height,
+            phi_max: 2.0 * math::PI_F32,
         };
     }
+
+    // keeps the part of the cone swept from the x axis by phi_max around the z axis
+    pub fn with_phi_max(mut self, phi_max: f32) -> Cone {
+        self.phi_max = math::clamp(phi_max, 0.0, 2.0 * math::PI_F32);
+        return self;
+    }
+
+    // area of the side, the cone has no base
+    pub fn area(&self) -> f32 {
+        return 0.5
+            * self.phi_max
+            * self.radius
+            * f32::sqrt(self.height * self.height + self.radius * self.radius);
+    }
+
+    fn is_in_range(&self, local_position: &vec3::Vec3) -> bool {
+        return local_position.z >= 0.0
+            && local_position.z <= self.height
+            && (self.phi_max >= 2.0 * math::PI_F32
+                || shape::calc_phi(local_position) <= self.phi_max);
+    }
 }
 
 impl shape::IntersectableShape for Cone {
@@ -69,14 +93,14 @@ impl shape::IntersectableShape for Cone {
         }
 
         let mut local_position = local_ray.calc_position(ray_time);
-        if local_position.z < 0.0 || local_position.z > self.height {
+        if !self.is_in_range(&local_position) {
             ray_time = ray_time_1;
             if ray_time < 0.0 {
                 return false;
             }
 
             local_position = local_ray.calc_position(ray_time);
-            if local_position.z < 0.0 || local_position.z > self.height {
+            if !self.is_in_range(&local_position) {
                 return false;
             }
         }
@@ -124,22 +148,22 @@ impl shape::IntersectableShape for Cone {
         }
 
         let mut local_position = local_ray.calc_position(ray_time);
-        if local_position.z < 0.0 || local_position.z > self.height {
+        if !self.is_in_range(&local_position) {
             ray_time = ray_time_1;
             if ray_time < 0.0 {
                 return None;
             }
 
             local_position = local_ray.calc_position(ray_time);
-            if local_position.z < 0.0 || local_position.z > self.height {
+            if !self.is_in_range(&local_position) {
                 return None;
             }
         }
 
         let v = local_position.z / self.height;
         let local_dpdu = vec3::Vec3::new(
-            -2.0 * math::PI_F32 * local_position.y,
-            2.0 * math::PI_F32 * local_position.x,
+            -self.phi_max * local_position.y,
+            self.phi_max * local_position.x,
             0.0,
         );
         let local_dpdv = vec3::Vec3::new(
@@ -161,10 +185,18 @@ impl shape::IntersectableShape for Cone {
             local_normal,
             local_dpdu,
             local_dpdv,
-            &self.object_to_world,
-            &self.normal_transform,
+            self.object_to_world,
+            self.normal_transform,
         ));
     }
+
+    fn aabb(&self) -> aabb::AABB {
+        return aabb::AABB::new(
+            vec3::Vec3::new(-self.radius, -self.radius, 0.0),
+            vec3::Vec3::new(self.radius, self.radius, self.height),
+        )
+        .transform(&self.object_to_world);
+    }
 }
 
 impl shape::SamplableShape for Cone {
@@ -178,8 +210,8 @@ impl shape::SamplableShape for Cone {
             (self.world_to_object * vec4::Vec4::from_vec3(surface_point_ref, 1.0)).to_vec3();
 
         let z = self.height * (-f32::sqrt(sample.x));
-        let radius = (self.radius / self.height) * z;
-        let theta = 2.0 * math::PI_F32 * sample.y;
+        let radius = -(self.radius / self.height) * z;
+        let theta = self.phi_max * sample.y;
 
         let local_sample_point = vec3::Vec3::new(
             radius * f32::cos(theta),
@@ -190,8 +222,8 @@ impl shape::SamplableShape for Cone {
         // calculate local normal
         let v = local_sample_point.z / self.height;
         let local_dpdu = vec3::Vec3::new(
-            -2.0 * math::PI_F32 * local_sample_point.y,
-            2.0 * math::PI_F32 * local_sample_point.x,
+            -self.phi_max * local_sample_point.y,
+            self.phi_max * local_sample_point.x,
             0.0,
         );
         let local_dpdv = vec3::Vec3::new(
@@ -207,13 +239,9 @@ impl shape::SamplableShape for Cone {
             return None;
         }
 
-        let area = math::PI_F32
-            * self.radius
-            * (self.radius + f32::sqrt(self.height * self.height + self.radius * self.radius));
-
         let world_surface_point =
             (self.object_to_world * vec4::Vec4::from_vec3(&local_sample_point, 1.0)).to_vec3();
-        let pdf = direction.length_sq() / (area * cos_theta);
+        let pdf = direction.length_sq() / (self.area() * cos_theta);
         return Some(shape::SampleShapeSurface::new(pdf, world_surface_point));
     }
 }
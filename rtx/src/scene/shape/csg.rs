@@ -0,0 +1,328 @@
+use crate::scene::aabb;
+use crate::scene::material;
+use crate::scene::ray;
+use crate::scene::shape;
+use std::rc;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl CsgOperation {
+    fn is_inside(&self, in_left: bool, in_right: bool) -> bool {
+        return match self {
+            CsgOperation::Union => in_left || in_right,
+            CsgOperation::Intersection => in_left && in_right,
+            CsgOperation::Difference => in_left && !in_right,
+        };
+    }
+}
+
+// constructive solid geometry of two closed operands, which can be csg nodes themselves. The ray is
+// split into the intervals inside each operand and combined, so every entry and exit of the result is
+// known. Normals of the result point out of it, which flips the surfaces of a subtracted operand
+pub struct Csg {
+    operation: CsgOperation,
+    left: rc::Rc<dyn shape::IntersectableShape>,
+    right: rc::Rc<dyn shape::IntersectableShape>,
+    material: Option<rc::Rc<dyn material::Material>>,
+    left_material: Option<rc::Rc<dyn material::Material>>,
+    right_material: Option<rc::Rc<dyn material::Material>>,
+}
+
+impl Csg {
+    pub fn new(
+        operation: CsgOperation,
+        left: rc::Rc<dyn shape::IntersectableShape>,
+        right: rc::Rc<dyn shape::IntersectableShape>,
+    ) -> Csg {
+        return Csg {
+            operation,
+            left,
+            right,
+            material: None,
+            left_material: None,
+            right_material: None,
+        };
+    }
+
+    // material of the whole node, without it a surface keeps the material of the operand it is on
+    pub fn with_material(mut self, material: rc::Rc<dyn material::Material>) -> Csg {
+        self.material = Some(material);
+        return self;
+    }
+
+    // materials of surfaces that come from each operand, unless a nested node already picked one
+    pub fn with_operand_materials(
+        mut self,
+        left_material: Option<rc::Rc<dyn material::Material>>,
+        right_material: Option<rc::Rc<dyn material::Material>>,
+    ) -> Csg {
+        self.left_material = left_material;
+        self.right_material = right_material;
+        return self;
+    }
+
+    pub fn operation(&self) -> CsgOperation {
+        return self.operation;
+    }
+
+    fn resolve_material(
+        &self,
+        surface: shape::IntersectableShapeSurface,
+        is_left: bool,
+    ) -> shape::IntersectableShapeSurface {
+        if let Some(material) = &self.material {
+            return surface.with_material(material.clone());
+        }
+
+        if surface.material().is_some() {
+            return surface;
+        }
+
+        let operand_material = if is_left {
+            &self.left_material
+        } else {
+            &self.right_material
+        };
+        return match operand_material {
+            Some(material) => surface.with_material(material.clone()),
+            None => surface,
+        };
+    }
+}
+
+impl shape::IntersectableShape for Csg {
+    fn is_intersect(&self, ray: &ray::Ray, max_distance: f32) -> bool {
+        return match self.intersect_ray(ray) {
+            Some(surface) => surface.ray_time() < max_distance,
+            None => false,
+        };
+    }
+
+    fn intersect_ray(&self, ray: &ray::Ray) -> Option<shape::IntersectableShapeSurface> {
+        return self.intersect_ray_all(ray).into_iter().next();
+    }
+
+    // merges the surfaces of both operands along the ray and keeps the ones where the ray crosses
+    // the boundary of the result
+    fn intersect_ray_all(&self, ray: &ray::Ray) -> Vec<shape::IntersectableShapeSurface> {
+        let left_surfaces = self.left.intersect_ray_all(ray);
+        let right_surfaces = self.right.intersect_ray_all(ray);
+
+        // operands are closed, an odd number of surfaces ahead means the ray starts inside
+        let mut in_left = left_surfaces.len() % 2 == 1;
+        let mut in_right = right_surfaces.len() % 2 == 1;
+        let mut is_inside = self.operation.is_inside(in_left, in_right);
+
+        let mut events: Vec<(shape::IntersectableShapeSurface, bool)> = left_surfaces
+            .into_iter()
+            .map(|surface| (surface, true))
+            .chain(right_surfaces.into_iter().map(|surface| (surface, false)))
+            .collect();
+        events.sort_by(|a, b| a.0.ray_time().partial_cmp(&b.0.ray_time()).unwrap());
+
+        let mut surfaces = Vec::new();
+        for (surface, is_left) in events {
+            if is_left {
+                in_left = !in_left;
+            } else {
+                in_right = !in_right;
+            }
+
+            let was_inside = is_inside;
+            is_inside = self.operation.is_inside(in_left, in_right);
+            if is_inside == was_inside {
+                continue;
+            }
+
+            // entering faces against the ray and leaving faces along it
+            let facing = surface.calc_world_normal().dot(ray.direction());
+            let surface = if (is_inside && facing > 0.0) || (!is_inside && facing < 0.0) {
+                surface.with_flipped_normal()
+            } else {
+                surface
+            };
+            surfaces.push(self.resolve_material(surface, is_left));
+        }
+
+        return surfaces;
+    }
+
+    fn aabb(&self) -> aabb::AABB {
+        let left_aabb = self.left.aabb();
+        let right_aabb = self.right.aabb();
+        return match self.operation {
+            CsgOperation::Union => {
+                let mut aabb = left_aabb;
+                aabb.merge_aabb(&right_aabb);
+                aabb
+            }
+            CsgOperation::Intersection => {
+                let aabb = left_aabb.intersect_aabb(&right_aabb);
+                if aabb.is_empty() {
+                    aabb::AABB::empty()
+                } else {
+                    aabb
+                }
+            }
+            CsgOperation::Difference => left_aabb,
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::mat4;
+    use crate::core::math;
+    use crate::core::vec3;
+    use crate::scene::material::matte;
+    use crate::scene::shape::sphere;
+    use crate::scene::shape::IntersectableShape;
+
+    fn create_sphere(x: f32, radius: f32) -> rc::Rc<dyn shape::IntersectableShape> {
+        return rc::Rc::new(sphere::Sphere::new(
+            mat4::Mat4::new().translate(&vec3::Vec3::new(x, 0.0, 0.0)),
+            radius,
+        ));
+    }
+
+    // two overlapping unit spheres along the x axis, the left one at -0.5 and the right one at 0.5
+    fn create_csg(operation: CsgOperation) -> Csg {
+        return Csg::new(operation, create_sphere(-0.5, 1.0), create_sphere(0.5, 1.0));
+    }
+
+    fn create_ray() -> ray::Ray {
+        return ray::Ray::new(
+            vec3::Vec3::new(10.0, 0.0, 0.0),
+            vec3::Vec3::new(-1.0, 0.0, 0.0),
+        );
+    }
+
+    fn assert_surfaces(csg: &Csg, ray: &ray::Ray, expected: &[(f32, f32)]) {
+        let surfaces = csg.intersect_ray_all(ray);
+        assert_eq!(surfaces.len(), expected.len());
+        for (surface, (ray_time, normal_x)) in surfaces.iter().zip(expected.iter()) {
+            assert!(math::equal_epsilon_f32(
+                surface.ray_time(),
+                *ray_time,
+                math::EPSILON_F32_4
+            ));
+            assert!(surface
+                .calc_world_normal()
+                .equal_epsilon(&vec3::Vec3::new(*normal_x, 0.0, 0.0), math::EPSILON_F32_4));
+        }
+    }
+
+    #[test]
+    fn test_union() {
+        let csg = create_csg(CsgOperation::Union);
+        assert_surfaces(&csg, &create_ray(), &[(8.5, 1.0), (11.5, -1.0)]);
+
+        // from the inside only the exit is left
+        let ray = ray::Ray::new(vec3::Vec3::from(0.0), vec3::Vec3::new(1.0, 0.0, 0.0));
+        assert_surfaces(&csg, &ray, &[(1.5, 1.0)]);
+
+        let aabb = csg.aabb();
+        assert!(aabb
+            .min()
+            .equal_epsilon(&vec3::Vec3::new(-1.5, -1.0, -1.0), math::EPSILON_F32_4));
+        assert!(aabb
+            .max()
+            .equal_epsilon(&vec3::Vec3::new(1.5, 1.0, 1.0), math::EPSILON_F32_4));
+    }
+
+    #[test]
+    fn test_intersection() {
+        let csg = create_csg(CsgOperation::Intersection);
+        assert_surfaces(&csg, &create_ray(), &[(9.5, 1.0), (10.5, -1.0)]);
+
+        let surface = csg.intersect_ray(&create_ray()).unwrap();
+        assert!(math::equal_epsilon_f32(
+            surface.ray_time(),
+            9.5,
+            math::EPSILON_F32_4
+        ));
+        assert!(csg.is_intersect(&create_ray(), 10.0));
+        assert!(!csg.is_intersect(&create_ray(), 9.0));
+
+        let disjoint = Csg::new(
+            CsgOperation::Intersection,
+            create_sphere(-3.0, 1.0),
+            create_sphere(3.0, 1.0),
+        );
+        assert!(disjoint.intersect_ray(&create_ray()).is_none());
+        assert!(disjoint.aabb().is_empty());
+    }
+
+    #[test]
+    fn test_difference() {
+        // the ray enters through the subtracted sphere, whose normal is flipped to face the ray
+        let csg = create_csg(CsgOperation::Difference);
+        assert_surfaces(&csg, &create_ray(), &[(10.5, 1.0), (11.5, -1.0)]);
+
+        // a ray that misses the subtracted sphere sees the left one as it is
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(-1.0, 0.0, 10.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        let surface = csg.intersect_ray(&ray).unwrap();
+        assert!(surface.calc_world_position().equal_epsilon(
+            &vec3::Vec3::new(-1.0, 0.0, f32::sqrt(0.75)),
+            math::EPSILON_F32_4
+        ));
+    }
+
+    #[test]
+    fn test_nested() {
+        // a hollow in the middle of the union
+        let union: rc::Rc<dyn shape::IntersectableShape> =
+            rc::Rc::new(create_csg(CsgOperation::Union));
+        let csg = Csg::new(CsgOperation::Difference, union, create_sphere(0.0, 0.25));
+        assert_surfaces(
+            &csg,
+            &create_ray(),
+            &[(8.5, 1.0), (9.75, -1.0), (10.25, 1.0), (11.5, -1.0)],
+        );
+    }
+
+    #[test]
+    fn test_material() {
+        let left_material: rc::Rc<dyn material::Material> =
+            rc::Rc::new(matte::Matte::new(vec3::Vec3::new(1.0, 0.0, 0.0), 0.0));
+        let right_material: rc::Rc<dyn material::Material> =
+            rc::Rc::new(matte::Matte::new(vec3::Vec3::new(0.0, 0.0, 1.0), 0.0));
+        let is_material = |surface: &shape::IntersectableShapeSurface,
+                           material: &rc::Rc<dyn material::Material>| {
+            return rc::Rc::as_ptr(surface.material().unwrap()) as *const u8
+                == rc::Rc::as_ptr(material) as *const u8;
+        };
+
+        // surfaces inherit the material of the operand they are on
+        let csg = create_csg(CsgOperation::Union)
+            .with_operand_materials(Some(left_material.clone()), Some(right_material.clone()));
+        let surfaces = csg.intersect_ray_all(&create_ray());
+        assert!(is_material(&surfaces[0], &right_material));
+        assert!(is_material(&surfaces[1], &left_material));
+
+        // the material of a nested node is kept by its parent
+        let nested = Csg::new(
+            CsgOperation::Difference,
+            rc::Rc::new(csg),
+            create_sphere(0.0, 0.25),
+        );
+        let surfaces = nested.intersect_ray_all(&create_ray());
+        assert!(is_material(&surfaces[0], &right_material));
+        assert!(surfaces[1].material().is_none());
+
+        // the material of the node replaces the ones of the operands
+        let nested = nested.with_material(left_material.clone());
+        let surfaces = nested.intersect_ray_all(&create_ray());
+        assert!(is_material(&surfaces[0], &left_material));
+        assert!(is_material(&surfaces[1], &left_material));
+    }
+}
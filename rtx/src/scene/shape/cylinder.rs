@@ -3,6 +3,7 @@ use crate::core::math;
 use crate::core::vec2;
 use crate::core::vec3;
 use crate::core::vec4;
+use crate::scene::aabb;
 use crate::scene::ray;
 use crate::scene::shape;
 use std::mem;
@@ -14,6 +15,7 @@ pub struct Cylinder {
     local_radius: f32,
     local_z_min: f32,
     local_z_max: f32,
+    phi_max: f32,
 }
 
 impl Cylinder {
@@ -34,8 +36,26 @@ impl Cylinder {
             local_radius,
             local_z_min,
             local_z_max,
+            phi_max: 2.0 * math::PI_F32,
         };
     }
+
+    // keeps the part of the cylinder swept from the x axis by phi_max around the z axis
+    pub fn with_phi_max(mut self, phi_max: f32) -> Cylinder {
+        self.phi_max = math::clamp(phi_max, 0.0, 2.0 * math::PI_F32);
+        return self;
+    }
+
+    pub fn area(&self) -> f32 {
+        return (self.local_z_max - self.local_z_min) * self.local_radius * self.phi_max;
+    }
+
+    fn is_in_range(&self, local_position: &vec3::Vec3) -> bool {
+        return local_position.z >= self.local_z_min
+            && local_position.z <= self.local_z_max
+            && (self.phi_max >= 2.0 * math::PI_F32
+                || shape::calc_phi(local_position) <= self.phi_max);
+    }
 }
 
 impl shape::IntersectableShape for Cylinder {
@@ -71,14 +91,14 @@ impl shape::IntersectableShape for Cylinder {
         }
 
         let mut local_position = local_ray.calc_position(ray_time);
-        if local_position.z < self.local_z_min || local_position.z > self.local_z_max {
+        if !self.is_in_range(&local_position) {
             ray_time = ray_time_1;
             if ray_time < 0.0 {
                 return false;
             }
 
             local_position = local_ray.calc_position(ray_time);
-            if local_position.z < self.local_z_min || local_position.z > self.local_z_max {
+            if !self.is_in_range(&local_position) {
                 return false;
             }
         }
@@ -118,21 +138,21 @@ impl shape::IntersectableShape for Cylinder {
         }
 
         let mut local_position = local_ray.calc_position(ray_time);
-        if local_position.z < self.local_z_min || local_position.z > self.local_z_max {
+        if !self.is_in_range(&local_position) {
             ray_time = ray_time_1;
             if ray_time < 0.0 {
                 return None;
             }
 
             local_position = local_ray.calc_position(ray_time);
-            if local_position.z < self.local_z_min || local_position.z > self.local_z_max {
+            if !self.is_in_range(&local_position) {
                 return None;
             }
         }
 
         let local_dpdu = vec3::Vec3::new(
-            -2.0 * math::PI_F32 * local_position.y,
-            2.0 * math::PI_F32 * local_position.x,
+            -self.phi_max * local_position.y,
+            self.phi_max * local_position.x,
             0.0,
         );
         let local_dpdv = vec3::Vec3::new(0.0, 0.0, self.local_z_max - self.local_z_min);
@@ -154,10 +174,18 @@ impl shape::IntersectableShape for Cylinder {
             local_normal,
             local_dpdu,
             local_dpdv,
-            &self.object_to_world,
-            &self.normal_transform,
+            self.object_to_world,
+            self.normal_transform,
         ));
     }
+
+    fn aabb(&self) -> aabb::AABB {
+        return aabb::AABB::new(
+            vec3::Vec3::new(-self.local_radius, -self.local_radius, self.local_z_min),
+            vec3::Vec3::new(self.local_radius, self.local_radius, self.local_z_max),
+        )
+        .transform(&self.object_to_world);
+    }
 }
 
 impl shape::SamplableShape for Cylinder {
@@ -170,7 +198,7 @@ impl shape::SamplableShape for Cylinder {
         let local_surface_point_ref =
             (self.world_to_object * vec4::Vec4::from_vec3(surface_point_ref, 1.0)).to_vec3();
 
-        let theta = 2.0 * math::PI_F32 * sample.x;
+        let theta = self.phi_max * sample.x;
         let z = (self.local_z_max - self.local_z_min) * sample.y + self.local_z_min;
         let local_sample_point = vec3::Vec3::new(
             self.local_radius * f32::cos(theta),
@@ -188,11 +216,41 @@ impl shape::SamplableShape for Cylinder {
             return None;
         }
 
-        let area = (self.local_z_max - self.local_z_min) * self.local_radius * 2.0 * math::PI_F32;
-
         let world_surface_point =
             (self.object_to_world * vec4::Vec4::from_vec3(&local_sample_point, 1.0)).to_vec3();
-        let pdf = direction.length_sq() / (area * cos_theta);
+        let pdf = direction.length_sq() / (self.area() * cos_theta);
         return Some(shape::SampleShapeSurface::new(pdf, world_surface_point));
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scene::shape::IntersectableShape;
+
+    #[test]
+    fn test_intersect_ray_phi_max() {
+        // the half with y >= 0, the near side is clipped away so the far side is hit from inside
+        let cylinder = Cylinder::new(mat4::Mat4::new(), 1.0, 0.0, 2.0).with_phi_max(math::PI_F32);
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.0, -10.0, 1.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+        );
+        let shape_surface = cylinder.intersect_ray(&ray).unwrap();
+        assert!(math::equal_epsilon_f32(
+            shape_surface.ray_time(),
+            11.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(shape_surface
+            .calc_world_normal()
+            .equal_epsilon(&vec3::Vec3::new(0.0, -1.0, 0.0), math::EPSILON_F32_5));
+        assert!(cylinder.is_intersect(&ray, 12.0));
+        assert!(!cylinder.is_intersect(&ray, 10.0));
+        assert!(math::equal_epsilon_f32(
+            cylinder.area(),
+            2.0 * math::PI_F32,
+            math::EPSILON_F32_5
+        ));
+    }
+}
@@ -3,12 +3,14 @@ use crate::core::math;
 use crate::core::vec2;
 use crate::core::vec3;
 use crate::core::vec4;
+use crate::scene::aabb;
 use crate::scene::ray;
 use crate::scene::shape;
 
 pub struct Disk {
     inner_radius: f32,
     outer_radius: f32,
+    phi_max: f32,
     object_to_world: mat4::Mat4,
     world_to_object: mat4::Mat4,
     normal_transform: mat4::Mat4,
@@ -25,6 +27,7 @@ impl Disk {
         return Disk {
             inner_radius,
             outer_radius,
+            phi_max: 2.0 * math::PI_F32,
             object_to_world,
             world_to_object,
             normal_transform,
@@ -32,11 +35,31 @@ impl Disk {
         };
     }
 
+    // keeps the slice of the disk swept from the x axis by phi_max around the z axis
+    pub fn with_phi_max(mut self, phi_max: f32) -> Disk {
+        self.phi_max = math::clamp(phi_max, 0.0, 2.0 * math::PI_F32);
+        return self;
+    }
+
+    pub fn area(&self) -> f32 {
+        return 0.5
+            * self.phi_max
+            * (self.outer_radius * self.outer_radius - self.inner_radius * self.inner_radius);
+    }
+
+    fn is_in_range(&self, local_position: &vec3::Vec3) -> bool {
+        let local_position_radius = local_position.length();
+        return local_position_radius >= self.inner_radius
+            && local_position_radius <= self.outer_radius
+            && (self.phi_max >= 2.0 * math::PI_F32
+                || shape::calc_phi(local_position) <= self.phi_max);
+    }
+
     fn uniform_sample_local_surface(&self, sample: &vec2::Vec2) -> vec3::Vec3 {
         let outer_radius_sq = self.outer_radius * self.outer_radius;
         let inner_radius_sq = self.inner_radius * self.inner_radius;
         let r = f32::sqrt(sample.x * (outer_radius_sq - inner_radius_sq) + inner_radius_sq);
-        let theta = 2.0 * math::PI_F32 * sample.y;
+        let theta = self.phi_max * sample.y;
         let local_sample_point = vec3::Vec3::new(r * f32::cos(theta), r * f32::sin(theta), 0.0);
 
         return local_sample_point;
@@ -87,10 +110,7 @@ impl shape::IntersectableShape for Disk {
         }
 
         let local_position = local_ray.calc_position(ray_time);
-        let local_position_radius = local_position.length();
-        return local_position_radius >= self.inner_radius
-            && local_position_radius <= self.outer_radius
-            && ray_time < max_distance;
+        return self.is_in_range(&local_position) && ray_time < max_distance;
     }
 
     fn intersect_ray(&self, ray: &ray::Ray) -> Option<shape::IntersectableShapeSurface> {
@@ -105,18 +125,12 @@ impl shape::IntersectableShape for Disk {
         }
 
         let local_position = local_ray.calc_position(ray_time);
-        let local_position_radius = local_position.length();
-
-        if local_position_radius < self.inner_radius {
-            return None;
-        }
-
-        if local_position_radius > self.outer_radius {
+        if !self.is_in_range(&local_position) {
             return None;
         }
 
-        let local_dpdu =
-            vec3::Vec3::new(-local_position.x, local_position.y, 0.0) * 2.0 * math::PI_F32;
+        let local_position_radius = local_position.length();
+        let local_dpdu = vec3::Vec3::new(-local_position.y, local_position.x, 0.0) * self.phi_max;
         let local_dpdv = vec3::Vec3::new(local_position.x, local_position.y, 0.0)
             * (self.inner_radius - self.outer_radius)
             / local_position_radius;
@@ -132,10 +146,18 @@ impl shape::IntersectableShape for Disk {
             normal,
             local_dpdu,
             local_dpdv,
-            &self.object_to_world,
-            &self.normal_transform,
+            self.object_to_world,
+            self.normal_transform,
         ));
     }
+
+    fn aabb(&self) -> aabb::AABB {
+        return aabb::AABB::new(
+            vec3::Vec3::new(-self.outer_radius, -self.outer_radius, 0.0),
+            vec3::Vec3::new(self.outer_radius, self.outer_radius, 0.0),
+        )
+        .transform(&self.object_to_world);
+    }
 }
 
 impl shape::SamplableShape for Disk {
@@ -168,12 +190,62 @@ impl shape::SamplableShape for Disk {
             return None;
         }
 
-        let area = math::PI_F32
-            * (self.outer_radius * self.outer_radius - self.inner_radius * self.inner_radius);
-
         let world_surface_point =
             (self.object_to_world * vec4::Vec4::from_vec3(&local_sample_point, 1.0)).to_vec3();
-        let pdf = direction.length_sq() / (area * cos_theta);
+        let pdf = direction.length_sq() / (self.area() * cos_theta);
         return Some(shape::SampleShapeSurface::new(pdf, world_surface_point));
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scene::shape::IntersectableShape;
+    use crate::scene::shape::SamplableShape;
+
+    #[test]
+    fn test_intersect_ray_phi_max() {
+        // a quarter of an annulus in the first quadrant
+        let disk = Disk::new(mat4::Mat4::new(), 0.5, 2.0).with_phi_max(0.5 * math::PI_F32);
+        let direction = vec3::Vec3::new(0.0, 0.0, -1.0);
+
+        let ray = ray::Ray::new(vec3::Vec3::new(1.0, 1.0, 5.0), direction);
+        let shape_surface = disk.intersect_ray(&ray).unwrap();
+        assert!(math::equal_epsilon_f32(
+            shape_surface.ray_time(),
+            5.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(disk.is_intersect(&ray, 6.0));
+
+        let ray = ray::Ray::new(vec3::Vec3::new(-1.0, 1.0, 5.0), direction);
+        assert!(disk.intersect_ray(&ray).is_none());
+        assert!(!disk.is_intersect(&ray, 6.0));
+
+        let ray = ray::Ray::new(vec3::Vec3::new(0.2, 0.2, 5.0), direction);
+        assert!(disk.intersect_ray(&ray).is_none());
+    }
+
+    #[test]
+    fn test_sample_surface_phi_max() {
+        let disk = Disk::new(mat4::Mat4::new(), 0.0, 1.0).with_phi_max(math::PI_F32);
+        let point_ref = vec3::Vec3::new(0.0, 0.0, 2.0);
+        for i in 0..16 {
+            let sample = vec2::Vec2::new((i as f32 + 0.5) / 16.0, (i as f32 * 0.37) % 1.0);
+            let sample_surface = disk
+                .sample_surface(&sample, &point_ref, &vec3::Vec3::new(0.0, 0.0, -1.0))
+                .unwrap();
+            assert!(sample_surface.surface_point.y >= 0.0);
+
+            // the pdf of a half disk is twice the one of a full disk
+            let direction = point_ref - sample_surface.surface_point;
+            let cos_theta = direction.normalize().unwrap().z;
+            let expected_pdf = direction.length_sq() / (0.5 * math::PI_F32 * cos_theta);
+            assert!(math::equal_epsilon_f32(
+                sample_surface.pdf,
+                expected_pdf,
+                math::EPSILON_F32_4
+            ));
+        }
+    }
+}
@@ -0,0 +1,618 @@
+use crate::core::mat4;
+use crate::core::math;
+use crate::core::polynomial;
+use crate::core::vec2;
+use crate::core::vec3;
+use crate::scene::aabb;
+use crate::scene::ray;
+use crate::scene::shape;
+
+// slack in cell coordinates and ray time, so hits on the edge shared by two cells are not lost
+const CELL_EPSILON: f32 = 1e-5;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum HeightfieldInterpolation {
+    // bilinear patch through the four corners of each cell
+    Bilinear,
+    // two triangles per cell, split along the diagonal from the first corner to the last one
+    Triangles,
+}
+
+// lowest and highest height of the cells in one level of the pyramid, a cell covers two by two
+// cells of the level below it
+struct MinMaxLevel {
+    resolution_x: usize,
+    resolution_y: usize,
+    bounds: Vec<(f32, f32)>,
+}
+
+impl MinMaxLevel {
+    fn bounds(&self, x: usize, y: usize) -> (f32, f32) {
+        return self.bounds[y * self.resolution_x + x];
+    }
+}
+
+// terrain over the unit square of the xy plane with the heights along z. The samples lie on a
+// regular grid stored row by row from y = 0. Rays walk the cells with a 2d dda that starts at the
+// coarsest level of a min-max pyramid and only descends into cells whose height range they pass
+// through. The uv of a hit is its position on the unit square
+pub struct Heightfield {
+    object_to_world: mat4::Mat4,
+    world_to_object: mat4::Mat4,
+    normal_transform: mat4::Mat4,
+    resolution_x: usize,
+    resolution_y: usize,
+    heights: Vec<f32>,
+    // smooth normals of the samples, interpolated over the cells
+    normals: Vec<vec3::Vec3>,
+    levels: Vec<MinMaxLevel>,
+    interpolation: HeightfieldInterpolation,
+}
+
+impl Heightfield {
+    pub fn new(
+        object_to_world: mat4::Mat4,
+        resolution_x: usize,
+        resolution_y: usize,
+        heights: Vec<f32>,
+    ) -> Heightfield {
+        assert!(resolution_x >= 2 && resolution_y >= 2);
+        assert_eq!(heights.len(), resolution_x * resolution_y);
+        let world_to_object = mat4::Mat4::inverse(&object_to_world).unwrap();
+        let normal_transform =
+            mat4::Mat4::inverse(&mat4::Mat4::transpose(&object_to_world)).unwrap();
+
+        let mut heightfield = Heightfield {
+            object_to_world,
+            world_to_object,
+            normal_transform,
+            resolution_x,
+            resolution_y,
+            heights,
+            normals: Vec::new(),
+            levels: Vec::new(),
+            interpolation: HeightfieldInterpolation::Bilinear,
+        };
+        heightfield.normals = heightfield.create_normals();
+        heightfield.levels = heightfield.create_levels();
+        return heightfield;
+    }
+
+    pub fn with_interpolation(mut self, interpolation: HeightfieldInterpolation) -> Heightfield {
+        self.interpolation = interpolation;
+        return self;
+    }
+
+    fn height(&self, x: usize, y: usize) -> f32 {
+        return self.heights[y * self.resolution_x + x];
+    }
+
+    fn normal(&self, x: usize, y: usize) -> vec3::Vec3 {
+        return self.normals[y * self.resolution_x + x];
+    }
+
+    // size of the cells of a level on the unit square
+    fn cell_size(&self, level: usize) -> (f32, f32) {
+        let scale = (1 << level) as f32;
+        return (
+            scale / (self.resolution_x - 1) as f32,
+            scale / (self.resolution_y - 1) as f32,
+        );
+    }
+
+    // gradients by central differences, one sided at the border
+    fn create_normals(&self) -> Vec<vec3::Vec3> {
+        let (cell_x, cell_y) = self.cell_size(0);
+        let mut normals = Vec::with_capacity(self.heights.len());
+        for y in 0..self.resolution_y {
+            for x in 0..self.resolution_x {
+                let x0 = x.saturating_sub(1);
+                let x1 = usize::min(x + 1, self.resolution_x - 1);
+                let y0 = y.saturating_sub(1);
+                let y1 = usize::min(y + 1, self.resolution_y - 1);
+                let dzdx = (self.height(x1, y) - self.height(x0, y)) / ((x1 - x0) as f32 * cell_x);
+                let dzdy = (self.height(x, y1) - self.height(x, y0)) / ((y1 - y0) as f32 * cell_y);
+                normals.push(vec3::Vec3::new(-dzdx, -dzdy, 1.0).normalize().unwrap());
+            }
+        }
+
+        return normals;
+    }
+
+    // halves the resolution until a single cell covers the whole grid
+    fn create_levels(&self) -> Vec<MinMaxLevel> {
+        let mut bounds = Vec::with_capacity((self.resolution_x - 1) * (self.resolution_y - 1));
+        for y in 0..self.resolution_y - 1 {
+            for x in 0..self.resolution_x - 1 {
+                let corners = [
+                    self.height(x, y),
+                    self.height(x + 1, y),
+                    self.height(x, y + 1),
+                    self.height(x + 1, y + 1),
+                ];
+                bounds.push((
+                    corners.iter().cloned().fold(f32::INFINITY, f32::min),
+                    corners.iter().cloned().fold(f32::NEG_INFINITY, f32::max),
+                ));
+            }
+        }
+
+        let mut levels = vec![MinMaxLevel {
+            resolution_x: self.resolution_x - 1,
+            resolution_y: self.resolution_y - 1,
+            bounds,
+        }];
+        loop {
+            let lower = levels.last().unwrap();
+            if lower.resolution_x == 1 && lower.resolution_y == 1 {
+                break;
+            }
+
+            let resolution_x = lower.resolution_x.div_ceil(2);
+            let resolution_y = lower.resolution_y.div_ceil(2);
+            let mut bounds = Vec::with_capacity(resolution_x * resolution_y);
+            for y in 0..resolution_y {
+                for x in 0..resolution_x {
+                    let mut cell_bounds = (f32::INFINITY, f32::NEG_INFINITY);
+                    for lower_y in 2 * y..usize::min(2 * y + 2, lower.resolution_y) {
+                        for lower_x in 2 * x..usize::min(2 * x + 2, lower.resolution_x) {
+                            let (min, max) = lower.bounds(lower_x, lower_y);
+                            cell_bounds =
+                                (f32::min(cell_bounds.0, min), f32::max(cell_bounds.1, max));
+                        }
+                    }
+                    bounds.push(cell_bounds);
+                }
+            }
+
+            levels.push(MinMaxLevel {
+                resolution_x,
+                resolution_y,
+                bounds,
+            });
+        }
+
+        return levels;
+    }
+
+    fn local_aabb(&self) -> aabb::AABB {
+        let (min, max) = self.levels.last().unwrap().bounds(0, 0);
+        return aabb::AABB::new(
+            vec3::Vec3::new(0.0, 0.0, min),
+            vec3::Vec3::new(1.0, 1.0, max),
+        );
+    }
+
+    // returns the ray time and the uv of the closest hit
+    fn intersect_local(
+        &self,
+        local_ray: &ray::Ray,
+        max_distance: f32,
+    ) -> Option<(f32, vec2::Vec2)> {
+        let (t_near, t_far) = self.local_aabb().intersect_ray(local_ray, max_distance)?;
+        let top = self.levels.len() - 1;
+        return self.traverse(local_ray, top, (0, 0), (0, 0), t_near, t_far);
+    }
+
+    // 2d dda over the cells of a level in the given ranges of cell indices, between the ray times
+    // where the ray enters and leaves them. Cells are visited front to back, so the first hit is
+    // the closest one
+    fn traverse(
+        &self,
+        ray: &ray::Ray,
+        level: usize,
+        range_x: (usize, usize),
+        range_y: (usize, usize),
+        t_start: f32,
+        t_end: f32,
+    ) -> Option<(f32, vec2::Vec2)> {
+        let (cell_x, cell_y) = self.cell_size(level);
+        let origin = ray.origin();
+        let direction = ray.direction();
+        let start = ray.calc_position(t_start);
+        let mut x = math::clamp(
+            f32::floor(start.x / cell_x),
+            range_x.0 as f32,
+            range_x.1 as f32,
+        ) as usize;
+        let mut y = math::clamp(
+            f32::floor(start.y / cell_y),
+            range_y.0 as f32,
+            range_y.1 as f32,
+        ) as usize;
+
+        // ray time of the next cell boundary along each axis and the time between two boundaries
+        let next_boundary = |cell: usize, cell_size: f32, origin: f32, direction: f32| {
+            if direction > 0.0 {
+                return (
+                    ((cell + 1) as f32 * cell_size - origin) / direction,
+                    cell_size / direction,
+                );
+            } else if direction < 0.0 {
+                return (
+                    (cell as f32 * cell_size - origin) / direction,
+                    -cell_size / direction,
+                );
+            }
+
+            return (f32::INFINITY, f32::INFINITY);
+        };
+        let (mut t_next_x, t_delta_x) = next_boundary(x, cell_x, origin.x, direction.x);
+        let (mut t_next_y, t_delta_y) = next_boundary(y, cell_y, origin.y, direction.y);
+
+        let mut t_enter = t_start;
+        loop {
+            let t_exit = f32::min(f32::min(t_next_x, t_next_y), t_end);
+            if let Some(hit) = self.visit_cell(ray, level, x, y, t_enter, t_exit) {
+                return Some(hit);
+            }
+
+            if t_exit >= t_end {
+                return None;
+            }
+
+            if t_next_x < t_next_y {
+                x = step_cell(x, direction.x > 0.0, range_x)?;
+                t_next_x += t_delta_x;
+            } else {
+                y = step_cell(y, direction.y > 0.0, range_y)?;
+                t_next_y += t_delta_y;
+            }
+            t_enter = t_exit;
+        }
+    }
+
+    // skips cells whose height range the ray passes above or below, the ray height is linear so
+    // its range over the cell is given by the ends
+    fn visit_cell(
+        &self,
+        ray: &ray::Ray,
+        level: usize,
+        x: usize,
+        y: usize,
+        t_enter: f32,
+        t_exit: f32,
+    ) -> Option<(f32, vec2::Vec2)> {
+        let z_enter = ray.origin().z + ray.direction().z * t_enter;
+        let z_exit = ray.origin().z + ray.direction().z * t_exit;
+        let (min, max) = self.levels[level].bounds(x, y);
+        if f32::max(z_enter, z_exit) < min - CELL_EPSILON
+            || f32::min(z_enter, z_exit) > max + CELL_EPSILON
+        {
+            return None;
+        }
+
+        if level == 0 {
+            return self.intersect_cell(ray, x, y, t_enter, t_exit);
+        }
+
+        let lower = &self.levels[level - 1];
+        return self.traverse(
+            ray,
+            level - 1,
+            (2 * x, usize::min(2 * x + 1, lower.resolution_x - 1)),
+            (2 * y, usize::min(2 * y + 1, lower.resolution_y - 1)),
+            t_enter,
+            t_exit,
+        );
+    }
+
+    // heights over the cell as h00 + a fx + b fy + c fx fy of the cell coordinates fx and fy, with
+    // the part of the cell it holds for as the sign of fx - fy it needs, or zero for all of it
+    fn cell_surfaces(&self, x: usize, y: usize) -> Vec<(f32, f32, f32, f32)> {
+        let h00 = self.height(x, y);
+        let h10 = self.height(x + 1, y);
+        let h01 = self.height(x, y + 1);
+        let h11 = self.height(x + 1, y + 1);
+        return match self.interpolation {
+            HeightfieldInterpolation::Bilinear => {
+                vec![(h10 - h00, h01 - h00, h00 - h10 - h01 + h11, 0.0)]
+            }
+            HeightfieldInterpolation::Triangles => vec![
+                (h10 - h00, h11 - h10, 0.0, 1.0),
+                (h11 - h01, h01 - h00, 0.0, -1.0),
+            ],
+        };
+    }
+
+    // the ray is written in cell coordinates from where it enters the cell, which keeps the
+    // coefficients small for rays that start far away
+    fn intersect_cell(
+        &self,
+        ray: &ray::Ray,
+        x: usize,
+        y: usize,
+        t_enter: f32,
+        t_exit: f32,
+    ) -> Option<(f32, vec2::Vec2)> {
+        let (cell_x, cell_y) = self.cell_size(0);
+        let entry = ray.calc_position(t_enter);
+        let direction = ray.direction();
+        let fx0 = (entry.x / cell_x - x as f32) as f64;
+        let fy0 = (entry.y / cell_y - y as f32) as f64;
+        let fxd = (direction.x / cell_x) as f64;
+        let fyd = (direction.y / cell_y) as f64;
+        let h00 = self.height(x, y) as f64;
+
+        let mut closest: Option<(f32, vec2::Vec2)> = None;
+        for (a, b, c, side) in self.cell_surfaces(x, y) {
+            let (a, b, c) = (a as f64, b as f64, c as f64);
+            let roots = polynomial::solve_quadratic(
+                c * fxd * fyd,
+                a * fxd + b * fyd + c * (fx0 * fyd + fy0 * fxd) - direction.z as f64,
+                h00 + a * fx0 + b * fy0 + c * fx0 * fy0 - entry.z as f64,
+            );
+            for s in roots {
+                let ray_time = t_enter + s as f32;
+                let fx = (fx0 + fxd * s) as f32;
+                let fy = (fy0 + fyd * s) as f32;
+                let in_cell = (-CELL_EPSILON..=1.0 + CELL_EPSILON).contains(&fx)
+                    && (-CELL_EPSILON..=1.0 + CELL_EPSILON).contains(&fy)
+                    && side * (fx - fy) >= -CELL_EPSILON;
+                if !in_cell
+                    || ray_time <= 0.0
+                    || ray_time < t_enter - CELL_EPSILON
+                    || ray_time > t_exit + CELL_EPSILON
+                    || matches!(closest, Some((closest_time, _)) if closest_time <= ray_time)
+                {
+                    continue;
+                }
+
+                let uv = vec2::Vec2::new(
+                    math::clamp((x as f32 + fx) * cell_x, 0.0, 1.0),
+                    math::clamp((y as f32 + fy) * cell_y, 0.0, 1.0),
+                );
+                closest = Some((ray_time, uv));
+            }
+        }
+
+        return closest;
+    }
+
+    fn create_surface(
+        &self,
+        local_ray: &ray::Ray,
+        ray_time: f32,
+        uv: &vec2::Vec2,
+    ) -> shape::IntersectableShapeSurface {
+        let grid_x = uv.x * (self.resolution_x - 1) as f32;
+        let grid_y = uv.y * (self.resolution_y - 1) as f32;
+        let x = usize::min(grid_x as usize, self.resolution_x - 2);
+        let y = usize::min(grid_y as usize, self.resolution_y - 2);
+        let fx = grid_x - x as f32;
+        let fy = grid_y - y as f32;
+
+        // slopes over the cell coordinates and the sample normals weighted like the heights
+        let surfaces = self.cell_surfaces(x, y);
+        let (n00, n10, n01, n11) = (
+            self.normal(x, y),
+            self.normal(x + 1, y),
+            self.normal(x, y + 1),
+            self.normal(x + 1, y + 1),
+        );
+        let (dzdfx, dzdfy, normal) = match self.interpolation {
+            HeightfieldInterpolation::Bilinear => {
+                let (a, b, c, _) = surfaces[0];
+                (
+                    a + c * fy,
+                    b + c * fx,
+                    math::lerp(fy, math::lerp(fx, n00, n10), math::lerp(fx, n01, n11)),
+                )
+            }
+            HeightfieldInterpolation::Triangles if fy <= fx => {
+                let (a, b, _, _) = surfaces[0];
+                (a, b, (1.0 - fx) * n00 + (fx - fy) * n10 + fy * n11)
+            }
+            HeightfieldInterpolation::Triangles => {
+                let (a, b, _, _) = surfaces[1];
+                (a, b, (1.0 - fy) * n00 + fx * n11 + (fy - fx) * n01)
+            }
+        };
+
+        let dpdu = vec3::Vec3::new(1.0, 0.0, dzdfx * (self.resolution_x - 1) as f32);
+        let dpdv = vec3::Vec3::new(0.0, 1.0, dzdfy * (self.resolution_y - 1) as f32);
+        let normal = normal
+            .normalize()
+            .unwrap_or_else(|| dpdu.cross(&dpdv).normalize().unwrap());
+
+        // materials build their shading frame from the normal and dpdu, so dpdu is kept orthogonal
+        // to the interpolated normal like on triangle meshes
+        let shading_dpdu = match (dpdu - normal * normal.dot(&dpdu)).normalize() {
+            Some(shading_dpdu) => shading_dpdu,
+            None => {
+                let mut shading_dpdu = vec3::Vec3::from(0.0);
+                let mut shading_dpdv = vec3::Vec3::from(0.0);
+                vec3::Vec3::coordinate_system(&normal, &mut shading_dpdu, &mut shading_dpdv);
+                shading_dpdu
+            }
+        };
+        let shading_dpdv = normal.cross(&shading_dpdu);
+
+        return shape::IntersectableShapeSurface::new(
+            ray_time,
+            local_ray.calc_position(ray_time),
+            normal,
+            shading_dpdu,
+            shading_dpdv,
+            self.object_to_world,
+            self.normal_transform,
+        )
+        .with_uv(*uv);
+    }
+}
+
+impl shape::IntersectableShape for Heightfield {
+    fn is_intersect(&self, ray: &ray::Ray, max_distance: f32) -> bool {
+        let local_ray = ray::Ray::transform(ray, &self.world_to_object);
+        return match self.intersect_local(&local_ray, max_distance) {
+            Some((ray_time, _)) => ray_time < max_distance,
+            None => false,
+        };
+    }
+
+    fn intersect_ray(&self, ray: &ray::Ray) -> Option<shape::IntersectableShapeSurface> {
+        let local_ray = ray::Ray::transform(ray, &self.world_to_object);
+        let (ray_time, uv) = self.intersect_local(&local_ray, f32::MAX)?;
+        return Some(self.create_surface(&local_ray, ray_time, &uv));
+    }
+
+    fn aabb(&self) -> aabb::AABB {
+        return self.local_aabb().transform(&self.object_to_world);
+    }
+}
+
+// next cell along an axis of the dda, None once the ray leaves the range
+fn step_cell(cell: usize, forward: bool, range: (usize, usize)) -> Option<usize> {
+    if forward {
+        return if cell < range.1 { Some(cell + 1) } else { None };
+    }
+
+    return if cell > range.0 { Some(cell - 1) } else { None };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scene::shape::IntersectableShape;
+
+    fn create_ray_down(x: f32, y: f32) -> ray::Ray {
+        return ray::Ray::new(vec3::Vec3::new(x, y, 5.0), vec3::Vec3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn test_intersect_ray() {
+        // a ramp that rises by one along y, moved to z = 1
+        let ramp = Heightfield::new(
+            mat4::Mat4::new().translate(&vec3::Vec3::new(0.0, 0.0, 1.0)),
+            3,
+            2,
+            vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+        );
+        let surface = ramp.intersect_ray(&create_ray_down(0.25, 0.5)).unwrap();
+        assert!(math::equal_epsilon_f32(
+            surface.ray_time(),
+            3.5,
+            math::EPSILON_F32_5
+        ));
+        assert!(surface
+            .uv()
+            .unwrap()
+            .equal_epsilon(&vec2::Vec2::new(0.25, 0.5), math::EPSILON_F32_5));
+        let expected_normal = vec3::Vec3::new(0.0, -1.0, 1.0).normalize().unwrap();
+        assert!(surface
+            .calc_world_normal()
+            .equal_epsilon(&expected_normal, math::EPSILON_F32_5));
+        assert!(math::equal_epsilon_f32(
+            surface.calc_world_normal().dot(&surface.calc_world_dpdu()),
+            0.0,
+            math::EPSILON_F32_5
+        ));
+
+        assert!(ramp.is_intersect(&create_ray_down(0.25, 0.5), 4.0));
+        assert!(!ramp.is_intersect(&create_ray_down(0.25, 0.5), 3.0));
+        assert!(ramp.intersect_ray(&create_ray_down(1.5, 0.5)).is_none());
+
+        // a ray along the slope below the ramp never reaches it
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.5, -1.0, 0.5),
+            vec3::Vec3::new(0.0, 1.0, 1.0),
+        );
+        assert!(ramp.intersect_ray(&ray).is_none());
+    }
+
+    #[test]
+    fn test_interpolation() {
+        // only the last corner is raised, the bilinear patch and the triangles differ inside
+        let heights = vec![0.0, 0.0, 0.0, 1.0];
+        let bilinear = Heightfield::new(mat4::Mat4::new(), 2, 2, heights.clone());
+        let triangles = Heightfield::new(mat4::Mat4::new(), 2, 2, heights)
+            .with_interpolation(HeightfieldInterpolation::Triangles);
+        let cases = [
+            (0.75, 0.25, 0.1875, 0.25),
+            (0.25, 0.75, 0.1875, 0.25),
+            (0.5, 0.5, 0.25, 0.5),
+        ];
+        for (x, y, bilinear_height, triangles_height) in cases.iter() {
+            let surface = bilinear.intersect_ray(&create_ray_down(*x, *y)).unwrap();
+            assert!(math::equal_epsilon_f32(
+                surface.calc_world_position().z,
+                *bilinear_height,
+                math::EPSILON_F32_5
+            ));
+            let surface = triangles.intersect_ray(&create_ray_down(*x, *y)).unwrap();
+            assert!(math::equal_epsilon_f32(
+                surface.calc_world_position().z,
+                *triangles_height,
+                math::EPSILON_F32_5
+            ));
+        }
+    }
+
+    #[test]
+    fn test_traversal_matches_all_cells() {
+        // odd resolutions leave partial cells at the border of every level
+        let (resolution_x, resolution_y) = (37, 22);
+        let mut heights = Vec::new();
+        for y in 0..resolution_y {
+            for x in 0..resolution_x {
+                heights.push(0.2 * f32::sin(0.7 * x as f32) * f32::cos(0.4 * y as f32));
+            }
+        }
+
+        for interpolation in [
+            HeightfieldInterpolation::Bilinear,
+            HeightfieldInterpolation::Triangles,
+        ]
+        .iter()
+        {
+            let terrain = Heightfield::new(
+                mat4::Mat4::new(),
+                resolution_x,
+                resolution_y,
+                heights.clone(),
+            )
+            .with_interpolation(*interpolation);
+            assert_eq!(terrain.levels.len(), 7);
+            let (min, max) = terrain.levels.last().unwrap().bounds(0, 0);
+            assert_eq!(min, heights.iter().cloned().fold(f32::INFINITY, f32::min));
+            assert_eq!(
+                max,
+                heights.iter().cloned().fold(f32::NEG_INFINITY, f32::max)
+            );
+
+            for i in 0..64 {
+                let angle = i as f32 * 0.37;
+                let ray = ray::Ray::new(
+                    vec3::Vec3::new(0.5 + f32::cos(angle), 0.5 + f32::sin(angle), 0.3),
+                    vec3::Vec3::new(
+                        -f32::cos(angle + 0.2),
+                        -f32::sin(angle + 0.2),
+                        -0.15 - 0.01 * i as f32,
+                    ),
+                );
+
+                let mut expected: Option<f32> = None;
+                for y in 0..resolution_y - 1 {
+                    for x in 0..resolution_x - 1 {
+                        if let Some((ray_time, _)) =
+                            terrain.intersect_cell(&ray, x, y, 0.0, f32::INFINITY)
+                        {
+                            expected = Some(expected.map_or(ray_time, |t| f32::min(t, ray_time)));
+                        }
+                    }
+                }
+
+                let hit = terrain
+                    .intersect_local(&ray, f32::MAX)
+                    .map(|(ray_time, _)| ray_time);
+                match (hit, expected) {
+                    (Some(hit), Some(expected)) => {
+                        assert!(math::equal_epsilon_f32(hit, expected, math::EPSILON_F32_4))
+                    }
+                    (None, None) => {}
+                    _ => panic!("ray {} hit {:?} but expected {:?}", i, hit, expected),
+                }
+            }
+        }
+    }
+}
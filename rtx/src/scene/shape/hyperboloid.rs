@@ -0,0 +1,328 @@
+use crate::core::mat4;
+use crate::core::math;
+use crate::core::vec2;
+use crate::core::vec3;
+use crate::core::vec4;
+use crate::scene::aabb;
+use crate::scene::ray;
+use crate::scene::shape;
+use std::mem;
+
+// hyperboloid of one sheet around the z axis with its waist at z = 0,
+// x^2 + y^2 = radius^2 + (slope z)^2. The slope is the one of the asymptotic cone, a slope of 0 is a
+// cylinder
+pub struct Hyperboloid {
+    object_to_world: mat4::Mat4,
+    world_to_object: mat4::Mat4,
+    normal_transform: mat4::Mat4,
+    local_radius: f32,
+    slope: f32,
+    local_z_min: f32,
+    local_z_max: f32,
+}
+
+impl Hyperboloid {
+    pub fn new(
+        object_to_world: mat4::Mat4,
+        local_radius: f32,
+        slope: f32,
+        local_z_min: f32,
+        local_z_max: f32,
+    ) -> Hyperboloid {
+        assert!(local_z_min < local_z_max);
+        let world_to_object = mat4::Mat4::inverse(&object_to_world).unwrap();
+        let normal_transform =
+            mat4::Mat4::inverse(&mat4::Mat4::transpose(&object_to_world)).unwrap();
+
+        return Hyperboloid {
+            object_to_world,
+            world_to_object,
+            normal_transform,
+            local_radius,
+            slope,
+            local_z_min,
+            local_z_max,
+        };
+    }
+
+    fn radius_at(&self, z: f32) -> f32 {
+        let slope_z = self.slope * z;
+        return f32::sqrt(self.local_radius * self.local_radius + slope_z * slope_z);
+    }
+
+    // the area element is 2 pi sqrt(radius^2 + slope^2 (1 + slope^2) z^2) dz
+    fn area_density(&self, z: f32) -> f32 {
+        let slope_sq = self.slope * self.slope;
+        return 2.0
+            * math::PI_F32
+            * f32::sqrt(
+                self.local_radius * self.local_radius + slope_sq * (1.0 + slope_sq) * z * z,
+            );
+    }
+
+    pub fn area(&self) -> f32 {
+        let radius_sq = self.local_radius * self.local_radius;
+        let slope_sq = self.slope * self.slope;
+        let rate = f32::sqrt(slope_sq * (1.0 + slope_sq));
+        let antiderivative = |z: f32| {
+            if rate == 0.0 {
+                return self.local_radius * z;
+            }
+
+            let root = f32::sqrt(radius_sq + rate * rate * z * z);
+            return 0.5 * z * root
+                + 0.5 * radius_sq / rate * f32::asinh(rate * z / self.local_radius);
+        };
+
+        return 2.0
+            * math::PI_F32
+            * (antiderivative(self.local_z_max) - antiderivative(self.local_z_min));
+    }
+
+    // nearest hit in the z range, returns the local ray time and position
+    fn intersect_local(&self, local_ray: &ray::Ray) -> Option<(f32, vec3::Vec3)> {
+        let o = local_ray.origin();
+        let d = local_ray.direction();
+        let slope_sq = self.slope * self.slope;
+        let a = d.x * d.x + d.y * d.y - slope_sq * d.z * d.z;
+        let b = 2.0 * (d.x * o.x + d.y * o.y - slope_sq * d.z * o.z);
+        let c =
+            o.x * o.x + o.y * o.y - slope_sq * o.z * o.z - self.local_radius * self.local_radius;
+
+        let (mut ray_time_0, mut ray_time_1) = if a == 0.0 {
+            // parallel to the asymptotic cone, there is a single hit
+            if b == 0.0 {
+                return None;
+            }
+
+            let ray_time = -c / b;
+            (ray_time, ray_time)
+        } else {
+            let discriminant = b * b - 4.0 * a * c;
+            if discriminant < 0.0 {
+                return None;
+            }
+
+            (
+                (-b - discriminant.sqrt()) / (2.0 * a),
+                (-b + discriminant.sqrt()) / (2.0 * a),
+            )
+        };
+        if ray_time_0 > ray_time_1 {
+            mem::swap(&mut ray_time_0, &mut ray_time_1);
+        }
+
+        for ray_time in [ray_time_0, ray_time_1].iter() {
+            if *ray_time < 0.0 {
+                continue;
+            }
+
+            let local_position = local_ray.calc_position(*ray_time);
+            if local_position.z >= self.local_z_min && local_position.z <= self.local_z_max {
+                return Some((*ray_time, local_position));
+            }
+        }
+
+        return None;
+    }
+
+    fn partial_derivatives(&self, local_position: &vec3::Vec3) -> (vec3::Vec3, vec3::Vec3) {
+        let local_dpdu = vec3::Vec3::new(
+            -2.0 * math::PI_F32 * local_position.y,
+            2.0 * math::PI_F32 * local_position.x,
+            0.0,
+        );
+
+        // d(x, y) / dz is (x, y) dr/dz / r, with r dr/dz = slope^2 z
+        let radius = self.radius_at(local_position.z);
+        let scale = self.slope * self.slope * local_position.z / (radius * radius);
+        let local_dpdv = (self.local_z_max - self.local_z_min)
+            * vec3::Vec3::new(local_position.x * scale, local_position.y * scale, 1.0);
+        return (local_dpdu, local_dpdv);
+    }
+
+    fn outward_normal(&self, local_position: &vec3::Vec3) -> vec3::Vec3 {
+        return vec3::Vec3::new(
+            local_position.x,
+            local_position.y,
+            -self.slope * self.slope * local_position.z,
+        )
+        .normalize()
+        .unwrap();
+    }
+}
+
+impl shape::IntersectableShape for Hyperboloid {
+    fn is_intersect(&self, ray: &ray::Ray, max_distance: f32) -> bool {
+        let local_ray = ray::Ray::transform(ray, &self.world_to_object);
+        return match self.intersect_local(&local_ray) {
+            Some((ray_time, _)) => ray_time < max_distance,
+            None => false,
+        };
+    }
+
+    fn intersect_ray(&self, ray: &ray::Ray) -> Option<shape::IntersectableShapeSurface> {
+        let local_ray = ray::Ray::transform(ray, &self.world_to_object);
+        let (ray_time, local_position) = self.intersect_local(&local_ray)?;
+
+        let (local_dpdu, local_dpdv) = self.partial_derivatives(&local_position);
+        let mut local_normal = self.outward_normal(&local_position);
+        if local_ray.direction().dot(&local_normal) > 0.0 {
+            local_normal = -local_normal;
+        }
+
+        return Some(shape::IntersectableShapeSurface::new(
+            ray_time,
+            local_position,
+            local_normal,
+            local_dpdu,
+            local_dpdv,
+            self.object_to_world,
+            self.normal_transform,
+        ));
+    }
+
+    fn aabb(&self) -> aabb::AABB {
+        let max_radius = f32::max(
+            self.radius_at(self.local_z_min),
+            self.radius_at(self.local_z_max),
+        );
+        return aabb::AABB::new(
+            vec3::Vec3::new(-max_radius, -max_radius, self.local_z_min),
+            vec3::Vec3::new(max_radius, max_radius, self.local_z_max),
+        )
+        .transform(&self.object_to_world);
+    }
+}
+
+impl shape::SamplableShape for Hyperboloid {
+    // samples z and the angle uniformly, the pdf accounts for the area that grows away from the waist
+    fn sample_surface(
+        &self,
+        sample: &vec2::Vec2,
+        surface_point_ref: &vec3::Vec3,
+        _surface_normal_ref: &vec3::Vec3,
+    ) -> Option<shape::SampleShapeSurface> {
+        let local_surface_point_ref =
+            (self.world_to_object * vec4::Vec4::from_vec3(surface_point_ref, 1.0)).to_vec3();
+
+        let z = math::lerp(sample.x, self.local_z_min, self.local_z_max);
+        let radius = self.radius_at(z);
+        let phi = 2.0 * math::PI_F32 * sample.y;
+        let local_sample_point = vec3::Vec3::new(radius * f32::cos(phi), radius * f32::sin(phi), z);
+        let local_normal = self.outward_normal(&local_sample_point);
+
+        let direction = local_surface_point_ref - local_sample_point;
+        let normalize_direction = direction.normalize()?;
+        let cos_theta = f32::abs(local_normal.dot(&normalize_direction));
+        if cos_theta == 0.0 {
+            return None;
+        }
+
+        let area_pdf = 1.0 / ((self.local_z_max - self.local_z_min) * self.area_density(z));
+        let world_surface_point =
+            (self.object_to_world * vec4::Vec4::from_vec3(&local_sample_point, 1.0)).to_vec3();
+        let pdf = area_pdf * direction.length_sq() / cos_theta;
+        return Some(shape::SampleShapeSurface::new(pdf, world_surface_point));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scene::shape::IntersectableShape;
+    use crate::scene::shape::SamplableShape;
+
+    #[test]
+    fn test_intersect_ray() {
+        let hyperboloid = Hyperboloid::new(mat4::Mat4::new(), 1.0, 1.0, -2.0, 2.0);
+
+        // through the waist
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(5.0, 0.0, 0.0),
+            vec3::Vec3::new(-1.0, 0.0, 0.0),
+        );
+        let surface = hyperboloid.intersect_ray(&ray).unwrap();
+        assert!(math::equal_epsilon_f32(
+            surface.ray_time(),
+            4.0,
+            math::EPSILON_F32_4
+        ));
+        assert!(surface
+            .calc_world_normal()
+            .equal_epsilon(&vec3::Vec3::new(1.0, 0.0, 0.0), math::EPSILON_F32_4));
+
+        // at z = 1 the radius is sqrt(2)
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(5.0, 0.0, 1.0),
+            vec3::Vec3::new(-1.0, 0.0, 0.0),
+        );
+        let surface = hyperboloid.intersect_ray(&ray).unwrap();
+        assert!(surface.calc_world_position().equal_epsilon(
+            &vec3::Vec3::new(f32::sqrt(2.0), 0.0, 1.0),
+            math::EPSILON_F32_4
+        ));
+        let normal = surface.calc_world_normal();
+        assert!(math::equal_epsilon_f32(
+            normal.dot(&surface.calc_world_dpdu()),
+            0.0,
+            math::EPSILON_F32_4
+        ));
+        assert!(math::equal_epsilon_f32(
+            normal.dot(&surface.calc_world_dpdv()),
+            0.0,
+            math::EPSILON_F32_4
+        ));
+
+        // along the axis through the open ends
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.0, 0.0, 10.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        assert!(hyperboloid.intersect_ray(&ray).is_none());
+        assert!(!hyperboloid.is_intersect(&ray, 100.0));
+    }
+
+    #[test]
+    fn test_area() {
+        // without a slope it is a cylinder
+        let cylinder = Hyperboloid::new(mat4::Mat4::new(), 2.0, 0.0, 0.0, 3.0);
+        assert!(math::equal_epsilon_f32(
+            cylinder.area(),
+            12.0 * math::PI_F32,
+            math::EPSILON_F32_4
+        ));
+
+        // midpoint rule over the area density
+        let hyperboloid = Hyperboloid::new(mat4::Mat4::new(), 1.0, 0.5, -1.0, 2.0);
+        let num_steps = 10000;
+        let dz = 3.0 / num_steps as f32;
+        let area: f32 = (0..num_steps)
+            .map(|i| hyperboloid.area_density(-1.0 + (i as f32 + 0.5) * dz) * dz)
+            .sum();
+        assert!(math::equal_epsilon_f32(
+            hyperboloid.area(),
+            area,
+            math::EPSILON_F32_2
+        ));
+    }
+
+    #[test]
+    fn test_sample_surface() {
+        let hyperboloid = Hyperboloid::new(mat4::Mat4::new(), 1.0, 1.0, -2.0, 2.0);
+        let point_ref = vec3::Vec3::new(10.0, 0.0, 0.0);
+        for i in 0..16 {
+            let sample = vec2::Vec2::new((i as f32 + 0.5) / 16.0, (i as f32 * 0.37) % 1.0);
+            let sample_surface = hyperboloid
+                .sample_surface(&sample, &point_ref, &vec3::Vec3::new(-1.0, 0.0, 0.0))
+                .unwrap();
+            let point = sample_surface.surface_point;
+            assert!(math::equal_epsilon_f32(
+                point.x * point.x + point.y * point.y - point.z * point.z,
+                1.0,
+                math::EPSILON_F32_4
+            ));
+            assert!(sample_surface.pdf > 0.0);
+        }
+    }
+}
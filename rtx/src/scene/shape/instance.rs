@@ -0,0 +1,210 @@
+use crate::core::mat4;
+use crate::core::vec3;
+use crate::core::vec4;
+use crate::scene::aabb;
+use crate::scene::ray;
+use crate::scene::shape;
+use std::rc;
+
+// a shared prototype placed in the world with its own transform. The prototype is intersected in its
+// own space, so one mesh or shape group can be reused by many instances without copying it
+pub struct Instance {
+    prototype: rc::Rc<dyn shape::IntersectableShape>,
+    object_to_world: mat4::Mat4,
+    world_to_object: mat4::Mat4,
+    normal_transform: mat4::Mat4,
+}
+
+impl Instance {
+    pub fn new(
+        prototype: rc::Rc<dyn shape::IntersectableShape>,
+        object_to_world: mat4::Mat4,
+    ) -> Instance {
+        let world_to_object = mat4::Mat4::inverse(&object_to_world).unwrap();
+        let normal_transform =
+            mat4::Mat4::inverse(&mat4::Mat4::transpose(&object_to_world)).unwrap();
+        return Instance {
+            prototype,
+            object_to_world,
+            world_to_object,
+            normal_transform,
+        };
+    }
+
+    pub fn prototype(&self) -> &rc::Rc<dyn shape::IntersectableShape> {
+        return &self.prototype;
+    }
+
+    pub fn object_to_world(&self) -> &mat4::Mat4 {
+        return &self.object_to_world;
+    }
+
+    // returns the ray in the prototype's space
+    fn local_ray(&self, ray: &ray::Ray) -> Option<ray::Ray> {
+        let local_direction = vec4::Vec4::to_vec3(
+            &(self.world_to_object * vec4::Vec4::from_vec3(ray.direction(), 0.0)),
+        );
+        if vec3::Vec3::length(&local_direction) == 0.0 {
+            return None;
+        }
+
+        return Some(ray::Ray::transform(ray, &self.world_to_object));
+    }
+
+    // the prototype reports ray times in its own space, which can be scaled again by its own
+    // transforms, so the ray time of the instance is measured from the hit in world space
+    fn to_world_surface(
+        &self,
+        ray: &ray::Ray,
+        surface: &shape::IntersectableShapeSurface,
+    ) -> shape::IntersectableShapeSurface {
+        let world_surface = surface.transform(0.0, &self.object_to_world, &self.normal_transform);
+        let distance = world_surface.calc_world_position().distance(ray.origin());
+        return world_surface.with_ray_time(distance);
+    }
+}
+
+impl shape::IntersectableShape for Instance {
+    // the closest hit is compared, since max_distance can not be mapped into the prototype's space
+    fn is_intersect(&self, ray: &ray::Ray, max_distance: f32) -> bool {
+        return match self.intersect_ray(ray) {
+            Some(surface) => surface.ray_time() < max_distance,
+            None => false,
+        };
+    }
+
+    fn intersect_ray(&self, ray: &ray::Ray) -> Option<shape::IntersectableShapeSurface> {
+        let local_ray = self.local_ray(ray)?;
+        let surface = self.prototype.intersect_ray(&local_ray)?;
+        return Some(self.to_world_surface(ray, &surface));
+    }
+
+    fn intersect_ray_all(&self, ray: &ray::Ray) -> Vec<shape::IntersectableShapeSurface> {
+        let local_ray = match self.local_ray(ray) {
+            Some(local_ray) => local_ray,
+            None => return Vec::new(),
+        };
+
+        return self
+            .prototype
+            .intersect_ray_all(&local_ray)
+            .iter()
+            .map(|surface| self.to_world_surface(ray, surface))
+            .collect();
+    }
+
+    fn aabb(&self) -> aabb::AABB {
+        return self.prototype.aabb().transform(&self.object_to_world);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+    use crate::scene::shape::sphere;
+    use crate::scene::shape::IntersectableShape;
+
+    fn create_instance(translation: vec3::Vec3, scale: f32) -> Instance {
+        let prototype = rc::Rc::new(sphere::Sphere::new(mat4::Mat4::new(), 1.0));
+        return Instance::new(
+            prototype,
+            mat4::Mat4::new()
+                .translate(&translation)
+                .scale(&vec3::Vec3::from(scale)),
+        );
+    }
+
+    #[test]
+    fn test_intersect_ray() {
+        let instance = create_instance(vec3::Vec3::new(3.0, 0.0, 0.0), 2.0);
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(3.0, 0.0, 10.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+
+        let surface = instance.intersect_ray(&ray).unwrap();
+        assert!(math::equal_epsilon_f32(
+            surface.ray_time(),
+            8.0,
+            math::EPSILON_F32_4
+        ));
+        assert!(surface
+            .calc_world_position()
+            .equal_epsilon(&vec3::Vec3::new(3.0, 0.0, 2.0), math::EPSILON_F32_4));
+        assert!(surface
+            .calc_world_normal()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 0.0, 1.0), math::EPSILON_F32_4));
+
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.0, 0.0, 10.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        assert!(instance.intersect_ray(&ray).is_none());
+    }
+
+    #[test]
+    fn test_is_intersect() {
+        let instance = create_instance(vec3::Vec3::new(3.0, 0.0, 0.0), 2.0);
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(3.0, 0.0, 10.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        assert!(instance.is_intersect(&ray, 8.5));
+        assert!(!instance.is_intersect(&ray, 7.5));
+    }
+
+    #[test]
+    fn test_scaled_prototype() {
+        // a sphere of radius 2 from its own transform, halved again by the instance
+        let prototype = rc::Rc::new(sphere::Sphere::new(
+            mat4::Mat4::new().scale(&vec3::Vec3::from(2.0)),
+            1.0,
+        ));
+        let instance = Instance::new(
+            prototype,
+            mat4::Mat4::new()
+                .translate(&vec3::Vec3::new(3.0, 0.0, 0.0))
+                .scale(&vec3::Vec3::from(0.5)),
+        );
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(3.0, 0.0, 10.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+
+        let surface = instance.intersect_ray(&ray).unwrap();
+        assert!(math::equal_epsilon_f32(
+            surface.ray_time(),
+            9.0,
+            math::EPSILON_F32_4
+        ));
+        assert!(surface
+            .calc_world_position()
+            .equal_epsilon(&vec3::Vec3::new(3.0, 0.0, 1.0), math::EPSILON_F32_4));
+        assert!(instance.is_intersect(&ray, 9.5));
+        assert!(!instance.is_intersect(&ray, 8.5));
+
+        let ray_times: Vec<f32> = instance
+            .intersect_ray_all(&ray)
+            .iter()
+            .map(|surface| surface.ray_time())
+            .collect();
+        assert_eq!(ray_times.len(), 2);
+        assert!(math::equal_epsilon_f32(
+            ray_times[1],
+            11.0,
+            math::EPSILON_F32_4
+        ));
+    }
+
+    #[test]
+    fn test_aabb() {
+        let aabb = create_instance(vec3::Vec3::new(3.0, 0.0, 0.0), 2.0).aabb();
+        assert!(aabb
+            .min()
+            .equal_epsilon(&vec3::Vec3::new(1.0, -2.0, -2.0), math::EPSILON_F32_4));
+        assert!(aabb
+            .max()
+            .equal_epsilon(&vec3::Vec3::new(5.0, 2.0, 2.0), math::EPSILON_F32_4));
+    }
+}
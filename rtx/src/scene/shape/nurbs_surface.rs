@@ -0,0 +1,647 @@
+use crate::core::mat4;
+use crate::core::vec2;
+use crate::core::vec3;
+use crate::scene::aabb;
+use crate::scene::accelerator;
+use crate::scene::accelerator::bvh;
+use crate::scene::ray;
+use crate::scene::shape;
+
+// grid cells per knot span of the tessellation that finds the starting points of the newton iteration
+const GRID_CELLS_PER_SPAN: usize = 8;
+
+const NEWTON_ITERATIONS: usize = 8;
+
+// distance between the ray and the surface a newton hit has to reach in object space
+const NEWTON_TOLERANCE: f32 = 1e-5;
+
+// triangle of the tessellation with the surface parameters of its corners
+struct GridTriangle {
+    positions: [vec3::Vec3; 3],
+    parameters: [vec2::Vec2; 3],
+}
+
+impl GridTriangle {
+    fn aabb(&self) -> aabb::AABB {
+        let mut aabb = aabb::AABB::empty();
+        for position in self.positions.iter() {
+            aabb.merge(position);
+        }
+
+        return aabb;
+    }
+
+    // returns the ray distance and the surface parameters of the hit
+    fn intersect(&self, ray: &ray::Ray) -> Option<(f32, vec2::Vec2)> {
+        let [p0, p1, p2] = self.positions;
+        let edge_1 = p1 - p0;
+        let edge_2 = p2 - p0;
+        let p = ray.direction().cross(&edge_2);
+        let det = edge_1.dot(&p);
+        if det == 0.0 {
+            return None;
+        }
+
+        let inv_det = 1.0 / det;
+        let s = *ray.origin() - p0;
+        let b1 = s.dot(&p) * inv_det;
+        if !(0.0..=1.0).contains(&b1) {
+            return None;
+        }
+
+        let q = s.cross(&edge_1);
+        let b2 = ray.direction().dot(&q) * inv_det;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return None;
+        }
+
+        let ray_time = edge_2.dot(&q) * inv_det;
+        if ray_time <= 0.0 {
+            return None;
+        }
+
+        let [uv0, uv1, uv2] = self.parameters;
+        return Some((ray_time, (1.0 - b1 - b2) * uv0 + b1 * uv1 + b2 * uv2));
+    }
+}
+
+// rational b-spline surface. The hit on a fine enough tessellation is refined with newton iterations
+// on the exact surface, so positions, dpdu and dpdv come from the surface and its parametric
+// derivatives. Bezier patches are the surfaces with a single knot span in each direction
+pub struct NurbsSurface {
+    object_to_world: mat4::Mat4,
+    world_to_object: mat4::Mat4,
+    normal_transform: mat4::Mat4,
+    degree_u: usize,
+    degree_v: usize,
+    knots_u: Vec<f32>,
+    knots_v: Vec<f32>,
+    // indexed by u first and v second
+    control_points: Vec<Vec<vec3::Vec3>>,
+    weights: Vec<Vec<f32>>,
+    grid: Vec<GridTriangle>,
+    bvh: bvh::Bvh,
+}
+
+impl NurbsSurface {
+    // there are degree + 1 more knots than control points in each direction, and the weights are
+    // positive
+    pub fn new(
+        object_to_world: mat4::Mat4,
+        degree_u: usize,
+        degree_v: usize,
+        knots_u: Vec<f32>,
+        knots_v: Vec<f32>,
+        control_points: Vec<Vec<vec3::Vec3>>,
+        weights: Vec<Vec<f32>>,
+    ) -> NurbsSurface {
+        let num_v = control_points[0].len();
+        assert!(knots_u.len() == control_points.len() + degree_u + 1);
+        assert!(knots_v.len() == num_v + degree_v + 1);
+        assert!(control_points.iter().all(|row| row.len() == num_v));
+        assert!(weights.len() == control_points.len());
+        assert!(weights
+            .iter()
+            .all(|row| row.len() == num_v && row.iter().all(|weight| *weight > 0.0)));
+        assert!(knots_u.windows(2).all(|knots| knots[0] <= knots[1]));
+        assert!(knots_v.windows(2).all(|knots| knots[0] <= knots[1]));
+
+        let world_to_object = mat4::Mat4::inverse(&object_to_world).unwrap();
+        let normal_transform =
+            mat4::Mat4::inverse(&mat4::Mat4::transpose(&object_to_world)).unwrap();
+        let mut surface = NurbsSurface {
+            object_to_world,
+            world_to_object,
+            normal_transform,
+            degree_u,
+            degree_v,
+            knots_u,
+            knots_v,
+            control_points,
+            weights,
+            grid: Vec::new(),
+            bvh: bvh::Bvh::new(&[]),
+        };
+        surface.grid = surface.tessellate_grid();
+        let grid_bounds: Vec<aabb::AABB> = surface
+            .grid
+            .iter()
+            .map(|triangle| triangle.aabb())
+            .collect();
+        surface.bvh = bvh::Bvh::new(&grid_bounds);
+        return surface;
+    }
+
+    // bezier patch of the degree given by the number of control points, four by four for a bicubic
+    // one like the patches of the utah teapot
+    pub fn new_bezier(
+        object_to_world: mat4::Mat4,
+        control_points: Vec<Vec<vec3::Vec3>>,
+    ) -> NurbsSurface {
+        let degree_u = control_points.len() - 1;
+        let degree_v = control_points[0].len() - 1;
+        let clamped_knots = |degree: usize| {
+            let mut knots = vec![0.0; degree + 1];
+            knots.extend(vec![1.0; degree + 1]);
+            return knots;
+        };
+        let weights = control_points
+            .iter()
+            .map(|row| vec![1.0; row.len()])
+            .collect();
+        return NurbsSurface::new(
+            object_to_world,
+            degree_u,
+            degree_v,
+            clamped_knots(degree_u),
+            clamped_knots(degree_v),
+            control_points,
+            weights,
+        );
+    }
+
+    // range of the surface parameters in each direction
+    pub fn domain(&self) -> (vec2::Vec2, vec2::Vec2) {
+        return (
+            vec2::Vec2::new(
+                self.knots_u[self.degree_u],
+                self.knots_u[self.knots_u.len() - self.degree_u - 1],
+            ),
+            vec2::Vec2::new(
+                self.knots_v[self.degree_v],
+                self.knots_v[self.knots_v.len() - self.degree_v - 1],
+            ),
+        );
+    }
+
+    // position and its derivatives along u and v in object space
+    pub fn evaluate(&self, u: f32, v: f32) -> (vec3::Vec3, vec3::Vec3, vec3::Vec3) {
+        let (domain_u, domain_v) = self.domain();
+        let u = f32::min(f32::max(u, domain_u.x), domain_u.y);
+        let v = f32::min(f32::max(v, domain_v.x), domain_v.y);
+        let span_u = find_span(&self.knots_u, self.degree_u, self.control_points.len(), u);
+        let span_v = find_span(
+            &self.knots_v,
+            self.degree_v,
+            self.control_points[0].len(),
+            v,
+        );
+        let (basis_u, derivative_u) =
+            basis_and_derivatives(&self.knots_u, span_u, self.degree_u, u);
+        let (basis_v, derivative_v) =
+            basis_and_derivatives(&self.knots_v, span_v, self.degree_v, v);
+
+        // the homogeneous point and weight, and their derivatives
+        let mut point = vec3::Vec3::from(0.0);
+        let mut point_du = vec3::Vec3::from(0.0);
+        let mut point_dv = vec3::Vec3::from(0.0);
+        let (mut weight, mut weight_du, mut weight_dv) = (0.0, 0.0, 0.0);
+        for k in 0..=self.degree_u {
+            for l in 0..=self.degree_v {
+                let i = span_u - self.degree_u + k;
+                let j = span_v - self.degree_v + l;
+                let control_weight = self.weights[i][j];
+                let control_point = control_weight * self.control_points[i][j];
+                point += basis_u[k] * basis_v[l] * control_point;
+                point_du += derivative_u[k] * basis_v[l] * control_point;
+                point_dv += basis_u[k] * derivative_v[l] * control_point;
+                weight += basis_u[k] * basis_v[l] * control_weight;
+                weight_du += derivative_u[k] * basis_v[l] * control_weight;
+                weight_dv += basis_u[k] * derivative_v[l] * control_weight;
+            }
+        }
+
+        let position = point / weight;
+        let dpdu = (point_du - weight_du * position) / weight;
+        let dpdv = (point_dv - weight_dv * position) / weight;
+        return (position, dpdu, dpdv);
+    }
+
+    // grid over every non empty knot span in both directions
+    fn tessellate_grid(&self) -> Vec<GridTriangle> {
+        let parameters_u = grid_parameters(&self.knots_u, self.degree_u);
+        let parameters_v = grid_parameters(&self.knots_v, self.degree_v);
+        let positions: Vec<Vec<vec3::Vec3>> = parameters_u
+            .iter()
+            .map(|u| {
+                parameters_v
+                    .iter()
+                    .map(|v| self.evaluate(*u, *v).0)
+                    .collect()
+            })
+            .collect();
+
+        let mut grid = Vec::new();
+        for i in 0..parameters_u.len() - 1 {
+            for j in 0..parameters_v.len() - 1 {
+                let corner = |di: usize, dj: usize| {
+                    return (
+                        positions[i + di][j + dj],
+                        vec2::Vec2::new(parameters_u[i + di], parameters_v[j + dj]),
+                    );
+                };
+                let (c00, c10, c11, c01) = (corner(0, 0), corner(1, 0), corner(1, 1), corner(0, 1));
+                for (a, b, c) in [(c00, c10, c11), (c00, c11, c01)].iter() {
+                    grid.push(GridTriangle {
+                        positions: [a.0, b.0, c.0],
+                        parameters: [a.1, b.1, c.1],
+                    });
+                }
+            }
+        }
+
+        return grid;
+    }
+
+    // solves surface(u, v) = origin + t direction from the hit on the tessellation
+    fn refine(
+        &self,
+        local_ray: &ray::Ray,
+        ray_time: f32,
+        uv: vec2::Vec2,
+    ) -> Option<(f32, f32, f32)> {
+        let (domain_u, domain_v) = self.domain();
+        let direction = *local_ray.direction();
+        let (mut u, mut v, mut t) = (uv.x, uv.y, ray_time);
+        for _ in 0..NEWTON_ITERATIONS {
+            let (position, dpdu, dpdv) = self.evaluate(u, v);
+            let residual = position - local_ray.calc_position(t);
+            if residual.length() < NEWTON_TOLERANCE {
+                break;
+            }
+
+            // cramer's rule on the jacobian with the columns dpdu, dpdv and -direction
+            let det = dpdu.dot(&dpdv.cross(&-direction));
+            if det == 0.0 {
+                return None;
+            }
+
+            u -= residual.dot(&dpdv.cross(&-direction)) / det;
+            v -= dpdu.dot(&residual.cross(&-direction)) / det;
+            t -= dpdu.dot(&dpdv.cross(&residual)) / det;
+            u = f32::min(f32::max(u, domain_u.x), domain_u.y);
+            v = f32::min(f32::max(v, domain_v.x), domain_v.y);
+        }
+
+        let (position, _, _) = self.evaluate(u, v);
+        if t <= 0.0 || (position - local_ray.calc_position(t)).length() >= NEWTON_TOLERANCE {
+            return None;
+        }
+
+        return Some((t, u, v));
+    }
+
+    // nearest hit, returns the local ray time and the surface parameters. Falls back to the hit on
+    // the tessellation where newton does not converge, which keeps silhouettes free of holes
+    fn intersect_local(&self, local_ray: &ray::Ray) -> Option<(f32, f32, f32)> {
+        let (ray_time, uv) =
+            accelerator::find_closest_hit(&self.bvh, local_ray, |triangle_idx, _| {
+                let (ray_time, uv) = self.grid[triangle_idx].intersect(local_ray)?;
+                return Some(((ray_time, uv), ray_time));
+            })?;
+        return match self.refine(local_ray, ray_time, uv) {
+            Some(hit) => Some(hit),
+            None => Some((ray_time, uv.x, uv.y)),
+        };
+    }
+
+    // degenerate points like the poles of a revolved surface take the normal next to them
+    fn local_normal(&self, u: f32, v: f32, dpdu: &vec3::Vec3, dpdv: &vec3::Vec3) -> vec3::Vec3 {
+        if let Some(normal) = dpdu.cross(dpdv).normalize() {
+            return normal;
+        }
+
+        let (domain_u, domain_v) = self.domain();
+        let nudge = 1e-3;
+        let (_, dpdu, dpdv) = self.evaluate(
+            u + nudge * (0.5 * (domain_u.x + domain_u.y) - u),
+            v + nudge * (0.5 * (domain_v.x + domain_v.y) - v),
+        );
+        return dpdu
+            .cross(&dpdv)
+            .normalize()
+            .unwrap_or(vec3::Vec3::new(0.0, 0.0, 1.0));
+    }
+}
+
+impl shape::IntersectableShape for NurbsSurface {
+    fn is_intersect(&self, ray: &ray::Ray, max_distance: f32) -> bool {
+        let local_ray = ray::Ray::transform(ray, &self.world_to_object);
+        return match self.intersect_local(&local_ray) {
+            Some((ray_time, _, _)) => ray_time < max_distance,
+            None => false,
+        };
+    }
+
+    fn intersect_ray(&self, ray: &ray::Ray) -> Option<shape::IntersectableShapeSurface> {
+        let local_ray = ray::Ray::transform(ray, &self.world_to_object);
+        let (ray_time, u, v) = self.intersect_local(&local_ray)?;
+
+        let (_, local_dpdu, local_dpdv) = self.evaluate(u, v);
+        let mut local_normal = self.local_normal(u, v, &local_dpdu, &local_dpdv);
+        if local_ray.direction().dot(&local_normal) > 0.0 {
+            local_normal = -local_normal;
+        }
+
+        return Some(shape::IntersectableShapeSurface::new(
+            ray_time,
+            local_ray.calc_position(ray_time),
+            local_normal,
+            local_dpdu,
+            local_dpdv,
+            self.object_to_world,
+            self.normal_transform,
+        ));
+    }
+
+    // the surface lies in the convex hull of its control points
+    fn aabb(&self) -> aabb::AABB {
+        let mut aabb = aabb::AABB::empty();
+        for control_point in self.control_points.iter().flatten() {
+            aabb.merge(control_point);
+        }
+
+        return aabb.transform(&self.object_to_world);
+    }
+}
+
+// index of the knot span that contains the parameter, the last span includes the end of the domain
+fn find_span(knots: &[f32], degree: usize, num_control_points: usize, t: f32) -> usize {
+    if t >= knots[num_control_points] {
+        return num_control_points - 1;
+    }
+
+    let mut span = degree;
+    while knots[span + 1] <= t {
+        span += 1;
+    }
+
+    return span;
+}
+
+// the degree + 1 basis functions that are not zero in the span, by the cox de boor recursion
+fn basis_functions(knots: &[f32], span: usize, degree: usize, t: f32) -> Vec<f32> {
+    let mut basis = vec![0.0; degree + 1];
+    let mut left = vec![0.0; degree + 1];
+    let mut right = vec![0.0; degree + 1];
+    basis[0] = 1.0;
+    for j in 1..=degree {
+        left[j] = t - knots[span + 1 - j];
+        right[j] = knots[span + j] - t;
+        let mut saved = 0.0;
+        for r in 0..j {
+            let temp = basis[r] / (right[r + 1] + left[j - r]);
+            basis[r] = saved + right[r + 1] * temp;
+            saved = left[j - r] * temp;
+        }
+        basis[j] = saved;
+    }
+
+    return basis;
+}
+
+// basis functions and their derivatives, which follow from the basis functions one degree lower
+fn basis_and_derivatives(
+    knots: &[f32],
+    span: usize,
+    degree: usize,
+    t: f32,
+) -> (Vec<f32>, Vec<f32>) {
+    let basis = basis_functions(knots, span, degree, t);
+    let mut derivatives = vec![0.0; degree + 1];
+    if degree == 0 {
+        return (basis, derivatives);
+    }
+
+    let lower = basis_functions(knots, span, degree - 1, t);
+    let degree_f = degree as f32;
+    for (k, derivative) in derivatives.iter_mut().enumerate() {
+        let i = span - degree + k;
+        if k > 0 {
+            let denominator = knots[i + degree] - knots[i];
+            if denominator > 0.0 {
+                *derivative += degree_f * lower[k - 1] / denominator;
+            }
+        }
+
+        if k < degree {
+            let denominator = knots[i + degree + 1] - knots[i + 1];
+            if denominator > 0.0 {
+                *derivative -= degree_f * lower[k] / denominator;
+            }
+        }
+    }
+
+    return (basis, derivatives);
+}
+
+fn grid_parameters(knots: &[f32], degree: usize) -> Vec<f32> {
+    let end = knots.len() - degree - 1;
+    let mut parameters = vec![knots[degree]];
+    for span in degree..end {
+        let (start, stop) = (knots[span], knots[span + 1]);
+        if stop <= start {
+            continue;
+        }
+
+        for i in 1..=GRID_CELLS_PER_SPAN {
+            parameters.push(start + (stop - start) * i as f32 / GRID_CELLS_PER_SPAN as f32);
+        }
+    }
+
+    return parameters;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+    use crate::scene::shape::IntersectableShape;
+
+    // bicubic patch over the unit square with the given heights, x and y follow u and v exactly
+    fn create_patch(heights: [[f32; 4]; 4]) -> NurbsSurface {
+        let control_points = (0..4)
+            .map(|i| {
+                (0..4)
+                    .map(|j| vec3::Vec3::new(i as f32 / 3.0, j as f32 / 3.0, heights[i][j]))
+                    .collect()
+            })
+            .collect();
+        return NurbsSurface::new_bezier(mat4::Mat4::new(), control_points);
+    }
+
+    fn bernstein(t: f32) -> [f32; 4] {
+        let s = 1.0 - t;
+        return [s * s * s, 3.0 * t * s * s, 3.0 * t * t * s, t * t * t];
+    }
+
+    #[test]
+    fn test_flat_patch() {
+        let patch = create_patch([[0.0; 4]; 4]);
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.3, 0.6, 2.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        let surface = patch.intersect_ray(&ray).unwrap();
+        assert!(math::equal_epsilon_f32(
+            surface.ray_time(),
+            2.0,
+            math::EPSILON_F32_4
+        ));
+        assert!(surface
+            .calc_world_position()
+            .equal_epsilon(&vec3::Vec3::new(0.3, 0.6, 0.0), math::EPSILON_F32_4));
+        assert!(surface
+            .calc_world_dpdu()
+            .equal_epsilon(&vec3::Vec3::new(1.0, 0.0, 0.0), math::EPSILON_F32_4));
+        assert!(surface
+            .calc_world_dpdv()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 1.0, 0.0), math::EPSILON_F32_4));
+        assert!(surface
+            .calc_world_normal()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 0.0, 1.0), math::EPSILON_F32_4));
+
+        let miss = ray::Ray::new(
+            vec3::Vec3::new(1.5, 0.5, 2.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        assert!(patch.intersect_ray(&miss).is_none());
+        assert!(!patch.is_intersect(&miss, 10.0));
+    }
+
+    #[test]
+    fn test_curved_patch() {
+        let heights = [
+            [0.0, 0.2, 0.1, 0.0],
+            [0.3, 1.0, 0.8, 0.1],
+            [0.1, 0.9, 1.2, 0.4],
+            [0.0, 0.2, 0.3, 0.0],
+        ];
+        let patch = create_patch(heights);
+        let (u, v) = (0.4, 0.7);
+        let ray = ray::Ray::new(vec3::Vec3::new(u, v, 5.0), vec3::Vec3::new(0.0, 0.0, -1.0));
+        let surface = patch.intersect_ray(&ray).unwrap();
+
+        // the height and its derivative along u from the bernstein polynomials
+        let (basis_u, basis_v) = (bernstein(u), bernstein(v));
+        let mut height = 0.0;
+        let mut height_du = 0.0;
+        for i in 0..4 {
+            for j in 0..4 {
+                height += basis_u[i] * basis_v[j] * heights[i][j];
+                if i < 3 {
+                    let basis_u_lower = [(1.0 - u) * (1.0 - u), 2.0 * u * (1.0 - u), u * u];
+                    height_du +=
+                        3.0 * basis_u_lower[i] * basis_v[j] * (heights[i + 1][j] - heights[i][j]);
+                }
+            }
+        }
+
+        assert!(surface
+            .calc_world_position()
+            .equal_epsilon(&vec3::Vec3::new(u, v, height), math::EPSILON_F32_4));
+        assert!(surface
+            .calc_world_dpdu()
+            .equal_epsilon(&vec3::Vec3::new(1.0, 0.0, height_du), math::EPSILON_F32_4));
+        let normal = surface.calc_world_normal();
+        assert!(math::equal_epsilon_f32(
+            normal.dot(&surface.calc_world_dpdv()),
+            0.0,
+            math::EPSILON_F32_4
+        ));
+        assert!(normal.z > 0.0);
+    }
+
+    #[test]
+    fn test_rational_surface() {
+        // a quarter of the unit cylinder, the rational arc is exactly circular
+        let weight = f32::sqrt(0.5);
+        let arc = [
+            vec3::Vec3::new(1.0, 0.0, 0.0),
+            vec3::Vec3::new(1.0, 1.0, 0.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+        ];
+        let control_points = arc
+            .iter()
+            .map(|point| vec![*point, *point + vec3::Vec3::new(0.0, 0.0, 1.0)])
+            .collect();
+        let cylinder = NurbsSurface::new(
+            mat4::Mat4::new(),
+            2,
+            1,
+            vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            vec![0.0, 0.0, 1.0, 1.0],
+            control_points,
+            vec![vec![1.0, 1.0], vec![weight, weight], vec![1.0, 1.0]],
+        );
+
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(5.0, 5.0, 0.5),
+            vec3::Vec3::new(-1.0, -1.0, 0.0),
+        );
+        let surface = cylinder.intersect_ray(&ray).unwrap();
+        assert!(math::equal_epsilon_f32(
+            surface.ray_time(),
+            5.0 * f32::sqrt(2.0) - 1.0,
+            math::EPSILON_F32_4
+        ));
+        let expected_normal = vec3::Vec3::new(weight, weight, 0.0);
+        assert!(surface.calc_world_position().equal_epsilon(
+            &(expected_normal + vec3::Vec3::new(0.0, 0.0, 0.5)),
+            math::EPSILON_F32_4
+        ));
+        assert!(surface
+            .calc_world_normal()
+            .equal_epsilon(&expected_normal, math::EPSILON_F32_4));
+    }
+
+    #[test]
+    fn test_knot_spans() {
+        // a biquadratic surface with two spans in u is still a plane for planar control points
+        let control_points = (0..4)
+            .map(|i| {
+                (0..3)
+                    .map(|j| vec3::Vec3::new(i as f32, j as f32, 0.0))
+                    .collect()
+            })
+            .collect();
+        let weights = vec![vec![1.0; 3]; 4];
+        let surface = NurbsSurface::new(
+            mat4::Mat4::new().translate(&vec3::Vec3::new(0.0, 0.0, 1.0)),
+            2,
+            2,
+            vec![0.0, 0.0, 0.0, 0.5, 1.0, 1.0, 1.0],
+            vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            control_points,
+            weights,
+        );
+        let (domain_u, domain_v) = surface.domain();
+        assert!(math::equal_epsilon_f32(
+            domain_u.y,
+            1.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(math::equal_epsilon_f32(
+            domain_v.y,
+            1.0,
+            math::EPSILON_F32_5
+        ));
+
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(1.7, 0.4, 3.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        let surface_hit = surface.intersect_ray(&ray).unwrap();
+        assert!(surface_hit
+            .calc_world_position()
+            .equal_epsilon(&vec3::Vec3::new(1.7, 0.4, 1.0), math::EPSILON_F32_4));
+
+        let aabb = surface.aabb();
+        assert!(aabb
+            .min()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 0.0, 1.0), math::EPSILON_F32_5));
+        assert!(aabb
+            .max()
+            .equal_epsilon(&vec3::Vec3::new(3.0, 2.0, 1.0), math::EPSILON_F32_5));
+    }
+}
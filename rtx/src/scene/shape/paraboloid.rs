@@ -0,0 +1,274 @@
+use crate::core::mat4;
+use crate::core::math;
+use crate::core::vec2;
+use crate::core::vec3;
+use crate::core::vec4;
+use crate::scene::aabb;
+use crate::scene::ray;
+use crate::scene::shape;
+use std::mem;
+
+// paraboloid around the z axis that reaches the radius at local_z_max, x^2 + y^2 = radius^2 z / z_max
+pub struct Paraboloid {
+    object_to_world: mat4::Mat4,
+    world_to_object: mat4::Mat4,
+    normal_transform: mat4::Mat4,
+    local_radius: f32,
+    local_z_min: f32,
+    local_z_max: f32,
+}
+
+impl Paraboloid {
+    pub fn new(
+        object_to_world: mat4::Mat4,
+        local_radius: f32,
+        local_z_min: f32,
+        local_z_max: f32,
+    ) -> Paraboloid {
+        assert!(0.0 <= local_z_min && local_z_min < local_z_max);
+        let world_to_object = mat4::Mat4::inverse(&object_to_world).unwrap();
+        let normal_transform =
+            mat4::Mat4::inverse(&mat4::Mat4::transpose(&object_to_world)).unwrap();
+
+        return Paraboloid {
+            object_to_world,
+            world_to_object,
+            normal_transform,
+            local_radius,
+            local_z_min,
+            local_z_max,
+        };
+    }
+
+    pub fn area(&self) -> f32 {
+        let radius_sq = self.local_radius * self.local_radius;
+        let k = 4.0 * self.local_z_max / radius_sq;
+        return (radius_sq * radius_sq * math::PI_F32
+            / (6.0 * self.local_z_max * self.local_z_max))
+            * (f32::powf(k * self.local_z_max + 1.0, 1.5)
+                - f32::powf(k * self.local_z_min + 1.0, 1.5));
+    }
+
+    // nearest hit in the z range, returns the local ray time and position
+    fn intersect_local(&self, local_ray: &ray::Ray) -> Option<(f32, vec3::Vec3)> {
+        let o = local_ray.origin();
+        let d = local_ray.direction();
+        let k = self.local_z_max / (self.local_radius * self.local_radius);
+        let a = k * (d.x * d.x + d.y * d.y);
+        let b = 2.0 * k * (d.x * o.x + d.y * o.y) - d.z;
+        let c = k * (o.x * o.x + o.y * o.y) - o.z;
+
+        let (mut ray_time_0, mut ray_time_1) = if a == 0.0 {
+            // parallel to the axis, there is a single hit
+            if b == 0.0 {
+                return None;
+            }
+
+            let ray_time = -c / b;
+            (ray_time, ray_time)
+        } else {
+            let discriminant = b * b - 4.0 * a * c;
+            if discriminant < 0.0 {
+                return None;
+            }
+
+            (
+                (-b - discriminant.sqrt()) / (2.0 * a),
+                (-b + discriminant.sqrt()) / (2.0 * a),
+            )
+        };
+        if ray_time_0 > ray_time_1 {
+            mem::swap(&mut ray_time_0, &mut ray_time_1);
+        }
+
+        for ray_time in [ray_time_0, ray_time_1].iter() {
+            if *ray_time < 0.0 {
+                continue;
+            }
+
+            let local_position = local_ray.calc_position(*ray_time);
+            if local_position.z >= self.local_z_min && local_position.z <= self.local_z_max {
+                return Some((*ray_time, local_position));
+            }
+        }
+
+        return None;
+    }
+
+    fn partial_derivatives(&self, local_position: &vec3::Vec3) -> (vec3::Vec3, vec3::Vec3) {
+        let local_dpdu = vec3::Vec3::new(
+            -2.0 * math::PI_F32 * local_position.y,
+            2.0 * math::PI_F32 * local_position.x,
+            0.0,
+        );
+        let half_inv_z = 0.5 / f32::max(local_position.z, f32::EPSILON);
+        let local_dpdv = (self.local_z_max - self.local_z_min)
+            * vec3::Vec3::new(
+                local_position.x * half_inv_z,
+                local_position.y * half_inv_z,
+                1.0,
+            );
+        return (local_dpdu, local_dpdv);
+    }
+
+    // the normal of the apex, where the partial derivatives degenerate, is the axis
+    fn outward_normal(&self, local_position: &vec3::Vec3) -> vec3::Vec3 {
+        let k = self.local_z_max / (self.local_radius * self.local_radius);
+        return vec3::Vec3::new(2.0 * k * local_position.x, 2.0 * k * local_position.y, -1.0)
+            .normalize()
+            .unwrap();
+    }
+}
+
+impl shape::IntersectableShape for Paraboloid {
+    fn is_intersect(&self, ray: &ray::Ray, max_distance: f32) -> bool {
+        let local_ray = ray::Ray::transform(ray, &self.world_to_object);
+        return match self.intersect_local(&local_ray) {
+            Some((ray_time, _)) => ray_time < max_distance,
+            None => false,
+        };
+    }
+
+    fn intersect_ray(&self, ray: &ray::Ray) -> Option<shape::IntersectableShapeSurface> {
+        let local_ray = ray::Ray::transform(ray, &self.world_to_object);
+        let (ray_time, local_position) = self.intersect_local(&local_ray)?;
+
+        let (local_dpdu, local_dpdv) = self.partial_derivatives(&local_position);
+        let mut local_normal = self.outward_normal(&local_position);
+        if local_ray.direction().dot(&local_normal) > 0.0 {
+            local_normal = -local_normal;
+        }
+
+        return Some(shape::IntersectableShapeSurface::new(
+            ray_time,
+            local_position,
+            local_normal,
+            local_dpdu,
+            local_dpdv,
+            self.object_to_world,
+            self.normal_transform,
+        ));
+    }
+
+    fn aabb(&self) -> aabb::AABB {
+        return aabb::AABB::new(
+            vec3::Vec3::new(-self.local_radius, -self.local_radius, self.local_z_min),
+            vec3::Vec3::new(self.local_radius, self.local_radius, self.local_z_max),
+        )
+        .transform(&self.object_to_world);
+    }
+}
+
+impl shape::SamplableShape for Paraboloid {
+    fn sample_surface(
+        &self,
+        sample: &vec2::Vec2,
+        surface_point_ref: &vec3::Vec3,
+        _surface_normal_ref: &vec3::Vec3,
+    ) -> Option<shape::SampleShapeSurface> {
+        let local_surface_point_ref =
+            (self.world_to_object * vec4::Vec4::from_vec3(surface_point_ref, 1.0)).to_vec3();
+
+        // the area up to z grows with (k z + 1)^1.5, inverting it samples the area uniformly
+        let radius_sq = self.local_radius * self.local_radius;
+        let k = 4.0 * self.local_z_max / radius_sq;
+        let area_min = f32::powf(k * self.local_z_min + 1.0, 1.5);
+        let area_max = f32::powf(k * self.local_z_max + 1.0, 1.5);
+        let z = (f32::powf(math::lerp(sample.x, area_min, area_max), 2.0 / 3.0) - 1.0) / k;
+        let z = math::clamp(z, self.local_z_min, self.local_z_max);
+
+        let radius = self.local_radius * f32::sqrt(z / self.local_z_max);
+        let phi = 2.0 * math::PI_F32 * sample.y;
+        let local_sample_point = vec3::Vec3::new(radius * f32::cos(phi), radius * f32::sin(phi), z);
+        let local_normal = self.outward_normal(&local_sample_point);
+
+        let direction = local_surface_point_ref - local_sample_point;
+        let normalize_direction = direction.normalize()?;
+        let cos_theta = f32::abs(local_normal.dot(&normalize_direction));
+        if cos_theta == 0.0 {
+            return None;
+        }
+
+        let world_surface_point =
+            (self.object_to_world * vec4::Vec4::from_vec3(&local_sample_point, 1.0)).to_vec3();
+        let pdf = direction.length_sq() / (self.area() * cos_theta);
+        return Some(shape::SampleShapeSurface::new(pdf, world_surface_point));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scene::shape::IntersectableShape;
+    use crate::scene::shape::SamplableShape;
+
+    #[test]
+    fn test_intersect_ray() {
+        let paraboloid = Paraboloid::new(mat4::Mat4::new(), 2.0, 0.0, 4.0);
+
+        // from above through the opening to the apex
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.0, 0.0, 10.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        let surface = paraboloid.intersect_ray(&ray).unwrap();
+        assert!(math::equal_epsilon_f32(
+            surface.ray_time(),
+            10.0,
+            math::EPSILON_F32_4
+        ));
+        assert!(surface
+            .calc_world_normal()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 0.0, 1.0), math::EPSILON_F32_4));
+
+        // from the side at z = 1, where the radius is 1
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(5.0, 0.0, 1.0),
+            vec3::Vec3::new(-1.0, 0.0, 0.0),
+        );
+        let surface = paraboloid.intersect_ray(&ray).unwrap();
+        assert!(surface
+            .calc_world_position()
+            .equal_epsilon(&vec3::Vec3::new(1.0, 0.0, 1.0), math::EPSILON_F32_4));
+        let normal = surface.calc_world_normal();
+        assert!(math::equal_epsilon_f32(
+            normal.dot(&surface.calc_world_dpdu()),
+            0.0,
+            math::EPSILON_F32_4
+        ));
+        assert!(math::equal_epsilon_f32(
+            normal.dot(&surface.calc_world_dpdv()),
+            0.0,
+            math::EPSILON_F32_4
+        ));
+        assert!(normal.x > 0.0);
+
+        // above the top of the paraboloid
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(5.0, 0.0, 5.0),
+            vec3::Vec3::new(-1.0, 0.0, 0.0),
+        );
+        assert!(paraboloid.intersect_ray(&ray).is_none());
+        assert!(!paraboloid.is_intersect(&ray, 100.0));
+    }
+
+    #[test]
+    fn test_sample_surface() {
+        let paraboloid = Paraboloid::new(mat4::Mat4::new(), 2.0, 1.0, 4.0);
+        let point_ref = vec3::Vec3::new(0.0, 0.0, 10.0);
+        for i in 0..16 {
+            let sample = vec2::Vec2::new((i as f32 + 0.5) / 16.0, (i as f32 * 0.37) % 1.0);
+            let sample_surface = paraboloid
+                .sample_surface(&sample, &point_ref, &vec3::Vec3::new(0.0, 0.0, -1.0))
+                .unwrap();
+            let point = sample_surface.surface_point;
+            assert!(point.z >= 1.0 && point.z <= 4.0);
+            assert!(math::equal_epsilon_f32(
+                point.x * point.x + point.y * point.y,
+                point.z,
+                math::EPSILON_F32_4
+            ));
+            assert!(sample_surface.pdf > 0.0);
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::core::mat4;
 use crate::core::math;
 use crate::core::vec3;
+use crate::scene::aabb;
 use crate::scene::ray;
 use crate::scene::shape;
 
@@ -72,8 +73,12 @@ impl shape::IntersectableShape for Plane {
             local_normal,
             local_dpdu,
             local_dpdv,
-            &self.object_to_world,
-            &self.normal_transform,
+            self.object_to_world,
+            self.normal_transform,
         ));
     }
+
+    fn aabb(&self) -> aabb::AABB {
+        return aabb::AABB::infinite();
+    }
 }
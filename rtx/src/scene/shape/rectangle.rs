@@ -3,6 +3,7 @@ use crate::core::math;
 use crate::core::vec2;
 use crate::core::vec3;
 use crate::core::vec4;
+use crate::scene::aabb;
 use crate::scene::ray;
 use crate::scene::shape;
 
@@ -124,10 +125,15 @@ impl shape::IntersectableShape for Rectangle {
             local_normal,
             local_dpdu,
             local_dpdv,
-            &self.object_to_world,
-            &self.normal_transform,
+            self.object_to_world,
+            self.normal_transform,
         ));
     }
+
+    fn aabb(&self) -> aabb::AABB {
+        return aabb::AABB::new(self.local_south_west_corner, self.local_north_east_corner)
+            .transform(&self.object_to_world);
+    }
 }
 
 impl shape::SamplableShape for Rectangle {
@@ -0,0 +1,281 @@
+pub mod operations;
+pub mod primitives;
+
+use crate::core::mat4;
+use crate::core::vec3;
+use crate::scene::aabb;
+use crate::scene::ray;
+use crate::scene::shape;
+
+// distance to the surface below which sphere tracing stops, well below the offset of secondary rays
+const HIT_DISTANCE: f32 = 1e-5;
+
+// offset of the central differences that estimate the normal
+const NORMAL_OFFSET: f32 = 1e-4;
+
+const DEFAULT_MAX_STEPS: usize = 256;
+
+pub trait SignedDistance {
+    // distance from the point to the surface, negative inside the solid. It does not have to be
+    // exact, the lipschitz bound of the shape scales down distances that overshoot
+    fn distance(&self, point: &vec3::Vec3) -> f32;
+}
+
+// solid whose surface is the zero set of a signed distance function, found by sphere tracing the
+// ray through the bounds given for it. The surface is clipped to the bounds
+pub struct SdfShape {
+    object_to_world: mat4::Mat4,
+    world_to_object: mat4::Mat4,
+    normal_transform: mat4::Mat4,
+    sdf: Box<dyn SignedDistance>,
+    local_aabb: aabb::AABB,
+    lipschitz_bound: f32,
+    max_steps: usize,
+}
+
+impl SdfShape {
+    pub fn new(
+        object_to_world: mat4::Mat4,
+        sdf: Box<dyn SignedDistance>,
+        local_aabb: aabb::AABB,
+    ) -> SdfShape {
+        let world_to_object = mat4::Mat4::inverse(&object_to_world).unwrap();
+        let normal_transform =
+            mat4::Mat4::inverse(&mat4::Mat4::transpose(&object_to_world)).unwrap();
+
+        return SdfShape {
+            object_to_world,
+            world_to_object,
+            normal_transform,
+            sdf,
+            local_aabb,
+            lipschitz_bound: 1.0,
+            max_steps: DEFAULT_MAX_STEPS,
+        };
+    }
+
+    // how much faster than the distance to the surface the function can change, steps are divided by
+    // it. Exact distances have a bound of one, twisted or otherwise deformed ones need more
+    pub fn with_lipschitz_bound(mut self, lipschitz_bound: f32) -> SdfShape {
+        assert!(lipschitz_bound > 0.0);
+        self.lipschitz_bound = lipschitz_bound;
+        return self;
+    }
+
+    // rays that take more steps are treated as misses, which happens to rays grazing the surface
+    pub fn with_max_steps(mut self, max_steps: usize) -> SdfShape {
+        self.max_steps = max_steps;
+        return self;
+    }
+
+    // sphere tracing from where the ray enters the bounds. Rays that start inside the solid trace
+    // the negated distance to find where they leave it
+    fn intersect_local(&self, local_ray: &ray::Ray, max_distance: f32) -> Option<f32> {
+        let (t_near, t_far) = self.local_aabb.intersect_ray(local_ray, max_distance)?;
+        let side = if t_near == 0.0 && self.sdf.distance(local_ray.origin()) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+
+        let mut ray_time = t_near;
+        for _ in 0..self.max_steps {
+            let distance = side * self.sdf.distance(&local_ray.calc_position(ray_time));
+            if distance < HIT_DISTANCE {
+                return Some(ray_time);
+            }
+
+            ray_time += distance / self.lipschitz_bound;
+            if ray_time > t_far {
+                return None;
+            }
+        }
+
+        return None;
+    }
+
+    // gradient of the distance by central differences
+    fn local_normal(&self, local_position: &vec3::Vec3) -> Option<vec3::Vec3> {
+        let mut gradient = vec3::Vec3::from(0.0);
+        for axis in 0..3 {
+            let mut offset = vec3::Vec3::from(0.0);
+            offset[axis] = NORMAL_OFFSET;
+            gradient[axis] = self.sdf.distance(&(local_position + offset))
+                - self.sdf.distance(&(local_position - offset));
+        }
+
+        return gradient.normalize();
+    }
+}
+
+impl shape::IntersectableShape for SdfShape {
+    fn is_intersect(&self, ray: &ray::Ray, max_distance: f32) -> bool {
+        let local_ray = ray::Ray::transform(ray, &self.world_to_object);
+        return self.intersect_local(&local_ray, max_distance).is_some();
+    }
+
+    fn intersect_ray(&self, ray: &ray::Ray) -> Option<shape::IntersectableShapeSurface> {
+        let local_ray = ray::Ray::transform(ray, &self.world_to_object);
+        let ray_time = self.intersect_local(&local_ray, f32::MAX)?;
+
+        let local_position = local_ray.calc_position(ray_time);
+        let local_normal = self
+            .local_normal(&local_position)
+            .unwrap_or(-*local_ray.direction());
+        let mut local_dpdu = vec3::Vec3::from(0.0);
+        let mut local_dpdv = vec3::Vec3::from(0.0);
+        vec3::Vec3::coordinate_system(&local_normal, &mut local_dpdu, &mut local_dpdv);
+
+        return Some(shape::IntersectableShapeSurface::new(
+            ray_time,
+            local_position,
+            local_normal,
+            local_dpdu,
+            local_dpdv,
+            self.object_to_world,
+            self.normal_transform,
+        ));
+    }
+
+    fn aabb(&self) -> aabb::AABB {
+        return self.local_aabb.transform(&self.object_to_world);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+    use crate::scene::shape::IntersectableShape;
+
+    fn create_unit_aabb(half_extent: f32) -> aabb::AABB {
+        return aabb::AABB::new(
+            vec3::Vec3::from(-half_extent),
+            vec3::Vec3::from(half_extent),
+        );
+    }
+
+    #[test]
+    fn test_intersect_ray() {
+        let sphere = SdfShape::new(
+            mat4::Mat4::new().translate(&vec3::Vec3::new(0.0, 0.0, -2.0)),
+            Box::new(primitives::SphereSdf::new(1.0)),
+            create_unit_aabb(1.0),
+        );
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.0, 0.0, 5.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        let surface = sphere.intersect_ray(&ray).unwrap();
+        assert!(math::equal_epsilon_f32(
+            surface.ray_time(),
+            6.0,
+            math::EPSILON_F32_4
+        ));
+        assert!(surface
+            .calc_world_normal()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 0.0, 1.0), math::EPSILON_F32_4));
+        let normal = surface.calc_world_normal();
+        assert!(math::equal_epsilon_f32(
+            normal.dot(&surface.calc_world_dpdu()),
+            0.0,
+            math::EPSILON_F32_4
+        ));
+        assert!(sphere.is_intersect(&ray, 6.5));
+        assert!(!sphere.is_intersect(&ray, 5.5));
+
+        // from the inside the ray finds where it leaves the solid
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.0, 0.0, -2.0),
+            vec3::Vec3::new(1.0, 0.0, 0.0),
+        );
+        let surface = sphere.intersect_ray(&ray).unwrap();
+        assert!(math::equal_epsilon_f32(
+            surface.ray_time(),
+            1.0,
+            math::EPSILON_F32_4
+        ));
+        assert!(surface
+            .calc_world_normal()
+            .equal_epsilon(&vec3::Vec3::new(1.0, 0.0, 0.0), math::EPSILON_F32_4));
+
+        let miss = ray::Ray::new(
+            vec3::Vec3::new(2.0, 0.0, 5.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        assert!(sphere.intersect_ray(&miss).is_none());
+    }
+
+    #[test]
+    fn test_bounds_clip_repetition() {
+        // a row of spheres every four units along x, only the ones inside the bounds are hit
+        let spheres = SdfShape::new(
+            mat4::Mat4::new(),
+            Box::new(operations::Repetition::new(
+                Box::new(primitives::SphereSdf::new(1.0)),
+                vec3::Vec3::new(4.0, 0.0, 0.0),
+            )),
+            aabb::AABB::new(
+                vec3::Vec3::new(-6.0, -1.0, -1.0),
+                vec3::Vec3::new(6.0, 1.0, 1.0),
+            ),
+        );
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(4.0, 0.0, 5.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        let surface = spheres.intersect_ray(&ray).unwrap();
+        assert!(surface
+            .calc_world_position()
+            .equal_epsilon(&vec3::Vec3::new(4.0, 0.0, 1.0), math::EPSILON_F32_4));
+
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(8.0, 0.0, 5.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        assert!(spheres.intersect_ray(&ray).is_none());
+
+        let aabb = spheres.aabb();
+        assert!(aabb
+            .max()
+            .equal_epsilon(&vec3::Vec3::new(6.0, 1.0, 1.0), math::EPSILON_F32_5));
+    }
+
+    #[test]
+    fn test_lipschitz_bound() {
+        // a twisted box overestimates distances, the bound keeps the hit on the surface
+        let sdf = || {
+            return Box::new(operations::Twist::new(
+                Box::new(primitives::RoundBoxSdf::new(
+                    vec3::Vec3::new(1.5, 0.3, 2.0),
+                    0.1,
+                )),
+                1.0,
+            ));
+        };
+        let twisted = SdfShape::new(mat4::Mat4::new(), sdf(), create_unit_aabb(2.0))
+            .with_lipschitz_bound(2.5);
+        for i in 0..8 {
+            let angle = i as f32 * 0.4;
+            let ray = ray::Ray::new(
+                vec3::Vec3::new(
+                    5.0 * f32::cos(angle),
+                    5.0 * f32::sin(angle),
+                    0.3 * i as f32 - 1.0,
+                ),
+                vec3::Vec3::new(-f32::cos(angle), -f32::sin(angle), 0.0),
+            );
+            if let Some(surface) = twisted.intersect_ray(&ray) {
+                let distance = sdf().distance(&surface.calc_world_position());
+                assert!(f32::abs(distance) < math::EPSILON_F32_3);
+            }
+        }
+
+        // the center line lies inside the box at every height, so rays towards it always hit
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(5.0, 0.0, 1.0),
+            vec3::Vec3::new(-1.0, 0.0, 0.0),
+        );
+        assert!(twisted.intersect_ray(&ray).is_some());
+    }
+}
@@ -0,0 +1,206 @@
+use crate::core::math;
+use crate::core::vec3;
+use crate::scene::shape::sdf;
+
+// union that blends the surfaces where they come closer than the smoothness, which is a plain union
+// for a smoothness of zero
+pub struct SmoothUnion {
+    left: Box<dyn sdf::SignedDistance>,
+    right: Box<dyn sdf::SignedDistance>,
+    smoothness: f32,
+}
+
+impl SmoothUnion {
+    pub fn new(
+        left: Box<dyn sdf::SignedDistance>,
+        right: Box<dyn sdf::SignedDistance>,
+        smoothness: f32,
+    ) -> SmoothUnion {
+        return SmoothUnion {
+            left,
+            right,
+            smoothness,
+        };
+    }
+}
+
+impl sdf::SignedDistance for SmoothUnion {
+    fn distance(&self, point: &vec3::Vec3) -> f32 {
+        let left = self.left.distance(point);
+        let right = self.right.distance(point);
+        if self.smoothness <= 0.0 {
+            return f32::min(left, right);
+        }
+
+        let h = math::clamp(0.5 + 0.5 * (right - left) / self.smoothness, 0.0, 1.0);
+        return math::lerp(h, right, left) - self.smoothness * h * (1.0 - h);
+    }
+}
+
+// the left solid with the right one cut out of it
+pub struct Subtraction {
+    left: Box<dyn sdf::SignedDistance>,
+    right: Box<dyn sdf::SignedDistance>,
+}
+
+impl Subtraction {
+    pub fn new(
+        left: Box<dyn sdf::SignedDistance>,
+        right: Box<dyn sdf::SignedDistance>,
+    ) -> Subtraction {
+        return Subtraction { left, right };
+    }
+}
+
+impl sdf::SignedDistance for Subtraction {
+    fn distance(&self, point: &vec3::Vec3) -> f32 {
+        return f32::max(self.left.distance(point), -self.right.distance(point));
+    }
+}
+
+// copies of the solid on a grid with the given period along each axis, a period of zero leaves the
+// axis alone. The copies should fit into their cells for the distance to stay a bound
+pub struct Repetition {
+    sdf: Box<dyn sdf::SignedDistance>,
+    period: vec3::Vec3,
+}
+
+impl Repetition {
+    pub fn new(sdf: Box<dyn sdf::SignedDistance>, period: vec3::Vec3) -> Repetition {
+        return Repetition { sdf, period };
+    }
+}
+
+impl sdf::SignedDistance for Repetition {
+    fn distance(&self, point: &vec3::Vec3) -> f32 {
+        let mut cell_point = *point;
+        for axis in 0..3 {
+            let period = self.period[axis];
+            if period > 0.0 {
+                cell_point[axis] -= period * f32::round(point[axis] / period);
+            }
+        }
+
+        return self.sdf.distance(&cell_point);
+    }
+}
+
+// moves the solid by the offset, which places primitives relative to each other
+pub struct Translation {
+    sdf: Box<dyn sdf::SignedDistance>,
+    offset: vec3::Vec3,
+}
+
+impl Translation {
+    pub fn new(sdf: Box<dyn sdf::SignedDistance>, offset: vec3::Vec3) -> Translation {
+        return Translation { sdf, offset };
+    }
+}
+
+impl sdf::SignedDistance for Translation {
+    fn distance(&self, point: &vec3::Vec3) -> f32 {
+        return self.sdf.distance(&(point - self.offset));
+    }
+}
+
+// rotates the solid around the z axis by an angle that grows with z. It stretches distances, so the
+// shape needs a lipschitz bound above one
+pub struct Twist {
+    sdf: Box<dyn sdf::SignedDistance>,
+    angle_per_unit: f32,
+}
+
+impl Twist {
+    pub fn new(sdf: Box<dyn sdf::SignedDistance>, angle_per_unit: f32) -> Twist {
+        return Twist {
+            sdf,
+            angle_per_unit,
+        };
+    }
+}
+
+impl sdf::SignedDistance for Twist {
+    fn distance(&self, point: &vec3::Vec3) -> f32 {
+        let angle = self.angle_per_unit * point.z;
+        let (sin, cos) = f32::sin_cos(angle);
+        let untwisted = vec3::Vec3::new(
+            cos * point.x + sin * point.y,
+            -sin * point.x + cos * point.y,
+            point.z,
+        );
+        return self.sdf.distance(&untwisted);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scene::shape::sdf::primitives;
+    use crate::scene::shape::sdf::SignedDistance;
+
+    fn create_sphere(center: vec3::Vec3, radius: f32) -> Box<dyn sdf::SignedDistance> {
+        return Box::new(Translation::new(
+            Box::new(primitives::SphereSdf::new(radius)),
+            center,
+        ));
+    }
+
+    #[test]
+    fn test_smooth_union_and_subtraction() {
+        let union = SmoothUnion::new(
+            create_sphere(vec3::Vec3::new(-1.0, 0.0, 0.0), 0.75),
+            create_sphere(vec3::Vec3::new(1.0, 0.0, 0.0), 0.75),
+            0.0,
+        );
+        assert!(math::equal_epsilon_f32(
+            union.distance(&vec3::Vec3::new(3.0, 0.0, 0.0)),
+            1.25,
+            math::EPSILON_F32_5
+        ));
+
+        // the blend fills the gap between the spheres
+        let smooth_union = SmoothUnion::new(
+            create_sphere(vec3::Vec3::new(-1.0, 0.0, 0.0), 0.75),
+            create_sphere(vec3::Vec3::new(1.0, 0.0, 0.0), 0.75),
+            1.5,
+        );
+        assert!(union.distance(&vec3::Vec3::from(0.0)) > 0.0);
+        assert!(smooth_union.distance(&vec3::Vec3::from(0.0)) < 0.0);
+
+        let subtraction = Subtraction::new(
+            Box::new(primitives::BoxSdf::new(vec3::Vec3::from(1.0))),
+            Box::new(primitives::SphereSdf::new(0.5)),
+        );
+        assert!(math::equal_epsilon_f32(
+            subtraction.distance(&vec3::Vec3::from(0.0)),
+            0.5,
+            math::EPSILON_F32_5
+        ));
+        assert!(subtraction.distance(&vec3::Vec3::new(0.75, 0.0, 0.0)) < 0.0);
+    }
+
+    #[test]
+    fn test_repetition_and_twist() {
+        let repetition = Repetition::new(
+            Box::new(primitives::SphereSdf::new(0.5)),
+            vec3::Vec3::new(4.0, 0.0, 0.0),
+        );
+        let point = vec3::Vec3::new(0.3, 1.0, 0.0);
+        for i in -2..3 {
+            assert!(math::equal_epsilon_f32(
+                repetition.distance(&(point + vec3::Vec3::new(4.0 * i as f32, 0.0, 0.0))),
+                repetition.distance(&point),
+                math::EPSILON_F32_5
+            ));
+        }
+
+        // a quarter turn per unit turns the box by a quarter at z = 1
+        let twist = Twist::new(
+            Box::new(primitives::BoxSdf::new(vec3::Vec3::new(2.0, 0.5, 5.0))),
+            0.5 * math::PI_F32,
+        );
+        assert!(twist.distance(&vec3::Vec3::new(1.5, 0.0, 0.0)) < 0.0);
+        assert!(twist.distance(&vec3::Vec3::new(1.5, 0.0, 1.0)) > 0.0);
+        assert!(twist.distance(&vec3::Vec3::new(0.0, 1.5, 1.0)) < 0.0);
+    }
+}
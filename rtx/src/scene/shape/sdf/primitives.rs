@@ -0,0 +1,184 @@
+use crate::core::math;
+use crate::core::vec3;
+use crate::scene::shape::sdf;
+
+pub struct SphereSdf {
+    radius: f32,
+}
+
+impl SphereSdf {
+    pub fn new(radius: f32) -> SphereSdf {
+        return SphereSdf { radius };
+    }
+}
+
+impl sdf::SignedDistance for SphereSdf {
+    fn distance(&self, point: &vec3::Vec3) -> f32 {
+        return point.length() - self.radius;
+    }
+}
+
+// box centered at the origin
+pub struct BoxSdf {
+    half_extents: vec3::Vec3,
+}
+
+impl BoxSdf {
+    pub fn new(half_extents: vec3::Vec3) -> BoxSdf {
+        return BoxSdf { half_extents };
+    }
+}
+
+impl sdf::SignedDistance for BoxSdf {
+    fn distance(&self, point: &vec3::Vec3) -> f32 {
+        return box_distance(point, &self.half_extents);
+    }
+}
+
+// box with its edges and corners rounded by the radius, it stays inside the half extents
+pub struct RoundBoxSdf {
+    half_extents: vec3::Vec3,
+    radius: f32,
+}
+
+impl RoundBoxSdf {
+    pub fn new(half_extents: vec3::Vec3, radius: f32) -> RoundBoxSdf {
+        return RoundBoxSdf {
+            half_extents,
+            radius,
+        };
+    }
+}
+
+impl sdf::SignedDistance for RoundBoxSdf {
+    fn distance(&self, point: &vec3::Vec3) -> f32 {
+        let inner_half_extents = self.half_extents - vec3::Vec3::from(self.radius);
+        return box_distance(point, &inner_half_extents) - self.radius;
+    }
+}
+
+// torus around the z axis like the torus shape
+pub struct TorusSdf {
+    major_radius: f32,
+    minor_radius: f32,
+}
+
+impl TorusSdf {
+    pub fn new(major_radius: f32, minor_radius: f32) -> TorusSdf {
+        return TorusSdf {
+            major_radius,
+            minor_radius,
+        };
+    }
+}
+
+impl sdf::SignedDistance for TorusSdf {
+    fn distance(&self, point: &vec3::Vec3) -> f32 {
+        let distance_to_axis = f32::sqrt(point.x * point.x + point.y * point.y);
+        let ring_x = distance_to_axis - self.major_radius;
+        return f32::sqrt(ring_x * ring_x + point.z * point.z) - self.minor_radius;
+    }
+}
+
+// segment between two points grown by the radius
+pub struct CapsuleSdf {
+    start: vec3::Vec3,
+    end: vec3::Vec3,
+    radius: f32,
+}
+
+impl CapsuleSdf {
+    pub fn new(start: vec3::Vec3, end: vec3::Vec3, radius: f32) -> CapsuleSdf {
+        return CapsuleSdf { start, end, radius };
+    }
+}
+
+impl sdf::SignedDistance for CapsuleSdf {
+    fn distance(&self, point: &vec3::Vec3) -> f32 {
+        let to_point = point - self.start;
+        let segment = self.end - self.start;
+        let length_sq = segment.length_sq();
+        let h = if length_sq > 0.0 {
+            math::clamp(to_point.dot(&segment) / length_sq, 0.0, 1.0)
+        } else {
+            0.0
+        };
+        return (to_point - h * segment).length() - self.radius;
+    }
+}
+
+fn box_distance(point: &vec3::Vec3, half_extents: &vec3::Vec3) -> f32 {
+    let q = point.abs() - half_extents;
+    let outside = vec3::Vec3::new(f32::max(q.x, 0.0), f32::max(q.y, 0.0), f32::max(q.z, 0.0));
+    let inside = f32::min(f32::max(q.x, f32::max(q.y, q.z)), 0.0);
+    return outside.length() + inside;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scene::shape::sdf::SignedDistance;
+
+    #[test]
+    fn test_distance() {
+        let cases: Vec<(Box<dyn SignedDistance>, vec3::Vec3, f32)> = vec![
+            (
+                Box::new(SphereSdf::new(1.0)),
+                vec3::Vec3::new(0.0, 3.0, 0.0),
+                2.0,
+            ),
+            (Box::new(SphereSdf::new(1.0)), vec3::Vec3::from(0.0), -1.0),
+            (
+                Box::new(BoxSdf::new(vec3::Vec3::new(1.0, 2.0, 3.0))),
+                vec3::Vec3::new(4.0, 6.0, 0.0),
+                5.0,
+            ),
+            (
+                Box::new(BoxSdf::new(vec3::Vec3::new(1.0, 2.0, 3.0))),
+                vec3::Vec3::new(0.5, 0.0, 0.0),
+                -0.5,
+            ),
+            (
+                Box::new(RoundBoxSdf::new(vec3::Vec3::from(1.0), 0.25)),
+                vec3::Vec3::new(2.0, 0.0, 0.0),
+                1.0,
+            ),
+            (
+                Box::new(RoundBoxSdf::new(vec3::Vec3::from(1.0), 0.25)),
+                vec3::Vec3::from(1.0),
+                f32::sqrt(3.0) * 0.25 - 0.25,
+            ),
+            (
+                Box::new(TorusSdf::new(2.0, 0.5)),
+                vec3::Vec3::new(0.0, 2.0, 1.0),
+                0.5,
+            ),
+            (
+                Box::new(CapsuleSdf::new(
+                    vec3::Vec3::new(0.0, 0.0, -1.0),
+                    vec3::Vec3::new(0.0, 0.0, 1.0),
+                    0.5,
+                )),
+                vec3::Vec3::new(0.0, 0.0, 3.0),
+                1.5,
+            ),
+            (
+                Box::new(CapsuleSdf::new(
+                    vec3::Vec3::new(0.0, 0.0, -1.0),
+                    vec3::Vec3::new(0.0, 0.0, 1.0),
+                    0.5,
+                )),
+                vec3::Vec3::new(2.0, 0.0, 0.3),
+                1.5,
+            ),
+        ];
+
+        for (sdf, point, expected) in cases.iter() {
+            assert!(math::equal_epsilon_f32(
+                sdf.distance(point),
+                *expected,
+                math::EPSILON_F32_5
+            ));
+        }
+    }
+}
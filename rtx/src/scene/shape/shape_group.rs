@@ -0,0 +1,129 @@
+use crate::scene::aabb;
+use crate::scene::accelerator;
+use crate::scene::ray;
+use crate::scene::shape;
+use std::rc;
+
+// shapes intersected together through their own acceleration structure, so a whole sub-world can be
+// the prototype of instances. The ray times of its surfaces are distances in the space of the group
+pub struct ShapeGroup {
+    shapes: Vec<rc::Rc<dyn shape::IntersectableShape>>,
+    accelerator: Box<dyn accelerator::Accelerator>,
+}
+
+impl ShapeGroup {
+    pub fn new(
+        shapes: Vec<rc::Rc<dyn shape::IntersectableShape>>,
+        accelerator_kind: accelerator::AcceleratorKind,
+    ) -> ShapeGroup {
+        let bounds: Vec<aabb::AABB> = shapes.iter().map(|shape| shape.aabb()).collect();
+        let accelerator = accelerator::create_accelerator(accelerator_kind, &bounds);
+        return ShapeGroup {
+            shapes,
+            accelerator,
+        };
+    }
+
+    pub fn shapes(&self) -> &Vec<rc::Rc<dyn shape::IntersectableShape>> {
+        return &self.shapes;
+    }
+}
+
+impl shape::IntersectableShape for ShapeGroup {
+    fn is_intersect(&self, ray: &ray::Ray, max_distance: f32) -> bool {
+        return self
+            .accelerator
+            .any_hit(ray, max_distance, &mut |shape_idx| {
+                self.shapes[shape_idx].is_intersect(ray, max_distance)
+            });
+    }
+
+    // hits are compared by their distance in the space of the group, shapes report ray times in their
+    // own space
+    fn intersect_ray(&self, ray: &ray::Ray) -> Option<shape::IntersectableShapeSurface> {
+        return accelerator::find_closest_hit(self.accelerator.as_ref(), ray, |shape_idx, _| {
+            let surface = self.shapes[shape_idx].intersect_ray(ray)?;
+            let distance = surface.calc_world_position().distance(ray.origin());
+            return Some((surface.with_ray_time(distance), distance));
+        });
+    }
+
+    fn aabb(&self) -> aabb::AABB {
+        return self.accelerator.aabb();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::mat4;
+    use crate::core::math;
+    use crate::core::vec3;
+    use crate::scene::shape::instance;
+    use crate::scene::shape::sphere;
+    use crate::scene::shape::IntersectableShape;
+
+    // two unit spheres along the z axis, the first one in front
+    fn create_group() -> ShapeGroup {
+        let shapes: Vec<rc::Rc<dyn shape::IntersectableShape>> = vec![
+            rc::Rc::new(sphere::Sphere::new(
+                mat4::Mat4::new().translate(&vec3::Vec3::new(0.0, 0.0, -3.0)),
+                1.0,
+            )),
+            rc::Rc::new(sphere::Sphere::new(
+                mat4::Mat4::new().translate(&vec3::Vec3::new(0.0, 0.0, 3.0)),
+                1.0,
+            )),
+        ];
+        return ShapeGroup::new(shapes, accelerator::AcceleratorKind::Bvh);
+    }
+
+    #[test]
+    fn test_intersect_ray() {
+        let group = create_group();
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.0, 0.0, 10.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        let surface = group.intersect_ray(&ray).unwrap();
+        assert!(math::equal_epsilon_f32(
+            surface.ray_time(),
+            6.0,
+            math::EPSILON_F32_4
+        ));
+        assert!(group.is_intersect(&ray, 6.5));
+        assert!(!group.is_intersect(&ray, 5.5));
+    }
+
+    #[test]
+    fn test_instanced_group() {
+        let group: rc::Rc<dyn shape::IntersectableShape> = rc::Rc::new(create_group());
+        let instances: Vec<instance::Instance> = (0..3)
+            .map(|i| {
+                instance::Instance::new(
+                    group.clone(),
+                    mat4::Mat4::new()
+                        .translate(&vec3::Vec3::new(4.0 * i as f32, 0.0, 0.0))
+                        .scale(&vec3::Vec3::from(0.5)),
+                )
+            })
+            .collect();
+
+        for (i, instance) in instances.iter().enumerate() {
+            let ray = ray::Ray::new(
+                vec3::Vec3::new(4.0 * i as f32, 0.0, 10.0),
+                vec3::Vec3::new(0.0, 0.0, -1.0),
+            );
+            let surface = instance.intersect_ray(&ray).unwrap();
+            assert!(math::equal_epsilon_f32(
+                surface.ray_time(),
+                8.0,
+                math::EPSILON_F32_4
+            ));
+            assert!(surface.calc_world_position().equal_epsilon(
+                &vec3::Vec3::new(4.0 * i as f32, 0.0, 2.0),
+                math::EPSILON_F32_4
+            ));
+        }
+    }
+}
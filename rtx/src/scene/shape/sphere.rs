@@ -3,15 +3,23 @@ use crate::core::math;
 use crate::core::vec2;
 use crate::core::vec3;
 use crate::core::vec4;
+use crate::scene::aabb;
 use crate::scene::ray;
 use crate::scene::shape;
 use crate::scene::shape::IntersectableShape;
 
+// sphere around the origin with its poles on the z axis. It can be clipped to the zone between two
+// heights and to a sweep around the z axis, for domes and bowls
 #[derive(Copy, Clone, Debug)]
 pub struct Sphere {
     world_center: vec3::Vec3,
     world_radius: f32,
     radius: f32,
+    z_min: f32,
+    z_max: f32,
+    theta_z_min: f32,
+    theta_z_max: f32,
+    phi_max: f32,
     object_to_world: mat4::Mat4,
     world_to_object: mat4::Mat4,
     normal_transform: mat4::Mat4,
@@ -33,6 +41,11 @@ impl Sphere {
             world_center,
             world_radius,
             radius,
+            z_min: -radius,
+            z_max: radius,
+            theta_z_min: math::PI_F32,
+            theta_z_max: 0.0,
+            phi_max: 2.0 * math::PI_F32,
             object_to_world,
             world_to_object,
             normal_transform,
@@ -40,63 +53,45 @@ impl Sphere {
         };
     }
 
-    fn uniform_sample_local_surface(&self, sample: &vec2::Vec2) -> vec3::Vec3 {
-        let x =
-            f32::cos(2.0 * math::PI_F32 * sample.y) * 2.0 * f32::sqrt(sample.x * (1.0 - sample.x));
-        let y =
-            f32::sin(2.0 * math::PI_F32 * sample.y) * 2.0 * f32::sqrt(sample.x * (1.0 - sample.x));
-        let z = 1.0 - 2.0 * sample.x;
-        return vec3::Vec3::new(x, y, z);
+    // keeps the zone of the sphere between two local heights
+    pub fn with_z_range(mut self, z_min: f32, z_max: f32) -> Sphere {
+        self.z_min = math::clamp(f32::min(z_min, z_max), -self.radius, self.radius);
+        self.z_max = math::clamp(f32::max(z_min, z_max), -self.radius, self.radius);
+        self.theta_z_min = f32::acos(self.z_min / self.radius);
+        self.theta_z_max = f32::acos(self.z_max / self.radius);
+        return self;
     }
 
-    fn behind_surface_tangent_plane(
-        &self,
-        local_surface_point: &vec3::Vec3,
-        local_surface_normal: &vec3::Vec3,
-    ) -> bool {
-        if (-local_surface_point).dot(&local_surface_normal) >= 0.0 {
-            return false;
-        }
-
-        let distance_center_plane = -local_surface_point.dot(&local_surface_normal);
-        return f32::abs(distance_center_plane) >= self.radius;
+    // keeps the part of the sphere swept from the x axis by phi_max around the z axis
+    pub fn with_phi_max(mut self, phi_max: f32) -> Sphere {
+        self.phi_max = math::clamp(phi_max, 0.0, 2.0 * math::PI_F32);
+        return self;
     }
-}
-
-impl shape::IntersectableShape for Sphere {
-    fn is_intersect(&self, ray: &ray::Ray, max_distance: f32) -> bool {
-        let local_ray = ray::Ray::transform(ray, &self.world_to_object);
-        let radius = self.radius;
-        let radius_sq = radius * radius;
-
-        let oc = -local_ray.origin();
-        let oc_length_sq = vec3::Vec3::length_sq(&oc);
-        let origin_outside = oc_length_sq >= radius_sq;
 
-        let tca = vec3::Vec3::dot(&oc, local_ray.direction());
-        if tca < 0.0 && origin_outside {
-            return false;
-        }
+    pub fn area(&self) -> f32 {
+        return self.phi_max * self.radius * (self.z_max - self.z_min);
+    }
 
-        let hc_length_sq = radius * radius - oc_length_sq + tca * tca;
-        if hc_length_sq < 0.0 {
-            return false;
-        }
+    fn is_partial(&self) -> bool {
+        return self.z_min > -self.radius
+            || self.z_max < self.radius
+            || self.phi_max < 2.0 * math::PI_F32;
+    }
 
-        let t: f32;
-        if origin_outside {
-            t = tca - f32::sqrt(hc_length_sq);
-        } else {
-            t = tca + f32::sqrt(hc_length_sq);
+    fn is_in_range(&self, local_position: &vec3::Vec3) -> bool {
+        if !self.is_partial() {
+            return true;
         }
 
-        return t < max_distance;
+        return local_position.z >= self.z_min
+            && local_position.z <= self.z_max
+            && shape::calc_phi(local_position) <= self.phi_max;
     }
 
-    fn intersect_ray(&self, ray: &ray::Ray) -> Option<shape::IntersectableShapeSurface> {
-        let local_ray = ray::Ray::transform(ray, &self.world_to_object);
-        let radius = self.radius;
-        let radius_sq = radius * radius;
+    // nearest hit in the clipped range, the far hit is tried when the near one is clipped away.
+    // Returns the local ray time and position
+    fn intersect_local(&self, local_ray: &ray::Ray) -> Option<(f32, vec3::Vec3)> {
+        let radius_sq = self.radius * self.radius;
 
         let oc = -local_ray.origin();
         let oc_length_sq = vec3::Vec3::length_sq(&oc);
@@ -107,28 +102,65 @@ impl shape::IntersectableShape for Sphere {
             return None;
         }
 
-        let hc_length_sq = radius * radius - oc_length_sq + tca * tca;
+        let hc_length_sq = radius_sq - oc_length_sq + tca * tca;
         if hc_length_sq < 0.0 {
             return None;
         }
 
-        let t: f32;
-        if origin_outside {
-            t = tca - f32::sqrt(hc_length_sq);
-        } else {
-            t = tca + f32::sqrt(hc_length_sq);
+        let hc_length = f32::sqrt(hc_length_sq);
+        for t in [tca - hc_length, tca + hc_length].iter() {
+            if *t < 0.0 {
+                continue;
+            }
+
+            let local_position = local_ray.calc_position(*t);
+            if self.is_in_range(&local_position) {
+                return Some((*t, local_position));
+            }
         }
 
-        // calculate position and normal
-        let local_position = local_ray.calc_position(t);
-        let maybe_local_normal = vec3::Vec3::normalize(&local_position);
-        if maybe_local_normal.is_none() {
+        return None;
+    }
+
+    fn uniform_sample_local_surface(&self, sample: &vec2::Vec2) -> vec3::Vec3 {
+        let x =
+            f32::cos(2.0 * math::PI_F32 * sample.y) * 2.0 * f32::sqrt(sample.x * (1.0 - sample.x));
+        let y =
+            f32::sin(2.0 * math::PI_F32 * sample.y) * 2.0 * f32::sqrt(sample.x * (1.0 - sample.x));
+        let z = 1.0 - 2.0 * sample.x;
+        return vec3::Vec3::new(x, y, z);
+    }
+
+    // the area of a zone grows linearly with its height, so a uniform height samples it uniformly
+    fn uniform_sample_local_partial_surface(&self, sample: &vec2::Vec2) -> vec3::Vec3 {
+        let z = math::lerp(sample.x, self.z_min, self.z_max);
+        let ring_radius = f32::sqrt(f32::max(self.radius * self.radius - z * z, 0.0));
+        let phi = self.phi_max * sample.y;
+        return vec3::Vec3::new(ring_radius * f32::cos(phi), ring_radius * f32::sin(phi), z);
+    }
+
+    fn sample_partial_surface(
+        &self,
+        sample: &vec2::Vec2,
+        local_surface_point_ref: &vec3::Vec3,
+    ) -> Option<shape::SampleShapeSurface> {
+        let local_sample_point = self.uniform_sample_local_partial_surface(sample);
+        let local_normal = local_sample_point.normalize()?;
+        let direction = local_surface_point_ref - local_sample_point;
+        let normalize_direction = direction.normalize()?;
+        let cos_theta = f32::abs(local_normal.dot(&normalize_direction));
+        if cos_theta == 0.0 {
             return None;
         }
 
-        let local_normal = maybe_local_normal.unwrap();
+        let world_surface_point =
+            (self.object_to_world * vec4::Vec4::from_vec3(&local_sample_point, 1.0)).to_vec3();
+        let pdf = direction.length_sq() / (self.area() * cos_theta);
+        return Some(shape::SampleShapeSurface::new(pdf, world_surface_point));
+    }
 
-        // calculate dpdu and dpdv
+    // dpdu and dpdv of the full sphere are parameterized around the y axis
+    fn calc_derivatives(&self, local_position: &vec3::Vec3) -> (vec3::Vec3, vec3::Vec3) {
         let two_pi = math::PI_F32 * 2.0;
         let theta = f32::acos(math::clamp(local_position.y / self.radius, -1.0, 1.0));
 
@@ -144,6 +176,56 @@ impl shape::IntersectableShape for Sphere {
                 -self.radius * f32::sin(theta),
                 local_position.y * sin_phi,
             );
+        return (local_dpdu, local_dpdv);
+    }
+
+    // a clipped sphere is parameterized around the z axis, which its z range and sweep refer to
+    fn calc_partial_derivatives(&self, local_position: &vec3::Vec3) -> (vec3::Vec3, vec3::Vec3) {
+        let phi = shape::calc_phi(local_position);
+        let theta = f32::acos(math::clamp(local_position.z / self.radius, -1.0, 1.0));
+        let local_dpdu = self.phi_max * vec3::Vec3::new(-local_position.y, local_position.x, 0.0);
+        let local_dpdv = (self.theta_z_max - self.theta_z_min)
+            * vec3::Vec3::new(
+                local_position.z * f32::cos(phi),
+                local_position.z * f32::sin(phi),
+                -self.radius * f32::sin(theta),
+            );
+        return (local_dpdu, local_dpdv);
+    }
+
+    fn behind_surface_tangent_plane(
+        &self,
+        local_surface_point: &vec3::Vec3,
+        local_surface_normal: &vec3::Vec3,
+    ) -> bool {
+        if (-local_surface_point).dot(&local_surface_normal) >= 0.0 {
+            return false;
+        }
+
+        let distance_center_plane = -local_surface_point.dot(&local_surface_normal);
+        return f32::abs(distance_center_plane) >= self.radius;
+    }
+}
+
+impl shape::IntersectableShape for Sphere {
+    fn is_intersect(&self, ray: &ray::Ray, max_distance: f32) -> bool {
+        let local_ray = ray::Ray::transform(ray, &self.world_to_object);
+        return match self.intersect_local(&local_ray) {
+            Some((t, _)) => t < max_distance,
+            None => false,
+        };
+    }
+
+    fn intersect_ray(&self, ray: &ray::Ray) -> Option<shape::IntersectableShapeSurface> {
+        let local_ray = ray::Ray::transform(ray, &self.world_to_object);
+        let (t, local_position) = self.intersect_local(&local_ray)?;
+        let local_normal = vec3::Vec3::normalize(&local_position)?;
+
+        let (local_dpdu, local_dpdv) = if self.is_partial() {
+            self.calc_partial_derivatives(&local_position)
+        } else {
+            self.calc_derivatives(&local_position)
+        };
 
         return Some(shape::IntersectableShapeSurface::new(
             t,
@@ -151,10 +233,18 @@ impl shape::IntersectableShape for Sphere {
             local_normal,
             local_dpdu,
             local_dpdv,
-            &self.object_to_world,
-            &self.normal_transform,
+            self.object_to_world,
+            self.normal_transform,
         ));
     }
+
+    fn aabb(&self) -> aabb::AABB {
+        return aabb::AABB::new(
+            vec3::Vec3::new(-self.radius, -self.radius, self.z_min),
+            vec3::Vec3::new(self.radius, self.radius, self.z_max),
+        )
+        .transform(&self.object_to_world);
+    }
 }
 
 impl shape::SamplableShape for Sphere {
@@ -166,6 +256,12 @@ impl shape::SamplableShape for Sphere {
     ) -> Option<shape::SampleShapeSurface> {
         let local_surface_point_ref =
             (self.world_to_object * vec4::Vec4::from_vec3(surface_point_ref, 1.0)).to_vec3();
+
+        // directions in the cone towards a clipped sphere can miss it, its area is sampled instead
+        if self.is_partial() {
+            return self.sample_partial_surface(sample, &local_surface_point_ref);
+        }
+
         let local_surface_normal_ref = (self.inverse_normal_transform
             * vec4::Vec4::from_vec3(surface_normal_ref, 0.0))
         .to_vec3()
@@ -221,7 +317,7 @@ impl shape::SamplableShape for Sphere {
 mod test {
     use super::*;
     use crate::core::math;
-    use crate::scene::shape::IntersectableShape;
+    use crate::scene::shape::SamplableShape;
 
     #[test]
     fn test_intersect_ray_not_intersect() {
@@ -356,4 +452,101 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_intersect_ray_dpdu() {
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(10.0, 0.0, 0.0),
+            vec3::Vec3::new(-1.0, 0.0, 0.0),
+        );
+
+        // the full sphere keeps its poles on the y axis
+        let sphere = Sphere::new(mat4::Mat4::new(), 1.0);
+        let shape_surface = sphere.intersect_ray(&ray).unwrap();
+        assert!(shape_surface
+            .calc_world_dpdu()
+            .normalize()
+            .unwrap()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 0.0, 1.0), math::EPSILON_F32_5));
+
+        // a clipped sphere sweeps around the z axis
+        let sphere = Sphere::new(mat4::Mat4::new(), 1.0).with_phi_max(math::PI_F32);
+        let shape_surface = sphere.intersect_ray(&ray).unwrap();
+        assert!(shape_surface
+            .calc_world_dpdu()
+            .normalize()
+            .unwrap()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 1.0, 0.0), math::EPSILON_F32_5));
+    }
+
+    #[test]
+    fn test_intersect_ray_z_range() {
+        // a bowl, the near hit on the upper half is clipped away
+        let sphere = Sphere::new(mat4::Mat4::new(), 1.0).with_z_range(-1.0, 0.0);
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.0, 0.0, 10.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        let shape_surface = sphere.intersect_ray(&ray).unwrap();
+        assert!(math::equal_epsilon_f32(
+            shape_surface.ray_time(),
+            11.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(sphere.is_intersect(&ray, 11.5));
+        assert!(!sphere.is_intersect(&ray, 10.5));
+
+        // from the side above the rim
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(10.0, 0.0, 0.5),
+            vec3::Vec3::new(-1.0, 0.0, 0.0),
+        );
+        assert!(sphere.intersect_ray(&ray).is_none());
+    }
+
+    #[test]
+    fn test_intersect_ray_phi_max() {
+        // the half with y >= 0
+        let sphere = Sphere::new(mat4::Mat4::new(), 1.0).with_phi_max(math::PI_F32);
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.0, -10.0, 0.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+        );
+        let shape_surface = sphere.intersect_ray(&ray).unwrap();
+        assert!(shape_surface
+            .calc_world_position()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 1.0, 0.0), math::EPSILON_F32_5));
+
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.5, -10.0, 0.0),
+            vec3::Vec3::new(0.0, 0.0, 1.0),
+        );
+        assert!(sphere.intersect_ray(&ray).is_none());
+        assert!(math::equal_epsilon_f32(
+            sphere.area(),
+            2.0 * math::PI_F32,
+            math::EPSILON_F32_5
+        ));
+    }
+
+    #[test]
+    fn test_sample_partial_surface() {
+        let sphere = Sphere::new(mat4::Mat4::new(), 2.0)
+            .with_z_range(0.5, 2.0)
+            .with_phi_max(0.5 * math::PI_F32);
+        let point_ref = vec3::Vec3::new(0.0, 0.0, 10.0);
+        for i in 0..16 {
+            let sample = vec2::Vec2::new((i as f32 + 0.5) / 16.0, (i as f32 * 0.37) % 1.0);
+            let sample_surface = sphere
+                .sample_surface(&sample, &point_ref, &vec3::Vec3::new(0.0, 0.0, -1.0))
+                .unwrap();
+            let point = sample_surface.surface_point;
+            assert!(math::equal_epsilon_f32(
+                point.length(),
+                2.0,
+                math::EPSILON_F32_5
+            ));
+            assert!(point.z >= 0.5 && point.x >= 0.0 && point.y >= 0.0);
+        }
+    }
 }
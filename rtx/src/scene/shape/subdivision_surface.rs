@@ -0,0 +1,820 @@
+use crate::core::mat4;
+use crate::core::math;
+use crate::core::vec3;
+use crate::scene::shape::triangle_mesh;
+use std::collections::HashMap;
+
+// levels the edge length based tessellation stops at, every level multiplies the faces by four
+const MAX_ADAPTIVE_LEVEL: usize = 6;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SubdivisionScheme {
+    // quads and polygons of any size, every face turns into quads
+    CatmullClark,
+    // triangles only, every triangle turns into four
+    Loop,
+}
+
+// a control cage that is refined towards its limit surface and tessellated into a triangle mesh, so
+// it is rendered through the mesh path. Boundary edges and creases with a sharpness of infinity keep
+// the surface sharp, a finite sharpness is used up by one for each level
+pub struct SubdivisionSurface {
+    object_to_world: mat4::Mat4,
+    scheme: SubdivisionScheme,
+    cage: ControlMesh,
+    level: usize,
+    max_edge_length: Option<f32>,
+}
+
+impl SubdivisionSurface {
+    // faces list the indices of their vertices counter clockwise seen from the outside
+    pub fn new(
+        object_to_world: mat4::Mat4,
+        scheme: SubdivisionScheme,
+        faces: Vec<Vec<usize>>,
+        positions: Vec<vec3::Vec3>,
+    ) -> SubdivisionSurface {
+        assert!(faces.iter().all(|face| face.len() >= 3));
+        assert!(faces.iter().flatten().all(|index| *index < positions.len()));
+        assert!(scheme != SubdivisionScheme::Loop || faces.iter().all(|face| face.len() == 3));
+
+        return SubdivisionSurface {
+            object_to_world,
+            scheme,
+            cage: ControlMesh {
+                faces,
+                positions,
+                sharpness: HashMap::new(),
+            },
+            level: 3,
+            max_edge_length: None,
+        };
+    }
+
+    // the edge between two vertices stays sharp for as many levels as its sharpness, f32::INFINITY
+    // keeps it sharp in the limit
+    pub fn with_crease(mut self, v0: usize, v1: usize, sharpness: f32) -> SubdivisionSurface {
+        self.cage.sharpness.insert(edge_key(v0, v1), sharpness);
+        return self;
+    }
+
+    pub fn with_level(mut self, level: usize) -> SubdivisionSurface {
+        self.level = level;
+        self.max_edge_length = None;
+        return self;
+    }
+
+    // picks the level from the size of the cage, edges are refined until they are about as long as
+    // max_edge_length in object space
+    pub fn with_max_edge_length(mut self, max_edge_length: f32) -> SubdivisionSurface {
+        self.max_edge_length = Some(max_edge_length);
+        return self;
+    }
+
+    pub fn scheme(&self) -> SubdivisionScheme {
+        return self.scheme;
+    }
+
+    pub fn level(&self) -> usize {
+        let max_edge_length = match self.max_edge_length {
+            Some(max_edge_length) => max_edge_length,
+            None => return self.level,
+        };
+
+        // every level halves the edges
+        let mut edge_length = self.cage.max_edge_length();
+        let mut level = 0;
+        while edge_length > max_edge_length && level < MAX_ADAPTIVE_LEVEL {
+            edge_length *= 0.5;
+            level += 1;
+        }
+
+        return level;
+    }
+
+    // refines the cage, moves the vertices onto the limit surface and gives them limit normals
+    pub fn tessellate(&self) -> triangle_mesh::TriangleMesh {
+        let mut mesh = self.cage.clone();
+        for _ in 0..self.level() {
+            mesh = match self.scheme {
+                SubdivisionScheme::CatmullClark => mesh.subdivide_catmull_clark(),
+                SubdivisionScheme::Loop => mesh.subdivide_loop(),
+            };
+        }
+
+        let (positions, normals) = mesh.limit(self.scheme);
+        let indices = mesh
+            .faces
+            .iter()
+            .flat_map(|face| {
+                (1..face.len() - 1).flat_map(move |i| vec![face[0], face[i], face[i + 1]])
+            })
+            .collect();
+        return triangle_mesh::TriangleMesh::new(
+            self.object_to_world,
+            indices,
+            positions,
+            normals,
+            Vec::new(),
+        );
+    }
+}
+
+fn edge_key(v0: usize, v1: usize) -> (usize, usize) {
+    return (usize::min(v0, v1), usize::max(v0, v1));
+}
+
+struct Edge {
+    vertices: (usize, usize),
+    faces: Vec<usize>,
+    sharpness: f32,
+}
+
+impl Edge {
+    // boundary and non manifold edges are infinitely sharp
+    fn sharpness(&self) -> f32 {
+        if self.faces.len() != 2 {
+            return f32::INFINITY;
+        }
+
+        return self.sharpness;
+    }
+
+    fn other_vertex(&self, vertex: usize) -> usize {
+        if self.vertices.0 == vertex {
+            return self.vertices.1;
+        }
+
+        return self.vertices.0;
+    }
+}
+
+// the edges of a mesh and the edges and faces around each of its vertices
+struct Topology {
+    edges: Vec<Edge>,
+    edge_lookup: HashMap<(usize, usize), usize>,
+    vertex_edges: Vec<Vec<usize>>,
+    vertex_faces: Vec<Vec<usize>>,
+}
+
+impl Topology {
+    fn edge(&self, v0: usize, v1: usize) -> usize {
+        return self.edge_lookup[&edge_key(v0, v1)];
+    }
+
+    // edges around the vertex that are sharp at this level
+    fn sharp_edges(&self, vertex: usize) -> Vec<&Edge> {
+        return self.vertex_edges[vertex]
+            .iter()
+            .map(|edge_idx| &self.edges[*edge_idx])
+            .filter(|edge| edge.sharpness() > 0.0)
+            .collect();
+    }
+}
+
+#[derive(Clone)]
+struct ControlMesh {
+    faces: Vec<Vec<usize>>,
+    positions: Vec<vec3::Vec3>,
+    sharpness: HashMap<(usize, usize), f32>,
+}
+
+impl ControlMesh {
+    fn topology(&self) -> Topology {
+        let mut edges: Vec<Edge> = Vec::new();
+        let mut edge_lookup = HashMap::new();
+        let mut vertex_edges = vec![Vec::new(); self.positions.len()];
+        let mut vertex_faces = vec![Vec::new(); self.positions.len()];
+        for (face_idx, face) in self.faces.iter().enumerate() {
+            for (i, v0) in face.iter().enumerate() {
+                let v1 = face[(i + 1) % face.len()];
+                let key = edge_key(*v0, v1);
+                let edge_idx = *edge_lookup.entry(key).or_insert_with(|| {
+                    edges.push(Edge {
+                        vertices: key,
+                        faces: Vec::new(),
+                        sharpness: self.sharpness.get(&key).copied().unwrap_or(0.0),
+                    });
+                    vertex_edges[key.0].push(edges.len() - 1);
+                    vertex_edges[key.1].push(edges.len() - 1);
+                    return edges.len() - 1;
+                });
+                edges[edge_idx].faces.push(face_idx);
+                vertex_faces[*v0].push(face_idx);
+            }
+        }
+
+        return Topology {
+            edges,
+            edge_lookup,
+            vertex_edges,
+            vertex_faces,
+        };
+    }
+
+    fn max_edge_length(&self) -> f32 {
+        return self
+            .faces
+            .iter()
+            .flat_map(|face| {
+                (0..face.len()).map(move |i| {
+                    self.positions[face[i]].distance(&self.positions[face[(i + 1) % face.len()]])
+                })
+            })
+            .fold(0.0, f32::max);
+    }
+
+    fn face_center(&self, face: &[usize]) -> vec3::Vec3 {
+        let mut center = vec3::Vec3::from(0.0);
+        for v in face.iter() {
+            center += self.positions[*v];
+        }
+
+        return center / face.len() as f32;
+    }
+
+    // edge points are the smooth point blended towards the midpoint by the sharpness of the edge
+    fn edge_point(&self, edge: &Edge, smooth_point: vec3::Vec3) -> vec3::Vec3 {
+        let midpoint = 0.5 * (self.positions[edge.vertices.0] + self.positions[edge.vertices.1]);
+        return math::lerp(f32::min(edge.sharpness(), 1.0), smooth_point, midpoint);
+    }
+
+    // vertices on two sharp edges follow the crease curve, vertices on more of them and the corners
+    // of a boundary stay where they are
+    fn vertex_point(
+        &self,
+        topology: &Topology,
+        vertex: usize,
+        smooth_point: vec3::Vec3,
+    ) -> vec3::Vec3 {
+        let sharp_edges = topology.sharp_edges(vertex);
+        let position = self.positions[vertex];
+        if sharp_edges.len() < 2 {
+            return smooth_point;
+        }
+
+        if sharp_edges.len() > 2 || topology.vertex_edges[vertex].len() == 2 {
+            return position;
+        }
+
+        let crease_point = (self.positions[sharp_edges[0].other_vertex(vertex)]
+            + 6.0 * position
+            + self.positions[sharp_edges[1].other_vertex(vertex)])
+            / 8.0;
+        let sharpness = 0.5 * (sharp_edges[0].sharpness() + sharp_edges[1].sharpness());
+        return math::lerp(f32::min(sharpness, 1.0), smooth_point, crease_point);
+    }
+
+    // creases lose one level of sharpness on both halves of the split edge
+    fn split_sharpness(
+        &self,
+        topology: &Topology,
+        edge_vertex_offset: usize,
+    ) -> HashMap<(usize, usize), f32> {
+        let mut sharpness = HashMap::new();
+        for (edge_idx, edge) in topology.edges.iter().enumerate() {
+            if edge.faces.len() != 2 || edge.sharpness <= 1.0 {
+                continue;
+            }
+
+            let edge_vertex = edge_vertex_offset + edge_idx;
+            sharpness.insert(edge_key(edge.vertices.0, edge_vertex), edge.sharpness - 1.0);
+            sharpness.insert(edge_key(edge.vertices.1, edge_vertex), edge.sharpness - 1.0);
+        }
+
+        return sharpness;
+    }
+
+    // vertex points keep the indices of their vertices, edge points and face points follow them
+    fn subdivide_catmull_clark(&self) -> ControlMesh {
+        let topology = self.topology();
+        let num_vertices = self.positions.len();
+        let face_vertex_offset = num_vertices + topology.edges.len();
+        let face_points: Vec<vec3::Vec3> = self
+            .faces
+            .iter()
+            .map(|face| self.face_center(face))
+            .collect();
+
+        let edge_points: Vec<vec3::Vec3> = topology
+            .edges
+            .iter()
+            .map(|edge| {
+                let mut smooth_point =
+                    self.positions[edge.vertices.0] + self.positions[edge.vertices.1];
+                for face_idx in edge.faces.iter() {
+                    smooth_point += face_points[*face_idx];
+                }
+                smooth_point /= (2 + edge.faces.len()) as f32;
+                return self.edge_point(edge, smooth_point);
+            })
+            .collect();
+
+        let vertex_points: Vec<vec3::Vec3> = (0..num_vertices)
+            .map(|vertex| {
+                let valence = topology.vertex_edges[vertex].len();
+                if valence == 0 {
+                    return self.positions[vertex];
+                }
+
+                // (q + 2 r + (n - 3) p) / n with the averages q of the face points and r of the
+                // edge midpoints
+                let position = self.positions[vertex];
+                let vertex_faces = &topology.vertex_faces[vertex];
+                let mut face_average = vec3::Vec3::from(0.0);
+                for face_idx in vertex_faces.iter() {
+                    face_average += face_points[*face_idx];
+                }
+                face_average /= vertex_faces.len() as f32;
+                let mut edge_average = vec3::Vec3::from(0.0);
+                for edge_idx in topology.vertex_edges[vertex].iter() {
+                    let edge = &topology.edges[*edge_idx];
+                    edge_average += 0.5 * (position + self.positions[edge.other_vertex(vertex)]);
+                }
+                edge_average /= valence as f32;
+
+                let n = valence as f32;
+                let smooth_point = (face_average + 2.0 * edge_average + (n - 3.0) * position) / n;
+                return self.vertex_point(&topology, vertex, smooth_point);
+            })
+            .collect();
+
+        let mut faces = Vec::new();
+        for (face_idx, face) in self.faces.iter().enumerate() {
+            for (i, v) in face.iter().enumerate() {
+                let next = face[(i + 1) % face.len()];
+                let prev = face[(i + face.len() - 1) % face.len()];
+                faces.push(vec![
+                    *v,
+                    num_vertices + topology.edge(*v, next),
+                    face_vertex_offset + face_idx,
+                    num_vertices + topology.edge(prev, *v),
+                ]);
+            }
+        }
+
+        let sharpness = self.split_sharpness(&topology, num_vertices);
+        let positions = vertex_points
+            .into_iter()
+            .chain(edge_points)
+            .chain(face_points)
+            .collect();
+        return ControlMesh {
+            faces,
+            positions,
+            sharpness,
+        };
+    }
+
+    // vertex points keep the indices of their vertices, edge points follow them
+    fn subdivide_loop(&self) -> ControlMesh {
+        let topology = self.topology();
+        let num_vertices = self.positions.len();
+
+        let edge_points: Vec<vec3::Vec3> = topology
+            .edges
+            .iter()
+            .map(|edge| {
+                // 3/8 of both ends and 1/8 of the vertices opposite to the edge
+                let mut smooth_point =
+                    0.375 * (self.positions[edge.vertices.0] + self.positions[edge.vertices.1]);
+                for face_idx in edge.faces.iter() {
+                    let face = &self.faces[*face_idx];
+                    let opposite = face
+                        .iter()
+                        .find(|v| **v != edge.vertices.0 && **v != edge.vertices.1)
+                        .unwrap();
+                    smooth_point += 0.125 * self.positions[*opposite];
+                }
+                return self.edge_point(edge, smooth_point);
+            })
+            .collect();
+
+        let vertex_points: Vec<vec3::Vec3> = (0..num_vertices)
+            .map(|vertex| {
+                let valence = topology.vertex_edges[vertex].len();
+                if valence == 0 {
+                    return self.positions[vertex];
+                }
+
+                let beta = loop_beta(valence);
+                let mut smooth_point = (1.0 - valence as f32 * beta) * self.positions[vertex];
+                for edge_idx in topology.vertex_edges[vertex].iter() {
+                    let neighbor = topology.edges[*edge_idx].other_vertex(vertex);
+                    smooth_point += beta * self.positions[neighbor];
+                }
+                return self.vertex_point(&topology, vertex, smooth_point);
+            })
+            .collect();
+
+        let mut faces = Vec::new();
+        for face in self.faces.iter() {
+            let (a, b, c) = (face[0], face[1], face[2]);
+            let ab = num_vertices + topology.edge(a, b);
+            let bc = num_vertices + topology.edge(b, c);
+            let ca = num_vertices + topology.edge(c, a);
+            faces.push(vec![a, ab, ca]);
+            faces.push(vec![b, bc, ab]);
+            faces.push(vec![c, ca, bc]);
+            faces.push(vec![ab, bc, ca]);
+        }
+
+        let sharpness = self.split_sharpness(&topology, num_vertices);
+        let positions = vertex_points.into_iter().chain(edge_points).collect();
+        return ControlMesh {
+            faces,
+            positions,
+            sharpness,
+        };
+    }
+
+    // faces around an interior vertex ordered by their shared edges, with the index of the vertex in
+    // each face. Returns None for vertices on a boundary or a non manifold part of the mesh
+    fn ordered_ring(&self, topology: &Topology, vertex: usize) -> Option<Vec<(usize, usize)>> {
+        let vertex_faces = &topology.vertex_faces[vertex];
+        if vertex_faces.is_empty() || vertex_faces.len() != topology.vertex_edges[vertex].len() {
+            return None;
+        }
+
+        let corner = |face_idx: usize| {
+            let face = &self.faces[face_idx];
+            return (face_idx, face.iter().position(|v| *v == vertex).unwrap());
+        };
+        let next = |face_idx: usize, i: usize| {
+            let face = &self.faces[face_idx];
+            return face[(i + 1) % face.len()];
+        };
+        let prev = |face_idx: usize, i: usize| {
+            let face = &self.faces[face_idx];
+            return face[(i + face.len() - 1) % face.len()];
+        };
+
+        // the next face shares the outgoing edge of the current one as its incoming edge
+        let mut ring = vec![corner(vertex_faces[0])];
+        while ring.len() < vertex_faces.len() {
+            let (face_idx, i) = *ring.last().unwrap();
+            let out_vertex = next(face_idx, i);
+            let following = vertex_faces
+                .iter()
+                .map(|face_idx| corner(*face_idx))
+                .find(|(face_idx, i)| prev(*face_idx, *i) == out_vertex)?;
+            ring.push(following);
+        }
+
+        let (face_idx, i) = *ring.last().unwrap();
+        if next(face_idx, i) != prev(ring[0].0, ring[0].1) {
+            return None;
+        }
+
+        return Some(ring);
+    }
+
+    // normal of the faces around the vertex weighted by their area, for vertices without a limit normal
+    fn average_normal(&self, topology: &Topology, vertex: usize) -> vec3::Vec3 {
+        let mut normal = vec3::Vec3::from(0.0);
+        for face_idx in topology.vertex_faces[vertex].iter() {
+            let face = &self.faces[*face_idx];
+            let center = self.face_center(face);
+            for (i, v) in face.iter().enumerate() {
+                let next = face[(i + 1) % face.len()];
+                normal += (self.positions[*v] - center).cross(&(self.positions[next] - center));
+            }
+        }
+
+        return normal.normalize().unwrap_or(vec3::Vec3::new(0.0, 0.0, 1.0));
+    }
+
+    // positions on the limit surface and normals from its tangents. Smooth vertices use the limit
+    // masks of the scheme, crease vertices the ones of the cubic b-spline along the crease. Crease
+    // and corner vertices have no single tangent plane and average the normals of their faces
+    fn limit(&self, scheme: SubdivisionScheme) -> (Vec<vec3::Vec3>, Vec<vec3::Vec3>) {
+        let topology = self.topology();
+        let face_size = match scheme {
+            SubdivisionScheme::CatmullClark => 4,
+            SubdivisionScheme::Loop => 3,
+        };
+
+        let mut positions = Vec::with_capacity(self.positions.len());
+        let mut normals = Vec::with_capacity(self.positions.len());
+        for vertex in 0..self.positions.len() {
+            let position = self.positions[vertex];
+            let sharp_edges = topology.sharp_edges(vertex);
+            // smooth vertices with a closed ring of faces of the size the scheme makes
+            let ring = self.ordered_ring(&topology, vertex).filter(|ring| {
+                sharp_edges.is_empty()
+                    && ring
+                        .iter()
+                        .all(|(face_idx, _)| self.faces[*face_idx].len() == face_size)
+            });
+            if let Some(ring) = ring {
+                let (limit_position, limit_normal) = match scheme {
+                    SubdivisionScheme::CatmullClark => self.catmull_clark_limit(position, &ring),
+                    SubdivisionScheme::Loop => self.loop_limit(position, &ring),
+                };
+                positions.push(limit_position);
+                normals.push(
+                    limit_normal
+                        .normalize()
+                        .unwrap_or_else(|| self.average_normal(&topology, vertex)),
+                );
+                continue;
+            }
+
+            if sharp_edges.len() == 2 && topology.vertex_edges[vertex].len() > 2 {
+                positions.push(
+                    (self.positions[sharp_edges[0].other_vertex(vertex)]
+                        + 4.0 * position
+                        + self.positions[sharp_edges[1].other_vertex(vertex)])
+                        / 6.0,
+                );
+            } else {
+                positions.push(position);
+            }
+            normals.push(self.average_normal(&topology, vertex));
+        }
+
+        return (positions, normals);
+    }
+
+    // the ring runs clockwise seen from the outside, which makes the second tangent cross the first
+    // one outwards
+    fn catmull_clark_limit(
+        &self,
+        position: vec3::Vec3,
+        ring: &[(usize, usize)],
+    ) -> (vec3::Vec3, vec3::Vec3) {
+        let n = ring.len();
+        let edge_vertices: Vec<vec3::Vec3> = ring
+            .iter()
+            .map(|(face_idx, i)| self.positions[self.faces[*face_idx][(i + 1) % 4]])
+            .collect();
+        let diagonal_vertices: Vec<vec3::Vec3> = ring
+            .iter()
+            .map(|(face_idx, i)| self.positions[self.faces[*face_idx][(i + 2) % 4]])
+            .collect();
+
+        let nf = n as f32;
+        let mut limit_position = nf * nf * position;
+        for i in 0..n {
+            limit_position += 4.0 * edge_vertices[i] + diagonal_vertices[i];
+        }
+        limit_position /= nf * (nf + 5.0);
+
+        // the diagonal vertex between edge vertices i and i + 1 is the one of the face i + 1
+        let angle = 2.0 * math::PI_F32 / nf;
+        let a = 1.0
+            + f32::cos(angle)
+            + f32::cos(angle / 2.0) * f32::sqrt(2.0 * (9.0 + f32::cos(angle)));
+        let mut tangent_0 = vec3::Vec3::from(0.0);
+        let mut tangent_1 = vec3::Vec3::from(0.0);
+        for i in 0..n {
+            let (cos_0, sin_0) = (f32::cos(angle * i as f32), f32::sin(angle * i as f32));
+            let (cos_1, sin_1) = (
+                f32::cos(angle * (i + 1) as f32),
+                f32::sin(angle * (i + 1) as f32),
+            );
+            let diagonal = diagonal_vertices[(i + 1) % n];
+            tangent_0 += a * cos_0 * edge_vertices[i] + (cos_0 + cos_1) * diagonal;
+            tangent_1 += a * sin_0 * edge_vertices[i] + (sin_0 + sin_1) * diagonal;
+        }
+
+        return (limit_position, tangent_1.cross(&tangent_0));
+    }
+
+    fn loop_limit(
+        &self,
+        position: vec3::Vec3,
+        ring: &[(usize, usize)],
+    ) -> (vec3::Vec3, vec3::Vec3) {
+        let n = ring.len();
+        let neighbors: Vec<vec3::Vec3> = ring
+            .iter()
+            .map(|(face_idx, i)| self.positions[self.faces[*face_idx][(i + 1) % 3]])
+            .collect();
+
+        let chi = 1.0 / (3.0 / (8.0 * loop_beta(n)) + n as f32);
+        let mut limit_position = (1.0 - n as f32 * chi) * position;
+        let angle = 2.0 * math::PI_F32 / n as f32;
+        let mut tangent_0 = vec3::Vec3::from(0.0);
+        let mut tangent_1 = vec3::Vec3::from(0.0);
+        for (i, neighbor) in neighbors.iter().enumerate() {
+            limit_position += chi * *neighbor;
+            tangent_0 += f32::cos(angle * i as f32) * *neighbor;
+            tangent_1 += f32::sin(angle * i as f32) * *neighbor;
+        }
+
+        return (limit_position, tangent_1.cross(&tangent_0));
+    }
+}
+
+fn loop_beta(valence: usize) -> f32 {
+    if valence == 3 {
+        return 3.0 / 16.0;
+    }
+
+    let n = valence as f32;
+    let c = 0.375 + 0.25 * f32::cos(2.0 * math::PI_F32 / n);
+    return (0.625 - c * c) / n;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scene::ray;
+    use crate::scene::shape::IntersectableShape;
+
+    fn create_cube(scheme: SubdivisionScheme) -> SubdivisionSurface {
+        let positions = (0..8)
+            .map(|i| {
+                vec3::Vec3::new(
+                    if i & 1 == 0 { -1.0 } else { 1.0 },
+                    if i & 2 == 0 { -1.0 } else { 1.0 },
+                    if i & 4 == 0 { -1.0 } else { 1.0 },
+                )
+            })
+            .collect();
+        let quads = vec![
+            vec![0, 2, 3, 1],
+            vec![4, 5, 7, 6],
+            vec![0, 1, 5, 4],
+            vec![2, 6, 7, 3],
+            vec![0, 4, 6, 2],
+            vec![1, 3, 7, 5],
+        ];
+        let faces = match scheme {
+            SubdivisionScheme::CatmullClark => quads,
+            SubdivisionScheme::Loop => quads
+                .iter()
+                .flat_map(|quad| {
+                    vec![
+                        vec![quad[0], quad[1], quad[2]],
+                        vec![quad[0], quad[2], quad[3]],
+                    ]
+                })
+                .collect(),
+        };
+        return SubdivisionSurface::new(mat4::Mat4::new(), scheme, faces, positions);
+    }
+
+    fn create_octahedron() -> SubdivisionSurface {
+        let positions = vec![
+            vec3::Vec3::new(1.0, 0.0, 0.0),
+            vec3::Vec3::new(-1.0, 0.0, 0.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+            vec3::Vec3::new(0.0, -1.0, 0.0),
+            vec3::Vec3::new(0.0, 0.0, 1.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        ];
+        let faces = vec![
+            vec![0, 2, 4],
+            vec![2, 1, 4],
+            vec![1, 3, 4],
+            vec![3, 0, 4],
+            vec![2, 0, 5],
+            vec![1, 2, 5],
+            vec![3, 1, 5],
+            vec![0, 3, 5],
+        ];
+        return SubdivisionSurface::new(
+            mat4::Mat4::new(),
+            SubdivisionScheme::Loop,
+            faces,
+            positions,
+        );
+    }
+
+    fn assert_outward_normals(mesh: &triangle_mesh::TriangleMesh) {
+        let buffers = mesh.buffers();
+        for (position, normal) in buffers.positions().iter().zip(buffers.normals().iter()) {
+            assert!(position.normalize().unwrap().dot(normal) > 0.5);
+        }
+    }
+
+    #[test]
+    fn test_catmull_clark() {
+        let cube = create_cube(SubdivisionScheme::CatmullClark);
+        let mesh = cube.tessellate();
+        assert_eq!(mesh.triangles().len(), 6 * 64 * 2);
+        assert_outward_normals(&mesh);
+
+        // the center of a face is a regular vertex, its limit normal follows from the symmetry
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(10.0, 0.0, 0.0),
+            vec3::Vec3::new(-1.0, 0.0, 0.0),
+        );
+        let surface = mesh.intersect_ray(&ray).unwrap();
+        assert!(surface.ray_time() > 9.0 && surface.ray_time() < 9.5);
+        assert!(surface
+            .calc_world_normal()
+            .equal_epsilon(&vec3::Vec3::new(1.0, 0.0, 0.0), math::EPSILON_F32_4));
+    }
+
+    #[test]
+    fn test_limit_position() {
+        // limit positions of the cage vertices do not depend on the level
+        for scheme in [SubdivisionScheme::CatmullClark, SubdivisionScheme::Loop].iter() {
+            let coarse = create_cube(*scheme).with_level(1).tessellate();
+            let fine = create_cube(*scheme).with_level(4).tessellate();
+            for i in 0..8 {
+                assert!(coarse.buffers().positions()[i]
+                    .equal_epsilon(&fine.buffers().positions()[i], math::EPSILON_F32_4));
+            }
+        }
+    }
+
+    #[test]
+    fn test_loop() {
+        let octahedron = create_octahedron();
+        let mesh = octahedron.tessellate();
+        assert_eq!(mesh.triangles().len(), 8 * 64);
+        assert_outward_normals(&mesh);
+
+        // the limit of a vertex of valence 4 with its neighbors around the origin is (1 - 4 chi) p
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(10.0, 0.0, 0.0),
+            vec3::Vec3::new(-1.0, 0.0, 0.0),
+        );
+        let surface = mesh.intersect_ray(&ray).unwrap();
+        assert!(math::equal_epsilon_f32(
+            surface.ray_time(),
+            10.0 - 96.0 / 220.0,
+            math::EPSILON_F32_4
+        ));
+        assert!(surface
+            .calc_world_normal()
+            .equal_epsilon(&vec3::Vec3::new(1.0, 0.0, 0.0), math::EPSILON_F32_4));
+    }
+
+    #[test]
+    fn test_creases_and_boundaries() {
+        // a cube with all of its edges creased stays a cube
+        let mut cube = create_cube(SubdivisionScheme::CatmullClark);
+        for (v0, v1) in [
+            (0, 1),
+            (2, 3),
+            (4, 5),
+            (6, 7),
+            (0, 2),
+            (1, 3),
+            (4, 6),
+            (5, 7),
+            (0, 4),
+            (1, 5),
+            (2, 6),
+            (3, 7),
+        ]
+        .iter()
+        {
+            cube = cube.with_crease(*v0, *v1, f32::INFINITY);
+        }
+        let mesh = cube.with_level(2).tessellate();
+        for position in mesh.buffers().positions().iter() {
+            let max_coordinate = f32::max(
+                f32::max(position.x.abs(), position.y.abs()),
+                position.z.abs(),
+            );
+            assert!(math::equal_epsilon_f32(
+                max_coordinate,
+                1.0,
+                math::EPSILON_F32_4
+            ));
+        }
+
+        // an open grid of quads stays flat and keeps its corners
+        let positions = (0..9)
+            .map(|i| vec3::Vec3::new((i % 3) as f32, (i / 3) as f32, 0.0))
+            .collect();
+        let faces = vec![
+            vec![0, 1, 4, 3],
+            vec![1, 2, 5, 4],
+            vec![3, 4, 7, 6],
+            vec![4, 5, 8, 7],
+        ];
+        let grid = SubdivisionSurface::new(
+            mat4::Mat4::new(),
+            SubdivisionScheme::CatmullClark,
+            faces,
+            positions,
+        );
+        let mesh = grid.tessellate();
+        let buffers = mesh.buffers();
+        assert!(buffers.positions()[0].equal_epsilon(&vec3::Vec3::from(0.0), math::EPSILON_F32_5));
+        assert!(buffers.positions()[8]
+            .equal_epsilon(&vec3::Vec3::new(2.0, 2.0, 0.0), math::EPSILON_F32_5));
+        for (position, normal) in buffers.positions().iter().zip(buffers.normals().iter()) {
+            assert!(math::equal_epsilon_f32(
+                position.z,
+                0.0,
+                math::EPSILON_F32_5
+            ));
+            assert!(normal.equal_epsilon(&vec3::Vec3::new(0.0, 0.0, 1.0), math::EPSILON_F32_4));
+        }
+    }
+
+    #[test]
+    fn test_adaptive_level() {
+        let cube = create_cube(SubdivisionScheme::CatmullClark).with_max_edge_length(0.3);
+        assert_eq!(cube.level(), 3);
+        assert_eq!(cube.with_level(1).level(), 1);
+    }
+}
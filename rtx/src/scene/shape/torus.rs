@@ -0,0 +1,300 @@
+use crate::core::mat4;
+use crate::core::math;
+use crate::core::polynomial;
+use crate::core::vec2;
+use crate::core::vec3;
+use crate::core::vec4;
+use crate::scene::aabb;
+use crate::scene::ray;
+use crate::scene::shape;
+
+// torus around the z axis, a tube of minor_radius swept along a circle of major_radius in the xy plane
+pub struct Torus {
+    object_to_world: mat4::Mat4,
+    world_to_object: mat4::Mat4,
+    normal_transform: mat4::Mat4,
+    major_radius: f32,
+    minor_radius: f32,
+}
+
+impl Torus {
+    pub fn new(object_to_world: mat4::Mat4, major_radius: f32, minor_radius: f32) -> Torus {
+        assert!(0.0 < minor_radius && minor_radius < major_radius);
+        let world_to_object = mat4::Mat4::inverse(&object_to_world).unwrap();
+        let normal_transform =
+            mat4::Mat4::inverse(&mat4::Mat4::transpose(&object_to_world)).unwrap();
+
+        return Torus {
+            object_to_world,
+            world_to_object,
+            normal_transform,
+            major_radius,
+            minor_radius,
+        };
+    }
+
+    pub fn area(&self) -> f32 {
+        return 4.0 * math::PI_F32 * math::PI_F32 * self.major_radius * self.minor_radius;
+    }
+
+    // nearest hit, returns the local ray time and position
+    fn intersect_local(&self, local_ray: &ray::Ray) -> Option<(f32, vec3::Vec3)> {
+        // the quartic is solved from the point of the ray closest to the center, which keeps its
+        // coefficients small for rays that start far away
+        let d = local_ray.direction();
+        let shift = -local_ray.origin().dot(d);
+        let o = local_ray.calc_position(shift);
+
+        let (ox, oy, oz) = (o.x as f64, o.y as f64, o.z as f64);
+        let (dx, dy, dz) = (d.x as f64, d.y as f64, d.z as f64);
+        let major_radius_sq = (self.major_radius as f64) * (self.major_radius as f64);
+        let minor_radius_sq = (self.minor_radius as f64) * (self.minor_radius as f64);
+
+        let direction_sq = dx * dx + dy * dy + dz * dz;
+        let origin_dot_direction = ox * dx + oy * dy + oz * dz;
+        let k = ox * ox + oy * oy + oz * oz - major_radius_sq - minor_radius_sq;
+        let roots = polynomial::solve_quartic(
+            direction_sq * direction_sq,
+            4.0 * direction_sq * origin_dot_direction,
+            2.0 * direction_sq * k
+                + 4.0 * origin_dot_direction * origin_dot_direction
+                + 4.0 * major_radius_sq * dz * dz,
+            4.0 * k * origin_dot_direction + 8.0 * major_radius_sq * oz * dz,
+            k * k - 4.0 * major_radius_sq * (minor_radius_sq - oz * oz),
+        );
+
+        let ray_time = roots
+            .into_iter()
+            .map(|root| root as f32 + shift)
+            .find(|ray_time| *ray_time > 0.0)?;
+        return Some((ray_time, local_ray.calc_position(ray_time)));
+    }
+
+    // angle around the z axis and angle around the tube
+    fn angles(&self, local_position: &vec3::Vec3) -> (f32, f32) {
+        let phi = f32::atan2(local_position.y, local_position.x);
+        let distance_to_axis =
+            f32::sqrt(local_position.x * local_position.x + local_position.y * local_position.y);
+        let theta = f32::atan2(local_position.z, distance_to_axis - self.major_radius);
+        return (phi, theta);
+    }
+
+    fn partial_derivatives(&self, phi: f32, theta: f32) -> (vec3::Vec3, vec3::Vec3) {
+        let two_pi = 2.0 * math::PI_F32;
+        let ring_radius = self.major_radius + self.minor_radius * f32::cos(theta);
+        let local_dpdu = two_pi
+            * vec3::Vec3::new(
+                -ring_radius * f32::sin(phi),
+                ring_radius * f32::cos(phi),
+                0.0,
+            );
+        let local_dpdv = two_pi
+            * self.minor_radius
+            * vec3::Vec3::new(
+                -f32::sin(theta) * f32::cos(phi),
+                -f32::sin(theta) * f32::sin(phi),
+                f32::cos(theta),
+            );
+        return (local_dpdu, local_dpdv);
+    }
+
+    fn outward_normal(&self, phi: f32, theta: f32) -> vec3::Vec3 {
+        return vec3::Vec3::new(
+            f32::cos(theta) * f32::cos(phi),
+            f32::cos(theta) * f32::sin(phi),
+            f32::sin(theta),
+        );
+    }
+}
+
+impl shape::IntersectableShape for Torus {
+    fn is_intersect(&self, ray: &ray::Ray, max_distance: f32) -> bool {
+        let local_ray = ray::Ray::transform(ray, &self.world_to_object);
+        return match self.intersect_local(&local_ray) {
+            Some((ray_time, _)) => ray_time < max_distance,
+            None => false,
+        };
+    }
+
+    fn intersect_ray(&self, ray: &ray::Ray) -> Option<shape::IntersectableShapeSurface> {
+        let local_ray = ray::Ray::transform(ray, &self.world_to_object);
+        let (ray_time, local_position) = self.intersect_local(&local_ray)?;
+
+        let (phi, theta) = self.angles(&local_position);
+        let (local_dpdu, local_dpdv) = self.partial_derivatives(phi, theta);
+        let mut local_normal = self.outward_normal(phi, theta);
+        if local_ray.direction().dot(&local_normal) > 0.0 {
+            local_normal = -local_normal;
+        }
+
+        return Some(shape::IntersectableShapeSurface::new(
+            ray_time,
+            local_position,
+            local_normal,
+            local_dpdu,
+            local_dpdv,
+            self.object_to_world,
+            self.normal_transform,
+        ));
+    }
+
+    fn aabb(&self) -> aabb::AABB {
+        let radius = self.major_radius + self.minor_radius;
+        return aabb::AABB::new(
+            vec3::Vec3::new(-radius, -radius, -self.minor_radius),
+            vec3::Vec3::new(radius, radius, self.minor_radius),
+        )
+        .transform(&self.object_to_world);
+    }
+}
+
+impl shape::SamplableShape for Torus {
+    // samples both angles uniformly, the pdf accounts for the outer side of the tube having more area
+    fn sample_surface(
+        &self,
+        sample: &vec2::Vec2,
+        surface_point_ref: &vec3::Vec3,
+        _surface_normal_ref: &vec3::Vec3,
+    ) -> Option<shape::SampleShapeSurface> {
+        let local_surface_point_ref =
+            (self.world_to_object * vec4::Vec4::from_vec3(surface_point_ref, 1.0)).to_vec3();
+
+        let phi = 2.0 * math::PI_F32 * sample.x;
+        let theta = 2.0 * math::PI_F32 * sample.y;
+        let ring_radius = self.major_radius + self.minor_radius * f32::cos(theta);
+        let local_sample_point = vec3::Vec3::new(
+            ring_radius * f32::cos(phi),
+            ring_radius * f32::sin(phi),
+            self.minor_radius * f32::sin(theta),
+        );
+        let local_normal = self.outward_normal(phi, theta);
+
+        let direction = local_surface_point_ref - local_sample_point;
+        let normalize_direction = direction.normalize()?;
+        let cos_theta = f32::abs(local_normal.dot(&normalize_direction));
+        if cos_theta == 0.0 {
+            return None;
+        }
+
+        let area_pdf = 1.0 / (4.0 * math::PI_F32 * math::PI_F32 * self.minor_radius * ring_radius);
+        let world_surface_point =
+            (self.object_to_world * vec4::Vec4::from_vec3(&local_sample_point, 1.0)).to_vec3();
+        let pdf = area_pdf * direction.length_sq() / cos_theta;
+        return Some(shape::SampleShapeSurface::new(pdf, world_surface_point));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scene::shape::IntersectableShape;
+    use crate::scene::shape::SamplableShape;
+
+    #[test]
+    fn test_intersect_ray() {
+        let torus = Torus::new(mat4::Mat4::new(), 2.0, 0.5);
+
+        // along the x axis the ray enters the tube at 2.5
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(10.0, 0.0, 0.0),
+            vec3::Vec3::new(-1.0, 0.0, 0.0),
+        );
+        let surface = torus.intersect_ray(&ray).unwrap();
+        assert!(math::equal_epsilon_f32(
+            surface.ray_time(),
+            7.5,
+            math::EPSILON_F32_4
+        ));
+        assert!(surface
+            .calc_world_normal()
+            .equal_epsilon(&vec3::Vec3::new(1.0, 0.0, 0.0), math::EPSILON_F32_4));
+
+        // from inside the hole the ray hits the inner side of the tube
+        let ray = ray::Ray::new(vec3::Vec3::from(0.0), vec3::Vec3::new(0.0, 1.0, 0.0));
+        let surface = torus.intersect_ray(&ray).unwrap();
+        assert!(math::equal_epsilon_f32(
+            surface.ray_time(),
+            1.5,
+            math::EPSILON_F32_4
+        ));
+
+        // through the hole along the axis
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.0, 0.0, 10.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        assert!(torus.intersect_ray(&ray).is_none());
+        assert!(!torus.is_intersect(&ray, 100.0));
+    }
+
+    #[test]
+    fn test_intersect_ray_far_away() {
+        let torus = Torus::new(mat4::Mat4::new(), 2.0, 0.5);
+
+        // a grazing ray from far away, the hit has to stay on the surface
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(1000.0, 0.0, 0.49),
+            vec3::Vec3::new(-1.0, 0.0, 0.0),
+        );
+        let surface = torus.intersect_ray(&ray).unwrap();
+        let position = surface.calc_world_position();
+        let distance_to_ring = vec3::Vec3::new(
+            f32::sqrt(position.x * position.x + position.y * position.y) - 2.0,
+            0.0,
+            position.z,
+        )
+        .length();
+        assert!(math::equal_epsilon_f32(
+            distance_to_ring,
+            0.5,
+            math::EPSILON_F32_3
+        ));
+        assert!(position.x > 0.0);
+    }
+
+    #[test]
+    fn test_partial_derivatives() {
+        let torus = Torus::new(mat4::Mat4::new(), 2.0, 0.5);
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(5.0, 0.0, 3.0),
+            vec3::Vec3::new(-2.7, 0.2, -2.7),
+        );
+        let surface = torus.intersect_ray(&ray).unwrap();
+        let normal = surface.calc_world_normal();
+        assert!(math::equal_epsilon_f32(
+            normal.dot(&surface.calc_world_dpdu()),
+            0.0,
+            math::EPSILON_F32_4
+        ));
+        assert!(math::equal_epsilon_f32(
+            normal.dot(&surface.calc_world_dpdv()),
+            0.0,
+            math::EPSILON_F32_4
+        ));
+    }
+
+    #[test]
+    fn test_sample_surface() {
+        let torus = Torus::new(mat4::Mat4::new(), 2.0, 0.5);
+        let point_ref = vec3::Vec3::new(0.0, 0.0, 10.0);
+        for i in 0..16 {
+            let sample = vec2::Vec2::new((i as f32 * 0.37) % 1.0, (i as f32 + 0.5) / 16.0);
+            let sample_surface = torus
+                .sample_surface(&sample, &point_ref, &vec3::Vec3::new(0.0, 0.0, -1.0))
+                .unwrap();
+            let point = sample_surface.surface_point;
+            let distance_to_ring = vec3::Vec3::new(
+                f32::sqrt(point.x * point.x + point.y * point.y) - 2.0,
+                0.0,
+                point.z,
+            )
+            .length();
+            assert!(math::equal_epsilon_f32(
+                distance_to_ring,
+                0.5,
+                math::EPSILON_F32_4
+            ));
+            assert!(sample_surface.pdf > 0.0);
+        }
+    }
+}
@@ -0,0 +1,641 @@
+use crate::core::mat4;
+use crate::core::sampling;
+use crate::core::vec2;
+use crate::core::vec3;
+use crate::core::vec4;
+use crate::scene::aabb;
+use crate::scene::accelerator;
+use crate::scene::accelerator::bvh;
+use crate::scene::accelerator::Accelerator;
+use crate::scene::ray;
+use crate::scene::shape;
+use std::rc;
+
+// vertex buffers shared by every triangle of a mesh. Positions and normals are stored in world space,
+// so the surfaces of the triangles use identity transforms
+#[derive(Debug)]
+pub struct TriangleMeshBuffers {
+    indices: Vec<usize>,
+    positions: Vec<vec3::Vec3>,
+    normals: Vec<vec3::Vec3>,
+    uvs: Vec<vec2::Vec2>,
+    colors: Vec<vec3::Vec3>,
+}
+
+impl TriangleMeshBuffers {
+    pub fn indices(&self) -> &Vec<usize> {
+        return &self.indices;
+    }
+
+    pub fn positions(&self) -> &Vec<vec3::Vec3> {
+        return &self.positions;
+    }
+
+    pub fn normals(&self) -> &Vec<vec3::Vec3> {
+        return &self.normals;
+    }
+
+    pub fn uvs(&self) -> &Vec<vec2::Vec2> {
+        return &self.uvs;
+    }
+
+    pub fn colors(&self) -> &Vec<vec3::Vec3> {
+        return &self.colors;
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Triangle {
+    buffers: rc::Rc<TriangleMeshBuffers>,
+    first_index: usize,
+}
+
+impl Triangle {
+    pub fn vertex_indices(&self) -> (usize, usize, usize) {
+        let indices = &self.buffers.indices;
+        return (
+            indices[self.first_index],
+            indices[self.first_index + 1],
+            indices[self.first_index + 2],
+        );
+    }
+
+    pub fn positions(&self) -> (vec3::Vec3, vec3::Vec3, vec3::Vec3) {
+        let (i0, i1, i2) = self.vertex_indices();
+        let positions = &self.buffers.positions;
+        return (positions[i0], positions[i1], positions[i2]);
+    }
+
+    pub fn area(&self) -> f32 {
+        let (p0, p1, p2) = self.positions();
+        return 0.5 * (p1 - p0).cross(&(p2 - p0)).length();
+    }
+
+    // triangles without uv coordinates use the parameterization (0, 0), (1, 0), (1, 1)
+    fn uvs(&self) -> (vec2::Vec2, vec2::Vec2, vec2::Vec2) {
+        if self.buffers.uvs.is_empty() {
+            return (
+                vec2::Vec2::new(0.0, 0.0),
+                vec2::Vec2::new(1.0, 0.0),
+                vec2::Vec2::new(1.0, 1.0),
+            );
+        }
+
+        let (i0, i1, i2) = self.vertex_indices();
+        let uvs = &self.buffers.uvs;
+        return (uvs[i0], uvs[i1], uvs[i2]);
+    }
+
+    // geometric normal follows the counter clockwise winding, or the shading normals when they exist
+    fn geometric_normal(&self, barycentric: &vec3::Vec3) -> Option<vec3::Vec3> {
+        let (p0, p1, p2) = self.positions();
+        let normal = (p1 - p0).cross(&(p2 - p0)).normalize()?;
+        return match self.shading_normal(barycentric) {
+            Some(shading_normal) if shading_normal.dot(&normal) < 0.0 => Some(-normal),
+            _ => Some(normal),
+        };
+    }
+
+    fn shading_normal(&self, barycentric: &vec3::Vec3) -> Option<vec3::Vec3> {
+        if self.buffers.normals.is_empty() {
+            return None;
+        }
+
+        let (i0, i1, i2) = self.vertex_indices();
+        let normals = &self.buffers.normals;
+        let normal =
+            barycentric.x * normals[i0] + barycentric.y * normals[i1] + barycentric.z * normals[i2];
+        return normal.normalize();
+    }
+
+    // watertight ray triangle intersection, returns the ray distance and the barycentric coordinates
+    fn intersect(&self, ray: &ray::Ray, max_distance: f32) -> Option<(f32, vec3::Vec3)> {
+        let (p0, p1, p2) = self.positions();
+
+        // translate the vertices to the ray origin and permute the largest ray direction axis to z
+        let direction = ray.direction();
+        let abs_direction = direction.abs();
+        let kz = if abs_direction.x > abs_direction.y {
+            if abs_direction.x > abs_direction.z {
+                0
+            } else {
+                2
+            }
+        } else if abs_direction.y > abs_direction.z {
+            1
+        } else {
+            2
+        };
+        let kx = (kz + 1) % 3;
+        let ky = (kx + 1) % 3;
+        let permute = |v: vec3::Vec3| vec3::Vec3::new(v[kx], v[ky], v[kz]);
+        let d = permute(*direction);
+        let mut p0t = permute(p0 - ray.origin());
+        let mut p1t = permute(p1 - ray.origin());
+        let mut p2t = permute(p2 - ray.origin());
+
+        // shear the ray direction onto the z axis
+        let shear_x = -d.x / d.z;
+        let shear_y = -d.y / d.z;
+        let shear_z = 1.0 / d.z;
+        p0t.x += shear_x * p0t.z;
+        p0t.y += shear_y * p0t.z;
+        p1t.x += shear_x * p1t.z;
+        p1t.y += shear_y * p1t.z;
+        p2t.x += shear_x * p2t.z;
+        p2t.y += shear_y * p2t.z;
+
+        // edge functions, recomputed in double precision when the ray hits an edge exactly
+        let mut e0 = p1t.x * p2t.y - p1t.y * p2t.x;
+        let mut e1 = p2t.x * p0t.y - p2t.y * p0t.x;
+        let mut e2 = p0t.x * p1t.y - p0t.y * p1t.x;
+        if e0 == 0.0 || e1 == 0.0 || e2 == 0.0 {
+            e0 = ((p1t.x as f64) * (p2t.y as f64) - (p1t.y as f64) * (p2t.x as f64)) as f32;
+            e1 = ((p2t.x as f64) * (p0t.y as f64) - (p2t.y as f64) * (p0t.x as f64)) as f32;
+            e2 = ((p0t.x as f64) * (p1t.y as f64) - (p0t.y as f64) * (p1t.x as f64)) as f32;
+        }
+
+        if (e0 < 0.0 || e1 < 0.0 || e2 < 0.0) && (e0 > 0.0 || e1 > 0.0 || e2 > 0.0) {
+            return None;
+        }
+
+        let det = e0 + e1 + e2;
+        if det == 0.0 {
+            return None;
+        }
+
+        // scaled hit distance, compared against the range before the division
+        p0t.z *= shear_z;
+        p1t.z *= shear_z;
+        p2t.z *= shear_z;
+        let scaled_distance = e0 * p0t.z + e1 * p1t.z + e2 * p2t.z;
+        if (det < 0.0 && (scaled_distance >= 0.0 || scaled_distance < max_distance * det))
+            || (det > 0.0 && (scaled_distance <= 0.0 || scaled_distance > max_distance * det))
+        {
+            return None;
+        }
+
+        let inv_det = 1.0 / det;
+        let distance = scaled_distance * inv_det;
+        if distance <= 0.0 || distance >= max_distance {
+            return None;
+        }
+
+        return Some((
+            distance,
+            vec3::Vec3::new(e0 * inv_det, e1 * inv_det, e2 * inv_det),
+        ));
+    }
+
+    fn create_surface(
+        &self,
+        ray_time: f32,
+        barycentric: &vec3::Vec3,
+    ) -> Option<shape::IntersectableShapeSurface> {
+        let (p0, p1, p2) = self.positions();
+        let position = barycentric.x * p0 + barycentric.y * p1 + barycentric.z * p2;
+        let geometric_normal = self.geometric_normal(barycentric)?;
+
+        // partial derivatives from the uv parameterization
+        let (uv0, uv1, uv2) = self.uvs();
+        let duv02 = uv0 - uv2;
+        let duv12 = uv1 - uv2;
+        let dp02 = p0 - p2;
+        let dp12 = p1 - p2;
+        let uv_det = duv02.x * duv12.y - duv02.y * duv12.x;
+        let mut dpdu = vec3::Vec3::from(0.0);
+        let mut dpdv = vec3::Vec3::from(0.0);
+        let mut degenerate_uv = f32::abs(uv_det) < 1e-8;
+        if !degenerate_uv {
+            let inv_uv_det = 1.0 / uv_det;
+            dpdu = (duv12.y * dp02 - duv02.y * dp12) * inv_uv_det;
+            dpdv = (duv02.x * dp12 - duv12.x * dp02) * inv_uv_det;
+            degenerate_uv = dpdu.cross(&dpdv).length_sq() == 0.0;
+        }
+
+        if degenerate_uv {
+            vec3::Vec3::coordinate_system(&geometric_normal, &mut dpdu, &mut dpdv);
+        }
+
+        // materials build their shading frame from the normal and dpdu, so dpdu is kept orthogonal
+        // to the interpolated shading normal
+        let normal = self.shading_normal(barycentric).unwrap_or(geometric_normal);
+        let shading_dpdu = match (dpdu - normal * normal.dot(&dpdu)).normalize() {
+            Some(shading_dpdu) => shading_dpdu,
+            None => {
+                let mut shading_dpdu = vec3::Vec3::from(0.0);
+                let mut shading_dpdv = vec3::Vec3::from(0.0);
+                vec3::Vec3::coordinate_system(&normal, &mut shading_dpdu, &mut shading_dpdv);
+                shading_dpdu
+            }
+        };
+        let shading_dpdv = normal.cross(&shading_dpdu);
+
+        let surface = shape::IntersectableShapeSurface::new(
+            ray_time,
+            position,
+            normal,
+            shading_dpdu,
+            shading_dpdv,
+            mat4::Mat4::new(),
+            mat4::Mat4::new(),
+        );
+        return match self.vertex_color(barycentric) {
+            Some(vertex_color) => Some(surface.with_vertex_color(vertex_color)),
+            None => Some(surface),
+        };
+    }
+
+    fn vertex_color(&self, barycentric: &vec3::Vec3) -> Option<vec3::Vec3> {
+        if self.buffers.colors.is_empty() {
+            return None;
+        }
+
+        let (i0, i1, i2) = self.vertex_indices();
+        let colors = &self.buffers.colors;
+        return Some(
+            barycentric.x * colors[i0] + barycentric.y * colors[i1] + barycentric.z * colors[i2],
+        );
+    }
+
+    fn uniform_sample_surface(&self, sample: &vec2::Vec2) -> vec3::Vec3 {
+        let sqrt_sample = f32::sqrt(sample.x);
+        let b0 = 1.0 - sqrt_sample;
+        let b1 = sample.y * sqrt_sample;
+        let (p0, p1, p2) = self.positions();
+        return b0 * p0 + b1 * p1 + (1.0 - b0 - b1) * p2;
+    }
+
+    // area measure to solid angle measure at the reference point, emitting towards both sides
+    fn solid_angle_pdf(
+        &self,
+        area_pdf: f32,
+        sample_point: &vec3::Vec3,
+        surface_point_ref: &vec3::Vec3,
+    ) -> Option<f32> {
+        let (p0, p1, p2) = self.positions();
+        let normal = (p1 - p0).cross(&(p2 - p0)).normalize()?;
+        let direction = surface_point_ref - sample_point;
+        let normalize_direction = direction.normalize()?;
+        let cos_theta = f32::abs(normal.dot(&normalize_direction));
+        if cos_theta == 0.0 {
+            return None;
+        }
+
+        return Some(area_pdf * direction.length_sq() / cos_theta);
+    }
+
+    fn completely_behind_surface_tangent_plane(
+        &self,
+        surface_point_ref: &vec3::Vec3,
+        surface_normal_ref: &vec3::Vec3,
+    ) -> bool {
+        let (p0, p1, p2) = self.positions();
+        return (p0 - surface_point_ref).dot(surface_normal_ref) < 0.0
+            && (p1 - surface_point_ref).dot(surface_normal_ref) < 0.0
+            && (p2 - surface_point_ref).dot(surface_normal_ref) < 0.0;
+    }
+}
+
+impl shape::IntersectableShape for Triangle {
+    fn is_intersect(&self, ray: &ray::Ray, max_distance: f32) -> bool {
+        return self.intersect(ray, max_distance).is_some();
+    }
+
+    fn intersect_ray(&self, ray: &ray::Ray) -> Option<shape::IntersectableShapeSurface> {
+        let (distance, barycentric) = self.intersect(ray, f32::INFINITY)?;
+        return self.create_surface(distance, &barycentric);
+    }
+
+    fn aabb(&self) -> aabb::AABB {
+        let (p0, p1, p2) = self.positions();
+        let mut aabb = aabb::AABB::new(p0, p0);
+        aabb.merge(&p1);
+        aabb.merge(&p2);
+        return aabb;
+    }
+}
+
+impl shape::SamplableShape for Triangle {
+    fn sample_surface(
+        &self,
+        sample: &vec2::Vec2,
+        surface_point_ref: &vec3::Vec3,
+        surface_normal_ref: &vec3::Vec3,
+    ) -> Option<shape::SampleShapeSurface> {
+        if self.completely_behind_surface_tangent_plane(surface_point_ref, surface_normal_ref) {
+            return None;
+        }
+
+        let area = self.area();
+        if area == 0.0 {
+            return None;
+        }
+
+        let sample_point = self.uniform_sample_surface(sample);
+        let pdf = self.solid_angle_pdf(1.0 / area, &sample_point, surface_point_ref)?;
+        return Some(shape::SampleShapeSurface::new(pdf, sample_point));
+    }
+}
+
+// the mesh can be added to the world and used as an area light as a whole. Its triangles can also be
+// added one by one
+#[derive(Clone)]
+pub struct TriangleMesh {
+    buffers: rc::Rc<TriangleMeshBuffers>,
+    triangles: Vec<Triangle>,
+    // none for meshes without triangles, which have nothing to sample
+    area_distribution: Option<rc::Rc<sampling::Distribution1D>>,
+    area: f32,
+    bvh: rc::Rc<bvh::Bvh>,
+}
+
+impl TriangleMesh {
+    // normals and uvs are either empty or given per vertex, every three indices form a triangle
+    pub fn new(
+        object_to_world: mat4::Mat4,
+        indices: Vec<usize>,
+        positions: Vec<vec3::Vec3>,
+        normals: Vec<vec3::Vec3>,
+        uvs: Vec<vec2::Vec2>,
+    ) -> TriangleMesh {
+        return TriangleMesh::new_with_colors(
+            object_to_world,
+            indices,
+            positions,
+            normals,
+            uvs,
+            Vec::new(),
+        );
+    }
+
+    // colors are either empty or given per vertex, they are interpolated onto the intersected surfaces
+    pub fn new_with_colors(
+        object_to_world: mat4::Mat4,
+        indices: Vec<usize>,
+        positions: Vec<vec3::Vec3>,
+        normals: Vec<vec3::Vec3>,
+        uvs: Vec<vec2::Vec2>,
+        colors: Vec<vec3::Vec3>,
+    ) -> TriangleMesh {
+        assert!(indices.len().is_multiple_of(3));
+        assert!(indices.iter().all(|index| *index < positions.len()));
+        assert!(normals.is_empty() || normals.len() == positions.len());
+        assert!(uvs.is_empty() || uvs.len() == positions.len());
+        assert!(colors.is_empty() || colors.len() == positions.len());
+
+        let normal_transform = object_to_world.transpose().inverse().unwrap();
+        let world_positions = positions
+            .iter()
+            .map(|position| (object_to_world * vec4::Vec4::from_vec3(position, 1.0)).to_vec3())
+            .collect();
+        let world_normals = normals
+            .iter()
+            .map(|normal| {
+                let world_normal =
+                    (normal_transform * vec4::Vec4::from_vec3(normal, 0.0)).to_vec3();
+                return world_normal.normalize().unwrap_or(world_normal);
+            })
+            .collect();
+
+        let buffers = rc::Rc::new(TriangleMeshBuffers {
+            indices,
+            positions: world_positions,
+            normals: world_normals,
+            uvs,
+            colors,
+        });
+        let triangles: Vec<Triangle> = (0..buffers.indices.len() / 3)
+            .map(|i| Triangle {
+                buffers: buffers.clone(),
+                first_index: i * 3,
+            })
+            .collect();
+        let areas: Vec<f32> = triangles.iter().map(|triangle| triangle.area()).collect();
+        let area = areas.iter().sum();
+        let area_distribution = if areas.is_empty() {
+            None
+        } else {
+            Some(rc::Rc::new(sampling::Distribution1D::new(&areas)))
+        };
+        let triangle_bounds: Vec<aabb::AABB> = triangles
+            .iter()
+            .map(shape::IntersectableShape::aabb)
+            .collect();
+        let bvh = rc::Rc::new(bvh::Bvh::new(&triangle_bounds));
+
+        return TriangleMesh {
+            buffers,
+            triangles,
+            area_distribution,
+            area,
+            bvh,
+        };
+    }
+
+    pub fn buffers(&self) -> &TriangleMeshBuffers {
+        return &self.buffers;
+    }
+
+    pub fn triangles(&self) -> &Vec<Triangle> {
+        return &self.triangles;
+    }
+
+    pub fn area(&self) -> f32 {
+        return self.area;
+    }
+}
+
+impl shape::IntersectableShape for TriangleMesh {
+    fn is_intersect(&self, ray: &ray::Ray, max_distance: f32) -> bool {
+        return self.bvh.any_hit(ray, max_distance, &mut |triangle_idx| {
+            self.triangles[triangle_idx]
+                .intersect(ray, max_distance)
+                .is_some()
+        });
+    }
+
+    fn intersect_ray(&self, ray: &ray::Ray) -> Option<shape::IntersectableShapeSurface> {
+        let (triangle_idx, distance, barycentric) =
+            accelerator::find_closest_hit(self.bvh.as_ref(), ray, |triangle_idx, max_distance| {
+                let (distance, barycentric) =
+                    self.triangles[triangle_idx].intersect(ray, max_distance)?;
+                return Some(((triangle_idx, distance, barycentric), distance));
+            })?;
+        return self.triangles[triangle_idx].create_surface(distance, &barycentric);
+    }
+
+    fn aabb(&self) -> aabb::AABB {
+        return self.bvh.aabb();
+    }
+}
+
+impl shape::SamplableShape for TriangleMesh {
+    // triangles are picked proportionally to their area, so the mesh is sampled uniformly by area
+    fn sample_surface(
+        &self,
+        sample: &vec2::Vec2,
+        surface_point_ref: &vec3::Vec3,
+        surface_normal_ref: &vec3::Vec3,
+    ) -> Option<shape::SampleShapeSurface> {
+        if self.area == 0.0 {
+            return None;
+        }
+
+        let area_distribution = self.area_distribution.as_ref()?;
+        // the offset of the sample inside the picked triangle's interval is reused to sample the triangle
+        let (value, _, triangle_index) = area_distribution.sample_continuous(sample.x);
+        let remapped_x = (value * (area_distribution.count() as f32)) - (triangle_index as f32);
+        let triangle = &self.triangles[triangle_index];
+        if triangle.completely_behind_surface_tangent_plane(surface_point_ref, surface_normal_ref) {
+            return None;
+        }
+
+        let sample_point = triangle.uniform_sample_surface(&vec2::Vec2::new(remapped_x, sample.y));
+        let pdf = triangle.solid_angle_pdf(1.0 / self.area, &sample_point, surface_point_ref)?;
+        return Some(shape::SampleShapeSurface::new(pdf, sample_point));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::math;
+    use crate::scene::shape::IntersectableShape;
+    use crate::scene::shape::SamplableShape;
+
+    fn create_quad(normals: Vec<vec3::Vec3>) -> TriangleMesh {
+        // unit quad on the xy plane facing +z, moved one unit up the z axis
+        return TriangleMesh::new(
+            mat4::Mat4::new().translate(&vec3::Vec3::new(0.0, 0.0, 1.0)),
+            vec![0, 1, 2, 0, 2, 3],
+            vec![
+                vec3::Vec3::new(-0.5, -0.5, 0.0),
+                vec3::Vec3::new(0.5, -0.5, 0.0),
+                vec3::Vec3::new(0.5, 0.5, 0.0),
+                vec3::Vec3::new(-0.5, 0.5, 0.0),
+            ],
+            normals,
+            vec![
+                vec2::Vec2::new(0.0, 0.0),
+                vec2::Vec2::new(1.0, 0.0),
+                vec2::Vec2::new(1.0, 1.0),
+                vec2::Vec2::new(0.0, 1.0),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_intersect_ray() {
+        let mesh = create_quad(Vec::new());
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.25, -0.25, 3.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+
+        let surface = mesh.intersect_ray(&ray).unwrap();
+        assert!(math::equal_epsilon_f32(
+            surface.ray_time(),
+            2.0,
+            math::EPSILON_F32_5
+        ));
+        assert!(surface
+            .calc_world_position()
+            .equal_epsilon(&vec3::Vec3::new(0.25, -0.25, 1.0), math::EPSILON_F32_5));
+        assert!(surface
+            .calc_world_normal()
+            .equal_epsilon(&vec3::Vec3::new(0.0, 0.0, 1.0), math::EPSILON_F32_5));
+        assert!(surface
+            .calc_world_dpdu()
+            .equal_epsilon(&vec3::Vec3::new(1.0, 0.0, 0.0), math::EPSILON_F32_5));
+
+        assert!(mesh.is_intersect(&ray, 2.5));
+        assert!(!mesh.is_intersect(&ray, 1.5));
+
+        let miss = ray::Ray::new(
+            vec3::Vec3::new(0.75, 0.0, 3.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        assert!(mesh.intersect_ray(&miss).is_none());
+    }
+
+    #[test]
+    fn test_intersect_ray_shared_edge() {
+        // a ray through the diagonal shared by both triangles must not slip through the mesh
+        let mesh = create_quad(Vec::new());
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.1, 0.1, 3.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+        assert!(mesh.intersect_ray(&ray).is_some());
+        assert!(mesh.triangles()[0].intersect_ray(&ray).is_some());
+        assert!(mesh.triangles()[1].intersect_ray(&ray).is_some());
+    }
+
+    #[test]
+    fn test_shading_normal() {
+        let tilted = vec3::Vec3::new(1.0, 0.0, 1.0).normalize().unwrap();
+        let straight = vec3::Vec3::new(0.0, 0.0, 1.0);
+        let mesh = create_quad(vec![straight, tilted, tilted, straight]);
+        let ray = ray::Ray::new(
+            vec3::Vec3::new(0.0, -0.25, 3.0),
+            vec3::Vec3::new(0.0, 0.0, -1.0),
+        );
+
+        let surface = mesh.intersect_ray(&ray).unwrap();
+        let normal = surface.calc_world_normal();
+        let expected = (0.5 * straight + 0.5 * tilted).normalize().unwrap();
+        assert!(normal.equal_epsilon(&expected, math::EPSILON_F32_5));
+        assert!(math::equal_epsilon_f32(
+            normal.dot(&surface.calc_world_dpdu()),
+            0.0,
+            math::EPSILON_F32_5
+        ));
+    }
+
+    #[test]
+    fn test_sample_surface() {
+        let mesh = create_quad(Vec::new());
+        assert!(math::equal_epsilon_f32(
+            mesh.area(),
+            1.0,
+            math::EPSILON_F32_5
+        ));
+
+        let surface_point_ref = vec3::Vec3::new(0.0, 0.0, 3.0);
+        let surface_normal_ref = vec3::Vec3::new(0.0, 0.0, -1.0);
+        for sample in [
+            vec2::Vec2::new(0.1, 0.3),
+            vec2::Vec2::new(0.6, 0.9),
+            vec2::Vec2::new(0.99, 0.01),
+        ]
+        .iter()
+        {
+            let sample_surface = mesh
+                .sample_surface(sample, &surface_point_ref, &surface_normal_ref)
+                .unwrap();
+            let point = sample_surface.surface_point;
+            assert!(math::equal_epsilon_f32(point.z, 1.0, math::EPSILON_F32_5));
+            assert!(point.x >= -0.5 && point.x <= 0.5);
+            assert!(point.y >= -0.5 && point.y <= 0.5);
+
+            let direction = surface_point_ref - point;
+            let cos_theta = direction.normalize().unwrap().z;
+            let expected_pdf = direction.length_sq() / cos_theta;
+            assert!(math::equal_epsilon_f32(
+                sample_surface.pdf,
+                expected_pdf,
+                math::EPSILON_F32_4
+            ));
+        }
+
+        // the quad is completely behind the reference point
+        assert!(mesh
+            .sample_surface(
+                &vec2::Vec2::new(0.5, 0.5),
+                &surface_point_ref,
+                &vec3::Vec3::new(0.0, 0.0, 1.0)
+            )
+            .is_none());
+    }
+}
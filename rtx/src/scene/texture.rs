@@ -0,0 +1,9 @@
+pub mod constant_texture;
+pub mod vertex_color_texture;
+
+use crate::core::vec3;
+use crate::scene::shape;
+
+pub trait Texture {
+    fn evaluate(&self, surface: &shape::IntersectableShapeSurface) -> vec3::Vec3;
+}
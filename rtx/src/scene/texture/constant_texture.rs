@@ -0,0 +1,19 @@
+use crate::core::vec3;
+use crate::scene::shape;
+use crate::scene::texture;
+
+pub struct ConstantTexture {
+    value: vec3::Vec3,
+}
+
+impl ConstantTexture {
+    pub fn new(value: vec3::Vec3) -> ConstantTexture {
+        return ConstantTexture { value };
+    }
+}
+
+impl texture::Texture for ConstantTexture {
+    fn evaluate(&self, _surface: &shape::IntersectableShapeSurface) -> vec3::Vec3 {
+        return self.value;
+    }
+}
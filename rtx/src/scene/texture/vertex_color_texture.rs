@@ -0,0 +1,50 @@
+use crate::core::vec3;
+use crate::scene::shape;
+use crate::scene::texture;
+
+// reads the color interpolated from the vertices of a mesh, surfaces without vertex colors use the fallback
+pub struct VertexColorTexture {
+    fallback: vec3::Vec3,
+}
+
+impl VertexColorTexture {
+    pub fn new(fallback: vec3::Vec3) -> VertexColorTexture {
+        return VertexColorTexture { fallback };
+    }
+}
+
+impl texture::Texture for VertexColorTexture {
+    fn evaluate(&self, surface: &shape::IntersectableShapeSurface) -> vec3::Vec3 {
+        return surface.vertex_color().unwrap_or(self.fallback);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::mat4;
+    use crate::core::math;
+    use crate::scene::texture::Texture;
+
+    #[test]
+    fn test_evaluate() {
+        let texture = VertexColorTexture::new(vec3::Vec3::from(0.5));
+        let surface = shape::IntersectableShapeSurface::new(
+            1.0,
+            vec3::Vec3::from(0.0),
+            vec3::Vec3::new(0.0, 0.0, 1.0),
+            vec3::Vec3::new(1.0, 0.0, 0.0),
+            vec3::Vec3::new(0.0, 1.0, 0.0),
+            mat4::Mat4::new(),
+            mat4::Mat4::new(),
+        );
+        assert!(texture
+            .evaluate(&surface)
+            .equal_epsilon(&vec3::Vec3::from(0.5), math::EPSILON_F32_6));
+
+        let surface = surface.with_vertex_color(vec3::Vec3::new(1.0, 0.0, 0.25));
+        assert!(texture
+            .evaluate(&surface)
+            .equal_epsilon(&vec3::Vec3::new(1.0, 0.0, 0.25), math::EPSILON_F32_6));
+    }
+}
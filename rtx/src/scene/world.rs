@@ -1,12 +1,20 @@
+use crate::scene::aabb;
+use crate::scene::accelerator;
 use crate::scene::light;
 use crate::scene::material;
 use crate::scene::ray;
 use crate::scene::shape;
+use std::cell;
 use std::rc;
 
 pub struct World {
     renderables: Vec<shape::RenderableShape>,
     lights: Vec<Box<dyn light::Light>>,
+    accelerator_kind: accelerator::AcceleratorKind,
+    // built on the first query after shapes were added and refit on the first query after shapes
+    // were replaced
+    accelerator: cell::RefCell<Option<Box<dyn accelerator::Accelerator>>>,
+    is_refit_needed: cell::Cell<bool>,
 }
 
 impl World {
@@ -14,16 +22,50 @@ impl World {
         return World {
             renderables: Vec::<shape::RenderableShape>::new(),
             lights: Vec::<Box<dyn light::Light>>::new(),
+            accelerator_kind: accelerator::AcceleratorKind::Bvh,
+            accelerator: cell::RefCell::new(None),
+            is_refit_needed: cell::Cell::new(false),
         };
     }
 
+    pub fn with_accelerator(mut self, accelerator_kind: accelerator::AcceleratorKind) -> World {
+        self.accelerator_kind = accelerator_kind;
+        self.accelerator = cell::RefCell::new(None);
+        return self;
+    }
+
+    // returns the index of the shape for set_shape
     pub fn add_shape(
         &mut self,
         shape: rc::Rc<dyn shape::IntersectableShape>,
         material: rc::Rc<dyn material::Material>,
-    ) {
+    ) -> usize {
         self.renderables
             .push(shape::RenderableShape::new(shape, material));
+        self.accelerator = cell::RefCell::new(None);
+        return self.renderables.len() - 1;
+    }
+
+    // replaces an added shape and keeps its material, e.g. with an instance of the same prototype at
+    // the transform of the next frame. The accelerator is refit to the new bounds instead of rebuilt
+    pub fn set_shape(&mut self, shape_idx: usize, shape: rc::Rc<dyn shape::IntersectableShape>) {
+        self.renderables[shape_idx].set_shape(shape);
+        self.is_refit_needed.set(true);
+    }
+
+    // refits the accelerator to the shapes set since the last query, which otherwise happens on the
+    // next query
+    pub fn refit(&mut self) {
+        if !self.is_refit_needed.get() {
+            return;
+        }
+
+        let bounds = self.shape_bounds();
+        if let Some(accelerator) = self.accelerator.get_mut() {
+            accelerator.refit(&bounds);
+        }
+
+        self.is_refit_needed.set(false);
     }
 
     pub fn add_light(&mut self, light: Box<dyn light::Light>) {
@@ -35,32 +77,131 @@ impl World {
     }
 
     pub fn is_intersect(&self, ray: &ray::Ray, max_distance: f32) -> bool {
-        for renderable in self.renderables.iter() {
-            if renderable.is_intersect(ray, max_distance) {
-                return true;
-            }
-        }
-
-        return false;
+        return self
+            .accelerator()
+            .any_hit(ray, max_distance, &mut |renderable_idx| {
+                self.renderables[renderable_idx].is_intersect(ray, max_distance)
+            });
     }
 
     pub fn intersect_ray(&self, ray: &ray::Ray) -> Option<shape::RenderableShapeSurface> {
-        let mut intersect: Option<shape::RenderableShapeSurface> = None;
-        for renderable in self.renderables.iter() {
-            match intersect.as_mut() {
-                Some(curr_renderable_surface) => {
-                    if let Some(potential_renderable_surface) = renderable.intersect_ray(&ray) {
-                        if curr_renderable_surface.shape_surface().ray_time()
-                            > potential_renderable_surface.shape_surface().ray_time()
-                        {
-                            *curr_renderable_surface = potential_renderable_surface;
-                        }
-                    }
-                }
-                None => intersect = renderable.intersect_ray(&ray),
+        // hits are compared by their distance in world space, shapes report ray times in their own space
+        let accelerator = self.accelerator();
+        return accelerator::find_closest_hit(accelerator.as_ref(), ray, |renderable_idx, _| {
+            let renderable_surface = self.renderables[renderable_idx].intersect_ray(ray)?;
+            let distance = renderable_surface
+                .shape_surface()
+                .calc_world_position()
+                .distance(ray.origin());
+            return Some((renderable_surface, distance));
+        });
+    }
+
+    fn shape_bounds(&self) -> Vec<aabb::AABB> {
+        return self
+            .renderables
+            .iter()
+            .map(|renderable| renderable.aabb())
+            .collect();
+    }
+
+    fn accelerator(&self) -> cell::Ref<'_, Box<dyn accelerator::Accelerator>> {
+        if self.accelerator.borrow().is_none() {
+            *self.accelerator.borrow_mut() = Some(accelerator::create_accelerator(
+                self.accelerator_kind,
+                &self.shape_bounds(),
+            ));
+        } else if self.is_refit_needed.get() {
+            if let Some(accelerator) = self.accelerator.borrow_mut().as_mut() {
+                accelerator.refit(&self.shape_bounds());
             }
         }
 
-        return intersect;
+        self.is_refit_needed.set(false);
+
+        return cell::Ref::map(self.accelerator.borrow(), |accelerator| {
+            accelerator.as_ref().unwrap()
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::mat4;
+    use crate::core::math;
+    use crate::core::vec3;
+    use crate::scene::animation;
+    use crate::scene::animation::transform_animation;
+    use crate::scene::material::matte;
+    use crate::scene::shape::instance;
+    use crate::scene::shape::sphere;
+
+    #[test]
+    fn test_set_shape_refits() {
+        // a unit sphere moving from x = 0 to x = 8 next to a static one at x = -4
+        let animation = transform_animation::TransformAnimation::new(
+            animation::AnimationCurve::new(vec![
+                animation::Keyframe::linear(0.0, vec3::Vec3::from(0.0)),
+                animation::Keyframe::linear(1.0, vec3::Vec3::new(8.0, 0.0, 0.0)),
+            ]),
+            animation::AnimationCurve::constant(vec3::Vec3::from(0.0)),
+            animation::AnimationCurve::constant(vec3::Vec3::from(1.0)),
+        );
+        let prototype: rc::Rc<dyn shape::IntersectableShape> =
+            rc::Rc::new(sphere::Sphere::new(mat4::Mat4::new(), 1.0));
+        let material = rc::Rc::new(matte::Matte::new(vec3::Vec3::from(0.5), 0.0));
+
+        let mut world = World::new();
+        world.add_shape(
+            rc::Rc::new(sphere::Sphere::new(
+                mat4::Mat4::new().translate(&vec3::Vec3::new(-4.0, 0.0, 0.0)),
+                1.0,
+            )),
+            material.clone(),
+        );
+        let moving_idx = world.add_shape(
+            rc::Rc::new(instance::Instance::new(
+                prototype.clone(),
+                animation.evaluate(0.0),
+            )),
+            material,
+        );
+
+        let ray_at = |x: f32| {
+            ray::Ray::new(
+                vec3::Vec3::new(x, 0.0, 10.0),
+                vec3::Vec3::new(0.0, 0.0, -1.0),
+            )
+        };
+        assert!(world.intersect_ray(&ray_at(0.0)).is_some());
+        let accelerator_ptr = world.accelerator().as_ref() as *const _ as *const u8;
+
+        for frame in 1..=4 {
+            let x = 2.0 * frame as f32;
+            let time = frame as f32 / 4.0;
+            world.set_shape(
+                moving_idx,
+                rc::Rc::new(instance::Instance::new(
+                    prototype.clone(),
+                    animation.evaluate(time),
+                )),
+            );
+            world.refit();
+
+            let surface = world.intersect_ray(&ray_at(x)).unwrap();
+            assert!(surface
+                .shape_surface()
+                .calc_world_position()
+                .equal_epsilon(&vec3::Vec3::new(x, 0.0, 1.0), math::EPSILON_F32_4));
+            assert!(!world.is_intersect(&ray_at(x - 2.0), f32::INFINITY));
+            assert!(world.is_intersect(&ray_at(-4.0), f32::INFINITY));
+
+            // the accelerator of the first frame is kept
+            assert_eq!(
+                world.accelerator().as_ref() as *const _ as *const u8,
+                accelerator_ptr
+            );
+        }
     }
 }
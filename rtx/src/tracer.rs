@@ -1,2 +1,3 @@
 pub mod monte_carlo;
+pub mod sequence;
 pub mod whitted;
@@ -9,20 +9,28 @@ use crate::scene::ray;
 use crate::scene::sampler;
 use crate::scene::world;
 
+// ray is the ray that hit the surface, wo points back along it and the shadow rays share its time
 fn estimate_one_light_direct(
     light: &dyn light::Light,
     surface_material: &dyn material::Material,
     surface_point: &vec3::Vec3,
     surface_normal: &vec3::Vec3,
     dpdu: &vec3::Vec3,
-    wo: &vec3::Vec3,
+    ray: &ray::Ray,
     world: &world::World,
     light_sample: &vec2::Vec2,
     shadow_check: bool,
 ) -> vec3::Vec3 {
+    let wo = -ray.direction();
     let maybe_radiance;
     if shadow_check {
-        maybe_radiance = light.sample_li(&light_sample, world, surface_point, surface_normal);
+        maybe_radiance = light.sample_li(
+            &light_sample,
+            world,
+            surface_point,
+            surface_normal,
+            ray.time(),
+        );
     } else {
         maybe_radiance =
             light.sample_li_no_shadow_check(&light_sample, world, surface_point, surface_normal);
@@ -42,7 +50,7 @@ fn estimate_all_lights_direct(
     surface_point: &vec3::Vec3,
     surface_normal: &vec3::Vec3,
     dpdu: &vec3::Vec3,
-    wo: &vec3::Vec3,
+    ray: &ray::Ray,
     world: &world::World,
     sampler: &mut dyn sampler::Sampler,
 ) -> vec3::Vec3 {
@@ -58,7 +66,7 @@ fn estimate_all_lights_direct(
                 surface_point,
                 surface_normal,
                 dpdu,
-                wo,
+                ray,
                 world,
                 &light_samples[i as usize],
                 true,
@@ -78,7 +86,7 @@ fn _estimate_all_lights_direct_with_uniform_contributions(
     surface_point: &vec3::Vec3,
     surface_normal: &vec3::Vec3,
     dpdu: &vec3::Vec3,
-    wo: &vec3::Vec3,
+    ray: &ray::Ray,
     world: &world::World,
     sampler: &mut dyn sampler::Sampler,
 ) -> vec3::Vec3 {
@@ -95,7 +103,7 @@ fn _estimate_all_lights_direct_with_uniform_contributions(
         surface_point,
         surface_normal,
         dpdu,
-        wo,
+        ray,
         world,
         &sample,
         true,
@@ -108,7 +116,7 @@ fn _estimate_all_lights_direct_with_linear_contributions(
     surface_point: &vec3::Vec3,
     surface_normal: &vec3::Vec3,
     dpdu: &vec3::Vec3,
-    wo: &vec3::Vec3,
+    ray: &ray::Ray,
     world: &world::World,
     sampler: &mut dyn sampler::Sampler,
 ) -> vec3::Vec3 {
@@ -125,7 +133,7 @@ fn _estimate_all_lights_direct_with_linear_contributions(
             surface_point,
             surface_normal,
             dpdu,
-            wo,
+            ray,
             world,
             sample,
             false,
@@ -160,7 +168,7 @@ fn _estimate_all_lights_direct_with_linear_contributions(
         surface_point,
         surface_normal,
         dpdu,
-        wo,
+        ray,
         world,
         &light_sample,
         true,
@@ -200,7 +208,7 @@ fn ray_trace(
             &surface_point_above,
             &normal,
             &dpdu,
-            &wo,
+            ray,
             world,
             sampler,
         );
@@ -220,12 +228,35 @@ pub fn render(
 ) {
     let image_width = image.width();
     let image_height = image.height();
+    let film_scale = camera.film_scale();
 
     for y in 0..image_height {
         for x in 0..image_width {
-            let ray = camera.create_ray(x as f32, y as f32);
-            if let Some(color) = ray_trace(&ray, world, sampler, 0, max_depth) {
-                image[y][x] = color;
+            let camera_sample = camera::CameraSample::new(
+                vec2::Vec2::new(x as f32, y as f32),
+                sampler.get_2d(),
+                sampler.get_1d(),
+            );
+            if camera.has_channel_rays() {
+                // trace every color channel separately and keep only its own component
+                let channel_masks = [
+                    vec3::Vec3::new(1.0, 0.0, 0.0),
+                    vec3::Vec3::new(0.0, 1.0, 0.0),
+                    vec3::Vec3::new(0.0, 0.0, 1.0),
+                ];
+                let mut color = vec3::Vec3::from(0.0);
+                for (channel, channel_mask) in channel_masks.iter().enumerate() {
+                    if let Some(ray) = camera.create_channel_ray(&camera_sample, channel) {
+                        if let Some(radiance) = ray_trace(&ray, world, sampler, 0, max_depth) {
+                            color += channel_mask * radiance;
+                        }
+                    }
+                }
+                image[y][x] = film_scale * color;
+            } else if let Some(ray) = camera.create_ray(&camera_sample) {
+                if let Some(color) = ray_trace(&ray, world, sampler, 0, max_depth) {
+                    image[y][x] = film_scale * color;
+                }
             }
         }
     }
@@ -0,0 +1,303 @@
+use crate::core::image;
+use crate::exporter::ppm;
+use crate::scene::camera;
+use crate::scene::sampler;
+use crate::scene::sampler::random_sampler;
+use crate::scene::world;
+use std::io;
+use std::ops;
+
+// numbered frame file, e.g. frame_0001.ppm for the prefix frame
+pub fn frame_file_name(output_prefix: &str, frame: usize) -> String {
+    return format!("{}_{:04}.ppm", output_prefix, frame);
+}
+
+// decorrelated per frame seed, so a frame renders the same regardless of the frames around it
+pub fn frame_seed(seed: u64, frame: usize) -> u64 {
+    // splitmix64 finalizer
+    let mut z = seed.wrapping_add((frame as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    return z ^ (z >> 31);
+}
+
+// frames to render, the frame files are named after the output prefix and the samplers of the
+// frames are seeded from the seed
+#[derive(Clone, Debug)]
+pub struct SequenceSettings {
+    pub output_prefix: String,
+    pub frames: ops::RangeInclusive<usize>,
+    pub frames_per_second: f32,
+    pub image_width: usize,
+    pub image_height: usize,
+    pub seed: u64,
+}
+
+impl SequenceSettings {
+    pub fn new(
+        output_prefix: &str,
+        frames: ops::RangeInclusive<usize>,
+        frames_per_second: f32,
+        image_width: usize,
+        image_height: usize,
+        seed: u64,
+    ) -> SequenceSettings {
+        assert!(frames_per_second > 0.0);
+        return SequenceSettings {
+            output_prefix: output_prefix.to_string(),
+            frames,
+            frames_per_second,
+            image_width,
+            image_height,
+            seed,
+        };
+    }
+}
+
+// updates the world to every frame time and writes the rendered frames to numbered files.
+// update_frame returns the camera of the frame, shapes it moves with World::set_shape are refit
+// before the frame is rendered instead of rebuilding the accelerator. The frame time only reaches
+// update_frame, the rays take their time from the shutter of the returned camera, which opens at 0
+// by default. Worlds with shapes keyed on the ray time, like AnimatedShape, need a camera whose
+// shutter opens at the frame time, e.g. camera::Shutter::new(time, time + exposure)
+pub fn render_sequence<C, U, R>(
+    settings: &SequenceSettings,
+    world: &mut world::World,
+    mut update_frame: U,
+    mut render_frame: R,
+) -> io::Result<()>
+where
+    C: camera::Camera,
+    U: FnMut(f32, &mut world::World) -> C,
+    R: FnMut(&C, &world::World, &mut dyn sampler::Sampler, &mut image::Image),
+{
+    for frame in settings.frames.clone() {
+        let time = (frame as f32) / settings.frames_per_second;
+        let camera = update_frame(time, world);
+        world.refit();
+        let mut sampler =
+            random_sampler::RandomSampler::from_seed(frame_seed(settings.seed, frame));
+        let mut image = image::Image::new(settings.image_width, settings.image_height);
+        render_frame(&camera, world, &mut sampler, &mut image);
+        ppm::write_to_file(&frame_file_name(&settings.output_prefix, frame), &image)?;
+    }
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::animated_transform;
+    use crate::core::mat4;
+    use crate::core::math;
+    use crate::core::vec2;
+    use crate::core::vec3;
+    use crate::scene::camera::perspective_camera;
+    use crate::scene::camera::Camera;
+    use crate::scene::material::matte;
+    use crate::scene::ray;
+    use crate::scene::shape::animated_shape;
+    use crate::scene::shape::sphere;
+    use std::fs;
+    use std::rc;
+
+    #[test]
+    fn test_frame_file_name() {
+        assert_eq!(frame_file_name("frame", 1), "frame_0001.ppm");
+        assert_eq!(frame_file_name("out/shot", 12345), "out/shot_12345.ppm");
+    }
+
+    #[test]
+    fn test_frame_seed() {
+        assert_eq!(frame_seed(7, 3), frame_seed(7, 3));
+        assert_ne!(frame_seed(7, 3), frame_seed(7, 4));
+        assert_ne!(frame_seed(7, 3), frame_seed(8, 3));
+    }
+
+    #[test]
+    fn test_render_sequence() {
+        let output_prefix = std::env::temp_dir()
+            .join(format!("rtx_sequence_{}", std::process::id()))
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        // a sphere that moves one unit along x every frame
+        let create_sphere = |x: f32| {
+            rc::Rc::new(sphere::Sphere::new(
+                mat4::Mat4::new().translate(&vec3::Vec3::new(x, 0.0, -5.0)),
+                1.0,
+            ))
+        };
+        let mut world = world::World::new();
+        let sphere_idx = world.add_shape(
+            create_sphere(0.0),
+            rc::Rc::new(matte::Matte::new(vec3::Vec3::from(0.5), 0.0)),
+        );
+
+        let mut frame_times = Vec::new();
+        let mut first_samples = Vec::new();
+        let mut hit_positions = Vec::new();
+        render_sequence(
+            &SequenceSettings::new(&output_prefix, 1..=3, 24.0, 2, 2, 42),
+            &mut world,
+            |time, world| {
+                frame_times.push(time);
+                world.set_shape(sphere_idx, create_sphere(time * 24.0));
+                let camera = perspective_camera::PerspectiveCamera::new(
+                    vec3::Vec3::from(0.0),
+                    vec3::Vec3::new(0.0, 0.0, -1.0),
+                    vec3::Vec3::new(0.0, 1.0, 0.0),
+                    math::degree_to_radian(60.0),
+                    1.0,
+                    2,
+                    2,
+                );
+                return camera;
+            },
+            |_camera, world, sampler, image| {
+                let ray = ray::Ray::new(
+                    vec3::Vec3::new((hit_positions.len() + 1) as f32, 0.0, 0.0),
+                    vec3::Vec3::new(0.0, 0.0, -1.0),
+                );
+                hit_positions.push(
+                    world
+                        .intersect_ray(&ray)
+                        .map(|surface| surface.shape_surface().calc_world_position()),
+                );
+
+                let sample = sampler.get_1d();
+                first_samples.push(sample);
+                image[0][0] = vec3::Vec3::from(sample);
+            },
+        )
+        .unwrap();
+
+        assert_eq!(frame_times.len(), 3);
+        assert!(math::equal_epsilon_f32(
+            frame_times[0],
+            1.0 / 24.0,
+            math::EPSILON_F32_6
+        ));
+        assert!(math::equal_epsilon_f32(
+            frame_times[2],
+            3.0 / 24.0,
+            math::EPSILON_F32_6
+        ));
+
+        // the hits follow the sphere from frame to frame
+        for (frame_idx, hit_position) in hit_positions.iter().enumerate() {
+            assert!(hit_position.unwrap().equal_epsilon(
+                &vec3::Vec3::new((frame_idx + 1) as f32, 0.0, -4.0),
+                math::EPSILON_F32_4
+            ));
+        }
+
+        // every frame is seeded on its own
+        let mut sampler = random_sampler::RandomSampler::from_seed(frame_seed(42, 2));
+        assert!(math::equal_epsilon_f32(
+            sampler::Sampler::get_1d(&mut sampler),
+            first_samples[1],
+            math::EPSILON_F32_6
+        ));
+
+        for frame in 1..=3 {
+            let file = frame_file_name(&output_prefix, frame);
+            assert!(fs::metadata(&file).is_ok());
+            fs::remove_file(&file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_render_animated_sequence() {
+        let output_prefix = std::env::temp_dir()
+            .join(format!("rtx_animated_sequence_{}", std::process::id()))
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        // a sphere keyed on the ray time that moves one unit along x every frame
+        let transform = animated_transform::AnimatedTransform::new(&[
+            (
+                0.0,
+                mat4::Mat4::new().translate(&vec3::Vec3::new(0.0, 0.0, -5.0)),
+            ),
+            (
+                1.0,
+                mat4::Mat4::new().translate(&vec3::Vec3::new(24.0, 0.0, -5.0)),
+            ),
+        ]);
+        let mut world = world::World::new();
+        world.add_shape(
+            rc::Rc::new(animated_shape::AnimatedShape::new(
+                rc::Rc::new(sphere::Sphere::new(mat4::Mat4::new(), 1.0)),
+                transform,
+            )),
+            rc::Rc::new(matte::Matte::new(vec3::Vec3::from(0.5), 0.0)),
+        );
+
+        let mut ray_times = Vec::new();
+        let mut hit_positions = Vec::new();
+        render_sequence(
+            &SequenceSettings::new(&output_prefix, 1..=2, 24.0, 2, 2, 42),
+            &mut world,
+            |time, _world| {
+                // the shutter follows the frame time, so the rays see the sphere of the frame
+                return perspective_camera::PerspectiveCamera::new(
+                    vec3::Vec3::from(0.0),
+                    vec3::Vec3::new(0.0, 0.0, -1.0),
+                    vec3::Vec3::new(0.0, 1.0, 0.0),
+                    math::degree_to_radian(60.0),
+                    1.0,
+                    2,
+                    2,
+                )
+                .with_shutter(camera::Shutter::new(time, time));
+            },
+            |camera, world, sampler, _image| {
+                let camera_ray = camera
+                    .create_ray(&camera::CameraSample::new(
+                        vec2::Vec2::new(1.0, 1.0),
+                        vec2::Vec2::new(0.5, 0.5),
+                        sampler.get_1d(),
+                    ))
+                    .unwrap();
+                ray_times.push(camera_ray.time());
+
+                let ray = ray::Ray::new_with_time(
+                    vec3::Vec3::new((hit_positions.len() + 1) as f32, 0.0, 0.0),
+                    vec3::Vec3::new(0.0, 0.0, -1.0),
+                    camera_ray.time(),
+                );
+                hit_positions.push(
+                    world
+                        .intersect_ray(&ray)
+                        .map(|surface| surface.shape_surface().calc_world_position()),
+                );
+            },
+        )
+        .unwrap();
+
+        for (frame_idx, ray_time) in ray_times.iter().enumerate() {
+            assert!(math::equal_epsilon_f32(
+                *ray_time,
+                (frame_idx + 1) as f32 / 24.0,
+                math::EPSILON_F32_6
+            ));
+        }
+
+        for (frame_idx, hit_position) in hit_positions.iter().enumerate() {
+            assert!(hit_position.unwrap().equal_epsilon(
+                &vec3::Vec3::new((frame_idx + 1) as f32, 0.0, -4.0),
+                math::EPSILON_F32_4
+            ));
+        }
+
+        for frame in 1..=2 {
+            let file = frame_file_name(&output_prefix, frame);
+            assert!(fs::metadata(&file).is_ok());
+            fs::remove_file(&file).unwrap();
+        }
+    }
+}
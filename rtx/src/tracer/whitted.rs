@@ -1,5 +1,6 @@
 use crate::core::image;
 use crate::core::math;
+use crate::core::vec2;
 use crate::core::vec3;
 use crate::scene::camera;
 use crate::scene::ray;
@@ -36,8 +37,13 @@ fn ray_trace(
 
         // add color from lights around the world
         for light in world.lights().iter() {
-            let maybe_radiance =
-                light.sample_li(&sampler.get_2d(), world, &surface_point_above, &normal);
+            let maybe_radiance = light.sample_li(
+                &sampler.get_2d(),
+                world,
+                &surface_point_above,
+                &normal,
+                ray.time(),
+            );
             if !maybe_radiance.is_none() {
                 let radiance = maybe_radiance.unwrap();
                 let bxdf = surface_material.bxdf(&normal, &dpdu, &wo, &radiance.wi);
@@ -57,7 +63,7 @@ fn ray_trace(
             );
             if !maybe_radiance.is_none() {
                 let radiance = maybe_radiance.unwrap();
-                let ray = ray::Ray::new(surface_point_above, radiance.wi);
+                let ray = ray::Ray::new_with_time(surface_point_above, radiance.wi, ray.time());
                 if let Some(li) = ray_trace(&ray, world, sampler, depth + 1, max_depth) {
                     lo += radiance.bxdf * li * f32::abs(vec3::Vec3::dot(&normal, &radiance.wi));
                 }
@@ -73,7 +79,7 @@ fn ray_trace(
             );
             if !maybe_radiance.is_none() {
                 let radiance = maybe_radiance.unwrap();
-                let ray = ray::Ray::new(surface_point_below, radiance.wi);
+                let ray = ray::Ray::new_with_time(surface_point_below, radiance.wi, ray.time());
                 if let Some(li) = ray_trace(&ray, world, sampler, depth + 1, max_depth) {
                     lo += radiance.bxdf * li * f32::abs(vec3::Vec3::dot(&normal, &radiance.wi));
                 }
@@ -95,12 +101,35 @@ pub fn render(
 ) {
     let image_width = image.width();
     let image_height = image.height();
+    let film_scale = camera.film_scale();
 
     for y in 0..image_height {
         for x in 0..image_width {
-            let ray = camera.create_ray(x as f32, y as f32);
-            if let Some(color) = ray_trace(&ray, world, sampler, 0, max_depth) {
-                image[y][x] = color;
+            let camera_sample = camera::CameraSample::new(
+                vec2::Vec2::new(x as f32, y as f32),
+                sampler.get_2d(),
+                sampler.get_1d(),
+            );
+            if camera.has_channel_rays() {
+                // trace every color channel separately and keep only its own component
+                let channel_masks = [
+                    vec3::Vec3::new(1.0, 0.0, 0.0),
+                    vec3::Vec3::new(0.0, 1.0, 0.0),
+                    vec3::Vec3::new(0.0, 0.0, 1.0),
+                ];
+                let mut color = vec3::Vec3::from(0.0);
+                for (channel, channel_mask) in channel_masks.iter().enumerate() {
+                    if let Some(ray) = camera.create_channel_ray(&camera_sample, channel) {
+                        if let Some(radiance) = ray_trace(&ray, world, sampler, 0, max_depth) {
+                            color += channel_mask * radiance;
+                        }
+                    }
+                }
+                image[y][x] = film_scale * color;
+            } else if let Some(ray) = camera.create_ray(&camera_sample) {
+                if let Some(color) = ray_trace(&ray, world, sampler, 0, max_depth) {
+                    image[y][x] = film_scale * color;
+                }
             }
         }
     }